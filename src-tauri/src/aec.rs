@@ -0,0 +1,150 @@
+// Acoustic echo cancellation for users on desktop speakers. The loopback capture is
+// the far-end reference (what the speakers play) and WebRTC's echo canceller removes
+// it from the microphone, so other players' voices don't come back as our own
+// chatbox text. WebRTC audio processing needs native libraries and is only built
+// with the `aec` feature.
+use serde::{Deserialize, Serialize};
+
+use crate::denoise::upsample;
+
+// WebRTC processes 10ms frames at 48kHz; the capture pipeline runs at 16kHz
+const FRAME_LEN: usize = 160;
+const UPSAMPLE_FACTOR: usize = 3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AecSuppression {
+    Low,
+    #[default]
+    Moderate,
+    High,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AecSettings {
+    // Needs loopback capture running to have a reference to cancel
+    pub enabled: bool,
+    pub suppression: AecSuppression,
+}
+
+impl AecSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.enabled && !cfg!(feature = "aec") {
+            return Err(
+                "This build of VRCTalk doesn't include echo cancellation support".to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+// Handle on the canceller shared by the loopback (render) and microphone (capture)
+// threads; clones refer to the same processor
+#[derive(Clone)]
+pub struct EchoCanceller {
+    #[cfg(feature = "aec")]
+    processor: webrtc_audio_processing::Processor,
+}
+
+#[cfg(feature = "aec")]
+impl EchoCanceller {
+    pub fn new(suppression: AecSuppression) -> Result<Self, String> {
+        let processor = webrtc_audio_processing::Processor::new(
+            &webrtc_audio_processing::InitializationConfig {
+                num_capture_channels: 1,
+                num_render_channels: 1,
+                ..Default::default()
+            },
+        )
+        .map_err(|e| format!("Failed to create echo canceller: {:?}", e))?;
+        let mut canceller = Self { processor };
+        canceller.configure(suppression);
+        Ok(canceller)
+    }
+
+    pub fn configure(&mut self, suppression: AecSuppression) {
+        use webrtc_audio_processing::EchoCancellationSuppressionLevel as Level;
+        let suppression_level = match suppression {
+            AecSuppression::Low => Level::Low,
+            AecSuppression::Moderate => Level::Moderate,
+            AecSuppression::High => Level::High,
+        };
+        self.processor.set_config(webrtc_audio_processing::Config {
+            echo_cancellation: Some(webrtc_audio_processing::EchoCancellation {
+                suppression_level,
+                // The two streams come from separate devices, so the delay is unknown
+                stream_delay_ms: None,
+                enable_delay_agnostic: true,
+                enable_extended_filter: true,
+            }),
+            ..Default::default()
+        });
+    }
+
+    fn render_frame(&mut self, frame: &mut [f32]) {
+        if let Err(e) = self.processor.process_render_frame(frame) {
+            println!("Echo canceller rejected a reference frame: {:?}", e);
+        }
+    }
+
+    fn capture_frame(&mut self, frame: &mut [f32]) {
+        if let Err(e) = self.processor.process_capture_frame(frame) {
+            println!("Echo canceller rejected a microphone frame: {:?}", e);
+        }
+    }
+}
+
+// Without the feature the canceller passes audio through; enabling it is refused up front
+#[cfg(not(feature = "aec"))]
+impl EchoCanceller {
+    pub fn new(_suppression: AecSuppression) -> Result<Self, String> {
+        Ok(Self {})
+    }
+
+    pub fn configure(&mut self, _suppression: AecSuppression) {}
+
+    fn render_frame(&mut self, _frame: &mut [f32]) {}
+
+    fn capture_frame(&mut self, _frame: &mut [f32]) {}
+}
+
+// One capture thread's side of the canceller, collecting 16kHz audio into frames
+pub struct AecEndpoint {
+    canceller: EchoCanceller,
+    pending: Vec<f32>,
+}
+
+impl AecEndpoint {
+    pub fn new(canceller: EchoCanceller) -> Self {
+        Self {
+            canceller,
+            pending: Vec::with_capacity(FRAME_LEN),
+        }
+    }
+
+    // Feed audio the speakers played (loopback)
+    pub fn render(&mut self, samples: &[f32]) {
+        self.pending.extend_from_slice(samples);
+        let frames = self.pending.len() / FRAME_LEN;
+        for frame in self.pending[..frames * FRAME_LEN].chunks(FRAME_LEN) {
+            let mut upsampled = upsample(frame);
+            self.canceller.render_frame(&mut upsampled);
+        }
+        self.pending.drain(..frames * FRAME_LEN);
+    }
+
+    // Remove the echo from microphone audio. Output lags the input by up to a frame.
+    pub fn capture(&mut self, samples: &[f32]) -> Vec<f32> {
+        self.pending.extend_from_slice(samples);
+        let frames = self.pending.len() / FRAME_LEN;
+        let mut output = Vec::with_capacity(frames * FRAME_LEN);
+        for frame in self.pending[..frames * FRAME_LEN].chunks(FRAME_LEN) {
+            let mut upsampled = upsample(frame);
+            self.canceller.capture_frame(&mut upsampled);
+            output.extend(upsampled.iter().step_by(UPSAMPLE_FACTOR).take(FRAME_LEN));
+        }
+        self.pending.drain(..frames * FRAME_LEN);
+        output
+    }
+}
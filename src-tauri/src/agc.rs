@@ -0,0 +1,100 @@
+// Automatic gain control for native capture. Quiet microphones are brought up to a
+// target level before VAD and Whisper see them; Whisper tends to return nothing or
+// hallucinate on audio peaking around -40dBFS.
+use serde::{Deserialize, Serialize};
+
+// Gain is re-evaluated every 10ms at 16kHz
+const BLOCK_LEN: usize = 160;
+// Blocks quieter than this are treated as silence and don't pull the gain up,
+// otherwise pauses get boosted into loud hiss
+const NOISE_FLOOR_DB: f32 = -60.0;
+// Level follower: react quickly to louder speech, relax slowly in quieter passages
+const ATTACK: f32 = 0.5;
+const RELEASE: f32 = 0.02;
+// Fraction of the way the applied gain moves towards the wanted gain per block
+const GAIN_SMOOTHING: f32 = 0.1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgcSettings {
+    pub enabled: bool,
+    // Speech level to normalize to, in dBFS RMS
+    pub target_db: f32,
+    // Upper bound on the boost, so a muted or unplugged mic isn't amplified into noise
+    pub max_gain_db: f32,
+}
+
+impl Default for AgcSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            target_db: -20.0,
+            max_gain_db: 30.0,
+        }
+    }
+}
+
+impl AgcSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-40.0..=-3.0).contains(&self.target_db) {
+            return Err("AGC target level must be between -40dB and -3dB".to_string());
+        }
+        if !(0.0..=60.0).contains(&self.max_gain_db) {
+            return Err("AGC max gain must be between 0dB and 60dB".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn db_to_linear(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+// Per-capture AGC state, owned by the capture thread
+pub struct Agc {
+    level: f32,
+    gain: f32,
+    block: Vec<f32>,
+}
+
+impl Default for Agc {
+    fn default() -> Self {
+        Self {
+            level: 0.0,
+            gain: 1.0,
+            block: Vec::with_capacity(BLOCK_LEN),
+        }
+    }
+}
+
+impl Agc {
+    // Scale 16kHz samples in place. Only ever boosts; loud mics are left alone.
+    pub fn process(&mut self, settings: &AgcSettings, samples: &mut [f32]) {
+        if !settings.enabled {
+            self.gain = 1.0;
+            return;
+        }
+        let target = db_to_linear(settings.target_db);
+        let max_gain = db_to_linear(settings.max_gain_db);
+        let noise_floor = db_to_linear(NOISE_FLOOR_DB);
+
+        for sample in samples.iter_mut() {
+            self.block.push(*sample);
+            *sample = (*sample * self.gain).clamp(-1.0, 1.0);
+            if self.block.len() < BLOCK_LEN {
+                continue;
+            }
+
+            let rms = (self.block.iter().map(|s| s * s).sum::<f32>() / BLOCK_LEN as f32).sqrt();
+            self.block.clear();
+            if rms < noise_floor {
+                continue;
+            }
+            let rate = if rms > self.level { ATTACK } else { RELEASE };
+            self.level += (rms - self.level) * rate;
+
+            let wanted = (target / self.level.max(f32::EPSILON)).clamp(1.0, max_gain);
+            self.gain += (wanted - self.gain) * GAIN_SMOOTHING;
+        }
+    }
+}
@@ -0,0 +1,123 @@
+// Local-agreement stabilization for streaming partials. A stream re-decodes its
+// growing audio buffer on every call; a word is only committed once two consecutive
+// decodes agree on it, so text shown in the chatbox grows but is never rewritten.
+
+#[derive(Default)]
+pub struct LocalAgreement {
+    // Words shown so far, never taken back
+    committed: Vec<String>,
+    // Full hypothesis of the last finished decode
+    previous: Vec<String>,
+    // Language that decode was in, so partials are post-processed like it was
+    pub language: String,
+}
+
+// Lowercase and strip punctuation so "Hello," and "hello" agree
+fn normalize(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn agreed_len(a: &[String], b: &[String]) -> usize {
+    a.iter()
+        .zip(b)
+        .take_while(|(a, b)| normalize(a) == normalize(b))
+        .count()
+}
+
+impl LocalAgreement {
+    // Feed the text decoded so far (possibly mid-decode). Returns true when the
+    // committed prefix grew.
+    pub fn update(&mut self, hypothesis: &str) -> bool {
+        let words: Vec<String> = hypothesis.split_whitespace().map(str::to_string).collect();
+        // A decode that contradicts committed words can't extend them
+        if agreed_len(&self.committed, &words) < self.committed.len() {
+            return false;
+        }
+
+        let agreed = agreed_len(&self.previous, &words);
+        if agreed <= self.committed.len() {
+            return false;
+        }
+        // Keep the committed words as first shown, even if this decode spells them differently
+        let start = self.committed.len();
+        self.committed.extend_from_slice(&words[start..agreed]);
+        true
+    }
+
+    // Record a finished decode as the hypothesis the next one has to agree with
+    pub fn finish_decode(&mut self, hypothesis: &str) -> bool {
+        let grew = self.update(hypothesis);
+        self.previous = hypothesis.split_whitespace().map(str::to_string).collect();
+        grew
+    }
+
+    pub fn committed(&self) -> String {
+        self.committed.join(" ")
+    }
+
+    // Words of `hypothesis` past the committed prefix, still liable to change
+    pub fn tentative(&self, hypothesis: &str) -> String {
+        hypothesis
+            .split_whitespace()
+            .skip(self.committed.len())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_commit_once_two_decodes_agree() {
+        let mut agreement = LocalAgreement::default();
+        assert!(!agreement.finish_decode("hello there"));
+        assert_eq!(agreement.committed(), "");
+        assert!(agreement.finish_decode("hello there friend"));
+        assert_eq!(agreement.committed(), "hello there");
+        assert_eq!(agreement.tentative("hello there friend"), "friend");
+    }
+
+    #[test]
+    fn contradicting_decode_never_rewrites_committed_words() {
+        let mut agreement = LocalAgreement::default();
+        agreement.finish_decode("I scream for ice");
+        agreement.finish_decode("I scream for ice cream");
+        assert_eq!(agreement.committed(), "I scream for ice");
+
+        assert!(!agreement.finish_decode("ice cream for everyone"));
+        assert!(!agreement.finish_decode("ice cream for everyone today"));
+        assert_eq!(agreement.committed(), "I scream for ice");
+    }
+
+    #[test]
+    fn punctuation_and_case_do_not_break_agreement() {
+        let mut agreement = LocalAgreement::default();
+        agreement.finish_decode("hello world");
+        assert!(agreement.finish_decode("Hello, world! How"));
+        assert_eq!(agreement.committed(), "Hello, world!");
+    }
+
+    #[test]
+    fn committed_words_keep_their_first_spelling() {
+        let mut agreement = LocalAgreement::default();
+        agreement.finish_decode("hello world");
+        agreement.finish_decode("hello world again");
+        assert!(agreement.finish_decode("Hello, World. Again and"));
+        assert_eq!(agreement.committed(), "hello world Again");
+    }
+
+    #[test]
+    fn partial_decodes_commit_without_replacing_the_previous_hypothesis() {
+        let mut agreement = LocalAgreement::default();
+        agreement.finish_decode("one two three");
+        assert!(agreement.update("one two"));
+        assert_eq!(agreement.committed(), "one two");
+        assert!(agreement.update("one two three four"));
+        assert_eq!(agreement.committed(), "one two three");
+    }
+}
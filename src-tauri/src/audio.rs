@@ -0,0 +1,1154 @@
+// Native audio capture. The microphone is read with cpal on a dedicated thread (its
+// streams aren't Send on every platform), downmixed and resampled to 16kHz mono,
+// cut into utterances by the VAD and fed straight to the configured transcription provider, which
+// keeps the webview's getUserMedia/MediaRecorder out of the loop entirely. On
+// Windows the speakers can be captured the same way (WASAPI loopback) so what
+// other players say is transcribed too.
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SizedSample};
+use rubato::{FftFixedIn, Resampler as _};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager, State};
+use tokio::sync::mpsc as async_mpsc;
+
+use crate::aec::{AecEndpoint, AecSettings, EchoCanceller};
+use crate::agc::{Agc, AgcSettings};
+use crate::captions::caption_transcript;
+use crate::denoise::DEFAULT_AUDIO_SOURCE;
+use crate::mixer::{InputMix, Mixer};
+use crate::noise_gate::{calibrate, NoiseCalibration, NoiseGate, NoiseGateSettings};
+use crate::push_to_talk::{GateOutput, PushToTalkGate, PushToTalkState};
+use crate::stt::{transcribe_with_provider, SttAppState, SttAudio};
+use crate::tts::TtsAppState;
+use crate::vad::{SegmentationSettings, StreamingSegmenter, VadConfig};
+use crate::whisper::{TranscribeOptions, WhisperAppState, WHISPER_SAMPLE_RATE};
+
+// Utterances waiting for transcription; beyond this the capture is falling behind
+// and new speech is dropped rather than delayed further
+const MAX_QUEUED_UTTERANCES: usize = 4;
+// How often the capture thread checks for a stop request while no audio arrives
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+// A stream that delivers nothing for this long is treated as a lost device; cpal
+// keeps calling back with silence while a working mic is quiet
+const DEVICE_STALL_TIMEOUT: Duration = Duration::from_secs(3);
+// Results of loopback capture are what other players said
+pub const LOOPBACK_AUDIO_SOURCE: &str = "others";
+// Wait between attempts to reopen a device after every input device disappeared
+const REOPEN_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+// Level meter update period, in 16kHz samples (100ms)
+const LEVEL_WINDOW: usize = WHISPER_SAMPLE_RATE as usize / 10;
+// Floor reported for digital silence instead of -inf
+const SILENCE_DB: f32 = -100.0;
+// Resampler input block, 10ms of device audio
+const RESAMPLER_CHUNKS_PER_SECOND: u32 = 100;
+// Default length of the noise floor measurement
+const DEFAULT_CALIBRATION_MS: u32 = 3000;
+// Rates worth listing when a device reports a continuous range
+const COMMON_SAMPLE_RATES: [u32; 8] = [8000, 16000, 22050, 32000, 44100, 48000, 96000, 192000];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureSource {
+    #[default]
+    Microphone,
+    // Whatever the speakers play (other players in voice chat), via WASAPI loopback
+    Loopback,
+}
+
+impl CaptureSource {
+    // Label on results and the key for per-source settings such as denoising
+    fn label(self) -> &'static str {
+        match self {
+            CaptureSource::Microphone => DEFAULT_AUDIO_SOURCE,
+            CaptureSource::Loopback => LOOPBACK_AUDIO_SOURCE,
+        }
+    }
+
+    // Whose words these are, so captions can be routed (own speech to the chatbox,
+    // other players to an overlay)
+    fn speaker(self) -> &'static str {
+        match self {
+            CaptureSource::Microphone => "me",
+            CaptureSource::Loopback => "others",
+        }
+    }
+}
+
+// Live per-source controls, applied to a running capture without restarting it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SourceSettings {
+    // Disabled sources keep their device open but transcribe nothing
+    pub enabled: bool,
+    // Linear gain applied after AGC
+    pub volume: f32,
+}
+
+impl Default for SourceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            volume: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureConfig {
+    pub source: CaptureSource,
+    // Input device ID from `list_audio_devices`; None uses the device picked with
+    // `set_audio_device`, or the system default. For loopback this names an output
+    // device and None is the default one.
+    pub device: Option<String>,
+    // Extra input devices mixed into the microphone, and the gain of each; empty
+    // uses the mix set with `audio_set_input_mix`
+    pub mix: InputMix,
+    pub model: String,
+    pub language: String,
+    // How speech is cut into the utterances sent for transcription
+    pub segmentation: SegmentationSettings,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            source: CaptureSource::default(),
+            device: None,
+            mix: InputMix::default(),
+            model: "base".to_string(),
+            language: "auto".to_string(),
+            segmentation: SegmentationSettings::default(),
+        }
+    }
+}
+
+impl CaptureConfig {
+    fn validate(&self) -> Result<(), String> {
+        self.segmentation.validate()?;
+        self.mix.validate()?;
+        if self.source == CaptureSource::Loopback && !self.mix.inputs.is_empty() {
+            return Err("Only microphone capture can mix several devices".to_string());
+        }
+        if self.model.trim().is_empty() {
+            return Err("No model selected for capture".to_string());
+        }
+        if self.source == CaptureSource::Loopback && !cfg!(target_os = "windows") {
+            return Err("Loopback capture is only supported on Windows".to_string());
+        }
+        Ok(())
+    }
+}
+
+struct CaptureSession {
+    config: CaptureConfig,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct AudioCaptureState {
+    session: Mutex<Option<CaptureSession>>,
+    // Loopback runs alongside the microphone as its own session
+    loopback: Mutex<Option<CaptureSession>>,
+    // Device chosen in settings, None follows the system default
+    device: Mutex<Option<String>>,
+    input_mix: Mutex<InputMix>,
+    agc: Mutex<AgcSettings>,
+    noise_gate: Mutex<NoiseGateSettings>,
+    echo_cancellation: Mutex<AecSettings>,
+    // Created the first time echo cancellation is enabled, shared by both sources
+    echo_canceller: Mutex<Option<EchoCanceller>>,
+    sources: Mutex<HashMap<CaptureSource, SourceSettings>>,
+}
+
+impl AudioCaptureState {
+    fn session(&self, source: CaptureSource) -> &Mutex<Option<CaptureSession>> {
+        match source {
+            CaptureSource::Microphone => &self.session,
+            CaptureSource::Loopback => &self.loopback,
+        }
+    }
+
+    // The shared canceller while echo cancellation is enabled
+    fn echo_canceller(&self) -> Option<EchoCanceller> {
+        let enabled = self
+            .echo_cancellation
+            .lock()
+            .is_ok_and(|settings| settings.enabled);
+        if !enabled {
+            return None;
+        }
+        self.echo_canceller.lock().ok()?.clone()
+    }
+
+    fn source_settings(&self, source: CaptureSource) -> SourceSettings {
+        self.sources
+            .lock()
+            .ok()
+            .and_then(|sources| sources.get(&source).cloned())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AudioDevice {
+    // cpal has no stable device IDs, the OS-reported name doubles as one
+    pub id: String,
+    pub name: String,
+    pub is_default: bool,
+    pub default_sample_rate: Option<u32>,
+    pub sample_rates: Vec<u32>,
+    pub max_channels: u16,
+}
+
+// Converts the device rate (usually 44.1 or 48kHz) to 16kHz with rubato's FFT-based
+// synchronous resampler, which band-limits properly instead of aliasing everything
+// above 8kHz into the speech band. Input is fed in whole chunks; the remainder waits
+// for the next buffer.
+struct Resampler {
+    // None when the device already runs at 16kHz
+    inner: Option<FftFixedIn<f32>>,
+    pending: Vec<f32>,
+}
+
+impl Resampler {
+    fn new(input_rate: u32) -> Result<Self, String> {
+        let inner = if input_rate == WHISPER_SAMPLE_RATE {
+            None
+        } else {
+            let chunk_len = (input_rate / RESAMPLER_CHUNKS_PER_SECOND).max(1) as usize;
+            let resampler = FftFixedIn::new(
+                input_rate as usize,
+                WHISPER_SAMPLE_RATE as usize,
+                chunk_len,
+                1,
+                1,
+            )
+            .map_err(|e| format!("Failed to create resampler for {}Hz: {}", input_rate, e))?;
+            Some(resampler)
+        };
+        Ok(Self {
+            inner,
+            pending: Vec::new(),
+        })
+    }
+
+    fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let Some(inner) = &mut self.inner else {
+            return input.to_vec();
+        };
+        self.pending.extend_from_slice(input);
+
+        let mut output = Vec::new();
+        loop {
+            let needed = inner.input_frames_next();
+            if self.pending.len() < needed {
+                break;
+            }
+            match inner.process(&[&self.pending[..needed]], None) {
+                Ok(resampled) => output.extend_from_slice(&resampled[0]),
+                Err(e) => println!("Resampling failed: {}", e),
+            }
+            self.pending.drain(..needed);
+        }
+        output
+    }
+}
+
+// Accumulates RMS and peak over fixed windows for the `audio-level` meter events
+#[derive(Default)]
+struct LevelMeter {
+    sum_squares: f64,
+    peak: f32,
+    count: usize,
+}
+
+impl LevelMeter {
+    fn to_db(level: f32) -> f32 {
+        if level > 0.0 {
+            (20.0 * level.log10()).max(SILENCE_DB)
+        } else {
+            SILENCE_DB
+        }
+    }
+
+    fn process(&mut self, samples: &[f32], stream: &OpenStream, app_handle: &tauri::AppHandle) {
+        for &sample in samples {
+            self.sum_squares += (sample as f64) * (sample as f64);
+            self.peak = self.peak.max(sample.abs());
+            self.count += 1;
+            if self.count < LEVEL_WINDOW {
+                continue;
+            }
+
+            let rms = (self.sum_squares / self.count as f64).sqrt() as f32;
+            let _ = app_handle.emit(
+                "audio-level",
+                serde_json::json!({
+                    "source": stream.source.label(),
+                    "device": stream.name,
+                    "rms": rms,
+                    "peak": self.peak,
+                    "rms_db": Self::to_db(rms),
+                    "peak_db": Self::to_db(self.peak),
+                }),
+            );
+            *self = Self::default();
+        }
+    }
+}
+
+fn find_input_device(name: Option<&str>) -> Result<cpal::Device, String> {
+    let host = cpal::default_host();
+    match name {
+        Some(name) => host
+            .input_devices()
+            .map_err(|e| format!("Failed to list input devices: {}", e))?
+            .find(|device| device.name().is_ok_and(|n| n == name))
+            .ok_or_else(|| format!("Input device '{}' not found", name)),
+        None => host
+            .default_input_device()
+            .ok_or_else(|| "No default input device".to_string()),
+    }
+}
+
+pub fn find_output_device(name: Option<&str>) -> Result<cpal::Device, String> {
+    let host = cpal::default_host();
+    match name {
+        Some(name) => host
+            .output_devices()
+            .map_err(|e| format!("Failed to list output devices: {}", e))?
+            .find(|device| device.name().is_ok_and(|n| n == name))
+            .ok_or_else(|| format!("Output device '{}' not found", name)),
+        None => host
+            .default_output_device()
+            .ok_or_else(|| "No default output device".to_string()),
+    }
+}
+
+fn describe_device(device: &cpal::Device, default_name: Option<&str>) -> Option<AudioDevice> {
+    let name = device.name().ok()?;
+    let ranges: Vec<cpal::SupportedStreamConfigRange> = device
+        .supported_input_configs()
+        .map(|configs| configs.collect())
+        .unwrap_or_default();
+
+    let mut sample_rates: Vec<u32> = ranges
+        .iter()
+        .flat_map(|range| {
+            let (min, max) = (range.min_sample_rate().0, range.max_sample_rate().0);
+            COMMON_SAMPLE_RATES
+                .into_iter()
+                .filter(move |rate| (min..=max).contains(rate))
+                .chain([min, max])
+        })
+        .collect();
+    sample_rates.sort_unstable();
+    sample_rates.dedup();
+
+    Some(AudioDevice {
+        id: name.clone(),
+        is_default: default_name == Some(name.as_str()),
+        name,
+        default_sample_rate: device
+            .default_input_config()
+            .ok()
+            .map(|config| config.sample_rate().0),
+        sample_rates,
+        max_channels: ranges.iter().map(|r| r.channels()).max().unwrap_or(0),
+    })
+}
+
+// Open an input stream that downmixes every buffer to mono f32 and hands it over.
+// `lost` is raised when the OS reports the device gone.
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: mpsc::Sender<Vec<f32>>,
+    lost: Arc<AtomicBool>,
+    app_handle: tauri::AppHandle,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels.max(1) as usize;
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let mono = data
+                    .chunks(channels)
+                    .map(|frame| {
+                        frame.iter().map(|&s| f32::from_sample(s)).sum::<f32>() / channels as f32
+                    })
+                    .collect();
+                let _ = samples.send(mono);
+            },
+            move |e| match e {
+                cpal::StreamError::DeviceNotAvailable => lost.store(true, Ordering::SeqCst),
+                e => {
+                    println!("Audio capture stream error: {}", e);
+                    let _ = app_handle.emit("audio-capture-error", e.to_string());
+                }
+            },
+            None,
+        )
+        .map_err(|e| format!("Failed to open input stream: {}", e))
+}
+
+// A running input stream; dropping it closes the device
+struct OpenStream {
+    _stream: cpal::Stream,
+    source: CaptureSource,
+    name: String,
+    resampler: Resampler,
+    samples: mpsc::Receiver<Vec<f32>>,
+    lost: Arc<AtomicBool>,
+}
+
+// Loopback opens an input stream on an output device, which WASAPI turns into a
+// capture of everything that device plays
+fn open_stream(
+    source: CaptureSource,
+    device_name: Option<&str>,
+    app_handle: &tauri::AppHandle,
+) -> Result<OpenStream, String> {
+    let (device, supported) = match source {
+        CaptureSource::Microphone => {
+            let device = find_input_device(device_name)?;
+            let supported = device.default_input_config();
+            (device, supported)
+        }
+        CaptureSource::Loopback => {
+            let device = find_output_device(device_name)?;
+            let supported = device.default_output_config();
+            (device, supported)
+        }
+    };
+    let name = device.name().unwrap_or_default();
+    let supported = supported.map_err(|e| format!("Failed to query input format: {}", e))?;
+    let sample_format = supported.sample_format();
+    let config: cpal::StreamConfig = supported.into();
+
+    let resampler = Resampler::new(config.sample_rate.0)?;
+    let (samples, receiver) = mpsc::channel();
+    let lost = Arc::new(AtomicBool::new(false));
+    let (flag, app_handle) = (lost.clone(), app_handle.clone());
+    let stream = match sample_format {
+        cpal::SampleFormat::F32 => {
+            build_stream::<f32>(&device, &config, samples, flag, app_handle)?
+        }
+        cpal::SampleFormat::I16 => {
+            build_stream::<i16>(&device, &config, samples, flag, app_handle)?
+        }
+        cpal::SampleFormat::U16 => {
+            build_stream::<u16>(&device, &config, samples, flag, app_handle)?
+        }
+        cpal::SampleFormat::I32 => {
+            build_stream::<i32>(&device, &config, samples, flag, app_handle)?
+        }
+        other => return Err(format!("Unsupported input sample format {:?}", other)),
+    };
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start input stream: {}", e))?;
+
+    println!("Capturing from '{}' at {}Hz", name, config.sample_rate.0);
+    Ok(OpenStream {
+        _stream: stream,
+        source,
+        name,
+        resampler,
+        samples: receiver,
+        lost,
+    })
+}
+
+// Keep trying the system default until a device shows up or capture is stopped
+fn reopen_default(
+    source: CaptureSource,
+    app_handle: &tauri::AppHandle,
+    stop: &AtomicBool,
+) -> Option<OpenStream> {
+    while !stop.load(Ordering::SeqCst) {
+        match open_stream(source, None, app_handle) {
+            Ok(stream) => return Some(stream),
+            Err(e) => println!("Waiting for an input device: {}", e),
+        }
+        thread::sleep(REOPEN_RETRY_INTERVAL);
+    }
+    None
+}
+
+// Open the extra devices of a microphone mix; one that can't be opened is left out
+fn open_mix_inputs(
+    config: &CaptureConfig,
+    app_handle: &tauri::AppHandle,
+) -> Vec<Option<OpenStream>> {
+    config
+        .mix
+        .inputs
+        .iter()
+        .map(
+            |input| match open_stream(config.source, Some(&input.device), app_handle) {
+                Ok(stream) => Some(stream),
+                Err(e) => {
+                    println!("Leaving '{}' out of the input mix: {}", input.device, e);
+                    let _ = app_handle.emit("audio-capture-error", e);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+// Move audio that arrived from the extra mixed devices into the mixer
+fn collect_mix_inputs(streams: &mut [Option<OpenStream>], mixer: &mut Mixer) {
+    for (index, slot) in streams.iter_mut().enumerate() {
+        if slot
+            .as_ref()
+            .is_some_and(|stream| stream.lost.load(Ordering::SeqCst))
+        {
+            if let Some(stream) = slot.take() {
+                println!("Mixed input device '{}' lost", stream.name);
+            }
+            mixer.clear(index);
+            continue;
+        }
+        let Some(stream) = slot else {
+            continue;
+        };
+        while let Ok(buffer) = stream.samples.try_recv() {
+            let resampled = stream.resampler.process(&buffer);
+            mixer.push(index, &resampled);
+        }
+    }
+}
+
+fn queue_utterance(utterances: &async_mpsc::Sender<Vec<f32>>, utterance: Vec<f32>) {
+    if utterances.try_send(utterance).is_err() {
+        println!("Transcription is falling behind, dropping a captured utterance");
+    }
+}
+
+// Owns the cpal stream for the lifetime of a capture session and cuts the resampled
+// audio into utterances at the pauses the VAD finds. When the device disappears
+// (headset asleep, USB unplugged) capture carries on from the system default device.
+fn capture_thread(
+    app_handle: tauri::AppHandle,
+    config: CaptureConfig,
+    stop: Arc<AtomicBool>,
+    utterances: async_mpsc::Sender<Vec<f32>>,
+    ready: mpsc::Sender<Result<(), String>>,
+) {
+    let mut stream = match open_stream(config.source, config.device.as_deref(), &app_handle) {
+        Ok(stream) => {
+            let _ = ready.send(Ok(()));
+            stream
+        }
+        Err(e) => {
+            let _ = ready.send(Err(e));
+            return;
+        }
+    };
+
+    let mut mix_streams = open_mix_inputs(&config, &app_handle);
+    let mut mixer = Mixer::new(mix_streams.len());
+
+    let whisper_state = app_handle.state::<WhisperAppState>();
+    let vad = match whisper_state.vad.lock() {
+        Ok(vad) => vad.clone(),
+        Err(_) => VadConfig::default(),
+    };
+    let mut segmenter =
+        StreamingSegmenter::new(vad, config.segmentation.clone(), WHISPER_SAMPLE_RATE);
+    let mut meter = LevelMeter::default();
+    let capture_state = app_handle.state::<AudioCaptureState>();
+    let push_to_talk = app_handle.state::<PushToTalkState>();
+    let tts_state = app_handle.state::<TtsAppState>();
+    let mut agc = Agc::default();
+    let mut noise_gate = NoiseGate::default();
+    let mut echo: Option<AecEndpoint> = None;
+    let mut gate = PushToTalkGate::default();
+    let mut last_audio = Instant::now();
+    while !stop.load(Ordering::SeqCst) {
+        // WASAPI loopback delivers nothing at all while nothing is playing
+        let stalled = config.source == CaptureSource::Microphone
+            && last_audio.elapsed() >= DEVICE_STALL_TIMEOUT;
+        if stream.lost.load(Ordering::SeqCst) || stalled {
+            println!(
+                "Input device '{}' lost, switching to the default device",
+                stream.name
+            );
+            let _ = app_handle.emit(
+                "audio-device-lost",
+                serde_json::json!({
+                    "source": config.source.label(),
+                    "device": stream.name,
+                    "fallback": "default"
+                }),
+            );
+            drop(stream);
+            let Some(reopened) = reopen_default(config.source, &app_handle, &stop) else {
+                return;
+            };
+            stream = reopened;
+            last_audio = Instant::now();
+            let _ = app_handle.emit(
+                "audio-device-changed",
+                serde_json::json!({ "source": config.source.label(), "device": stream.name }),
+            );
+            continue;
+        }
+
+        let samples = match stream.samples.recv_timeout(STOP_POLL_INTERVAL) {
+            Ok(samples) => samples,
+            Err(_) => continue,
+        };
+        last_audio = Instant::now();
+        let mut resampled = stream.resampler.process(&samples);
+        collect_mix_inputs(&mut mix_streams, &mut mixer);
+        mixer.process(&config.mix, &mut resampled);
+        // The meter shows the raw input so a muted or too quiet mic stays visible
+        meter.process(&resampled, &stream, &app_handle);
+        // Loopback keeps feeding the echo reference even while disabled as a source
+        match capture_state.echo_canceller() {
+            Some(canceller) => {
+                let endpoint = echo.get_or_insert_with(|| AecEndpoint::new(canceller));
+                match config.source {
+                    CaptureSource::Loopback => endpoint.render(&resampled),
+                    CaptureSource::Microphone => resampled = endpoint.capture(&resampled),
+                }
+            }
+            None => echo = None,
+        }
+        let source_settings = capture_state.source_settings(config.source);
+        // Disabling a source ends the utterance in progress
+        if !source_settings.enabled {
+            if let Some(utterance) = segmenter.flush() {
+                queue_utterance(&utterances, utterance);
+            }
+            continue;
+        }
+        if let Ok(settings) = capture_state.noise_gate.lock() {
+            noise_gate.process(&settings, &mut resampled);
+        }
+        if let Ok(settings) = capture_state.agc.lock() {
+            agc.process(&settings, &mut resampled);
+        }
+        // Keep our own synthetic voice out of the microphone's transcription; after AGC,
+        // which would otherwise undo the ducking
+        if config.source == CaptureSource::Microphone {
+            match tts_state.mic_duck_gain() {
+                Some(gain) if gain <= 0.0 => {
+                    if let Some(utterance) = segmenter.flush() {
+                        queue_utterance(&utterances, utterance);
+                    }
+                    continue;
+                }
+                Some(gain) => {
+                    for sample in resampled.iter_mut() {
+                        *sample *= gain;
+                    }
+                }
+                None => {}
+            }
+        }
+        // Our own speech played on the device loopback records (spoken captions on the
+        // default output) would come back as captions of other players
+        if config.source == CaptureSource::Loopback && tts_state.is_playing_on(&stream.name) {
+            if let Some(utterance) = segmenter.flush() {
+                queue_utterance(&utterances, utterance);
+            }
+            continue;
+        }
+        if source_settings.volume != 1.0 {
+            for sample in resampled.iter_mut() {
+                *sample = (*sample * source_settings.volume).clamp(-1.0, 1.0);
+            }
+        }
+        // Push-to-talk only gates the user's own microphone
+        let (samples, released) = match config.source {
+            CaptureSource::Microphone => match gate.process(&push_to_talk, resampled) {
+                GateOutput::Pass(samples) => (samples, false),
+                GateOutput::Hold => continue,
+                GateOutput::Release(samples) => (samples, true),
+            },
+            CaptureSource::Loopback => (resampled, false),
+        };
+        if let Ok(vad) = whisper_state.vad.lock() {
+            segmenter.set_config(&vad);
+        }
+        let mut finished = segmenter.push(&samples);
+        // Releasing the key ends the utterance, send whatever was said
+        if released {
+            finished.extend(segmenter.flush());
+        }
+        for utterance in finished {
+            queue_utterance(&utterances, utterance);
+        }
+    }
+    // Don't lose the sentence that was in progress when capture was stopped
+    if let Some(utterance) = segmenter.flush() {
+        queue_utterance(&utterances, utterance);
+    }
+    println!("Stopped capturing from '{}'", stream.name);
+}
+
+// Transcribe captured utterances one at a time as they arrive
+async fn transcribe_utterances(
+    app_handle: tauri::AppHandle,
+    config: CaptureConfig,
+    mut utterances: async_mpsc::Receiver<Vec<f32>>,
+) {
+    while let Some(samples) = utterances.recv().await {
+        let options = TranscribeOptions {
+            source: Some(config.source.label().to_string()),
+            ..Default::default()
+        };
+        let result = transcribe_with_provider(
+            &app_handle,
+            &app_handle.state::<SttAppState>(),
+            &app_handle.state::<WhisperAppState>(),
+            SttAudio::Pcm(samples),
+            config.model.clone(),
+            &config.language,
+            options,
+        )
+        .await;
+
+        match result {
+            Ok(result) => {
+                let transcription_payload = serde_json::json!({
+                    "source": config.source.label(),
+                    "speaker": config.source.speaker(),
+                    "text": result.text,
+                    "result": result
+                });
+                let _ = app_handle.emit("capture-transcription", &transcription_payload);
+                // Other players' speech becomes local captions, off the transcription path
+                if config.source == CaptureSource::Loopback {
+                    tauri::async_runtime::spawn(caption_transcript(
+                        app_handle.clone(),
+                        result.text.clone(),
+                        result.language.clone(),
+                    ));
+                }
+            }
+            Err(e) => println!("ERROR: Captured audio transcription failed: {}", e),
+        }
+    }
+}
+
+fn stop_session(session: CaptureSession) {
+    session.stop.store(true, Ordering::SeqCst);
+    if session.thread.join().is_err() {
+        println!("Audio capture thread panicked");
+    }
+}
+
+fn start_session(
+    app_handle: &tauri::AppHandle,
+    config: CaptureConfig,
+) -> Result<CaptureSession, String> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let (utterance_tx, utterance_rx) = async_mpsc::channel(MAX_QUEUED_UTTERANCES);
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let thread = {
+        let app_handle = app_handle.clone();
+        let config = config.clone();
+        let stop = stop.clone();
+        thread::Builder::new()
+            .name(format!("audio-capture-{}", config.source.label()))
+            .spawn(move || capture_thread(app_handle, config, stop, utterance_tx, ready_tx))
+            .map_err(|e| format!("Failed to start capture thread: {}", e))?
+    };
+    // Surface device errors to the caller instead of failing silently in the thread
+    ready_rx
+        .recv()
+        .map_err(|_| "Audio capture thread exited unexpectedly".to_string())??;
+
+    tauri::async_runtime::spawn(transcribe_utterances(
+        app_handle.clone(),
+        config.clone(),
+        utterance_rx,
+    ));
+    let _ = app_handle.emit("audio-capture-started", &config);
+    Ok(CaptureSession {
+        config,
+        stop,
+        thread,
+    })
+}
+
+// Start capturing from the microphone (or loopback) and transcribing in the
+// background, replacing any capture of the same source already running. Results
+// arrive as `capture-transcription` events labelled with the source.
+#[tauri::command]
+pub fn audio_start_capture(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AudioCaptureState>,
+    mut config: CaptureConfig,
+) -> Result<(), String> {
+    config.validate()?;
+    if config.source == CaptureSource::Microphone && config.device.is_none() {
+        config.device = state
+            .device
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+            .clone();
+    }
+    if config.source == CaptureSource::Microphone && config.mix.inputs.is_empty() {
+        config.mix = state
+            .input_mix
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+            .clone();
+    }
+    let mut session = state
+        .session(config.source)
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    if let Some(previous) = session.take() {
+        stop_session(previous);
+    }
+    *session = Some(start_session(&app_handle, config)?);
+    Ok(())
+}
+
+// Stop one capture source, the microphone when none is given
+#[tauri::command]
+pub fn audio_stop_capture(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AudioCaptureState>,
+    source: Option<CaptureSource>,
+) -> Result<bool, String> {
+    let source = source.unwrap_or_default();
+    let session = state
+        .session(source)
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .take();
+    let Some(session) = session else {
+        return Ok(false);
+    };
+    stop_session(session);
+    let _ = app_handle.emit("audio-capture-stopped", source);
+    Ok(true)
+}
+
+// Settings of the running capture for a source (microphone by default), None when
+// not capturing
+#[tauri::command]
+pub fn audio_get_capture(
+    state: State<'_, AudioCaptureState>,
+    source: Option<CaptureSource>,
+) -> Result<Option<CaptureConfig>, String> {
+    Ok(state
+        .session(source.unwrap_or_default())
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .as_ref()
+        .map(|session| session.config.clone()))
+}
+
+// Input devices with their capabilities, so the VR headset mic can be told apart
+// from the desktop one
+#[tauri::command]
+pub async fn list_audio_devices() -> Result<Vec<AudioDevice>, String> {
+    // Enumeration can block on some drivers
+    tokio::task::spawn_blocking(|| {
+        let host = cpal::default_host();
+        let default_name = host.default_input_device().and_then(|d| d.name().ok());
+        let devices = host
+            .input_devices()
+            .map_err(|e| format!("Failed to list input devices: {}", e))?
+            .filter_map(|device| describe_device(&device, default_name.as_deref()))
+            .collect();
+        Ok(devices)
+    })
+    .await
+    .map_err(|e| format!("Device enumeration failed: {}", e))?
+}
+
+// Pick the capture device (None for the system default). A running capture moves
+// over to it right away.
+#[tauri::command]
+pub fn set_audio_device(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AudioCaptureState>,
+    id: Option<String>,
+) -> Result<(), String> {
+    if let Some(id) = &id {
+        find_input_device(Some(id))?;
+    }
+    println!(
+        "Audio input device set to {}",
+        id.as_deref().unwrap_or("default")
+    );
+    *state
+        .device
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = id.clone();
+    restart_microphone(&app_handle, &state, |config| config.device = id)
+}
+
+// Restart a running microphone capture with changed settings
+fn restart_microphone(
+    app_handle: &tauri::AppHandle,
+    state: &AudioCaptureState,
+    update: impl FnOnce(&mut CaptureConfig),
+) -> Result<(), String> {
+    let mut session = state
+        .session
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    let Some(previous) = session.take() else {
+        return Ok(());
+    };
+    let mut config = previous.config.clone();
+    stop_session(previous);
+    update(&mut config);
+    *session = Some(start_session(app_handle, config)?);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn audio_get_input_mix(state: State<'_, AudioCaptureState>) -> Result<InputMix, String> {
+    Ok(state
+        .input_mix
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+// Mix extra input devices into the microphone (e.g. a desk mic next to the VR
+// headset), each with its own gain. A running capture picks the mix up right away.
+#[tauri::command]
+pub fn audio_set_input_mix(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AudioCaptureState>,
+    mix: InputMix,
+) -> Result<(), String> {
+    mix.validate()?;
+    for input in &mix.inputs {
+        find_input_device(Some(&input.device))?;
+    }
+    println!(
+        "Input mix set to {} extra device(s), primary gain {:.2}",
+        mix.inputs.len(),
+        mix.primary_gain
+    );
+    *state
+        .input_mix
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = mix.clone();
+    restart_microphone(&app_handle, &state, |config| config.mix = mix)
+}
+
+#[tauri::command]
+pub fn audio_get_agc(state: State<'_, AudioCaptureState>) -> Result<AgcSettings, String> {
+    Ok(state
+        .agc
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+// Takes effect immediately, including on a running capture
+#[tauri::command]
+pub fn audio_set_agc(
+    state: State<'_, AudioCaptureState>,
+    settings: AgcSettings,
+) -> Result<(), String> {
+    settings.validate()?;
+    println!(
+        "AGC enabled: {} (target {}dB, max gain {}dB)",
+        settings.enabled, settings.target_db, settings.max_gain_db
+    );
+    *state
+        .agc
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = settings;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn audio_get_sources(
+    state: State<'_, AudioCaptureState>,
+) -> Result<HashMap<CaptureSource, SourceSettings>, String> {
+    Ok([CaptureSource::Microphone, CaptureSource::Loopback]
+        .into_iter()
+        .map(|source| (source, state.source_settings(source)))
+        .collect())
+}
+
+// Enable/disable or change the volume of one source, live
+#[tauri::command]
+pub fn audio_set_source(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AudioCaptureState>,
+    source: CaptureSource,
+    settings: SourceSettings,
+) -> Result<(), String> {
+    if !(0.0..=4.0).contains(&settings.volume) {
+        return Err("Source volume must be between 0 and 4".to_string());
+    }
+    println!(
+        "Capture source '{}' enabled: {}, volume {}",
+        source.label(),
+        settings.enabled,
+        settings.volume
+    );
+    state
+        .sources
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .insert(source, settings.clone());
+    let _ = app_handle.emit(
+        "audio-source-changed",
+        serde_json::json!({ "source": source, "settings": settings }),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn audio_get_noise_gate(
+    state: State<'_, AudioCaptureState>,
+) -> Result<NoiseGateSettings, String> {
+    Ok(state
+        .noise_gate
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+// Takes effect immediately, including on a running capture
+#[tauri::command]
+pub fn audio_set_noise_gate(
+    state: State<'_, AudioCaptureState>,
+    settings: NoiseGateSettings,
+) -> Result<(), String> {
+    settings.validate()?;
+    println!(
+        "Noise gate enabled: {} (threshold {}dB, hold {}ms)",
+        settings.enabled, settings.threshold_db, settings.hold_ms
+    );
+    *state
+        .noise_gate
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = settings;
+    Ok(())
+}
+
+// Record 16kHz audio from a microphone for a fixed time on the calling thread
+fn record_samples(
+    app_handle: &tauri::AppHandle,
+    device: Option<&str>,
+    duration: Duration,
+) -> Result<Vec<f32>, String> {
+    let mut stream = open_stream(CaptureSource::Microphone, device, app_handle)?;
+    let started = Instant::now();
+    let mut samples = Vec::new();
+    while started.elapsed() < duration {
+        if let Ok(buffer) = stream.samples.recv_timeout(STOP_POLL_INTERVAL) {
+            samples.extend(stream.resampler.process(&buffer));
+        }
+    }
+    Ok(samples)
+}
+
+// Measure the selected microphone's background noise for a few seconds while the
+// user stays quiet, and suggest a noise gate threshold just above it
+#[tauri::command]
+pub async fn audio_calibrate_noise_gate(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AudioCaptureState>,
+    duration_ms: Option<u32>,
+) -> Result<NoiseCalibration, String> {
+    let duration_ms = duration_ms
+        .unwrap_or(DEFAULT_CALIBRATION_MS)
+        .clamp(1000, 10_000);
+    let device = state
+        .device
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone();
+
+    println!("Calibrating noise gate for {}ms", duration_ms);
+    let samples = tokio::task::spawn_blocking(move || {
+        record_samples(
+            &app_handle,
+            device.as_deref(),
+            Duration::from_millis(duration_ms as u64),
+        )
+    })
+    .await
+    .map_err(|e| format!("Calibration task failed: {}", e))??;
+    if samples.is_empty() {
+        return Err("No audio received from the microphone".to_string());
+    }
+
+    let calibration = calibrate(&samples);
+    println!(
+        "Noise floor {:.1}dB (peak {:.1}dB), suggested gate threshold {:.1}dB",
+        calibration.noise_floor_db, calibration.peak_db, calibration.suggested_threshold_db
+    );
+    Ok(calibration)
+}
+
+#[tauri::command]
+pub fn audio_get_echo_cancellation(
+    state: State<'_, AudioCaptureState>,
+) -> Result<AecSettings, String> {
+    Ok(state
+        .echo_cancellation
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+// Cancel speaker echo from the microphone using the loopback capture as reference
+#[tauri::command]
+pub fn audio_set_echo_cancellation(
+    state: State<'_, AudioCaptureState>,
+    settings: AecSettings,
+) -> Result<(), String> {
+    settings.validate()?;
+    if settings.enabled {
+        let mut canceller = state
+            .echo_canceller
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        match canceller.as_mut() {
+            Some(canceller) => canceller.configure(settings.suppression),
+            None => *canceller = Some(EchoCanceller::new(settings.suppression)?),
+        }
+
+        let loopback_running = state
+            .loopback
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+            .is_some();
+        if !loopback_running {
+            println!("Echo cancellation has no reference until loopback capture is started");
+        }
+    }
+    println!(
+        "Echo cancellation enabled: {} ({:?} suppression)",
+        settings.enabled, settings.suppression
+    );
+    *state
+        .echo_cancellation
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = settings;
+    Ok(())
+}
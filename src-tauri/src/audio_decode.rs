@@ -0,0 +1,309 @@
+use hound::{SampleFormat, WavReader};
+use std::io::{Cursor, ErrorKind};
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{CodecParameters, DecoderOptions, CODEC_TYPE_NULL, CODEC_TYPE_OPUS};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+// Opus always decodes at 48kHz; 120ms is the longest frame a packet can hold
+const OPUS_SAMPLE_RATE: u32 = 48_000;
+const OPUS_MAX_FRAME_SAMPLES: usize = 5_760;
+
+// Average interleaved frames down to mono
+fn mix_down(interleaved: &[f32], channels: usize, out: &mut Vec<f32>) {
+    let channels = channels.max(1);
+    out.extend(
+        interleaved
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32),
+    );
+}
+
+fn next_packet(
+    format: &mut dyn FormatReader,
+) -> Result<Option<symphonia::core::formats::Packet>, String> {
+    match format.next_packet() {
+        Ok(packet) => Ok(Some(packet)),
+        Err(SymphoniaError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => Ok(None),
+        // Chained streams (e.g. a new Ogg logical stream) aren't needed for a single utterance
+        Err(SymphoniaError::ResetRequired) => Ok(None),
+        Err(e) => Err(format!("Failed to read audio packet: {}", e)),
+    }
+}
+
+fn decode_with_symphonia(
+    format: &mut dyn FormatReader,
+    track_id: u32,
+    codec_params: &CodecParameters,
+) -> Result<(Vec<f32>, u32), String> {
+    let mut decoder = symphonia::default::get_codecs()
+        .make(codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Unsupported audio codec: {}", e))?;
+
+    let mut samples = Vec::new();
+    let mut sample_rate = codec_params.sample_rate.unwrap_or(0);
+    while let Some(packet) = next_packet(format)? {
+        if packet.track_id() != track_id {
+            continue;
+        }
+        match decoder.decode(&packet) {
+            Ok(decoded) => {
+                let spec = *decoded.spec();
+                sample_rate = spec.rate;
+                let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+                buffer.copy_interleaved_ref(decoded);
+                mix_down(buffer.samples(), spec.channels.count(), &mut samples);
+            }
+            // A corrupt packet only loses a few ms of audio, keep going
+            Err(SymphoniaError::DecodeError(e)) => println!("Skipping corrupt audio packet: {}", e),
+            Err(e) => return Err(format!("Failed to decode audio: {}", e)),
+        }
+    }
+
+    Ok((samples, sample_rate))
+}
+
+// Channel count and pre-skip from the OpusHead header, when the container exposes it
+fn opus_header(codec_params: &CodecParameters) -> (usize, usize) {
+    let channels = codec_params.channels.map(|c| c.count());
+    match codec_params.extra_data.as_deref() {
+        Some(head) if head.len() >= 12 && head.starts_with(b"OpusHead") => (
+            channels.unwrap_or(head[9] as usize),
+            u16::from_le_bytes([head[10], head[11]]) as usize,
+        ),
+        _ => (channels.unwrap_or(1), 0),
+    }
+}
+
+fn decode_opus(
+    format: &mut dyn FormatReader,
+    track_id: u32,
+    codec_params: &CodecParameters,
+) -> Result<(Vec<f32>, u32), String> {
+    let (channels, pre_skip) = opus_header(codec_params);
+    let opus_channels = match channels {
+        1 => opus::Channels::Mono,
+        2 => opus::Channels::Stereo,
+        n => return Err(format!("Unsupported Opus channel count: {}", n)),
+    };
+    let mut decoder = opus::Decoder::new(OPUS_SAMPLE_RATE, opus_channels)
+        .map_err(|e| format!("Failed to create Opus decoder: {}", e))?;
+
+    let mut samples = Vec::new();
+    let mut frame = vec![0.0f32; OPUS_MAX_FRAME_SAMPLES * channels];
+    while let Some(packet) = next_packet(format)? {
+        if packet.track_id() != track_id {
+            continue;
+        }
+        match decoder.decode_float(&packet.data, &mut frame, false) {
+            Ok(decoded) => mix_down(&frame[..decoded * channels], channels, &mut samples),
+            Err(e) => println!("Skipping corrupt Opus packet: {}", e),
+        }
+    }
+
+    // The encoder's lookahead is padded onto the start of the stream
+    samples.drain(..pre_skip.min(samples.len()));
+    Ok((samples, OPUS_SAMPLE_RATE))
+}
+
+// Decode a compressed audio file (Ogg/Opus, Ogg/Vorbis, WebM, FLAC, MP3, ...) to mono
+// f32 samples, returned with their sample rate. `extension` is a hint for the prober.
+pub fn decode_audio_file(data: &[u8], extension: &str) -> Result<(Vec<f32>, u32), String> {
+    let source = MediaSourceStream::new(Box::new(Cursor::new(data.to_vec())), Default::default());
+    let mut hint = Hint::new();
+    hint.with_extension(extension);
+
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            source,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| format!("Unrecognized audio container: {}", e))?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| "No audio track found".to_string())?;
+    let track_id = track.id;
+    let codec_params = track.codec_params.clone();
+
+    let (samples, sample_rate) = if codec_params.codec == CODEC_TYPE_OPUS {
+        decode_opus(format.as_mut(), track_id, &codec_params)?
+    } else {
+        decode_with_symphonia(format.as_mut(), track_id, &codec_params)?
+    };
+    if sample_rate == 0 {
+        return Err("Audio stream has no sample rate".to_string());
+    }
+
+    println!(
+        "Decoded {} audio: {} samples at {}Hz",
+        extension,
+        samples.len(),
+        sample_rate
+    );
+    Ok((samples, sample_rate))
+}
+
+// Decode a WAV file (8/16/24/32-bit integer or 32/64-bit float PCM, any channel
+// count) to mono f32 samples, returned with their sample rate.
+pub fn decode_wav(data: &[u8]) -> Result<(Vec<f32>, u32), String> {
+    match WavReader::new(Cursor::new(data)) {
+        Ok(reader) => {
+            let (samples, sample_rate) = read_wav_samples(reader)?;
+            // A data length left at zero by a streaming writer reads as empty
+            if samples.is_empty() {
+                return decode_wav_chunks(data);
+            }
+            Ok((samples, sample_rate))
+        }
+        // Streaming writers leave sizes unset and some devices add odd chunks
+        Err(e) => {
+            println!(
+                "Strict WAV parsing failed ({}), scanning chunks manually",
+                e
+            );
+            decode_wav_chunks(data)
+        }
+    }
+}
+
+fn read_wav_samples<R: std::io::Read>(mut reader: WavReader<R>) -> Result<(Vec<f32>, u32), String> {
+    let spec = reader.spec();
+    println!(
+        "WAV format - Sample rate: {}, Channels: {}, Bits per sample: {}, Format: {:?}",
+        spec.sample_rate, spec.channels, spec.bits_per_sample, spec.sample_format
+    );
+
+    // Stop at the first unreadable sample, a truncated final frame is common
+    let interleaved: Vec<f32> = match (spec.sample_format, spec.bits_per_sample) {
+        (SampleFormat::Float, 32) => reader.samples::<f32>().map_while(Result::ok).collect(),
+        (SampleFormat::Int, bits @ 1..=32) => {
+            let scale = 1.0 / (1u64 << (bits - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map_while(Result::ok)
+                .map(|s| s as f32 * scale)
+                .collect()
+        }
+        (format, bits) => {
+            return Err(format!(
+                "Unsupported WAV sample format: {:?} {}-bit",
+                format, bits
+            ))
+        }
+    };
+
+    let mut samples = Vec::with_capacity(interleaved.len() / spec.channels.max(1) as usize);
+    mix_down(&interleaved, spec.channels as usize, &mut samples);
+    Ok((samples, spec.sample_rate))
+}
+
+// Format tags from the fmt chunk
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+// Walk the RIFF chunks by hand, tolerating unset or oversized chunk lengths
+fn decode_wav_chunks(data: &[u8]) -> Result<(Vec<f32>, u32), String> {
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return Err("Not a RIFF/WAVE file".to_string());
+    }
+
+    let mut format = None;
+    let mut offset = 12;
+    while offset + 8 <= data.len() {
+        let id = &data[offset..offset + 4];
+        let size = read_u32(data, offset + 4).unwrap_or(0) as usize;
+        let body_start = offset + 8;
+
+        if id == b"fmt " {
+            let fmt = &data[body_start..body_start.saturating_add(size).min(data.len())];
+            let mut tag = read_u16(fmt, 0).ok_or("Truncated WAV fmt chunk")?;
+            if tag == WAVE_FORMAT_EXTENSIBLE {
+                // The real format is the first two bytes of the SubFormat GUID
+                tag = read_u16(fmt, 24).ok_or("Truncated WAV fmt chunk")?;
+            }
+            let channels = read_u16(fmt, 2).ok_or("Truncated WAV fmt chunk")?;
+            let sample_rate = read_u32(fmt, 4).ok_or("Truncated WAV fmt chunk")?;
+            let bits = read_u16(fmt, 14).ok_or("Truncated WAV fmt chunk")?;
+            format = Some((tag, channels, sample_rate, bits));
+        } else if id == b"data" {
+            let (tag, channels, sample_rate, bits) =
+                format.ok_or("WAV data chunk appears before the fmt chunk")?;
+            // A zero or too-large size means the writer never patched the header
+            let end = match size {
+                0 => data.len(),
+                size => body_start.saturating_add(size).min(data.len()),
+            };
+            let interleaved = decode_wav_pcm(&data[body_start..end], tag, bits)?;
+
+            println!(
+                "WAV format (lenient) - Sample rate: {}, Channels: {}, Bits per sample: {}",
+                sample_rate, channels, bits
+            );
+            let mut samples = Vec::with_capacity(interleaved.len() / channels.max(1) as usize);
+            mix_down(&interleaved, channels as usize, &mut samples);
+            return Ok((samples, sample_rate));
+        }
+
+        // Chunks are padded to an even length
+        offset = body_start.saturating_add(size).saturating_add(size % 2);
+    }
+
+    Err("WAV file has no data chunk".to_string())
+}
+
+fn decode_wav_pcm(data: &[u8], tag: u16, bits: u16) -> Result<Vec<f32>, String> {
+    let samples = match (tag, bits) {
+        // 8-bit WAV is unsigned
+        (WAVE_FORMAT_PCM, 8) => data.iter().map(|&b| (b as f32 - 128.0) / 128.0).collect(),
+        (WAVE_FORMAT_PCM, 16) => data
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32_768.0)
+            .collect(),
+        (WAVE_FORMAT_PCM, 24) => data
+            .chunks_exact(3)
+            // Place the 3 bytes in the top of an i32 so the sign extends
+            .map(|b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.0)
+            .collect(),
+        (WAVE_FORMAT_PCM, 32) => data
+            .chunks_exact(4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0)
+            .collect(),
+        (WAVE_FORMAT_IEEE_FLOAT, 32) => data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        (WAVE_FORMAT_IEEE_FLOAT, 64) => data
+            .chunks_exact(8)
+            .map(|b| f64::from_le_bytes(b.try_into().unwrap_or([0; 8])) as f32)
+            .collect(),
+        (tag, bits) => {
+            return Err(format!(
+                "Unsupported WAV encoding: format {:#x}, {}-bit",
+                tag, bits
+            ))
+        }
+    };
+    Ok(samples)
+}
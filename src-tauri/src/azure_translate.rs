@@ -0,0 +1,188 @@
+// Microsoft Azure Translator (Translator Text API v3), for users whose organization
+// already has Azure credits. Multi-service and regional resources need the region
+// alongside the key; a custom category selects a Custom Translator model.
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::translate::{TranslateError, TranslationProvider, AUTO_LANGUAGE};
+
+const ENDPOINT: &str = "https://api.cognitive.microsofttranslator.com/translate";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Texts per request allowed by the API (the character cap is far above a transcript)
+const MAX_BATCH_TEXTS: usize = 100;
+// Monthly character allowance of the F0 pricing tier
+pub const AZURE_FREE_CHARACTERS: u64 = 2_000_000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AzureTranslatorConfig {
+    pub api_key: String,
+    // Resource region, e.g. "japaneast"; not needed for global resources
+    #[serde(default)]
+    pub region: Option<String>,
+    // Custom Translator category ID
+    #[serde(default)]
+    pub category: Option<String>,
+    // On the F0 tier, for usage warnings
+    #[serde(default)]
+    pub free_tier: bool,
+}
+
+impl AzureTranslatorConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.api_key.trim().is_empty() {
+            return Err("Azure Translator needs an API key".to_string());
+        }
+        Ok(())
+    }
+
+    fn region(&self) -> Option<&str> {
+        self.region
+            .as_deref()
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+    }
+
+    pub fn category(&self) -> Option<&str> {
+        self.category
+            .as_deref()
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+    }
+}
+
+#[derive(Serialize)]
+struct AzureText<'a> {
+    #[serde(rename = "Text")]
+    text: &'a str,
+}
+
+#[derive(Deserialize)]
+struct AzureResult {
+    translations: Vec<AzureTranslation>,
+}
+
+#[derive(Deserialize)]
+struct AzureTranslation {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct AzureErrorBody {
+    error: AzureError,
+}
+
+#[derive(Deserialize)]
+struct AzureError {
+    code: u64,
+    message: String,
+}
+
+// Azure uses script subtags for Chinese and bare codes otherwise
+fn language_code(code: &str) -> String {
+    let code = code.to_lowercase();
+    match code.as_str() {
+        "zh" | "zh-cn" | "zh-hans" => "zh-Hans".to_string(),
+        "zh-tw" | "zh-hk" | "zh-hant" => "zh-Hant".to_string(),
+        code => code.split('-').next().unwrap_or(code).to_string(),
+    }
+}
+
+fn map_request_error(e: reqwest::Error) -> TranslateError {
+    if e.is_timeout() {
+        TranslateError::Unavailable("Azure Translator timed out".to_string())
+    } else {
+        TranslateError::Unavailable(format!("Azure Translator request failed: {}", e))
+    }
+}
+
+// Azure reports an exhausted free quota as 403 with error code 403001
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, TranslateError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let (code, message) = match response.json::<AzureErrorBody>().await {
+        Ok(body) => (body.error.code, body.error.message),
+        Err(_) => (0, status.to_string()),
+    };
+    Err(match status.as_u16() {
+        401 => TranslateError::Config(format!("Azure rejected the API key: {}", message)),
+        403 if code == 403001 => TranslateError::RateLimited(message),
+        403 => TranslateError::Config(format!("Azure refused: {}", message)),
+        429 => TranslateError::RateLimited(message),
+        _ if status.is_server_error() => TranslateError::Unavailable(message),
+        _ => TranslateError::Failed(format!("Azure returned {}: {}", status, message)),
+    })
+}
+
+impl TranslationProvider for AzureTranslatorConfig {
+    fn name(&self) -> &'static str {
+        "azure"
+    }
+
+    async fn translate(
+        &self,
+        texts: &[String],
+        source: &str,
+        target: &str,
+    ) -> Result<Vec<String>, TranslateError> {
+        self.validate().map_err(TranslateError::Config)?;
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| TranslateError::Config(format!("Failed to create HTTP client: {}", e)))?;
+
+        let mut query = vec![
+            ("api-version", "3.0".to_string()),
+            ("to", language_code(target)),
+        ];
+        if source != AUTO_LANGUAGE {
+            query.push(("from", language_code(source)));
+        }
+        if let Some(category) = self.category() {
+            query.push(("category", category.to_string()));
+        }
+
+        let mut translated = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(MAX_BATCH_TEXTS) {
+            let body: Vec<AzureText> = batch
+                .iter()
+                .map(|text| AzureText {
+                    text: text.as_str(),
+                })
+                .collect();
+            let mut request = client
+                .post(ENDPOINT)
+                .query(&query)
+                .header("Ocp-Apim-Subscription-Key", self.api_key.trim())
+                .json(&body);
+            if let Some(region) = self.region() {
+                request = request.header("Ocp-Apim-Subscription-Region", region);
+            }
+            let response = request.send().await.map_err(map_request_error)?;
+            let results: Vec<AzureResult> =
+                check_status(response).await?.json().await.map_err(|e| {
+                    TranslateError::Failed(format!("Invalid Azure Translator response: {}", e))
+                })?;
+            if results.len() != batch.len() {
+                return Err(TranslateError::Failed(format!(
+                    "Azure returned {} translations for {} texts",
+                    results.len(),
+                    batch.len()
+                )));
+            }
+            for result in results {
+                let text = result
+                    .translations
+                    .into_iter()
+                    .next()
+                    .map(|t| t.text)
+                    .ok_or_else(|| {
+                        TranslateError::Failed("Azure returned an empty translation".to_string())
+                    })?;
+                translated.push(text);
+            }
+        }
+        Ok(translated)
+    }
+}
@@ -0,0 +1,279 @@
+use serde::Serialize;
+use std::time::Instant;
+use tauri::{Emitter, State};
+
+use crate::whisper::{
+    get_model_path, load_whisper_context, model_weights_file, prepare_audio,
+    run_inference_on_context, run_with_context, InferenceOptions, TranscribeOptions,
+    TranscriptionResult, TranscriptionSegment, WhisperAppState, WHISPER_SAMPLE_RATE,
+};
+
+// A short recording of real speech, so the decoder does the same work it would on a
+// user's utterance rather than stopping early on noise
+const BENCHMARK_CLIP: &[u8] = include_bytes!("../samples/benchmark.wav");
+
+#[derive(Serialize)]
+pub struct BenchmarkResult {
+    pub model: String,
+    pub backend: String,
+    pub load_ms: u64,
+    pub inference_ms: u64,
+    pub audio_ms: u64,
+    // Inference time divided by audio duration; below 1.0 keeps up with real time
+    pub real_time_factor: f32,
+    // Growth of the process's resident memory while the model was loaded
+    pub memory_bytes: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl BenchmarkResult {
+    fn failed(model: &str, error: String) -> Self {
+        Self {
+            model: model.to_string(),
+            backend: String::new(),
+            load_ms: 0,
+            inference_ms: 0,
+            audio_ms: 0,
+            real_time_factor: 0.0,
+            memory_bytes: None,
+            error: Some(error),
+        }
+    }
+}
+
+// One model's take on the audio passed to `whisper_compare`
+#[derive(Serialize)]
+pub struct ComparisonResult {
+    pub model: String,
+    pub transcript: Option<TranscriptionResult>,
+    // Time spent making the model resident, 0 when it was already loaded
+    pub load_ms: u64,
+    pub inference_ms: u64,
+    pub audio_ms: u64,
+    pub real_time_factor: f32,
+    // Duration-weighted mean token probability of the segments
+    pub confidence: Option<f32>,
+    // Duration-weighted mean token log-probability, Whisper's usual confidence gate
+    pub avg_logprob: Option<f32>,
+    pub error: Option<String>,
+}
+
+impl ComparisonResult {
+    fn failed(model: &str, audio_ms: u64, error: String) -> Self {
+        Self {
+            model: model.to_string(),
+            transcript: None,
+            load_ms: 0,
+            inference_ms: 0,
+            audio_ms,
+            real_time_factor: 0.0,
+            confidence: None,
+            avg_logprob: None,
+            error: Some(error),
+        }
+    }
+}
+
+// Average a per-segment score, weighting each segment by its duration
+fn weighted_mean(
+    segments: &[TranscriptionSegment],
+    score: fn(&TranscriptionSegment) -> f32,
+) -> Option<f32> {
+    let weight = |s: &TranscriptionSegment| (s.end_ms - s.start_ms).max(1) as f32;
+    let total: f32 = segments.iter().map(weight).sum();
+    (!segments.is_empty())
+        .then(|| segments.iter().map(|s| score(s) * weight(s)).sum::<f32>() / total)
+}
+
+fn resident_memory() -> Option<u64> {
+    memory_stats::memory_stats().map(|stats| stats.physical_mem as u64)
+}
+
+// Load each downloaded model from scratch and time it on a sample clip (or the
+// user's own recording), so users can pick the largest model their machine keeps up with.
+#[tauri::command]
+pub async fn whisper_benchmark(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    models: Vec<String>,
+    audio_data: Option<Vec<u8>>,
+) -> Result<Vec<BenchmarkResult>, String> {
+    println!("=== WHISPER BENCHMARK START ===");
+    let vad = state
+        .vad
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone();
+    let audio_data = audio_data.as_deref().unwrap_or(BENCHMARK_CLIP);
+    let samples = prepare_audio(audio_data, false, &vad)?
+        .ok_or_else(|| "No speech detected in benchmark audio".to_string())?
+        .samples;
+    let audio_ms = (samples.len() as u64 * 1000) / WHISPER_SAMPLE_RATE as u64;
+
+    let backend = *state
+        .backend
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    let decoding = state
+        .decoding
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone();
+
+    let mut results = Vec::with_capacity(models.len());
+    for (index, model) in models.iter().enumerate() {
+        let _ = app_handle.emit(
+            "benchmark-progress",
+            serde_json::json!({
+                "model": model,
+                "index": index,
+                "total": models.len()
+            }),
+        );
+
+        let model_file = match model_weights_file(model)
+            .and_then(|file| Ok(get_model_path(&app_handle, model)?.join(file)))
+        {
+            Ok(path) if path.exists() => path,
+            Ok(_) => {
+                results.push(BenchmarkResult::failed(
+                    model,
+                    "Model not downloaded".into(),
+                ));
+                continue;
+            }
+            Err(e) => {
+                results.push(BenchmarkResult::failed(model, e));
+                continue;
+            }
+        };
+
+        let model_name = model.clone();
+        let samples = samples.clone();
+        let mut options = InferenceOptions::new("en");
+        options.decoding = decoding.clone();
+        let manager = state.models.clone();
+
+        let result = tokio::task::spawn_blocking(move || {
+            // Keep regular model loads out of the way so memory readings stay meaningful
+            let _guard = manager.lock_loading()?;
+            let model_file = model_file
+                .to_str()
+                .ok_or_else(|| "Invalid model path".to_string())?;
+
+            let memory_before = resident_memory();
+            let load_started = Instant::now();
+            let (ctx, used_backend) = load_whisper_context(model_file, backend)?;
+            let load_ms = load_started.elapsed().as_millis() as u64;
+            let memory_after = resident_memory();
+
+            let inference_started = Instant::now();
+            run_inference_on_context(&ctx, &samples, options)?;
+            let inference_ms = inference_started.elapsed().as_millis() as u64;
+
+            Ok::<_, String>(BenchmarkResult {
+                model: model_name,
+                backend: used_backend.as_str().to_string(),
+                load_ms,
+                inference_ms,
+                audio_ms,
+                real_time_factor: inference_ms as f32 / audio_ms.max(1) as f32,
+                memory_bytes: memory_before
+                    .zip(memory_after)
+                    .map(|(before, after)| after.saturating_sub(before)),
+                error: None,
+            })
+        })
+        .await
+        .map_err(|e| format!("Benchmark task failed: {}", e))?;
+
+        match result {
+            Ok(result) => {
+                println!(
+                    "Benchmark {}: load {}ms, inference {}ms, RTF {:.2}",
+                    result.model, result.load_ms, result.inference_ms, result.real_time_factor
+                );
+                results.push(result);
+            }
+            Err(e) => results.push(BenchmarkResult::failed(model, e)),
+        }
+    }
+
+    Ok(results)
+}
+
+// Run the same recording through each model in turn (typically two, e.g. base and
+// small) and return every transcript with its latency and confidence, so users can
+// judge whether a larger model is worth the extra delay.
+#[tauri::command]
+pub async fn whisper_compare(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    audio_data: Vec<u8>,
+    models: Vec<String>,
+    language: String,
+    options: Option<TranscribeOptions>,
+) -> Result<Vec<ComparisonResult>, String> {
+    if models.len() < 2 {
+        return Err("Select at least two models to compare".to_string());
+    }
+    println!("=== WHISPER COMPARISON START ({}) ===", models.join(" vs "));
+
+    let options = options
+        .unwrap_or_default()
+        .into_inference_options(&state, &language)?;
+    let speech = prepare_audio(&audio_data, options.denoise, &options.vad)?
+        .ok_or_else(|| "No speech detected in comparison audio".to_string())?;
+    let audio_ms = (speech.samples.len() as u64 * 1000) / WHISPER_SAMPLE_RATE as u64;
+
+    let mut results = Vec::with_capacity(models.len());
+    for (index, model) in models.iter().enumerate() {
+        let _ = app_handle.emit(
+            "compare-progress",
+            serde_json::json!({
+                "model": model,
+                "index": index,
+                "total": models.len()
+            }),
+        );
+
+        let samples = speech.samples.clone();
+        let model_options = options.for_language(&options.language);
+        let started = Instant::now();
+        let result = run_with_context(app_handle.clone(), &state, model.clone(), move |ctx| {
+            let inference_started = Instant::now();
+            let transcript = run_inference_on_context(ctx, &samples, model_options)?;
+            Ok((transcript, inference_started.elapsed().as_millis() as u64))
+        })
+        .await;
+
+        let (transcript, inference_ms) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                println!("Comparison {} failed: {}", model, e);
+                results.push(ComparisonResult::failed(model, audio_ms, e));
+                continue;
+            }
+        };
+        let load_ms = (started.elapsed().as_millis() as u64).saturating_sub(inference_ms);
+        let confidence = weighted_mean(&transcript.segments, |s| s.probability);
+        let avg_logprob = weighted_mean(&transcript.segments, |s| s.avg_logprob);
+        println!(
+            "Comparison {}: inference {}ms, confidence {:?}: \"{}\"",
+            model, inference_ms, confidence, transcript.text
+        );
+        results.push(ComparisonResult {
+            model: model.clone(),
+            transcript: Some(transcript),
+            load_ms,
+            inference_ms,
+            audio_ms,
+            real_time_factor: inference_ms as f32 / audio_ms.max(1) as f32,
+            confidence,
+            avg_logprob,
+            error: None,
+        });
+    }
+
+    Ok(results)
+}
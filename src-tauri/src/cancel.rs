@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+// Shared set of cancellation flags for long-running work (downloads, transcription
+// jobs), keyed by an ID the frontend can use to abort it.
+#[derive(Clone, Default)]
+pub struct CancelRegistry {
+    flags: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+}
+
+impl CancelRegistry {
+    pub fn register(&self, key: &str) -> Result<CancelRegistration, String> {
+        let mut guard = self
+            .flags
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        if guard.contains_key(key) {
+            return Err(format!("{} is already in progress", key));
+        }
+
+        let flag = Arc::new(AtomicBool::new(false));
+        guard.insert(key.to_string(), flag.clone());
+        Ok(CancelRegistration {
+            registry: self.clone(),
+            key: key.to_string(),
+            flag,
+        })
+    }
+
+    // Returns false if nothing with this key is running
+    pub fn cancel(&self, key: &str) -> Result<bool, String> {
+        let guard = self
+            .flags
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        match guard.get(key) {
+            Some(flag) => {
+                flag.store(true, Ordering::SeqCst);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn active(&self) -> Vec<String> {
+        self.flags
+            .lock()
+            .map(|guard| guard.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+// Removes its flag from the registry when dropped, so every exit path of the
+// owning operation cleans up after itself.
+pub struct CancelRegistration {
+    registry: CancelRegistry,
+    key: String,
+    flag: Arc<AtomicBool>,
+}
+
+impl CancelRegistration {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn flag(&self) -> Arc<AtomicBool> {
+        self.flag.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for CancelRegistration {
+    fn drop(&mut self) {
+        if let Ok(mut guard) = self.registry.flags.lock() {
+            guard.remove(&self.key);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+// Live captions of other players: loopback transcripts are translated into the
+// user's language and shown locally as `caption` events, never sent to the chatbox.
+// Optionally the translation is also spoken into the user's headphones, as a live
+// interpreter.
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, State};
+
+use crate::translate::{translate_transcript, TranslateAppState};
+use crate::tts::{speak, TtsAppState, TtsPriority, TtsRoute};
+
+// Captions kept for `captions_history`
+const MAX_HISTORY: usize = 100;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptionSettings {
+    pub enabled: bool,
+    // Language captions are translated into
+    pub target: String,
+    // Speak translated captions on the TTS headphones route
+    pub speak: bool,
+}
+
+impl Default for CaptionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target: "en".to_string(),
+            speak: false,
+        }
+    }
+}
+
+impl CaptionSettings {
+    fn validate(&self) -> Result<(), String> {
+        if self.enabled && self.target.trim().is_empty() {
+            return Err("Captions need a target language".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Caption {
+    pub text: String,
+    // Same as `text` when it was already in the target language or translation failed
+    pub translation: String,
+    pub language: String,
+    // Provider that translated it, empty when nothing was translated
+    pub provider: String,
+    pub timestamp_ms: i64,
+}
+
+#[derive(Default)]
+pub struct CaptionsAppState {
+    settings: Mutex<CaptionSettings>,
+    history: Mutex<VecDeque<Caption>>,
+}
+
+// Translate a loopback transcript and publish it as a caption, if captions are on
+pub async fn caption_transcript(app_handle: tauri::AppHandle, text: String, language: String) {
+    let text = text.trim().to_string();
+    let state = app_handle.state::<CaptionsAppState>();
+    let settings = match state.settings.lock() {
+        Ok(settings) if settings.enabled && !text.is_empty() => settings.clone(),
+        _ => return,
+    };
+
+    let translate_state = app_handle.state::<TranslateAppState>();
+    let (translation, provider) = match translate_transcript(
+        &app_handle,
+        &translate_state,
+        text.clone(),
+        &language,
+        &settings.target,
+        None,
+    )
+    .await
+    {
+        Ok(translation) => (translation.text, translation.provider),
+        Err(e) => {
+            println!("Caption translation failed: {}", e);
+            (text.clone(), String::new())
+        }
+    };
+
+    let caption = Caption {
+        text,
+        translation,
+        language,
+        provider,
+        timestamp_ms: chrono::Utc::now().timestamp_millis(),
+    };
+    if let Ok(mut history) = state.history.lock() {
+        if history.len() >= MAX_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(caption.clone());
+    }
+    let _ = app_handle.emit("caption", &caption);
+
+    // Only what was actually translated; speech already in the user's language was
+    // heard in the first place
+    if settings.speak && !caption.provider.is_empty() {
+        let tts_state = app_handle.state::<TtsAppState>();
+        if let Err(e) = speak(
+            &app_handle,
+            &tts_state,
+            &caption.translation,
+            TtsRoute::Headphones,
+            TtsPriority::Normal,
+        ) {
+            println!("Speaking caption failed: {}", e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn captions_get_settings(
+    state: State<'_, CaptionsAppState>,
+) -> Result<CaptionSettings, String> {
+    Ok(state
+        .settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+#[tauri::command]
+pub fn captions_set_settings(
+    state: State<'_, CaptionsAppState>,
+    settings: CaptionSettings,
+) -> Result<(), String> {
+    settings.validate()?;
+    println!(
+        "Captions {} (into {}, spoken: {})",
+        if settings.enabled {
+            "enabled"
+        } else {
+            "disabled"
+        },
+        settings.target,
+        settings.speak
+    );
+    *state
+        .settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = settings;
+    Ok(())
+}
+
+// Recent captions, oldest first
+#[tauri::command]
+pub fn captions_history(state: State<'_, CaptionsAppState>) -> Result<Vec<Caption>, String> {
+    Ok(state
+        .history
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .iter()
+        .cloned()
+        .collect())
+}
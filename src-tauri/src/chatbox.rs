@@ -0,0 +1,199 @@
+use rosc::encoder;
+use rosc::{OscMessage, OscPacket, OscType};
+use std::net::{Ipv4Addr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::chatbox_format::ChatboxFormat;
+
+// VRChat truncates chatbox input past this many characters
+pub const CHATBOX_MAX_CHARS: usize = 144;
+
+// VRChat drops chatbox messages that arrive faster than this
+const MIN_SEND_INTERVAL: Duration = Duration::from_millis(1500);
+
+#[derive(Default)]
+pub struct ChatboxAppState {
+    pub preview: AtomicBool,
+    pub last_send: Arc<Mutex<Option<Instant>>>,
+    pub format: Mutex<ChatboxFormat>,
+}
+
+// Split a message into chatbox-sized pages, preferring to break on whitespace. Messages
+// that fit are sent as they are; longer ones keep their line breaks and spacing except
+// at the page breaks.
+pub fn split_message(msg: &str, max_chars: usize) -> Vec<String> {
+    let max_chars = max_chars.max(1);
+    let chars: Vec<char> = msg.chars().collect();
+    // Also covers the empty message, which still clears the chatbox
+    if chars.len() <= max_chars {
+        return vec![msg.to_string()];
+    }
+
+    let mut pages = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let end = start + max_chars;
+        let cut = if end >= chars.len() {
+            chars.len()
+        } else {
+            // Break at the last whitespace on the page, hard-splitting words that can never
+            // fit (e.g. CJK text without spaces)
+            (start + 1..=end)
+                .rev()
+                .find(|&i| chars[i].is_whitespace())
+                .unwrap_or(end)
+        };
+        let page: String = chars[start..cut].iter().collect();
+        pages.push(page.trim_end().to_string());
+        start = cut;
+        while chars.get(start).is_some_and(|c| c.is_whitespace()) {
+            start += 1;
+        }
+    }
+    pages
+}
+
+fn send_chatbox_osc(msg: &str, address: &str, port: &str) -> Result<(), String> {
+    let sock = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .map_err(|e| format!("Failed to bind socket: {}", e))?;
+
+    let msg_buf = encoder::encode(&OscPacket::Message(OscMessage {
+        addr: "/chatbox/input".to_string(),
+        args: vec![OscType::String(msg.to_string()), OscType::Bool(true)],
+    }))
+    .map_err(|e| format!("Failed to encode OSC message: {}", e))?;
+
+    let target = format!("{}:{}", address, port);
+    sock.send_to(&msg_buf, &target)
+        .map_err(|e| format!("Failed to send OSC message: {}", e))?;
+
+    Ok(())
+}
+
+// Wait until enough time has passed since the previous chatbox message
+async fn wait_for_rate_limit(last_send: &Arc<Mutex<Option<Instant>>>) -> Result<u64, String> {
+    let wait = {
+        let guard = last_send
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        guard
+            .map(|t| MIN_SEND_INTERVAL.saturating_sub(t.elapsed()))
+            .unwrap_or(Duration::ZERO)
+    };
+
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+
+    let mut guard = last_send
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    *guard = Some(Instant::now());
+
+    Ok(wait.as_millis() as u64)
+}
+
+#[tauri::command]
+pub fn set_chatbox_preview(state: State<'_, ChatboxAppState>, enabled: bool) -> Result<(), String> {
+    state.preview.store(enabled, Ordering::SeqCst);
+    println!(
+        "Chatbox preview mode {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_chatbox_preview(state: State<'_, ChatboxAppState>) -> Result<bool, String> {
+    Ok(state.preview.load(Ordering::SeqCst))
+}
+
+#[tauri::command]
+pub async fn send_message(
+    app: AppHandle,
+    state: State<'_, ChatboxAppState>,
+    msg: String,
+    address: String,
+    port: String,
+) -> Result<(), String> {
+    let pages = split_message(&msg, CHATBOX_MAX_CHARS);
+    let preview = state.preview.load(Ordering::SeqCst);
+    let last_send = state.last_send.clone();
+
+    for (i, page) in pages.iter().enumerate() {
+        let waited_ms = wait_for_rate_limit(&last_send).await?;
+
+        if preview {
+            // Dry run: report exactly what would have been sent instead of hitting the network
+            let preview_payload = serde_json::json!({
+                "text": page,
+                "page": i + 1,
+                "total_pages": pages.len(),
+                "address": address,
+                "port": port,
+                "waited_ms": waited_ms
+            });
+            let _ = app.emit("chatbox-preview", &preview_payload);
+        } else {
+            send_chatbox_osc(page, &address, &port)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_message_is_one_page() {
+        assert_eq!(
+            split_message("hello world", CHATBOX_MAX_CHARS),
+            vec!["hello world"]
+        );
+    }
+
+    #[test]
+    fn empty_message_still_clears_the_chatbox() {
+        assert_eq!(split_message("", CHATBOX_MAX_CHARS), vec![""]);
+    }
+
+    #[test]
+    fn pages_break_on_whitespace() {
+        assert_eq!(split_message("aaa bbb ccc", 7), vec!["aaa bbb", "ccc"]);
+    }
+
+    #[test]
+    fn words_longer_than_a_page_are_hard_split() {
+        assert_eq!(split_message("あいうえお", 2), vec!["あい", "うえ", "お"]);
+    }
+
+    #[test]
+    fn short_messages_keep_their_whitespace() {
+        let message = "first line\nsecond  line\n";
+        assert_eq!(split_message(message, CHATBOX_MAX_CHARS), vec![message]);
+    }
+
+    #[test]
+    fn multi_line_messages_break_on_line_breaks() {
+        assert_eq!(
+            split_message("aaa\nbbb\nccc  ddd", 9),
+            vec!["aaa\nbbb", "ccc  ddd"]
+        );
+    }
+
+    #[test]
+    fn pages_fit_the_chatbox() {
+        let message = "the quick brown fox jumps over the lazy dog ".repeat(20);
+        let pages = split_message(&message, CHATBOX_MAX_CHARS);
+        assert!(pages.len() > 1);
+        assert!(pages
+            .iter()
+            .all(|page| page.chars().count() <= CHATBOX_MAX_CHARS));
+        assert_eq!(pages.join(" "), message.trim());
+    }
+}
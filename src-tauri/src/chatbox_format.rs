@@ -0,0 +1,219 @@
+// Builds the chatbox line from the original text and its translations, so the
+// layout (order, separator, language tags, what to do past the chatbox limit) is
+// decided in one place before the message reaches `send_message`.
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::chatbox::{ChatboxAppState, CHATBOX_MAX_CHARS};
+use crate::transliterate::Transliteration;
+
+const ELLIPSIS: char = '…';
+const MAX_SEPARATOR_LEN: usize = 16;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatboxOrder {
+    OriginalFirst,
+    #[default]
+    TranslationFirst,
+    TranslationOnly,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatboxOverflow {
+    // Send everything, split over several chatbox pages
+    #[default]
+    Paginate,
+    // Shorten the original text (dropping it if need be) so the translations fit one page
+    ShortenOriginal,
+    // Cut the whole message at the chatbox limit
+    Truncate,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatboxFormat {
+    pub order: ChatboxOrder,
+    pub separator: String,
+    // "{text}" and "{lang}" are replaced with the text and its language tag
+    pub original_template: String,
+    pub translation_template: String,
+    pub overflow: ChatboxOverflow,
+    // Romanization of the original and translated text
+    pub transliteration: Transliteration,
+}
+
+impl Default for ChatboxFormat {
+    fn default() -> Self {
+        Self {
+            order: ChatboxOrder::default(),
+            separator: " | ".to_string(),
+            original_template: "{text} [{lang}]".to_string(),
+            translation_template: "[{lang}] {text}".to_string(),
+            overflow: ChatboxOverflow::default(),
+            transliteration: Transliteration::default(),
+        }
+    }
+}
+
+impl ChatboxFormat {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.separator.chars().count() > MAX_SEPARATOR_LEN {
+            return Err(format!(
+                "Separator must be at most {} characters",
+                MAX_SEPARATOR_LEN
+            ));
+        }
+        self.transliteration.validate()?;
+        for template in [&self.original_template, &self.translation_template] {
+            if !template.contains("{text}") {
+                return Err(format!("Template '{}' is missing {{text}}", template));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChatboxTranslation {
+    pub text: String,
+    pub language: String,
+}
+
+// Short uppercase tag for a language code ("ja" is shown as the more familiar "JP")
+fn language_tag(code: &str) -> String {
+    let base = code.split('-').next().unwrap_or(code).to_lowercase();
+    if base == "ja" {
+        return "JP".to_string();
+    }
+    base.to_uppercase()
+}
+
+fn apply_template(template: &str, text: &str, language: &str) -> String {
+    template
+        .replace("{lang}", &language_tag(language))
+        .replace("{text}", text)
+        .trim()
+        .to_string()
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    truncated = truncated.trim_end().to_string();
+    truncated.push(ELLIPSIS);
+    truncated
+}
+
+fn join(format: &ChatboxFormat, original: Option<String>, translations: &[String]) -> String {
+    let mut segments: Vec<String> = translations.to_vec();
+    match (format.order, original) {
+        (ChatboxOrder::OriginalFirst, Some(original)) => segments.insert(0, original),
+        (ChatboxOrder::TranslationFirst, Some(original)) => segments.push(original),
+        _ => {}
+    }
+    segments
+        .into_iter()
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join(&format.separator)
+}
+
+pub fn format_message(
+    format: &ChatboxFormat,
+    original: &str,
+    source: &str,
+    translations: &[ChatboxTranslation],
+) -> String {
+    let original = format.transliteration.apply(original.trim(), source);
+    let original = original.as_str();
+    let translated: Vec<String> = translations
+        .iter()
+        .filter(|t| !t.text.trim().is_empty())
+        .map(|t| {
+            let text = format.transliteration.apply(t.text.trim(), &t.language);
+            apply_template(&format.translation_template, &text, &t.language)
+        })
+        .collect();
+    // Nothing to pair the original with, send it as is
+    if translated.is_empty() {
+        return original.to_string();
+    }
+    let with_original = |text: &str| {
+        (format.order != ChatboxOrder::TranslationOnly && !text.is_empty())
+            .then(|| apply_template(&format.original_template, text, source))
+    };
+
+    let message = join(format, with_original(original), &translated);
+    if message.chars().count() <= CHATBOX_MAX_CHARS {
+        return message;
+    }
+    match format.overflow {
+        ChatboxOverflow::Paginate => message,
+        ChatboxOverflow::Truncate => truncate(&message, CHATBOX_MAX_CHARS),
+        ChatboxOverflow::ShortenOriginal => {
+            // Room left for the original once the translations and its template are in
+            let without_original = join(format, with_original(""), &translated);
+            let decoration = apply_template(&format.original_template, "", source)
+                .chars()
+                .count()
+                + format.separator.chars().count();
+            let room =
+                CHATBOX_MAX_CHARS.saturating_sub(without_original.chars().count() + decoration + 1);
+            let message = if room >= 8 {
+                join(
+                    format,
+                    with_original(&truncate(original, room)),
+                    &translated,
+                )
+            } else {
+                without_original
+            };
+            truncate(&message, CHATBOX_MAX_CHARS)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_chatbox_format(state: State<'_, ChatboxAppState>) -> Result<ChatboxFormat, String> {
+    state
+        .format
+        .lock()
+        .map(|format| format.clone())
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))
+}
+
+#[tauri::command]
+pub fn set_chatbox_format(
+    state: State<'_, ChatboxAppState>,
+    format: ChatboxFormat,
+) -> Result<(), String> {
+    format.validate()?;
+    println!(
+        "Chatbox format: {:?}, separator '{}', overflow {:?}",
+        format.order, format.separator, format.overflow
+    );
+    *state
+        .format
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = format;
+    Ok(())
+}
+
+// Combine the original text and its translations into the message to send
+#[tauri::command]
+pub fn format_chatbox_message(
+    state: State<'_, ChatboxAppState>,
+    original: String,
+    source: String,
+    translations: Vec<ChatboxTranslation>,
+) -> Result<String, String> {
+    let format = state
+        .format
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    Ok(format_message(&format, &original, &source, &translations))
+}
@@ -0,0 +1,414 @@
+// App settings owned by the backend: one typed document saved to the app data directory
+// with a schema version, upgraded step by step by the migrations below when an older
+// file is loaded. The fields the backend uses are typed; everything else the frontend
+// stores rides along untouched in `extra`. Changes go out as `settings-changed` events.
+// The first run imports the frontend's old config.json. Named profiles of part of the
+// settings live in the same document (see `settings_profiles`).
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, State};
+
+use crate::chatbox_format::ChatboxFormat;
+use crate::settings_profiles::{ProfileSettings, SettingsProfile, DEFAULT_PROFILE_NAME};
+
+const SETTINGS_FILE: &str = "settings.json";
+// Written to the app config directory by the frontend before settings moved here
+const LEGACY_CONFIG_FILE: &str = "config.json";
+pub const SETTINGS_VERSION: u32 = 2;
+
+// MIGRATIONS[n] upgrades a version n document to version n + 1
+const MIGRATIONS: [fn(&mut Map<String, Value>); SETTINGS_VERSION as usize] =
+    [migrate_v0_to_v1, migrate_v1_to_v2];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VrchatSettings {
+    pub translation_first: bool,
+    pub only_translation: bool,
+    pub disable_when_muted: bool,
+    pub send_typing_status_while_talking: bool,
+    // Milliseconds between chatbox updates
+    pub chatbox_update_speed: u64,
+    pub osc_address: String,
+    pub osc_port: u16,
+}
+
+impl Default for VrchatSettings {
+    fn default() -> Self {
+        Self {
+            translation_first: true,
+            only_translation: false,
+            disable_when_muted: false,
+            send_typing_status_while_talking: true,
+            chatbox_update_speed: 1000,
+            osc_address: "127.0.0.1".to_string(),
+            osc_port: 9000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub source_language: String,
+    pub target_language: String,
+    pub secondary_target_language: Option<String>,
+    // 0 translates, 1 only transcribes
+    pub mode: u8,
+    // "webspeech" or "whisper"
+    pub recognizer: String,
+    pub whisper_model: String,
+    pub translator: String,
+    pub chatbox_format: ChatboxFormat,
+    pub vrchat_settings: VrchatSettings,
+    pub profiles: Vec<SettingsProfile>,
+    // Name of the profile edits are saved into
+    pub active_profile: String,
+    // Everything else the frontend saves, kept as it is
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let mut settings = Self {
+            version: SETTINGS_VERSION,
+            source_language: "en-US".to_string(),
+            target_language: "ja".to_string(),
+            secondary_target_language: None,
+            mode: 0,
+            recognizer: "webspeech".to_string(),
+            whisper_model: "base".to_string(),
+            translator: "groq".to_string(),
+            chatbox_format: ChatboxFormat::default(),
+            vrchat_settings: VrchatSettings::default(),
+            profiles: Vec::new(),
+            active_profile: DEFAULT_PROFILE_NAME.to_string(),
+            extra: Map::new(),
+        };
+        settings.profiles.push(SettingsProfile {
+            name: DEFAULT_PROFILE_NAME.to_string(),
+            settings: ProfileSettings::from(&settings),
+        });
+        settings
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.source_language.trim().is_empty() || self.target_language.trim().is_empty() {
+            return Err("Settings need a source and target language".to_string());
+        }
+        if self.mode > 1 {
+            return Err(format!("Unknown mode {}", self.mode));
+        }
+        if self.whisper_model.trim().is_empty() {
+            return Err("Settings need a Whisper model".to_string());
+        }
+        if self.vrchat_settings.osc_address.trim().is_empty() {
+            return Err("Settings need an OSC address".to_string());
+        }
+        if self.vrchat_settings.osc_port == 0 {
+            return Err("Invalid OSC port 0".to_string());
+        }
+        self.chatbox_format.validate()?;
+        self.validate_profiles()
+    }
+}
+
+#[derive(Default)]
+pub struct SettingsState {
+    settings: Mutex<Settings>,
+}
+
+impl SettingsState {
+    // Change the settings under one lock, so concurrent edits can't undo each other, then
+    // save them (into the active profile too) and announce the change. Nothing is kept
+    // if `change` or validation fails.
+    pub fn modify(
+        &self,
+        app_handle: &tauri::AppHandle,
+        change: impl FnOnce(&mut Settings) -> Result<(), String>,
+    ) -> Result<Settings, String> {
+        let mut current = self
+            .settings
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        let mut settings = current.clone();
+        change(&mut settings)?;
+        settings.version = SETTINGS_VERSION;
+        settings.sync_active_profile();
+        settings.validate()?;
+        save_settings(app_handle, &settings)?;
+        *current = settings.clone();
+        drop(current);
+        let _ = app_handle.emit("settings-changed", &settings);
+        Ok(settings)
+    }
+}
+
+// The frontend's config.json: `selected_microphone` was replaced by
+// `audio_input_device`, and old files stored the OSC port as a string
+fn migrate_v0_to_v1(document: &mut Map<String, Value>) {
+    document.remove("selected_microphone");
+    if let Some(Value::Object(vrchat)) = document.get_mut("vrchat_settings") {
+        let port = vrchat
+            .get("osc_port")
+            .and_then(Value::as_str)
+            .and_then(|port| port.trim().parse::<u16>().ok());
+        if let Some(port) = port {
+            vrchat.insert("osc_port".to_string(), Value::from(port));
+        }
+    }
+}
+
+// Profiles arrived: what was set up so far becomes the active "Default" profile
+fn migrate_v1_to_v2(document: &mut Map<String, Value>) {
+    let mut profile = Map::new();
+    profile.insert("name".to_string(), Value::from(DEFAULT_PROFILE_NAME));
+    for key in [
+        "source_language",
+        "target_language",
+        "secondary_target_language",
+        "recognizer",
+        "whisper_model",
+        "translator",
+        "chatbox_format",
+        "vrchat_settings",
+    ] {
+        if let Some(value) = document.get(key) {
+            profile.insert(key.to_string(), value.clone());
+        }
+    }
+    document.insert(
+        "profiles".to_string(),
+        Value::Array(vec![Value::Object(profile)]),
+    );
+    document.insert(
+        "active_profile".to_string(),
+        Value::from(DEFAULT_PROFILE_NAME),
+    );
+}
+
+// Bring a saved document up to SETTINGS_VERSION. Files without a version predate it.
+fn migrate(mut document: Map<String, Value>) -> Result<Settings, String> {
+    let version = document.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+    if version > SETTINGS_VERSION {
+        return Err(format!(
+            "Settings are from a newer VRCTalk (version {}, this one reads up to {})",
+            version, SETTINGS_VERSION
+        ));
+    }
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(&mut document);
+        println!("Migrated settings from version {} to {}", from, from + 1);
+    }
+    document.insert("version".to_string(), Value::from(SETTINGS_VERSION));
+    serde_json::from_value(Value::Object(document)).map_err(|e| format!("Invalid settings: {}", e))
+}
+
+// Apply `patch` on top of `target`: objects are merged key by key, anything else replaced
+fn merge(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+fn settings_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join(SETTINGS_FILE))
+}
+
+fn legacy_config_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_config = app_handle
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to get app config directory: {}", e))?;
+    Ok(app_config.join(LEGACY_CONFIG_FILE))
+}
+
+fn save_settings(app_handle: &tauri::AppHandle, settings: &Settings) -> Result<(), String> {
+    let path = settings_path(app_handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let data = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    // Written next to the real file first so a crash can't leave it half written
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, data).map_err(|e| format!("Failed to save settings: {}", e))?;
+    fs::rename(&temp, &path).map_err(|e| format!("Failed to save settings: {}", e))
+}
+
+// Read and upgrade the settings file, or the frontend's config.json on the first run
+fn read_settings(app_handle: &tauri::AppHandle) -> Result<Option<Settings>, String> {
+    let path = settings_path(app_handle)?;
+    let (path, data) = match fs::read_to_string(&path) {
+        Ok(data) => (path, data),
+        Err(_) => {
+            let legacy = legacy_config_path(app_handle)?;
+            match fs::read_to_string(&legacy) {
+                Ok(data) => {
+                    println!("Importing settings from {}", legacy.display());
+                    (legacy, data)
+                }
+                Err(_) => return Ok(None),
+            }
+        }
+    };
+    let document = match serde_json::from_str::<Value>(&data) {
+        Ok(Value::Object(document)) => document,
+        Ok(_) => return Err(format!("{} isn't a JSON object", path.display())),
+        Err(e) => return Err(format!("Invalid {}: {}", path.display(), e)),
+    };
+    let migrated = document.get("version").and_then(Value::as_u64) != Some(SETTINGS_VERSION as u64);
+    let settings = migrate(document)?;
+    if migrated {
+        save_settings(app_handle, &settings)?;
+    }
+    Ok(Some(settings))
+}
+
+// Restore the saved settings at startup; without any the defaults apply
+pub fn load_settings(app_handle: &tauri::AppHandle) {
+    let settings = match read_settings(app_handle) {
+        Ok(Some(settings)) => settings,
+        Ok(None) => return,
+        Err(e) => {
+            println!("Ignoring saved settings: {}", e);
+            return;
+        }
+    };
+    println!("Loaded settings (version {})", settings.version);
+    let state = app_handle.state::<SettingsState>();
+    if let Ok(mut current) = state.settings.lock() {
+        *current = settings;
+    }
+}
+
+pub fn current_settings(state: &SettingsState) -> Result<Settings, String> {
+    Ok(state
+        .settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+#[tauri::command]
+pub fn get_settings(state: State<'_, SettingsState>) -> Result<Settings, String> {
+    current_settings(&state)
+}
+
+// Merge `patch` (any subset of the settings, nested objects included) into the current
+// settings, returning the result
+#[tauri::command]
+pub fn update_settings(
+    app_handle: tauri::AppHandle,
+    state: State<'_, SettingsState>,
+    patch: Value,
+) -> Result<Settings, String> {
+    if !patch.is_object() {
+        return Err("Settings updates must be a JSON object".to_string());
+    }
+    state.modify(&app_handle, |settings| {
+        let mut document = serde_json::to_value(&*settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        merge(&mut document, patch);
+        *settings =
+            serde_json::from_value(document).map_err(|e| format!("Invalid settings: {}", e))?;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(document) => document,
+            _ => unreachable!(),
+        }
+    }
+
+    // A config.json as the frontend saved it before settings moved to the backend
+    fn legacy_config() -> Map<String, Value> {
+        document(json!({
+            "source_language": "ja-JP",
+            "target_language": "en",
+            "mode": 0,
+            "selected_microphone": "default",
+            "recognizer": "whisper",
+            "whisper_model": "small",
+            "translator": "google",
+            "theme": "dark",
+            "vrchat_settings": {
+                "translation_first": false,
+                "osc_address": "127.0.0.1",
+                "osc_port": " 9001 ",
+            },
+        }))
+    }
+
+    #[test]
+    fn legacy_config_migrates_to_the_current_version() {
+        let settings = migrate(legacy_config()).unwrap();
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.source_language, "ja-JP");
+        assert_eq!(settings.whisper_model, "small");
+        assert!(!settings.vrchat_settings.translation_first);
+        // Unknown frontend fields ride along, removed ones don't
+        assert_eq!(settings.extra.get("theme"), Some(&json!("dark")));
+        assert!(!settings.extra.contains_key("selected_microphone"));
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn string_osc_port_becomes_a_number() {
+        let settings = migrate(legacy_config()).unwrap();
+        assert_eq!(settings.vrchat_settings.osc_port, 9001);
+    }
+
+    #[test]
+    fn legacy_settings_become_the_default_profile() {
+        let settings = migrate(legacy_config()).unwrap();
+        assert_eq!(settings.active_profile, DEFAULT_PROFILE_NAME);
+        assert_eq!(settings.profiles.len(), 1);
+        let profile = &settings.profiles[0];
+        assert_eq!(profile.name, DEFAULT_PROFILE_NAME);
+        assert_eq!(profile.settings.source_language, "ja-JP");
+        assert_eq!(profile.settings.target_language, "en");
+        assert_eq!(profile.settings.translator, "google");
+        assert_eq!(profile.settings.vrchat_settings.osc_port, 9001);
+    }
+
+    #[test]
+    fn current_version_is_left_alone() {
+        let mut current = document(serde_json::to_value(Settings::default()).unwrap());
+        current.insert("source_language".to_string(), json!("de-DE"));
+        let settings = migrate(current).unwrap();
+        assert_eq!(settings.source_language, "de-DE");
+        assert_eq!(settings.profiles.len(), 1);
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let mut newer = legacy_config();
+        newer.insert("version".to_string(), json!(SETTINGS_VERSION + 1));
+        assert!(migrate(newer).is_err());
+    }
+}
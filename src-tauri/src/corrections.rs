@@ -0,0 +1,225 @@
+// User dictionary fixing names and community slang Whisper keeps getting wrong
+// ("kana" -> "Kanna"). Saved to the app data directory so it survives restarts.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{Manager, State};
+
+use crate::hotword::{normalize_words, words_match};
+use crate::whisper::{TranscriptionResult, WhisperAppState};
+
+const CORRECTIONS_FILE: &str = "corrections.json";
+
+fn default_fuzzy() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Correction {
+    // What Whisper writes, one or more words, matched case- and punctuation-insensitively
+    pub heard: String,
+    pub replacement: String,
+    // Also match words a letter or two off; an entry whose `heard` equals its
+    // `replacement` then works as a plain list of names to snap to
+    #[serde(default = "default_fuzzy")]
+    pub fuzzy: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CorrectionDictionary {
+    pub enabled: bool,
+    pub entries: Vec<Correction>,
+}
+
+impl Default for CorrectionDictionary {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            entries: Vec::new(),
+        }
+    }
+}
+
+// Punctuation around a word, kept when the word is replaced ("kana," -> "Kanna,")
+fn surrounding_punctuation(token: &str) -> (&str, &str) {
+    let start = token
+        .find(|c: char| c.is_alphanumeric())
+        .unwrap_or(token.len());
+    let end = token
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_alphanumeric())
+        .map(|(i, c)| i + c.len_utf8())
+        .unwrap_or(start)
+        .max(start);
+    (&token[..start], &token[end..])
+}
+
+impl CorrectionDictionary {
+    // Tokens covered by `heard` starting at `words[0]`, if it matches there
+    fn match_at(words: &[String], heard: &[String], fuzzy: bool) -> Option<usize> {
+        let n = heard.len();
+        if words.len() >= n {
+            let window = &words[..n];
+            if window == heard {
+                return Some(n);
+            }
+            if fuzzy && window.iter().zip(heard).all(|(w, h)| words_match(w, h)) {
+                return Some(n);
+            }
+        }
+        // Split or merged differently ("kanna cs" for "kannacs")
+        let joined = heard.concat();
+        (1..=(n + 1).min(words.len()))
+            .filter(|&k| k != n)
+            .find(|&k| words[..k].concat() == joined)
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        if !self.enabled || self.entries.is_empty() {
+            return text.to_string();
+        }
+
+        // Longest phrases first so "kanna cs" wins over "kanna"
+        let mut entries: Vec<(Vec<String>, &Correction)> = self
+            .entries
+            .iter()
+            .map(|entry| (normalize_words(&entry.heard), entry))
+            .filter(|(heard, _)| !heard.is_empty())
+            .collect();
+        entries.sort_by_key(|(heard, _)| std::cmp::Reverse(heard.len()));
+
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+        let words: Vec<String> = tokens.iter().map(|t| normalize_words(t).concat()).collect();
+        let mut corrected = Vec::new();
+        let mut changed = false;
+        let mut i = 0;
+        while i < tokens.len() {
+            let matched = if words[i].is_empty() {
+                None
+            } else {
+                entries.iter().find_map(|(heard, entry)| {
+                    Self::match_at(&words[i..], heard, entry.fuzzy).map(|len| (len, entry))
+                })
+            };
+            let Some((len, entry)) = matched else {
+                corrected.push(tokens[i].to_string());
+                i += 1;
+                continue;
+            };
+            let (leading, _) = surrounding_punctuation(tokens[i]);
+            let (_, trailing) = surrounding_punctuation(tokens[i + len - 1]);
+            corrected.push(format!(
+                "{}{}{}",
+                leading,
+                entry.replacement.trim(),
+                trailing
+            ));
+            changed = true;
+            i += len;
+        }
+        let corrected = if changed {
+            corrected.join(" ")
+        } else {
+            text.to_string()
+        };
+
+        // Languages written without spaces (Japanese, Chinese, ...) can only be
+        // matched as exact substrings
+        self.entries
+            .iter()
+            .filter(|entry| !entry.heard.is_ascii() && !entry.heard.trim().contains(' '))
+            .filter(|entry| !entry.heard.trim().is_empty())
+            .fold(corrected, |text, entry| {
+                text.replace(entry.heard.trim(), entry.replacement.trim())
+            })
+    }
+
+    // Correct the transcript and every segment. Word-level entries keep what was
+    // heard so multi-word replacements don't break their timings.
+    pub fn apply_to_result(&self, result: &mut TranscriptionResult) {
+        if !self.enabled || self.entries.is_empty() {
+            return;
+        }
+        result.text = self.apply(&result.text);
+        for segment in &mut result.segments {
+            segment.text = self.apply(&segment.text);
+        }
+    }
+}
+
+fn corrections_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join(CORRECTIONS_FILE))
+}
+
+// Restore the saved dictionary at startup; a missing file means no corrections yet
+pub fn load_corrections(app_handle: &tauri::AppHandle) {
+    let Ok(path) = corrections_path(app_handle) else {
+        return;
+    };
+    let Ok(data) = fs::read_to_string(&path) else {
+        return;
+    };
+    match serde_json::from_str::<CorrectionDictionary>(&data) {
+        Ok(dictionary) => {
+            println!("Loaded {} correction(s)", dictionary.entries.len());
+            let state = app_handle.state::<WhisperAppState>();
+            if let Ok(mut corrections) = state.corrections.lock() {
+                *corrections = dictionary;
+            }
+        }
+        Err(e) => println!("Ignoring invalid {}: {}", CORRECTIONS_FILE, e),
+    }
+}
+
+fn save_corrections(
+    app_handle: &tauri::AppHandle,
+    dictionary: &CorrectionDictionary,
+) -> Result<(), String> {
+    let path = corrections_path(app_handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let data = serde_json::to_string_pretty(dictionary)
+        .map_err(|e| format!("Failed to serialize corrections: {}", e))?;
+    fs::write(&path, data).map_err(|e| format!("Failed to save corrections: {}", e))
+}
+
+#[tauri::command]
+pub fn whisper_get_corrections(
+    state: State<'_, WhisperAppState>,
+) -> Result<CorrectionDictionary, String> {
+    Ok(state
+        .corrections
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+#[tauri::command]
+pub fn whisper_set_corrections(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    mut dictionary: CorrectionDictionary,
+) -> Result<(), String> {
+    dictionary
+        .entries
+        .retain(|entry| !entry.heard.trim().is_empty());
+    save_corrections(&app_handle, &dictionary)?;
+    println!(
+        "Updated correction dictionary: enabled {}, {} entries",
+        dictionary.enabled,
+        dictionary.entries.len()
+    );
+    *state
+        .corrections
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = dictionary;
+    Ok(())
+}
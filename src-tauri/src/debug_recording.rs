@@ -0,0 +1,190 @@
+// Optional dump of every audio segment submitted for transcription, written as the
+// 16kHz mono WAV the engine works on. When someone reports "it transcribed nothing"
+// they can share exactly what was heard. Old files are rotated out under a count
+// and size cap so the folder can be left enabled.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{Manager, State};
+
+use crate::openai_stt::encode_wav;
+use crate::whisper::process_audio_for_whisper;
+
+const DEBUG_AUDIO_DIR: &str = "debug_audio";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DebugRecordingSettings {
+    pub enabled: bool,
+    // Oldest recordings are deleted beyond either limit
+    pub max_files: usize,
+    pub max_total_mb: u64,
+}
+
+impl Default for DebugRecordingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_files: 100,
+            max_total_mb: 200,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DebugRecordingState {
+    settings: Mutex<DebugRecordingSettings>,
+}
+
+#[derive(Serialize)]
+pub struct DebugRecordingInfo {
+    pub settings: DebugRecordingSettings,
+    // Where the recordings go, so the UI can point users at it
+    pub folder: String,
+}
+
+fn debug_audio_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join(DEBUG_AUDIO_DIR))
+}
+
+// Delete the oldest recordings until the folder is within both limits. File names
+// start with a timestamp, so name order is age order.
+fn rotate(dir: &Path, settings: &DebugRecordingSettings) -> Result<(), String> {
+    let mut files: Vec<(PathBuf, u64)> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read debug audio folder: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "wav"))
+        .filter_map(|entry| Some((entry.path(), entry.metadata().ok()?.len())))
+        .collect();
+    files.sort();
+
+    let max_bytes = settings.max_total_mb * 1024 * 1024;
+    let mut total: u64 = files.iter().map(|(_, size)| size).sum();
+    let mut count = files.len();
+    for (path, size) in files {
+        if count <= settings.max_files && total <= max_bytes {
+            break;
+        }
+        if let Err(e) = fs::remove_file(&path) {
+            println!(
+                "Failed to remove old debug recording {}: {}",
+                path.display(),
+                e
+            );
+            continue;
+        }
+        count -= 1;
+        total = total.saturating_sub(size);
+    }
+    Ok(())
+}
+
+fn write_recording(
+    dir: PathBuf,
+    settings: &DebugRecordingSettings,
+    samples: &[f32],
+    source: &str,
+) -> Result<PathBuf, String> {
+    let wav = encode_wav(samples)?;
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create debug audio folder: {}", e))?;
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S%.3f");
+    let source: String = source
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let path = dir.join(format!("{}_{}.wav", timestamp, source));
+    fs::write(&path, wav).map_err(|e| format!("Failed to write debug recording: {}", e))?;
+
+    rotate(&dir, settings)?;
+    Ok(path)
+}
+
+// Where to record and with which limits, or None while debug recording is off
+fn recording_target(app_handle: &tauri::AppHandle) -> Option<(PathBuf, DebugRecordingSettings)> {
+    let state = app_handle.state::<DebugRecordingState>();
+    let settings = match state.settings.lock() {
+        Ok(settings) if settings.enabled => settings.clone(),
+        _ => return None,
+    };
+    match debug_audio_dir(app_handle) {
+        Ok(dir) => Some((dir, settings)),
+        Err(e) => {
+            println!("Debug recording skipped: {}", e);
+            None
+        }
+    }
+}
+
+fn record(
+    dir: PathBuf,
+    settings: DebugRecordingSettings,
+    source: &str,
+    samples: impl FnOnce() -> Result<Vec<f32>, String> + Send + 'static,
+) {
+    let source = source.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        match samples().and_then(|samples| write_recording(dir, &settings, &samples, &source)) {
+            Ok(path) => println!("Saved debug recording {}", path.display()),
+            Err(e) => println!("Debug recording failed: {}", e),
+        }
+    });
+}
+
+// Save a submitted segment in the background when debug recording is enabled
+pub fn record_segment(app_handle: &tauri::AppHandle, audio_data: &[u8], source: &str) {
+    let Some((dir, settings)) = recording_target(app_handle) else {
+        return;
+    };
+    let audio_data = audio_data.to_vec();
+    record(dir, settings, source, move || {
+        process_audio_for_whisper(&audio_data)
+    });
+}
+
+// Same for 16kHz PCM from native capture
+pub fn record_samples(app_handle: &tauri::AppHandle, samples: &[f32], source: &str) {
+    let Some((dir, settings)) = recording_target(app_handle) else {
+        return;
+    };
+    let samples = samples.to_vec();
+    record(dir, settings, source, move || Ok(samples));
+}
+
+#[tauri::command]
+pub fn whisper_get_debug_recording(
+    app_handle: tauri::AppHandle,
+    state: State<'_, DebugRecordingState>,
+) -> Result<DebugRecordingInfo, String> {
+    let settings = state
+        .settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone();
+    let folder = debug_audio_dir(&app_handle)?.to_string_lossy().to_string();
+    Ok(DebugRecordingInfo { settings, folder })
+}
+
+#[tauri::command]
+pub fn whisper_set_debug_recording(
+    state: State<'_, DebugRecordingState>,
+    settings: DebugRecordingSettings,
+) -> Result<(), String> {
+    if settings.max_files == 0 || settings.max_total_mb == 0 {
+        return Err("Debug recording limits must be greater than zero".to_string());
+    }
+    println!(
+        "Debug recording enabled: {} (max {} files, {}MB)",
+        settings.enabled, settings.max_files, settings.max_total_mb
+    );
+    *state
+        .settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = settings;
+    Ok(())
+}
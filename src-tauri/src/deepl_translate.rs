@@ -0,0 +1,211 @@
+// DeepL API provider. Free and Pro keys use different hosts; Free keys end in ":fx".
+// Many users find DeepL noticeably better than Google for Japanese <-> English.
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::translate::{TranslateError, TranslationProvider, AUTO_LANGUAGE};
+
+const FREE_API_URL: &str = "https://api-free.deepl.com/v2";
+const PRO_API_URL: &str = "https://api.deepl.com/v2";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Texts per request allowed by the API
+const MAX_BATCH_TEXTS: usize = 50;
+// Monthly character allowance of DeepL API Free
+pub const DEEPL_FREE_CHARACTERS: u64 = 500_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeepLFormality {
+    #[default]
+    Default,
+    More,
+    Less,
+}
+
+impl DeepLFormality {
+    // The "prefer_" variants fall back to default for target languages without
+    // formality support instead of failing the request
+    fn as_param(self) -> Option<&'static str> {
+        match self {
+            DeepLFormality::Default => None,
+            DeepLFormality::More => Some("prefer_more"),
+            DeepLFormality::Less => Some("prefer_less"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeepLConfig {
+    pub api_key: String,
+    #[serde(default)]
+    pub formality: DeepLFormality,
+}
+
+impl DeepLConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.api_key.trim().is_empty() {
+            return Err("DeepL needs an API key".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn is_free(&self) -> bool {
+        self.api_key.trim().ends_with(":fx")
+    }
+
+    fn api_url(&self) -> &'static str {
+        if self.is_free() {
+            FREE_API_URL
+        } else {
+            PRO_API_URL
+        }
+    }
+
+    fn client(&self) -> Result<reqwest::Client, TranslateError> {
+        reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| TranslateError::Config(format!("Failed to create HTTP client: {}", e)))
+    }
+
+    fn auth_header(&self) -> String {
+        format!("DeepL-Auth-Key {}", self.api_key.trim())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeepLUsage {
+    pub character_count: u64,
+    pub character_limit: u64,
+}
+
+#[derive(Serialize)]
+struct DeepLRequest<'a> {
+    text: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_lang: Option<String>,
+    target_lang: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    formality: Option<&'static str>,
+}
+
+#[derive(Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>,
+}
+
+#[derive(Deserialize)]
+struct DeepLTranslation {
+    text: String,
+}
+
+// DeepL takes bare uppercase source languages ("JA")
+fn source_lang(code: &str) -> Option<String> {
+    if code == AUTO_LANGUAGE {
+        return None;
+    }
+    let base = code.split('-').next().unwrap_or(code);
+    Some(base.to_uppercase())
+}
+
+// Targets need a variant for English, Portuguese and Chinese
+fn target_lang(code: &str) -> String {
+    let code = code.to_lowercase();
+    match code.as_str() {
+        "en" => "EN-US".to_string(),
+        "pt" => "PT-BR".to_string(),
+        "zh" | "zh-cn" | "zh-hans" => "ZH-HANS".to_string(),
+        "zh-tw" | "zh-hk" | "zh-hant" => "ZH-HANT".to_string(),
+        code if code.starts_with("en-") || code.starts_with("pt-") => code.to_uppercase(),
+        code => code.split('-').next().unwrap_or(code).to_uppercase(),
+    }
+}
+
+fn map_request_error(e: reqwest::Error) -> TranslateError {
+    if e.is_timeout() {
+        TranslateError::Unavailable("DeepL timed out".to_string())
+    } else {
+        TranslateError::Unavailable(format!("DeepL request failed: {}", e))
+    }
+}
+
+// DeepL reports a bad key as 403 and an exhausted quota as 456
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, TranslateError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(match status.as_u16() {
+        401 | 403 => TranslateError::Config(format!("DeepL rejected the API key: {}", body)),
+        429 => TranslateError::RateLimited("DeepL is rate limiting requests".to_string()),
+        456 => TranslateError::RateLimited("DeepL character quota exceeded".to_string()),
+        _ if status.is_server_error() => {
+            TranslateError::Unavailable(format!("DeepL returned {}", status))
+        }
+        _ => TranslateError::Failed(format!("DeepL returned {}: {}", status, body)),
+    })
+}
+
+impl TranslationProvider for DeepLConfig {
+    fn name(&self) -> &'static str {
+        "deepl"
+    }
+
+    async fn translate(
+        &self,
+        texts: &[String],
+        source: &str,
+        target: &str,
+    ) -> Result<Vec<String>, TranslateError> {
+        self.validate().map_err(TranslateError::Config)?;
+        let client = self.client()?;
+
+        let mut translated = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(MAX_BATCH_TEXTS) {
+            let request = DeepLRequest {
+                text: batch,
+                source_lang: source_lang(source),
+                target_lang: target_lang(target),
+                formality: self.formality.as_param(),
+            };
+            let response = client
+                .post(format!("{}/translate", self.api_url()))
+                .header("Authorization", self.auth_header())
+                .json(&request)
+                .send()
+                .await
+                .map_err(map_request_error)?;
+            let response: DeepLResponse =
+                check_status(response).await?.json().await.map_err(|e| {
+                    TranslateError::Failed(format!("Invalid DeepL response: {}", e))
+                })?;
+            if response.translations.len() != batch.len() {
+                return Err(TranslateError::Failed(format!(
+                    "DeepL returned {} translations for {} texts",
+                    response.translations.len(),
+                    batch.len()
+                )));
+            }
+            translated.extend(response.translations.into_iter().map(|t| t.text));
+        }
+        Ok(translated)
+    }
+}
+
+// Characters translated this billing period and the account's limit
+pub async fn deepl_usage(config: &DeepLConfig) -> Result<DeepLUsage, TranslateError> {
+    config.validate().map_err(TranslateError::Config)?;
+    let response = config
+        .client()?
+        .get(format!("{}/usage", config.api_url()))
+        .header("Authorization", config.auth_header())
+        .send()
+        .await
+        .map_err(map_request_error)?;
+    check_status(response)
+        .await?
+        .json()
+        .await
+        .map_err(|e| TranslateError::Failed(format!("Invalid DeepL usage response: {}", e)))
+}
@@ -0,0 +1,55 @@
+// RNNoise (via nnnoiseless) noise suppression, applied before VAD and Whisper to
+// clean up fan hum, keyboard clatter and VR link compression artifacts.
+use nnnoiseless::DenoiseState;
+
+// RNNoise is trained on 48kHz audio, Whisper input is 16kHz
+const RNNOISE_SAMPLE_RATE: u32 = 48_000;
+const UPSAMPLE_FACTOR: usize = 3;
+
+// Input source used when a transcription call doesn't name one
+pub const DEFAULT_AUDIO_SOURCE: &str = "microphone";
+
+// Linear interpolation to 3x the rate (16kHz to 48kHz)
+pub fn upsample(samples: &[f32]) -> Vec<f32> {
+    let mut upsampled = Vec::with_capacity(samples.len() * UPSAMPLE_FACTOR);
+    for (i, &sample) in samples.iter().enumerate() {
+        let next = samples.get(i + 1).copied().unwrap_or(sample);
+        for step in 0..UPSAMPLE_FACTOR {
+            let t = step as f32 / UPSAMPLE_FACTOR as f32;
+            upsampled.push(sample + (next - sample) * t);
+        }
+    }
+    upsampled
+}
+
+// Denoise 16kHz mono samples, returning 16kHz output of the same length
+pub fn denoise_samples(samples: &[f32], sample_rate: u32) -> Vec<f32> {
+    if samples.is_empty() || sample_rate * UPSAMPLE_FACTOR as u32 != RNNOISE_SAMPLE_RATE {
+        return samples.to_vec();
+    }
+
+    // RNNoise expects samples in 16-bit integer range
+    let input: Vec<f32> = upsample(samples)
+        .into_iter()
+        .map(|s| s * i16::MAX as f32)
+        .collect();
+
+    let mut state = DenoiseState::new();
+    let mut output = Vec::with_capacity(input.len());
+    let mut frame_out = [0.0f32; DenoiseState::FRAME_SIZE];
+    let mut frame_in = [0.0f32; DenoiseState::FRAME_SIZE];
+    for chunk in input.chunks(DenoiseState::FRAME_SIZE) {
+        // Zero-pad the final partial frame
+        frame_in.fill(0.0);
+        frame_in[..chunk.len()].copy_from_slice(chunk);
+        state.process_frame(&mut frame_out, &frame_in);
+        output.extend_from_slice(&frame_out[..chunk.len()]);
+    }
+
+    output
+        .iter()
+        .step_by(UPSAMPLE_FACTOR)
+        .take(samples.len())
+        .map(|&s| (s / i16::MAX as f32).clamp(-1.0, 1.0))
+        .collect()
+}
@@ -0,0 +1,593 @@
+use futures_util::future::try_join_all;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use tokio::sync::Semaphore;
+
+use crate::config::{current_settings, SettingsState};
+use crate::whisper::WhisperAppState;
+
+const LEGACY_DOWNLOAD_SETTINGS_FILE: &str = "download_settings.json";
+
+const DEFAULT_HF_ENDPOINT: &str = "https://huggingface.co";
+// Branch downloaded from when a model doesn't pin a revision
+const DEFAULT_REVISION: &str = "main";
+
+const DEFAULT_MAX_CONNECTIONS: usize = 4;
+const MAX_CONNECTIONS_LIMIT: usize = 16;
+// Files at least this large are split into ranged requests, one per connection
+const CHUNKED_DOWNLOAD_MIN_BYTES: u64 = 64 * 1024 * 1024;
+// Smallest range worth its own connection
+const MIN_CHUNK_BYTES: u64 = 16 * 1024 * 1024;
+// Below this a rate cap would make even the small models take ages
+const MIN_RATE_LIMIT_BYTES: u64 = 64 * 1024;
+
+// Network options for model downloads. Users behind the GFW or corporate proxies
+// can point at a Hugging Face mirror (e.g. https://hf-mirror.com) and/or a proxy.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DownloadSettings {
+    pub mirror_url: Option<String>,
+    // http://, https:// or socks5:// proxy URL
+    pub proxy: Option<String>,
+    pub hf_token: Option<String>,
+    // Simultaneous HTTP connections per model, shared by files and chunks (default 4)
+    pub max_connections: Option<usize>,
+    // Split large files into ranged requests (default on)
+    pub chunked: Option<bool>,
+    // Combined download rate cap across all connections, so a model download
+    // doesn't starve VRChat of bandwidth (default unlimited)
+    pub max_bytes_per_second: Option<u64>,
+}
+
+impl DownloadSettings {
+    fn endpoint(&self) -> &str {
+        self.mirror_url
+            .as_deref()
+            .map(|url| url.trim().trim_end_matches('/'))
+            .filter(|url| !url.is_empty())
+            .unwrap_or(DEFAULT_HF_ENDPOINT)
+    }
+
+    pub fn connections(&self) -> usize {
+        self.max_connections
+            .unwrap_or(DEFAULT_MAX_CONNECTIONS)
+            .clamp(1, MAX_CONNECTIONS_LIMIT)
+    }
+
+    fn chunked(&self) -> bool {
+        self.chunked.unwrap_or(true)
+    }
+
+    fn rate_limit(&self) -> Option<u64> {
+        self.max_bytes_per_second
+            .filter(|&limit| limit > 0)
+            .map(|limit| limit.max(MIN_RATE_LIMIT_BYTES))
+    }
+
+    pub fn resolve_url(&self, repo_id: &str, revision: Option<&str>, filename: &str) -> String {
+        format!(
+            "{}/{}/resolve/{}/{}",
+            self.endpoint(),
+            repo_id,
+            revision.unwrap_or(DEFAULT_REVISION),
+            filename
+        )
+    }
+
+    pub fn client_builder(&self) -> Result<reqwest::ClientBuilder, String> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(proxy) = self.proxy.as_deref().filter(|p| !p.trim().is_empty()) {
+            let proxy = reqwest::Proxy::all(proxy.trim())
+                .map_err(|e| format!("Invalid proxy URL '{}': {}", proxy, e))?;
+            builder = builder.proxy(proxy);
+        }
+        Ok(builder)
+    }
+
+    // Attach the Hugging Face token, but only to requests for the configured endpoint
+    // so it never leaks to custom model hosts
+    pub fn authorize(
+        &self,
+        request: reqwest::RequestBuilder,
+        url: &str,
+    ) -> reqwest::RequestBuilder {
+        match self.hf_token.as_deref().filter(|t| !t.trim().is_empty()) {
+            Some(token) if url.starts_with(self.endpoint()) => request.bearer_auth(token.trim()),
+            _ => request,
+        }
+    }
+}
+
+// Restore the network options at startup, before the manifest refresh needs the
+// mirror or proxy. The frontend keeps them (token included) in the settings store as
+// `whisper_downloads`, which is the only copy; empty fields mean the defaults.
+pub fn load_download_settings(app_handle: &tauri::AppHandle) {
+    // Older builds also saved them to a file of their own
+    if let Ok(app_data) = app_handle.path().app_data_dir() {
+        let _ = fs::remove_file(app_data.join(LEGACY_DOWNLOAD_SETTINGS_FILE));
+    }
+    let Ok(settings) = current_settings(&app_handle.state::<SettingsState>()) else {
+        return;
+    };
+    let Some(downloads) = settings.extra.get("whisper_downloads") else {
+        return;
+    };
+    match serde_json::from_value::<DownloadSettings>(downloads.clone()) {
+        Ok(downloads) => {
+            println!("Loaded model download settings");
+            let state = app_handle.state::<WhisperAppState>();
+            if let Ok(mut current) = state.download_settings.lock() {
+                *current = downloads;
+            }
+        }
+        Err(e) => println!("Ignoring invalid model download settings: {}", e),
+    }
+}
+
+pub const DOWNLOAD_CANCELLED: &str = "Download cancelled";
+
+// A corrupted download is re-fetched this many times before giving up
+pub const MAX_DOWNLOAD_ATTEMPTS: u32 = 2;
+
+// Progress events are emitted at most this often (~4 Hz) to keep the IPC bridge quiet
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+// Weight of the newest sample in the smoothed transfer speed
+const SPEED_SMOOTHING: f64 = 0.3;
+
+// Returned by a ranged request the server answered with the whole file
+const RANGES_UNSUPPORTED: &str = "Server does not support ranged requests";
+
+// Hugging Face reports the SHA-256 of LFS files in the X-Linked-Etag header of the
+// resolve redirect, which saves us from shipping checksums for every model file.
+pub async fn fetch_expected_sha256(settings: &DownloadSettings, url: &str) -> Option<String> {
+    let client = settings
+        .client_builder()
+        .ok()?
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .ok()?;
+    let response = settings
+        .authorize(client.head(url), url)
+        .send()
+        .await
+        .ok()?;
+
+    let etag = response
+        .headers()
+        .get("x-linked-etag")
+        .or_else(|| response.headers().get("etag"))?
+        .to_str()
+        .ok()?
+        .trim_matches('"')
+        .to_lowercase();
+
+    if etag.len() == 64 && etag.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(etag)
+    } else {
+        None
+    }
+}
+
+// Aggregates byte counts across all files of a model into a single, throttled
+// `download-progress` stream with overall percent, transfer speed and ETA.
+struct DownloadProgress {
+    app_handle: tauri::AppHandle,
+    model_id: String,
+    total_bytes: u64,
+    // Bytes of files that are already complete (downloaded or skipped)
+    completed_bytes: u64,
+    // Bytes received so far for files still downloading
+    in_flight: HashMap<String, u64>,
+    // Most recently active file, reported in the payload
+    file: String,
+    last_emit: Option<Instant>,
+    last_emit_bytes: u64,
+    bytes_per_second: f64,
+}
+
+impl DownloadProgress {
+    fn downloaded(&self) -> u64 {
+        (self.completed_bytes + self.in_flight.values().sum::<u64>()).min(self.total_bytes)
+    }
+
+    // Start (or restart, on retry) downloading a file
+    fn start_file(&mut self, file: &str) {
+        self.file = file.to_string();
+        self.in_flight.insert(file.to_string(), 0);
+        self.last_emit_bytes = self.last_emit_bytes.min(self.downloaded());
+    }
+
+    fn advance(&mut self, file: &str, bytes: u64) {
+        self.file = file.to_string();
+        *self.in_flight.entry(file.to_string()).or_default() += bytes;
+        if self
+            .last_emit
+            .is_some_and(|last| last.elapsed() < PROGRESS_INTERVAL)
+        {
+            return;
+        }
+        self.emit();
+    }
+
+    fn finish_file(&mut self, file: &str, size: u64) {
+        self.file = file.to_string();
+        self.in_flight.remove(file);
+        self.completed_bytes += size;
+        self.emit();
+    }
+
+    // Count a file that was already on disk without it skewing the transfer speed
+    fn skip_file(&mut self, file: &str, size: u64) {
+        self.file = file.to_string();
+        self.completed_bytes += size;
+        self.last_emit_bytes += size;
+        self.emit();
+    }
+
+    fn emit(&mut self) {
+        let downloaded = self.downloaded();
+        let now = Instant::now();
+        if let Some(last) = self.last_emit {
+            let elapsed = now.duration_since(last).as_secs_f64();
+            if elapsed > 0.0 {
+                let sample = downloaded.saturating_sub(self.last_emit_bytes) as f64 / elapsed;
+                self.bytes_per_second = if self.bytes_per_second == 0.0 {
+                    sample
+                } else {
+                    SPEED_SMOOTHING * sample + (1.0 - SPEED_SMOOTHING) * self.bytes_per_second
+                };
+            }
+        }
+        self.last_emit = Some(now);
+        self.last_emit_bytes = downloaded;
+
+        let progress = if self.total_bytes > 0 {
+            (downloaded as f64 / self.total_bytes as f64) * 100.0
+        } else {
+            0.0
+        };
+        let eta_seconds = (self.bytes_per_second > 0.0).then(|| {
+            (self.total_bytes.saturating_sub(downloaded) as f64 / self.bytes_per_second).round()
+                as u64
+        });
+
+        let progress_payload = serde_json::json!({
+            "model": self.model_id,
+            "file": self.file,
+            "progress": progress,
+            "downloaded": downloaded,
+            "total": self.total_bytes,
+            "bytes_per_second": self.bytes_per_second.round() as u64,
+            "eta_seconds": eta_seconds
+        });
+        let _ = self.app_handle.emit("download-progress", &progress_payload);
+    }
+}
+
+// Token bucket shared by every connection of a download
+struct RateLimiter {
+    bytes_per_second: u64,
+    // When the bytes received so far will have been "paid for" at the capped rate
+    next_free: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes_per_second,
+            next_free: Mutex::new(Instant::now()),
+        }
+    }
+
+    // Sleep long enough that `bytes` more keeps the transfer under the cap
+    async fn throttle(&self, bytes: u64) {
+        let wait = {
+            let Ok(mut next_free) = self.next_free.lock() else {
+                return;
+            };
+            // Idle time isn't banked, so a stalled connection can't burst afterwards
+            let now = Instant::now();
+            let cost = Duration::from_secs_f64(bytes as f64 / self.bytes_per_second as f64);
+            *next_free = (*next_free).max(now) + cost;
+            next_free.saturating_duration_since(now)
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+// One model download: its files are fetched concurrently, large ones in ranged
+// chunks, with the total number of open connections capped by the settings.
+pub struct ModelDownload<'a> {
+    settings: &'a DownloadSettings,
+    client: reqwest::Client,
+    connections: Semaphore,
+    progress: Mutex<DownloadProgress>,
+    rate_limiter: Option<RateLimiter>,
+    cancel: &'a AtomicBool,
+}
+
+impl<'a> ModelDownload<'a> {
+    pub fn new(
+        app_handle: &tauri::AppHandle,
+        settings: &'a DownloadSettings,
+        model_id: &str,
+        total_bytes: u64,
+        cancel: &'a AtomicBool,
+    ) -> Result<Self, String> {
+        let client = settings
+            .client_builder()?
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        Ok(Self {
+            settings,
+            client,
+            connections: Semaphore::new(settings.connections()),
+            progress: Mutex::new(DownloadProgress {
+                app_handle: app_handle.clone(),
+                model_id: model_id.to_string(),
+                total_bytes,
+                completed_bytes: 0,
+                in_flight: HashMap::new(),
+                file: String::new(),
+                last_emit: None,
+                last_emit_bytes: 0,
+                bytes_per_second: 0.0,
+            }),
+            rate_limiter: settings.rate_limit().map(RateLimiter::new),
+            cancel,
+        })
+    }
+
+    pub fn settings(&self) -> &DownloadSettings {
+        self.settings
+    }
+
+    // A poisoned progress lock only means a missed progress event
+    fn with_progress(&self, f: impl FnOnce(&mut DownloadProgress)) {
+        if let Ok(mut progress) = self.progress.lock() {
+            f(&mut progress);
+        }
+    }
+
+    async fn throttle(&self, bytes: u64) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.throttle(bytes).await;
+        }
+    }
+
+    pub fn finish_file(&self, file: &str, size: u64) {
+        self.with_progress(|p| p.finish_file(file, size));
+    }
+
+    pub fn skip_file(&self, file: &str, size: u64) {
+        self.with_progress(|p| p.skip_file(file, size));
+    }
+
+    // Download a file and return its SHA-256. `size` is the size published in the
+    // catalog, used to decide whether the file is worth splitting into chunks.
+    pub async fn download_file(
+        &self,
+        url: &str,
+        filename: &str,
+        size: u64,
+        local_path: &Path,
+    ) -> Result<String, String> {
+        println!("Downloading {} from {}", filename, url);
+
+        let chunk_count = (size / MIN_CHUNK_BYTES).min(self.settings.connections() as u64);
+        if self.settings.chunked() && size >= CHUNKED_DOWNLOAD_MIN_BYTES && chunk_count > 1 {
+            match self
+                .download_chunked(url, filename, size, chunk_count, local_path)
+                .await
+            {
+                Err(e) if e == RANGES_UNSUPPORTED => {
+                    println!("{} for {}, using a single connection", e, filename)
+                }
+                result => return result,
+            }
+        }
+        self.download_single(url, filename, local_path).await
+    }
+
+    async fn download_single(
+        &self,
+        url: &str,
+        filename: &str,
+        local_path: &Path,
+    ) -> Result<String, String> {
+        self.with_progress(|p| p.start_file(filename));
+        let _permit = self
+            .connections
+            .acquire()
+            .await
+            .map_err(|e| format!("Connection limiter closed: {}", e))?;
+
+        let response = self
+            .settings
+            .authorize(self.client.get(url), url)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "HTTP error {}: {}",
+                response.status(),
+                response.status().canonical_reason().unwrap_or("Unknown")
+            ));
+        }
+
+        let total_size = response.content_length().unwrap_or(0);
+        println!("File size: {} bytes", total_size);
+
+        let mut file =
+            fs::File::create(local_path).map_err(|e| format!("Failed to create file: {}", e))?;
+
+        let mut stream = response.bytes_stream();
+        let mut downloaded = 0u64;
+        let mut hasher = Sha256::new();
+
+        while let Some(chunk) = stream.next().await {
+            if self.cancel.load(Ordering::SeqCst) {
+                println!("Download of {} cancelled", filename);
+                return Err(DOWNLOAD_CANCELLED.to_string());
+            }
+
+            let chunk = chunk.map_err(|e| format!("Failed to read chunk: {}", e))?;
+            file.write_all(&chunk)
+                .map_err(|e| format!("Failed to write chunk: {}", e))?;
+            hasher.update(&chunk);
+
+            downloaded += chunk.len() as u64;
+            self.with_progress(|p| p.advance(filename, chunk.len() as u64));
+            self.throttle(chunk.len() as u64).await;
+
+            if total_size > 0 && (downloaded % (1024 * 1024) == 0 || downloaded == total_size) {
+                // Log every MB or at completion
+                println!(
+                    "Progress: {:.1}% ({}/{} bytes)",
+                    (downloaded as f64 / total_size as f64) * 100.0,
+                    downloaded,
+                    total_size
+                );
+            }
+        }
+
+        println!(
+            "Successfully downloaded {} ({} bytes)",
+            filename, downloaded
+        );
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    async fn download_chunked(
+        &self,
+        url: &str,
+        filename: &str,
+        size: u64,
+        chunk_count: u64,
+        local_path: &Path,
+    ) -> Result<String, String> {
+        self.with_progress(|p| p.start_file(filename));
+        println!(
+            "Downloading {} in {} chunks ({} bytes)",
+            filename, chunk_count, size
+        );
+
+        // Preallocate so every chunk can write at its own offset
+        fs::File::create(local_path)
+            .and_then(|file| file.set_len(size))
+            .map_err(|e| format!("Failed to create file: {}", e))?;
+
+        let chunk_size = size.div_ceil(chunk_count);
+        let ranges = (0..chunk_count)
+            .map(|i| (i * chunk_size, ((i + 1) * chunk_size).min(size) - 1))
+            .filter(|(start, end)| start <= end);
+        try_join_all(
+            ranges.map(|(start, end)| self.download_range(url, filename, local_path, start, end)),
+        )
+        .await?;
+
+        println!("Successfully downloaded {} ({} bytes)", filename, size);
+        hash_file(local_path.to_path_buf()).await
+    }
+
+    // Fetch bytes start..=end of a file into the same range of the local file
+    async fn download_range(
+        &self,
+        url: &str,
+        filename: &str,
+        local_path: &Path,
+        start: u64,
+        end: u64,
+    ) -> Result<(), String> {
+        let _permit = self
+            .connections
+            .acquire()
+            .await
+            .map_err(|e| format!("Connection limiter closed: {}", e))?;
+
+        let response = self
+            .settings
+            .authorize(self.client.get(url), url)
+            .header(reqwest::header::RANGE, format!("bytes={}-{}", start, end))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+
+        let status = response.status();
+        if status != reqwest::StatusCode::PARTIAL_CONTENT {
+            if status.is_success() {
+                return Err(RANGES_UNSUPPORTED.to_string());
+            }
+            return Err(format!(
+                "HTTP error {}: {}",
+                status,
+                status.canonical_reason().unwrap_or("Unknown")
+            ));
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .open(local_path)
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+        file.seek(SeekFrom::Start(start))
+            .map_err(|e| format!("Failed to seek: {}", e))?;
+
+        let mut stream = response.bytes_stream();
+        let mut received = 0u64;
+        while let Some(chunk) = stream.next().await {
+            if self.cancel.load(Ordering::SeqCst) {
+                println!("Download of {} cancelled", filename);
+                return Err(DOWNLOAD_CANCELLED.to_string());
+            }
+
+            let chunk = chunk.map_err(|e| format!("Failed to read chunk: {}", e))?;
+            file.write_all(&chunk)
+                .map_err(|e| format!("Failed to write chunk: {}", e))?;
+            received += chunk.len() as u64;
+            self.with_progress(|p| p.advance(filename, chunk.len() as u64));
+            self.throttle(chunk.len() as u64).await;
+        }
+
+        let expected = end - start + 1;
+        if received != expected {
+            return Err(format!(
+                "Chunk {}-{} of {} ended early ({} of {} bytes)",
+                start, end, filename, received, expected
+            ));
+        }
+        Ok(())
+    }
+}
+
+// SHA-256 of a file on disk, read off the async runtime
+pub async fn hash_file(path: PathBuf) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut file = fs::File::open(&path).map_err(|e| format!("Failed to open file: {}", e))?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0u8; 1024 * 1024];
+        loop {
+            let read = file
+                .read(&mut buffer)
+                .map_err(|e| format!("Failed to read file: {}", e))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {:?}", e))?
+}
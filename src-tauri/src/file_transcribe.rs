@@ -0,0 +1,241 @@
+// Offline transcription of recorded audio files (e.g. VRChat session recordings).
+// Each speech region found by the VAD is decoded on its own so timestamps line up
+// with the original file, which is what captions need.
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use tauri::{Emitter, State};
+use whisper_rs::WhisperContext;
+
+use crate::denoise::denoise_samples;
+use crate::utterance::group_utterances;
+use crate::vad::detect_speech_segments;
+use crate::whisper::{
+    process_audio_for_whisper, register_job, run_inference_on_context, run_with_context,
+    InferenceOptions, TranscribeOptions, TranscriptionResult, WhisperAppState,
+    TRANSCRIPTION_CANCELLED, WHISPER_SAMPLE_RATE,
+};
+
+#[derive(Serialize)]
+pub struct FileTranscript {
+    pub path: String,
+    pub duration_ms: i64,
+    // None when the file couldn't be transcribed, see `error`
+    pub transcript: Option<TranscriptionResult>,
+    pub error: Option<String>,
+}
+
+// Where a file sits in the current batch, for `file-transcription-progress` events
+#[derive(Clone)]
+pub struct FileProgress {
+    pub app_handle: tauri::AppHandle,
+    pub job_id: String,
+    pub path: String,
+    pub file_index: usize,
+    pub file_count: usize,
+}
+
+impl FileProgress {
+    fn emit(&self, processed_ms: i64, duration_ms: i64) {
+        let progress_payload = serde_json::json!({
+            "job_id": self.job_id,
+            "path": self.path,
+            "file_index": self.file_index,
+            "file_count": self.file_count,
+            "processed_ms": processed_ms,
+            "duration_ms": duration_ms,
+            "progress": if duration_ms > 0 {
+                processed_ms as f64 / duration_ms as f64 * 100.0
+            } else {
+                100.0
+            }
+        });
+        let _ = self
+            .app_handle
+            .emit("file-transcription-progress", &progress_payload);
+    }
+}
+
+fn samples_to_ms(samples: usize) -> i64 {
+    (samples as u64 * 1000 / WHISPER_SAMPLE_RATE as u64) as i64
+}
+
+fn transcribe_on_context(
+    ctx: &WhisperContext,
+    samples: &[f32],
+    options: &InferenceOptions,
+    progress: &FileProgress,
+) -> Result<TranscriptionResult, String> {
+    let duration_ms = samples_to_ms(samples.len());
+    let speech = detect_speech_segments(samples, WHISPER_SAMPLE_RATE, &options.vad);
+    println!(
+        "Transcribing {}: {}ms of audio, {} speech region(s)",
+        progress.path,
+        duration_ms,
+        speech.len()
+    );
+    progress.emit(0, duration_ms);
+
+    let mut segments = Vec::new();
+    for region in &speech {
+        let mut result = run_inference_on_context(
+            ctx,
+            &samples[region.start..region.end],
+            options.for_language(&options.language),
+        )?;
+        let offset_ms = samples_to_ms(region.start);
+        for segment in &mut result.segments {
+            segment.shift(offset_ms);
+        }
+        segments.extend(result.segments);
+        progress.emit(samples_to_ms(region.end), duration_ms);
+    }
+    progress.emit(duration_ms, duration_ms);
+
+    let text = segments
+        .iter()
+        .map(|s| s.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    // The language spoken for the longest total time represents the file
+    let mut durations: HashMap<&str, i64> = HashMap::new();
+    for segment in &segments {
+        *durations.entry(segment.language.as_str()).or_default() +=
+            segment.end_ms - segment.start_ms;
+    }
+    let language = durations
+        .into_iter()
+        .max_by_key(|(_, duration)| *duration)
+        .map(|(language, _)| language.to_string())
+        .unwrap_or_else(|| options.language.clone());
+
+    let mut result = TranscriptionResult {
+        text,
+        language,
+        segments,
+        utterances: Vec::new(),
+    };
+    let region_starts_ms: Vec<i64> = speech.iter().map(|r| samples_to_ms(r.start)).collect();
+    group_utterances(&mut result, &region_starts_ms, &progress.path);
+    Ok(result)
+}
+
+// Read and decode a file, then transcribe it with the requested model
+pub async fn transcribe_file(
+    state: &WhisperAppState,
+    model: &str,
+    options: &InferenceOptions,
+    progress: FileProgress,
+) -> Result<FileTranscript, String> {
+    let data =
+        fs::read(&progress.path).map_err(|e| format!("Failed to read {}: {}", progress.path, e))?;
+    let mut samples = process_audio_for_whisper(&data)?;
+    drop(data);
+    if options.denoise {
+        samples = denoise_samples(&samples, WHISPER_SAMPLE_RATE);
+    }
+
+    let path = progress.path.clone();
+    let duration_ms = samples_to_ms(samples.len());
+    let options = options.for_language(&options.language);
+    let transcript = run_with_context(
+        progress.app_handle.clone(),
+        state,
+        model.to_string(),
+        move |ctx| transcribe_on_context(ctx, &samples, &options, &progress),
+    )
+    .await?;
+
+    println!(
+        "Transcribed {} ({} segments)",
+        path,
+        transcript.segments.len()
+    );
+    Ok(FileTranscript {
+        path,
+        duration_ms,
+        transcript: Some(transcript),
+        error: None,
+    })
+}
+
+// Transcribe an audio file from disk (WAV, FLAC, MP3, Ogg/Opus, WebM). Timestamps
+// are relative to the start of the file. Cancellable via the job ID announced in
+// `transcription-started`; progress is reported through `file-transcription-progress`.
+#[tauri::command]
+pub async fn whisper_transcribe_file(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    path: String,
+    model: String,
+    language: String,
+    job_id: Option<String>,
+    options: Option<TranscribeOptions>,
+) -> Result<FileTranscript, String> {
+    println!("=== WHISPER FILE TRANSCRIPTION START ===");
+    let mut options = options
+        .unwrap_or_default()
+        .into_inference_options(&state, &language)?;
+    let job = register_job(&app_handle, &state, job_id)?;
+    options.abort = Some(job.flag());
+
+    let progress = FileProgress {
+        app_handle,
+        job_id: job.key().to_string(),
+        path,
+        file_index: 0,
+        file_count: 1,
+    };
+    transcribe_file(&state, &model, &options, progress).await
+}
+
+// Transcribe several files one after another as a single cancellable job. A file
+// that fails to decode or transcribe is reported in its entry and the batch continues.
+#[tauri::command]
+pub async fn whisper_transcribe_files(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    paths: Vec<String>,
+    model: String,
+    language: String,
+    job_id: Option<String>,
+    options: Option<TranscribeOptions>,
+) -> Result<Vec<FileTranscript>, String> {
+    println!(
+        "=== WHISPER BATCH TRANSCRIPTION START ({} files) ===",
+        paths.len()
+    );
+    let mut options = options
+        .unwrap_or_default()
+        .into_inference_options(&state, &language)?;
+    let job = register_job(&app_handle, &state, job_id)?;
+    options.abort = Some(job.flag());
+
+    let file_count = paths.len();
+    let mut transcripts = Vec::with_capacity(file_count);
+    for (file_index, path) in paths.into_iter().enumerate() {
+        let progress = FileProgress {
+            app_handle: app_handle.clone(),
+            job_id: job.key().to_string(),
+            path: path.clone(),
+            file_index,
+            file_count,
+        };
+        match transcribe_file(&state, &model, &options, progress).await {
+            Ok(transcript) => transcripts.push(transcript),
+            Err(e) if e == TRANSCRIPTION_CANCELLED => return Err(e),
+            Err(e) => {
+                println!("ERROR: Failed to transcribe {}: {}", path, e);
+                transcripts.push(FileTranscript {
+                    path,
+                    duration_ms: 0,
+                    transcript: None,
+                    error: Some(e),
+                });
+            }
+        }
+    }
+
+    println!("=== WHISPER BATCH TRANSCRIPTION COMPLETE ===");
+    Ok(transcripts)
+}
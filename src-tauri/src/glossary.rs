@@ -0,0 +1,343 @@
+// Terms handled around machine translation: usernames, world names and memes are kept
+// as they are, and chosen terms get a fixed translation. Matched terms are swapped for
+// numbered placeholders the providers leave alone, then restored in the translation.
+// Saved to the app data directory so it survives restarts.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{Manager, State};
+
+use crate::translate::TranslateAppState;
+use crate::translation_cache::save_translation_cache;
+
+const GLOSSARY_FILE: &str = "glossary.json";
+
+// Some providers turn ASCII brackets into full-width ones for CJK targets
+const PLACEHOLDER_BRACKETS: [(&str, &str); 3] = [("[", "]"), ("［", "］"), ("【", "】")];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GlossaryEntry {
+    pub term: String,
+    // Forced translation; without one the term is kept untranslated
+    #[serde(default)]
+    pub translation: Option<String>,
+    // Target language the forced translation is for, any when unset
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+impl GlossaryEntry {
+    // Forced translations for another target language are left to the provider
+    fn applies_to(&self, target: &str) -> bool {
+        match &self.target {
+            Some(language) if self.translation.is_some() => same_base(language, target),
+            _ => true,
+        }
+    }
+
+    // What the term becomes in the translation, written as in the glossary
+    fn output(&self) -> String {
+        self.translation
+            .as_deref()
+            .unwrap_or(&self.term)
+            .trim()
+            .to_string()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Glossary {
+    pub enabled: bool,
+    pub entries: Vec<GlossaryEntry>,
+}
+
+impl Default for Glossary {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            entries: Vec::new(),
+        }
+    }
+}
+
+// Text with glossary terms replaced by placeholders, and what each placeholder becomes
+pub struct ProtectedText {
+    pub text: String,
+    replacements: Vec<String>,
+}
+
+fn same_base(a: &str, b: &str) -> bool {
+    let base = |code: &str| code.split('-').next().unwrap_or(code).to_lowercase();
+    base(a) == base(b)
+}
+
+// Byte ranges of `term` in `text`. ASCII terms match case-insensitively on word
+// boundaries; others (Japanese, Chinese, ...) as exact substrings.
+fn find_term(text: &str, term: &str) -> Vec<(usize, usize)> {
+    if !term.is_ascii() {
+        return text
+            .match_indices(term)
+            .map(|(start, m)| (start, start + m.len()))
+            .collect();
+    }
+    let lower = text.to_ascii_lowercase();
+    let term = term.to_ascii_lowercase();
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    lower
+        .match_indices(&term)
+        .map(|(start, _)| (start, start + term.len()))
+        .filter(|&(start, end)| {
+            !is_word(text[..start].chars().next_back()) && !is_word(text[end..].chars().next())
+        })
+        .collect()
+}
+
+impl Glossary {
+    pub fn protect(&self, text: &str, target: &str) -> ProtectedText {
+        let mut protected = ProtectedText {
+            text: text.to_string(),
+            replacements: Vec::new(),
+        };
+        if !self.enabled {
+            return protected;
+        }
+
+        // Longest terms first so "Kanna CS" wins over "Kanna"
+        let mut entries: Vec<&GlossaryEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| !entry.term.trim().is_empty() && entry.applies_to(target))
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.term.trim().len()));
+
+        // Shorter terms can't match inside text a longer one already claimed
+        let mut matches: Vec<(usize, usize, String)> = Vec::new();
+        for entry in entries {
+            for (start, end) in find_term(text, entry.term.trim()) {
+                if matches.iter().any(|&(s, e, _)| start < e && s < end) {
+                    continue;
+                }
+                matches.push((start, end, entry.output()));
+            }
+        }
+        matches.sort_by_key(|&(start, ..)| start);
+
+        let mut protected_text = String::with_capacity(text.len());
+        let mut last = 0;
+        for (start, end, output) in matches {
+            protected_text.push_str(&text[last..start]);
+            protected_text.push_str(&format!("[{}]", protected.replacements.len()));
+            protected.replacements.push(output);
+            last = end;
+        }
+        protected_text.push_str(&text[last..]);
+        protected.text = protected_text;
+        protected
+    }
+}
+
+// Next placeholder in `text` with an index below `count`: its byte range and index.
+// Providers sometimes pad the number with spaces ("[ 0 ]", "[0 ]").
+fn find_placeholder(text: &str, count: usize) -> Option<(usize, usize, usize)> {
+    text.char_indices().find_map(|(start, _)| {
+        PLACEHOLDER_BRACKETS.iter().find_map(|(open, close)| {
+            let inner = text[start..].strip_prefix(open)?.trim_start();
+            let digits = inner.len() - inner.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let index = inner[..digits]
+                .parse::<usize>()
+                .ok()
+                .filter(|&i| i < count)?;
+            let after = inner[digits..].trim_start().strip_prefix(close)?;
+            Some((start, text.len() - after.len(), index))
+        })
+    })
+}
+
+impl ProtectedText {
+    pub fn is_empty(&self) -> bool {
+        self.replacements.is_empty()
+    }
+
+    // Put the glossary terms back into the translated text
+    pub fn restore(&self, translated: &str) -> String {
+        let mut restored = String::with_capacity(translated.len());
+        let mut found = vec![false; self.replacements.len()];
+        let mut rest = translated;
+        while let Some((start, end, index)) = find_placeholder(rest, self.replacements.len()) {
+            restored.push_str(&rest[..start]);
+            restored.push_str(&self.replacements[index]);
+            found[index] = true;
+            rest = &rest[end..];
+        }
+        restored.push_str(rest);
+
+        for (output, found) in self.replacements.iter().zip(found) {
+            if !found {
+                println!("Glossary term '{}' was lost in translation", output);
+            }
+        }
+        restored
+    }
+}
+
+fn glossary_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join(GLOSSARY_FILE))
+}
+
+// Restore the saved glossary at startup; a missing file means no terms yet
+pub fn load_glossary(app_handle: &tauri::AppHandle) {
+    let Ok(path) = glossary_path(app_handle) else {
+        return;
+    };
+    let Ok(data) = fs::read_to_string(&path) else {
+        return;
+    };
+    match serde_json::from_str::<Glossary>(&data) {
+        Ok(glossary) => {
+            println!("Loaded {} glossary term(s)", glossary.entries.len());
+            let state = app_handle.state::<TranslateAppState>();
+            if let Ok(mut current) = state.glossary.lock() {
+                *current = glossary;
+            }
+        }
+        Err(e) => println!("Ignoring invalid {}: {}", GLOSSARY_FILE, e),
+    }
+}
+
+fn save_glossary(app_handle: &tauri::AppHandle, glossary: &Glossary) -> Result<(), String> {
+    let path = glossary_path(app_handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let data = serde_json::to_string_pretty(glossary)
+        .map_err(|e| format!("Failed to serialize glossary: {}", e))?;
+    fs::write(&path, data).map_err(|e| format!("Failed to save glossary: {}", e))
+}
+
+#[tauri::command]
+pub fn translate_get_glossary(state: State<'_, TranslateAppState>) -> Result<Glossary, String> {
+    Ok(state
+        .glossary
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+#[tauri::command]
+pub fn translate_set_glossary(
+    app_handle: tauri::AppHandle,
+    state: State<'_, TranslateAppState>,
+    mut glossary: Glossary,
+) -> Result<(), String> {
+    glossary
+        .entries
+        .retain(|entry| !entry.term.trim().is_empty());
+    save_glossary(&app_handle, &glossary)?;
+    println!(
+        "Updated glossary: enabled {}, {} terms",
+        glossary.enabled,
+        glossary.entries.len()
+    );
+    *state
+        .glossary
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = glossary;
+
+    // Cached translations were made with the old terms
+    let mut cache = state
+        .cache
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    cache.clear();
+    save_translation_cache(&app_handle, &cache);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glossary(entries: &[(&str, Option<&str>, Option<&str>)]) -> Glossary {
+        Glossary {
+            enabled: true,
+            entries: entries
+                .iter()
+                .map(|&(term, translation, target)| GlossaryEntry {
+                    term: term.to_string(),
+                    translation: translation.map(str::to_string),
+                    target: target.map(str::to_string),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn longer_terms_win_over_overlapping_ones() {
+        let names = glossary(&[("Kanna", None, None), ("Kanna CS", None, None)]);
+        let protected = names.protect("Kanna CS meets Kanna", "ja");
+        assert_eq!(protected.text, "[0] meets [1]");
+        assert_eq!(protected.restore("[0]が[1]に会う"), "Kanna CSがKannaに会う");
+
+        let places = glossary(&[("New York", None, None), ("York City", None, None)]);
+        assert_eq!(places.protect("New York City", "fr").text, "New [0]");
+    }
+
+    #[test]
+    fn terms_never_match_inside_placeholders() {
+        let glossary = glossary(&[("Kanna CS", None, None), ("0", Some("zero"), None)]);
+        let protected = glossary.protect("Kanna CS has 0 ideas", "de");
+        assert_eq!(protected.text, "[0] has [1] ideas");
+        assert_eq!(
+            protected.restore("[0] hat [1] Ideen"),
+            "Kanna CS hat zero Ideen"
+        );
+    }
+
+    #[test]
+    fn case_variants_match_and_restore_as_written_in_the_glossary() {
+        let glossary = glossary(&[("VRChat", None, None)]);
+        let protected = glossary.protect("i love vrchat and VRCHAT", "fr");
+        assert_eq!(protected.text, "i love [0] and [1]");
+        assert_eq!(
+            protected.restore("j'adore [0] et [1]"),
+            "j'adore VRChat et VRChat"
+        );
+        // Only whole words match
+        assert!(glossary.protect("vrchats", "fr").is_empty());
+    }
+
+    #[test]
+    fn mangled_placeholder_spacing_is_restored() {
+        let glossary = glossary(&[("Kanna", None, None)]);
+        let protected = glossary.protect("Kanna Kanna Kanna Kanna Kanna", "ja");
+        assert_eq!(
+            protected.restore("[ 0 ] [1 ] [ 2] ［３］ 【 4 】"),
+            "Kanna Kanna Kanna ［３］ Kanna"
+        );
+        assert_eq!(
+            protected.restore("[0]、［ 1 ］、[2]、【3】、[4]"),
+            "Kanna、Kanna、Kanna、Kanna、Kanna"
+        );
+    }
+
+    #[test]
+    fn unknown_placeholders_are_left_alone() {
+        let glossary = glossary(&[("Kanna", None, None)]);
+        let protected = glossary.protect("hi Kanna", "ja");
+        assert_eq!(protected.restore("[1] [x] [0]"), "[1] [x] Kanna");
+    }
+
+    #[test]
+    fn forced_translations_only_apply_to_their_target() {
+        let glossary = glossary(&[("pizza", Some("ピッツァ"), Some("ja"))]);
+        let protected = glossary.protect("pizza time", "ja-JP");
+        assert_eq!(protected.restore("[0]タイム"), "ピッツァタイム");
+        assert!(glossary.protect("pizza time", "fr").is_empty());
+    }
+}
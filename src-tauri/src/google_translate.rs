@@ -0,0 +1,164 @@
+// Google's free web translation endpoint (the one the browser extension uses). It
+// needs no key, which makes it the zero-configuration default. Several texts go out
+// in one request, one per line, to stay clear of its unpublished rate limit.
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::translate::{TranslateError, TranslationProvider};
+
+const ENDPOINT: &str = "https://translate.googleapis.com/translate_a/single";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+// The text travels in the query string; URL-encoded CJK text triples in size and
+// the endpoint rejects URLs much beyond 16KB
+const MAX_BATCH_BYTES: usize = 4000;
+
+#[derive(Deserialize)]
+struct GoogleResponse {
+    #[serde(default)]
+    sentences: Vec<GoogleSentence>,
+}
+
+#[derive(Deserialize)]
+struct GoogleSentence {
+    #[serde(default)]
+    trans: Option<String>,
+}
+
+// Group texts into batches that fit one request; a text too long for any batch
+// still goes out on its own
+fn batches(texts: &[String]) -> Vec<&[String]> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut bytes = 0;
+    for (index, text) in texts.iter().enumerate() {
+        if index > start && bytes + text.len() + 1 > MAX_BATCH_BYTES {
+            batches.push(&texts[start..index]);
+            start = index;
+            bytes = 0;
+        }
+        bytes += text.len() + 1;
+    }
+    if start < texts.len() {
+        batches.push(&texts[start..]);
+    }
+    batches
+}
+
+fn map_request_error(e: reqwest::Error) -> TranslateError {
+    if e.is_timeout() {
+        TranslateError::Unavailable("Google Translate timed out".to_string())
+    } else {
+        TranslateError::Unavailable(format!("Google Translate request failed: {}", e))
+    }
+}
+
+pub struct GoogleTranslate;
+
+impl GoogleTranslate {
+    // Translate newline-separated text, returning the translated lines
+    async fn request(
+        &self,
+        client: &reqwest::Client,
+        text: &str,
+        source: &str,
+        target: &str,
+    ) -> Result<Vec<String>, TranslateError> {
+        let response = client
+            .get(ENDPOINT)
+            .query(&[
+                ("client", "gtx"),
+                ("sl", source),
+                ("tl", target),
+                ("dt", "t"),
+                ("dj", "1"),
+                ("q", text),
+            ])
+            .send()
+            .await
+            .map_err(map_request_error)?;
+        let status = response.status();
+        match status.as_u16() {
+            // Throttled clients get a captcha page, usually as 429 and sometimes 403
+            403 | 429 => {
+                return Err(TranslateError::RateLimited(format!(
+                    "Google Translate returned {}",
+                    status
+                )))
+            }
+            _ if status.is_server_error() => {
+                return Err(TranslateError::Unavailable(format!(
+                    "Google Translate returned {}",
+                    status
+                )))
+            }
+            _ if !status.is_success() => {
+                return Err(TranslateError::Failed(format!(
+                    "Google Translate returned {}",
+                    status
+                )))
+            }
+            _ => {}
+        }
+
+        let body: GoogleResponse = response.json().await.map_err(|e| {
+            TranslateError::Failed(format!("Invalid Google Translate response: {}", e))
+        })?;
+        let translated: String = body
+            .sentences
+            .into_iter()
+            .filter_map(|sentence| sentence.trans)
+            .collect();
+        if translated.trim().is_empty() {
+            return Err(TranslateError::Failed(
+                "Google Translate returned an empty translation".to_string(),
+            ));
+        }
+        Ok(translated
+            .lines()
+            .map(|line| line.trim().to_string())
+            .collect())
+    }
+}
+
+impl TranslationProvider for GoogleTranslate {
+    fn name(&self) -> &'static str {
+        "google"
+    }
+
+    async fn translate(
+        &self,
+        texts: &[String],
+        source: &str,
+        target: &str,
+    ) -> Result<Vec<String>, TranslateError> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| TranslateError::Config(format!("Failed to create HTTP client: {}", e)))?;
+
+        let mut translated = Vec::with_capacity(texts.len());
+        for batch in batches(texts) {
+            // Line breaks inside a text would throw off the line count
+            let lines: Vec<String> = batch.iter().map(|text| text.replace('\n', " ")).collect();
+            let result = self
+                .request(&client, &lines.join("\n"), source, target)
+                .await?;
+            if result.len() == batch.len() {
+                translated.extend(result);
+                continue;
+            }
+
+            // Google merged or split lines; translate this batch one text at a time
+            println!(
+                "Google Translate returned {} lines for {} texts, retrying individually",
+                result.len(),
+                batch.len()
+            );
+            for line in &lines {
+                let result = self.request(&client, line, source, target).await?;
+                translated.push(result.join(" "));
+            }
+        }
+        Ok(translated)
+    }
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+// Inference backends whisper.cpp can be built with. Only the ones compiled in via
+// cargo features (and whose drivers are present at runtime) can actually be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WhisperBackend {
+    Cpu,
+    Cuda,
+    Vulkan,
+    Metal,
+}
+
+impl WhisperBackend {
+    pub fn parse(backend: &str) -> Result<Self, String> {
+        match backend.to_lowercase().as_str() {
+            "cpu" => Ok(WhisperBackend::Cpu),
+            "cuda" => Ok(WhisperBackend::Cuda),
+            "vulkan" => Ok(WhisperBackend::Vulkan),
+            "metal" => Ok(WhisperBackend::Metal),
+            _ => Err(format!("Unknown backend: {}", backend)),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WhisperBackend::Cpu => "cpu",
+            WhisperBackend::Cuda => "cuda",
+            WhisperBackend::Vulkan => "vulkan",
+            WhisperBackend::Metal => "metal",
+        }
+    }
+
+    pub fn uses_gpu(self) -> bool {
+        self != WhisperBackend::Cpu
+    }
+
+    // Whether whisper.cpp was built with support for this backend
+    fn is_compiled(self) -> bool {
+        match self {
+            WhisperBackend::Cpu => true,
+            WhisperBackend::Cuda => cfg!(feature = "cuda"),
+            WhisperBackend::Vulkan => cfg!(feature = "vulkan"),
+            WhisperBackend::Metal => cfg!(all(feature = "metal", target_os = "macos")),
+        }
+    }
+
+    // Backends whisper.cpp can be built with, best first
+    const CANDIDATES: [WhisperBackend; 4] = [
+        WhisperBackend::Cuda,
+        WhisperBackend::Metal,
+        WhisperBackend::Vulkan,
+        WhisperBackend::Cpu,
+    ];
+
+    // Map a ggml backend registry name ("CUDA", "Vulkan", "Metal"/"MTL") to a backend
+    fn from_registry(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "cuda" => Some(WhisperBackend::Cuda),
+            "vulkan" => Some(WhisperBackend::Vulkan),
+            "metal" | "mtl" => Some(WhisperBackend::Metal),
+            _ => None,
+        }
+    }
+}
+
+// A GPU as ggml enumerates it. whisper.cpp counts these in registry order and uses
+// the one at `WhisperContextParameters::gpu_device`, so that index is what selects a
+// backend when several (e.g. CUDA and Vulkan) are compiled in.
+#[derive(Clone, Debug)]
+pub struct GpuDevice {
+    pub backend: WhisperBackend,
+    pub index: i32,
+    pub name: String,
+}
+
+// The ggml device registry that whisper-rs links in. whisper-rs doesn't wrap it, but
+// it is the same list whisper.cpp picks its GPU from.
+mod ggml {
+    use std::ffi::{c_char, c_void};
+
+    pub type Device = *mut c_void;
+    pub type Registry = *mut c_void;
+
+    pub const DEVICE_TYPE_CPU: i32 = 0;
+
+    extern "C" {
+        pub fn ggml_backend_dev_count() -> usize;
+        pub fn ggml_backend_dev_get(index: usize) -> Device;
+        pub fn ggml_backend_dev_name(device: Device) -> *const c_char;
+        pub fn ggml_backend_dev_type(device: Device) -> i32;
+        pub fn ggml_backend_dev_backend_reg(device: Device) -> Registry;
+        pub fn ggml_backend_reg_name(registry: Registry) -> *const c_char;
+    }
+}
+
+fn c_string(ptr: *const std::ffi::c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    // SAFETY: ggml returns NUL-terminated names that live as long as the registry
+    unsafe { std::ffi::CStr::from_ptr(ptr) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+// List the GPUs ggml can use, in the order whisper.cpp numbers them
+pub fn gpu_devices() -> Vec<GpuDevice> {
+    let mut devices = Vec::new();
+    // SAFETY: the registry is initialized on first use and never freed; indices come
+    // from ggml_backend_dev_count
+    unsafe {
+        for i in 0..ggml::ggml_backend_dev_count() {
+            let device = ggml::ggml_backend_dev_get(i);
+            if device.is_null() || ggml::ggml_backend_dev_type(device) == ggml::DEVICE_TYPE_CPU {
+                continue;
+            }
+            let registry = c_string(ggml::ggml_backend_reg_name(
+                ggml::ggml_backend_dev_backend_reg(device),
+            ));
+            // Accelerator devices such as BLAS aren't GPUs whisper.cpp can select
+            let Some(backend) = WhisperBackend::from_registry(&registry) else {
+                continue;
+            };
+            devices.push(GpuDevice {
+                backend,
+                index: devices.len() as i32,
+                name: c_string(ggml::ggml_backend_dev_name(device)),
+            });
+        }
+    }
+    devices
+}
+
+// The first GPU of the given backend, which whisper.cpp will use for that index
+pub fn gpu_device(backend: WhisperBackend) -> Option<GpuDevice> {
+    gpu_devices().into_iter().find(|d| d.backend == backend)
+}
+
+// Probe which backends can be initialized on this machine, best first
+pub fn detect_backends() -> Vec<WhisperBackend> {
+    let devices = gpu_devices();
+    let available: Vec<WhisperBackend> = WhisperBackend::CANDIDATES
+        .into_iter()
+        .filter(|b| b.is_compiled() && (!b.uses_gpu() || devices.iter().any(|d| d.backend == *b)))
+        .collect();
+
+    println!(
+        "Detected Whisper backends: {:?} (GPUs: {:?})",
+        available.iter().map(|b| b.as_str()).collect::<Vec<_>>(),
+        devices.iter().map(|d| d.name.as_str()).collect::<Vec<_>>()
+    );
+    available
+}
+
+// Pick the fastest available backend, falling back to the CPU
+pub fn preferred_backend(available: &[WhisperBackend]) -> WhisperBackend {
+    available.first().copied().unwrap_or(WhisperBackend::Cpu)
+}
@@ -0,0 +1,306 @@
+// Post-processing that drops text Whisper invents rather than hears: subtitle credits
+// from its training data, repetition loops, and output decoded from near-silence.
+use serde::{Deserialize, Serialize};
+
+use crate::whisper::{TranscriptionResult, TranscriptionSegment, WHISPER_SAMPLE_RATE};
+
+// Credit and outro lines that leak in from subtitled training videos, normalized. They
+// only count when they make up the segment (optionally followed by a credited name),
+// so speech that merely mentions subtitles or subscribing is kept.
+const KNOWN_HALLUCINATIONS: &[&str] = &[
+    "subtitles by",
+    "subtitled by",
+    "captions by",
+    "captioning by",
+    "transcribed by",
+    "transcription by",
+    "amaraorg",
+    "thanks for watching",
+    "thank you for watching",
+    "please subscribe",
+    "like and subscribe",
+    "ご視聴ありがとうございました",
+    "チャンネル登録よろしくお願いします",
+    "チャンネル登録お願いします",
+    "字幕",
+];
+
+// Longest phrase (in words) checked for repetition loops
+const MAX_NGRAM: usize = 6;
+
+// Segments below this log-probability are low confidence, as in the Whisper reference
+const LOW_LOGPROB: f32 = -1.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HallucinationFilter {
+    pub enabled: bool,
+    // Drop segments that are subtitle credits or outro lines
+    pub drop_known_phrases: bool,
+    // Consecutive repeats of a phrase kept before the rest of the segment is cut off
+    pub max_repeats: usize,
+    // Low-confidence segments above this no-speech probability are dropped
+    pub no_speech_probability: f32,
+    // Segments whose audio RMS is below this are treated as silence
+    pub min_rms: f32,
+}
+
+impl Default for HallucinationFilter {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            drop_known_phrases: true,
+            max_repeats: 2,
+            no_speech_probability: 0.6,
+            min_rms: 0.005,
+        }
+    }
+}
+
+impl HallucinationFilter {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_repeats == 0 {
+            return Err("Max repeats must be at least 1".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.no_speech_probability) {
+            return Err(format!(
+                "No-speech probability must be between 0 and 1, got {}",
+                self.no_speech_probability
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.min_rms) {
+            return Err(format!(
+                "Minimum RMS must be between 0 and 1, got {}",
+                self.min_rms
+            ));
+        }
+        Ok(())
+    }
+
+    // Filter a whole result from any provider. Results with segments are filtered per
+    // segment and their text rebuilt from what is kept; text-only results (Wyoming)
+    // only get the checks that don't need timestamps or probabilities. Known phrases
+    // are left alone there too: one credit line could take a whole transcript with it.
+    pub fn apply_to_result(&self, result: &mut TranscriptionResult, samples: &[f32]) {
+        if !self.enabled {
+            return;
+        }
+        if result.segments.is_empty() {
+            result.text = self.apply_to_text(&result.text, samples);
+            return;
+        }
+        result.segments = self.apply(std::mem::take(&mut result.segments), samples);
+        result.text = result
+            .segments
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+
+    // Text-only filtering, with `samples` being all the audio the text was decoded from
+    fn apply_to_text(&self, text: &str, samples: &[f32]) -> String {
+        if text.is_empty() {
+            return String::new();
+        }
+        if let Some(reason) = self.text_rejection_reason(samples) {
+            println!("Dropped hallucinated transcript '{}': {}", text, reason);
+            return String::new();
+        }
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let normalized: Vec<String> = words.iter().map(|w| normalize(w)).collect();
+        match repetition_cut(&normalized, self.max_repeats) {
+            Some(cut) => {
+                println!(
+                    "Truncated repetition loop in '{}' after {} words",
+                    text, cut
+                );
+                words[..cut].join(" ")
+            }
+            None => text.to_string(),
+        }
+    }
+
+    fn text_rejection_reason(&self, samples: &[f32]) -> Option<String> {
+        let rms = rms(samples)?;
+        if rms < self.min_rms {
+            return Some(format!("near-silent audio (RMS {:.4})", rms));
+        }
+        None
+    }
+
+    fn known_phrase(&self, text: &str) -> Option<&'static str> {
+        if !self.drop_known_phrases {
+            return None;
+        }
+        let text = normalize(text);
+        KNOWN_HALLUCINATIONS
+            .iter()
+            .find(|phrase| {
+                text.strip_prefix(**phrase)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+            })
+            .copied()
+    }
+
+    // Filter decoded segments; `samples` is the audio the segment timestamps refer to
+    pub fn apply(
+        &self,
+        segments: Vec<TranscriptionSegment>,
+        samples: &[f32],
+    ) -> Vec<TranscriptionSegment> {
+        if !self.enabled {
+            return segments;
+        }
+
+        let mut kept: Vec<TranscriptionSegment> = Vec::with_capacity(segments.len());
+        let mut repeat_count = 0;
+        for mut segment in segments {
+            if let Some(reason) = self.rejection_reason(&segment, samples) {
+                println!(
+                    "Dropped hallucinated segment '{}': {}",
+                    segment.text, reason
+                );
+                continue;
+            }
+
+            self.truncate_repetition(&mut segment);
+
+            // The same sentence decoded over and over across segments
+            let key = normalize(&segment.text);
+            if kept.last().is_some_and(|last| normalize(&last.text) == key) {
+                repeat_count += 1;
+                if repeat_count >= self.max_repeats {
+                    println!("Dropped repeated segment '{}'", segment.text);
+                    continue;
+                }
+            } else {
+                repeat_count = 0;
+            }
+            kept.push(segment);
+        }
+        kept
+    }
+
+    fn rejection_reason(&self, segment: &TranscriptionSegment, samples: &[f32]) -> Option<String> {
+        if let Some(phrase) = self.known_phrase(&segment.text) {
+            return Some(format!("known phrase '{}'", phrase));
+        }
+
+        if segment.no_speech_probability > self.no_speech_probability
+            && segment.avg_logprob < LOW_LOGPROB
+        {
+            return Some(format!(
+                "no-speech probability {:.2}",
+                segment.no_speech_probability
+            ));
+        }
+
+        let rms = segment_rms(segment, samples)?;
+        if rms < self.min_rms {
+            return Some(format!("near-silent audio (RMS {:.4})", rms));
+        }
+        None
+    }
+
+    // Cut a segment off once a phrase repeats more than `max_repeats` times in a row
+    fn truncate_repetition(&self, segment: &mut TranscriptionSegment) {
+        let words: Vec<&str> = segment.text.split_whitespace().collect();
+        let normalized: Vec<String> = words.iter().map(|w| normalize(w)).collect();
+        let Some(cut) = repetition_cut(&normalized, self.max_repeats) else {
+            return;
+        };
+
+        println!(
+            "Truncated repetition loop in '{}' after {} words",
+            segment.text, cut
+        );
+        segment.text = words[..cut].join(" ");
+        // Token words line up with whitespace words except for unusual tokenizations
+        if segment.words.len() == words.len() {
+            segment.words.truncate(cut);
+            if let Some(last) = segment.words.last() {
+                segment.end_ms = last.end_ms;
+            }
+        }
+    }
+}
+
+// Lowercase and strip punctuation so "Okay." and "okay," compare equal
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Word index at which to cut the first phrase repeated more than `max_repeats` times
+fn repetition_cut(words: &[String], max_repeats: usize) -> Option<usize> {
+    for start in 0..words.len() {
+        for n in 1..=MAX_NGRAM {
+            let Some(phrase) = words.get(start..start + n) else {
+                break;
+            };
+            let mut repeats = 1;
+            while words.get(start + repeats * n..start + (repeats + 1) * n) == Some(phrase) {
+                repeats += 1;
+            }
+            if repeats > max_repeats {
+                return Some(start + n * max_repeats);
+            }
+        }
+    }
+    None
+}
+
+// Root-mean-square level of the audio under a segment
+fn segment_rms(segment: &TranscriptionSegment, samples: &[f32]) -> Option<f32> {
+    let samples_per_ms = WHISPER_SAMPLE_RATE as i64 / 1000;
+    let start = (segment.start_ms.max(0) * samples_per_ms) as usize;
+    let end = ((segment.end_ms.max(0) * samples_per_ms) as usize).min(samples.len());
+    rms(samples.get(start..end)?)
+}
+
+fn rms(audio: &[f32]) -> Option<f32> {
+    if audio.is_empty() {
+        return None;
+    }
+    Some((audio.iter().map(|s| s * s).sum::<f32>() / audio.len() as f32).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_known(text: &str) -> bool {
+        HallucinationFilter::default().known_phrase(text).is_some()
+    }
+
+    #[test]
+    fn credit_lines_are_known_phrases() {
+        assert!(is_known("Thanks for watching!"));
+        assert!(is_known("Subtitles by the Amara.org community"));
+        assert!(is_known("ご視聴ありがとうございました"));
+        assert!(is_known("字幕"));
+    }
+
+    #[test]
+    fn speech_mentioning_a_phrase_is_kept() {
+        assert!(!is_known("字幕をオンにして"));
+        assert!(!is_known("I said thanks for watching my stream"));
+        assert!(!is_known("thanks for watchingg"));
+    }
+
+    #[test]
+    fn text_only_results_keep_known_phrases() {
+        let audio = vec![0.1; WHISPER_SAMPLE_RATE as usize];
+        let text = "Thanks for watching, see you tomorrow";
+        assert_eq!(
+            HallucinationFilter::default().apply_to_text(text, &audio),
+            text
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+use sysinfo::System;
+use tauri::State;
+
+use crate::gpu::WhisperBackend;
+use crate::whisper::WhisperAppState;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+// VRChat itself wants a few GB of VRAM, only count what's left over
+pub const VRCHAT_VRAM_RESERVE: u64 = 3 * GIB;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GpuInfo {
+    pub name: String,
+    // None when the driver doesn't report it
+    pub vram_bytes: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ModelRecommendation {
+    pub model: String,
+    pub backend: WhisperBackend,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HardwareProfile {
+    pub cpu_name: String,
+    pub logical_cores: usize,
+    pub physical_cores: Option<usize>,
+    // SIMD extensions whisper.cpp makes use of (avx, avx2, f16c, fma, avx512f, neon)
+    pub simd: Vec<String>,
+    pub total_memory_bytes: u64,
+    pub available_memory_bytes: u64,
+    pub gpus: Vec<GpuInfo>,
+    pub backends: Vec<WhisperBackend>,
+    pub recommendation: ModelRecommendation,
+}
+
+fn detect_simd() -> Vec<String> {
+    let mut simd = Vec::new();
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx") {
+            simd.push("avx".to_string());
+        }
+        if is_x86_feature_detected!("avx2") {
+            simd.push("avx2".to_string());
+        }
+        if is_x86_feature_detected!("fma") {
+            simd.push("fma".to_string());
+        }
+        if is_x86_feature_detected!("f16c") {
+            simd.push("f16c".to_string());
+        }
+        if is_x86_feature_detected!("avx512f") {
+            simd.push("avx512f".to_string());
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            simd.push("neon".to_string());
+        }
+    }
+    simd
+}
+
+// Run a probe tool without flashing a console window on Windows
+fn probe_command(program: &str, args: &[&str]) -> Option<String> {
+    let mut command = Command::new(program);
+    command.args(args);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// nvidia-smi reports exact VRAM for every NVIDIA GPU on all platforms
+fn detect_nvidia_gpus() -> Vec<GpuInfo> {
+    let Some(output) = probe_command(
+        "nvidia-smi",
+        &[
+            "--query-gpu=name,memory.total",
+            "--format=csv,noheader,nounits",
+        ],
+    ) else {
+        return Vec::new();
+    };
+
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, vram_mib) = line.rsplit_once(',')?;
+            Some(GpuInfo {
+                name: name.trim().to_string(),
+                vram_bytes: vram_mib
+                    .trim()
+                    .parse::<u64>()
+                    .ok()
+                    .map(|mib| mib * 1024 * 1024),
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn detect_other_gpus() -> Vec<GpuInfo> {
+    // amdgpu exposes VRAM size through sysfs
+    let Ok(entries) = std::fs::read_dir("/sys/class/drm") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.starts_with("card") && !name.contains('-')
+        })
+        .filter_map(|entry| {
+            let device = entry.path().join("device");
+            let vendor = std::fs::read_to_string(device.join("vendor")).ok()?;
+            let name = match vendor.trim() {
+                "0x1002" => "AMD GPU",
+                "0x8086" => "Intel GPU",
+                _ => return None,
+            };
+            let vram_bytes = std::fs::read_to_string(device.join("mem_info_vram_total"))
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok());
+            Some(GpuInfo {
+                name: name.to_string(),
+                vram_bytes,
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+fn detect_other_gpus() -> Vec<GpuInfo> {
+    // AdapterRAM is a 32-bit field, so cards with more than 4GB report 4GB at most
+    let Some(output) = probe_command(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_VideoController | ForEach-Object { \"$($_.Name)|$($_.AdapterRAM)\" }",
+        ],
+    ) else {
+        return Vec::new();
+    };
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, vram) = line.trim().rsplit_once('|')?;
+            if name.to_lowercase().contains("nvidia") {
+                return None;
+            }
+            Some(GpuInfo {
+                name: name.to_string(),
+                vram_bytes: vram.parse::<u64>().ok().filter(|&v| v > 0),
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn detect_other_gpus() -> Vec<GpuInfo> {
+    // Apple Silicon GPUs share system memory, reported by the caller
+    if cfg!(target_arch = "aarch64") {
+        vec![GpuInfo {
+            name: "Apple Silicon GPU".to_string(),
+            vram_bytes: None,
+        }]
+    } else {
+        Vec::new()
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn detect_other_gpus() -> Vec<GpuInfo> {
+    Vec::new()
+}
+
+fn detect_gpus() -> Vec<GpuInfo> {
+    let mut gpus = detect_nvidia_gpus();
+    gpus.extend(detect_other_gpus());
+    // Containers and VMs may hide sysfs; the CUDA driver alone still implies a GPU
+    if gpus.is_empty() && Path::new("/proc/driver/nvidia").exists() {
+        gpus.push(GpuInfo {
+            name: "NVIDIA GPU".to_string(),
+            vram_bytes: None,
+        });
+    }
+    gpus
+}
+
+// VRAM of the largest GPU. Unified memory on Apple Silicon: leave half for the
+// system and VRChat.
+fn usable_vram(
+    gpus: &[GpuInfo],
+    total_memory: u64,
+    gpu_backend: Option<WhisperBackend>,
+) -> Option<u64> {
+    gpus.iter()
+        .filter_map(|gpu| gpu.vram_bytes)
+        .max()
+        .or_else(|| (gpu_backend == Some(WhisperBackend::Metal)).then_some(total_memory / 2))
+}
+
+// Probe the VRAM available to a GPU backend; None when it can't be determined
+pub fn detect_vram(backend: WhisperBackend) -> Option<u64> {
+    let mut system = System::new();
+    system.refresh_memory();
+    usable_vram(&detect_gpus(), system.total_memory(), Some(backend))
+}
+
+// Memory a model needs at runtime, in bytes, for the GPU/RAM fit check
+pub fn model_footprint(model: &str) -> u64 {
+    match model {
+        "tiny" | "tiny-q5_1" | "tiny-q8_0" | "base" | "base-q5_1" | "base-q8_0" => GIB / 2,
+        "small-q5_1" | "small-q8_0" => GIB,
+        "small" => 3 * GIB / 2,
+        "medium-q5_0" | "medium-q8_0" => 2 * GIB,
+        "medium" => 3 * GIB,
+        "large-q5_0" => 5 * GIB / 2,
+        _ => 5 * GIB,
+    }
+}
+
+// Map a hardware profile to the largest model likely to keep up with live speech
+pub fn recommend_model(
+    logical_cores: usize,
+    simd: &[String],
+    total_memory: u64,
+    gpus: &[GpuInfo],
+    backends: &[WhisperBackend],
+) -> ModelRecommendation {
+    let gpu_backend = backends.iter().copied().find(|b| b.uses_gpu());
+    let vram = usable_vram(gpus, total_memory, gpu_backend);
+
+    if let Some(backend) = gpu_backend {
+        let Some(vram) = vram else {
+            return ModelRecommendation {
+                model: "small".to_string(),
+                backend,
+                reason: format!(
+                    "{} acceleration (VRAM size unknown)",
+                    backend.as_str().to_uppercase()
+                ),
+            };
+        };
+
+        let spare = vram.saturating_sub(VRCHAT_VRAM_RESERVE);
+        let model = ["large", "large-q5_0", "medium", "medium-q5_0", "small"]
+            .into_iter()
+            .find(|model| model_footprint(model) <= spare)
+            .unwrap_or("base");
+        return ModelRecommendation {
+            model: model.to_string(),
+            backend,
+            reason: format!(
+                "{} acceleration with {:.1} GB of VRAM",
+                backend.as_str().to_uppercase(),
+                vram as f64 / GIB as f64
+            ),
+        };
+    }
+
+    // CPU inference: speed is the limit, quantized models decode noticeably faster
+    let has_fast_simd = simd.iter().any(|s| s == "avx2" || s == "neon");
+    let model = if !has_fast_simd || logical_cores < 4 {
+        "tiny"
+    } else if logical_cores < 8 {
+        "base-q8_0"
+    } else if logical_cores < 16 {
+        "small-q5_1"
+    } else {
+        "small"
+    };
+    let model = if model_footprint(model) > total_memory / 4 {
+        "tiny"
+    } else {
+        model
+    };
+
+    ModelRecommendation {
+        model: model.to_string(),
+        backend: WhisperBackend::Cpu,
+        reason: format!(
+            "CPU inference on {} threads{}",
+            logical_cores,
+            if has_fast_simd {
+                ""
+            } else {
+                " without AVX2/NEON"
+            }
+        ),
+    }
+}
+
+fn probe_hardware(backends: Vec<WhisperBackend>) -> HardwareProfile {
+    let mut system = System::new();
+    system.refresh_memory();
+    system.refresh_cpu();
+
+    let cpu_name = system
+        .cpus()
+        .first()
+        .map(|cpu| cpu.brand().trim().to_string())
+        .unwrap_or_default();
+    let logical_cores = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let simd = detect_simd();
+
+    let gpus = detect_gpus();
+    let total_memory = system.total_memory();
+    let recommendation = recommend_model(logical_cores, &simd, total_memory, &gpus, &backends);
+
+    HardwareProfile {
+        cpu_name,
+        logical_cores,
+        physical_cores: system.physical_core_count(),
+        simd,
+        total_memory_bytes: total_memory,
+        available_memory_bytes: system.available_memory(),
+        gpus,
+        backends,
+        recommendation,
+    }
+}
+
+#[tauri::command]
+pub async fn get_hardware_profile(
+    state: State<'_, WhisperAppState>,
+) -> Result<HardwareProfile, String> {
+    let backends = state.available_backends.clone();
+    let profile = tokio::task::spawn_blocking(move || probe_hardware(backends))
+        .await
+        .map_err(|e| format!("Hardware probe failed: {}", e))?;
+
+    println!(
+        "Hardware: {} ({} threads, {:?}), {:.1} GB RAM, GPUs: {:?} -> recommend {} on {}",
+        profile.cpu_name,
+        profile.logical_cores,
+        profile.simd,
+        profile.total_memory_bytes as f64 / GIB as f64,
+        profile
+            .gpus
+            .iter()
+            .map(|g| g.name.as_str())
+            .collect::<Vec<_>>(),
+        profile.recommendation.model,
+        profile.recommendation.backend.as_str()
+    );
+    Ok(profile)
+}
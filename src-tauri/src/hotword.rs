@@ -0,0 +1,165 @@
+// Wake-word gating: while enabled, transcripts are only passed on after the user
+// says a wake phrase ("hey chat"), so not every utterance ends up in the chatbox.
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HotwordConfig {
+    pub enabled: bool,
+    pub phrases: Vec<String>,
+    // How long transcription stays armed after the wake phrase or the last utterance
+    pub armed_seconds: u32,
+    // Smaller local model used to listen for the wake phrase while disarmed
+    pub detector_model: Option<String>,
+}
+
+impl Default for HotwordConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            phrases: vec!["hey chat".to_string()],
+            armed_seconds: 10,
+            detector_model: None,
+        }
+    }
+}
+
+impl HotwordConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.enabled && self.phrases.iter().all(|p| normalize_words(p).is_empty()) {
+            return Err("At least one wake phrase is required".to_string());
+        }
+        if !(1..=600).contains(&self.armed_seconds) {
+            return Err(format!(
+                "Armed duration must be between 1 and 600 seconds, got {}",
+                self.armed_seconds
+            ));
+        }
+        Ok(())
+    }
+}
+
+// What to do with a transcript under the gate
+pub enum GateOutcome {
+    // Pass this text on (the wake phrase already stripped)
+    Forward(String),
+    // The wake phrase was heard; forward whatever followed it, if anything
+    Armed { phrase: String, remainder: String },
+    // Disarmed and no wake phrase, drop the transcript
+    Suppressed,
+}
+
+#[derive(Default)]
+pub struct HotwordGate {
+    pub config: HotwordConfig,
+    armed_until: Option<Instant>,
+}
+
+impl HotwordGate {
+    pub fn set_config(&mut self, config: HotwordConfig) {
+        if !config.enabled {
+            self.armed_until = None;
+        }
+        self.config = config;
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.armed_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    fn arm(&mut self) {
+        self.armed_until =
+            Some(Instant::now() + Duration::from_secs(self.config.armed_seconds as u64));
+    }
+
+    pub fn process(&mut self, text: &str) -> GateOutcome {
+        if !self.config.enabled {
+            return GateOutcome::Forward(text.to_string());
+        }
+
+        let found = self
+            .config
+            .phrases
+            .iter()
+            .find_map(|phrase| find_phrase(text, phrase).map(|end| (phrase.clone(), end)));
+
+        match found {
+            Some((phrase, end)) => {
+                self.arm();
+                let remainder = text[end..]
+                    .trim_start_matches(|c: char| !c.is_alphanumeric())
+                    .trim()
+                    .to_string();
+                GateOutcome::Armed { phrase, remainder }
+            }
+            None if self.is_armed() => {
+                // Keep listening while the conversation continues
+                if !text.trim().is_empty() {
+                    self.arm();
+                }
+                GateOutcome::Forward(text.to_string())
+            }
+            None => GateOutcome::Suppressed,
+        }
+    }
+}
+
+pub fn normalize_words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|w| {
+            w.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Whisper often mishears a letter or two ("hey chad"); longer words get more slack
+pub fn words_match(heard: &str, expected: &str) -> bool {
+    edit_distance(heard, expected) <= expected.chars().count() / 4
+}
+
+// Byte offset in `text` just past a fuzzy match of `phrase`
+fn find_phrase(text: &str, phrase: &str) -> Option<usize> {
+    let expected = normalize_words(phrase);
+    if expected.is_empty() {
+        return None;
+    }
+
+    // Keep each word's end offset so the remainder can be sliced from the original text
+    let mut words = Vec::new();
+    let mut offset = 0;
+    for raw in text.split_whitespace() {
+        let start = offset + text[offset..].find(raw)?;
+        offset = start + raw.len();
+        let normalized = normalize_words(raw).concat();
+        if !normalized.is_empty() {
+            words.push((normalized, offset));
+        }
+    }
+
+    words.windows(expected.len()).find_map(|window| {
+        window
+            .iter()
+            .zip(&expected)
+            .all(|((heard, _), expected)| words_match(heard, expected))
+            .then(|| window[window.len() - 1].1)
+    })
+}
@@ -0,0 +1,629 @@
+// Inverse text normalization: rewrite spelled-out numbers, times and units the way
+// people type them ("twenty three percent" -> "23%", "three thirty p m" -> "3:30 PM"),
+// so chatbox messages don't read like raw ASR output. Rules are per language;
+// languages without rules pass through unchanged.
+use serde::{Deserialize, Serialize};
+
+use crate::whisper::TranscriptionResult;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Zero,
+    Unit,
+    Teen,
+    Tens,
+    // A complete multiple of a hundred in one word ("doscientos")
+    Hundreds,
+    // Multiplies what precedes it ("hundred")
+    Hundred,
+    Scale,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Placement {
+    // "5%", "20°"
+    Attached,
+    // "5 km"
+    Spaced,
+    // "$5"
+    Prefix,
+}
+
+struct LanguageRules {
+    cardinals: &'static [(&'static str, u64, Kind)],
+    ordinals: &'static [(&'static str, u64, Kind)],
+    // Word joining number parts and the kinds it may follow ("one hundred and five")
+    connector: Option<(&'static str, &'static [Kind])>,
+    // "mil" means 1000 on its own, "thousand" needs a count before it
+    bare_scales: bool,
+    decimal_point: &'static str,
+    decimal_separator: char,
+    // Word sequences following a number, longest first
+    units: &'static [(&'static [&'static str], &'static str, Placement)],
+    // Parse "three p m" style clock times
+    meridiem: bool,
+}
+
+const ENGLISH: LanguageRules = LanguageRules {
+    cardinals: &[
+        ("zero", 0, Kind::Zero),
+        ("one", 1, Kind::Unit),
+        ("two", 2, Kind::Unit),
+        ("three", 3, Kind::Unit),
+        ("four", 4, Kind::Unit),
+        ("five", 5, Kind::Unit),
+        ("six", 6, Kind::Unit),
+        ("seven", 7, Kind::Unit),
+        ("eight", 8, Kind::Unit),
+        ("nine", 9, Kind::Unit),
+        ("ten", 10, Kind::Teen),
+        ("eleven", 11, Kind::Teen),
+        ("twelve", 12, Kind::Teen),
+        ("thirteen", 13, Kind::Teen),
+        ("fourteen", 14, Kind::Teen),
+        ("fifteen", 15, Kind::Teen),
+        ("sixteen", 16, Kind::Teen),
+        ("seventeen", 17, Kind::Teen),
+        ("eighteen", 18, Kind::Teen),
+        ("nineteen", 19, Kind::Teen),
+        ("twenty", 20, Kind::Tens),
+        ("thirty", 30, Kind::Tens),
+        ("forty", 40, Kind::Tens),
+        ("fifty", 50, Kind::Tens),
+        ("sixty", 60, Kind::Tens),
+        ("seventy", 70, Kind::Tens),
+        ("eighty", 80, Kind::Tens),
+        ("ninety", 90, Kind::Tens),
+        ("hundred", 100, Kind::Hundred),
+        ("thousand", 1_000, Kind::Scale),
+        ("million", 1_000_000, Kind::Scale),
+        ("billion", 1_000_000_000, Kind::Scale),
+    ],
+    ordinals: &[
+        ("first", 1, Kind::Unit),
+        ("second", 2, Kind::Unit),
+        ("third", 3, Kind::Unit),
+        ("fourth", 4, Kind::Unit),
+        ("fifth", 5, Kind::Unit),
+        ("sixth", 6, Kind::Unit),
+        ("seventh", 7, Kind::Unit),
+        ("eighth", 8, Kind::Unit),
+        ("ninth", 9, Kind::Unit),
+        ("tenth", 10, Kind::Teen),
+        ("eleventh", 11, Kind::Teen),
+        ("twelfth", 12, Kind::Teen),
+        ("thirteenth", 13, Kind::Teen),
+        ("fourteenth", 14, Kind::Teen),
+        ("fifteenth", 15, Kind::Teen),
+        ("sixteenth", 16, Kind::Teen),
+        ("seventeenth", 17, Kind::Teen),
+        ("eighteenth", 18, Kind::Teen),
+        ("nineteenth", 19, Kind::Teen),
+        ("twentieth", 20, Kind::Tens),
+        ("thirtieth", 30, Kind::Tens),
+        ("fortieth", 40, Kind::Tens),
+        ("fiftieth", 50, Kind::Tens),
+        ("sixtieth", 60, Kind::Tens),
+        ("seventieth", 70, Kind::Tens),
+        ("eightieth", 80, Kind::Tens),
+        ("ninetieth", 90, Kind::Tens),
+        ("hundredth", 100, Kind::Hundred),
+        ("thousandth", 1_000, Kind::Scale),
+    ],
+    connector: Some(("and", &[Kind::Hundred, Kind::Scale])),
+    bare_scales: false,
+    decimal_point: "point",
+    decimal_separator: '.',
+    units: &[
+        (&["kilometers", "per", "hour"], "km/h", Placement::Spaced),
+        (&["kilometres", "per", "hour"], "km/h", Placement::Spaced),
+        (&["miles", "per", "hour"], "mph", Placement::Spaced),
+        (&["per", "cent"], "%", Placement::Attached),
+        (&["percent"], "%", Placement::Attached),
+        (&["degrees"], "°", Placement::Attached),
+        (&["dollars"], "$", Placement::Prefix),
+        (&["dollar"], "$", Placement::Prefix),
+        (&["euros"], "€", Placement::Prefix),
+        (&["euro"], "€", Placement::Prefix),
+        (&["kilometers"], "km", Placement::Spaced),
+        (&["kilometres"], "km", Placement::Spaced),
+        (&["kilograms"], "kg", Placement::Spaced),
+        (&["centimeters"], "cm", Placement::Spaced),
+        (&["centimetres"], "cm", Placement::Spaced),
+    ],
+    meridiem: true,
+};
+
+const SPANISH: LanguageRules = LanguageRules {
+    cardinals: &[
+        ("cero", 0, Kind::Zero),
+        ("un", 1, Kind::Unit),
+        ("uno", 1, Kind::Unit),
+        ("una", 1, Kind::Unit),
+        ("dos", 2, Kind::Unit),
+        ("tres", 3, Kind::Unit),
+        ("cuatro", 4, Kind::Unit),
+        ("cinco", 5, Kind::Unit),
+        ("seis", 6, Kind::Unit),
+        ("siete", 7, Kind::Unit),
+        ("ocho", 8, Kind::Unit),
+        ("nueve", 9, Kind::Unit),
+        ("diez", 10, Kind::Teen),
+        ("once", 11, Kind::Teen),
+        ("doce", 12, Kind::Teen),
+        ("trece", 13, Kind::Teen),
+        ("catorce", 14, Kind::Teen),
+        ("quince", 15, Kind::Teen),
+        ("dieciséis", 16, Kind::Teen),
+        ("diecisiete", 17, Kind::Teen),
+        ("dieciocho", 18, Kind::Teen),
+        ("diecinueve", 19, Kind::Teen),
+        ("veinte", 20, Kind::Teen),
+        ("veintiuno", 21, Kind::Teen),
+        ("veintiún", 21, Kind::Teen),
+        ("veintidós", 22, Kind::Teen),
+        ("veintitrés", 23, Kind::Teen),
+        ("veinticuatro", 24, Kind::Teen),
+        ("veinticinco", 25, Kind::Teen),
+        ("veintiséis", 26, Kind::Teen),
+        ("veintisiete", 27, Kind::Teen),
+        ("veintiocho", 28, Kind::Teen),
+        ("veintinueve", 29, Kind::Teen),
+        ("treinta", 30, Kind::Tens),
+        ("cuarenta", 40, Kind::Tens),
+        ("cincuenta", 50, Kind::Tens),
+        ("sesenta", 60, Kind::Tens),
+        ("setenta", 70, Kind::Tens),
+        ("ochenta", 80, Kind::Tens),
+        ("noventa", 90, Kind::Tens),
+        ("cien", 100, Kind::Hundreds),
+        ("ciento", 100, Kind::Hundreds),
+        ("doscientos", 200, Kind::Hundreds),
+        ("trescientos", 300, Kind::Hundreds),
+        ("cuatrocientos", 400, Kind::Hundreds),
+        ("quinientos", 500, Kind::Hundreds),
+        ("seiscientos", 600, Kind::Hundreds),
+        ("setecientos", 700, Kind::Hundreds),
+        ("ochocientos", 800, Kind::Hundreds),
+        ("novecientos", 900, Kind::Hundreds),
+        ("mil", 1_000, Kind::Scale),
+        ("millón", 1_000_000, Kind::Scale),
+        ("millones", 1_000_000, Kind::Scale),
+    ],
+    ordinals: &[],
+    connector: Some(("y", &[Kind::Tens])),
+    bare_scales: true,
+    decimal_point: "coma",
+    decimal_separator: ',',
+    units: &[
+        (&["kilómetros", "por", "hora"], "km/h", Placement::Spaced),
+        (&["por", "ciento"], "%", Placement::Attached),
+        (&["grados"], "°", Placement::Attached),
+        (&["euros"], "€", Placement::Spaced),
+        (&["kilómetros"], "km", Placement::Spaced),
+        (&["kilogramos"], "kg", Placement::Spaced),
+        (&["centímetros"], "cm", Placement::Spaced),
+    ],
+    meridiem: false,
+};
+
+fn rules_for(language: &str) -> Option<&'static LanguageRules> {
+    match language.split(['-', '_']).next().unwrap_or("") {
+        "en" => Some(&ENGLISH),
+        "es" => Some(&SPANISH),
+        _ => None,
+    }
+}
+
+fn ordinal_suffix(value: u64) -> &'static str {
+    match (value % 10, value % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+// A whitespace-separated token split into its lowercase word and surrounding punctuation
+struct Token<'a> {
+    original: &'a str,
+    leading: &'a str,
+    core: String,
+    trailing: &'a str,
+}
+
+fn split_token(part: &str) -> Token<'_> {
+    let start = part
+        .find(|c: char| c.is_alphanumeric())
+        .unwrap_or(part.len());
+    let end = part
+        .rfind(|c: char| c.is_alphanumeric())
+        .map(|i| i + part[i..].chars().next().map_or(1, char::len_utf8))
+        .unwrap_or(start)
+        .max(start);
+    Token {
+        original: part,
+        leading: &part[..start],
+        // "a.m." -> "am"
+        core: part[start..end].to_lowercase().replace('.', ""),
+        trailing: &part[end..],
+    }
+}
+
+fn tokenize<'a>(text: &'a str, rules: &LanguageRules) -> Vec<Token<'a>> {
+    let mut tokens = Vec::new();
+    for word in text.split_whitespace() {
+        // "twenty-three" is two number words, "well-known" stays one token
+        let parts: Vec<Token> = word
+            .split('-')
+            .filter(|p| !p.is_empty())
+            .map(split_token)
+            .collect();
+        if parts.len() > 1 && parts.iter().all(|t| rules.lookup(&t.core).is_some()) {
+            tokens.extend(parts);
+        } else {
+            tokens.push(split_token(word));
+        }
+    }
+    tokens
+}
+
+struct ParsedNumber {
+    value: u64,
+    // Digits after the decimal point, as spoken
+    decimals: String,
+    ordinal: bool,
+    // Tokens consumed
+    len: usize,
+}
+
+impl LanguageRules {
+    fn lookup(&self, word: &str) -> Option<(u64, Kind, bool)> {
+        self.cardinals
+            .iter()
+            .find(|(w, _, _)| *w == word)
+            .map(|&(_, value, kind)| (value, kind, false))
+            .or_else(|| {
+                self.ordinals
+                    .iter()
+                    .find(|(w, _, _)| *w == word)
+                    .map(|&(_, value, kind)| (value, kind, true))
+            })
+    }
+
+    // Longest well-formed number starting at `tokens[0]`
+    fn parse_number(&self, tokens: &[Token]) -> Option<ParsedNumber> {
+        let mut total = 0u64;
+        let mut current = 0u64;
+        let mut previous: Option<Kind> = None;
+        let mut last_scale = u64::MAX;
+        let mut ordinal = false;
+        let mut len = 0;
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let word = tokens[i].core.as_str();
+            if let Some((connector, after)) = self.connector {
+                let joins = previous.is_some_and(|p| after.contains(&p))
+                    && word == connector
+                    && tokens
+                        .get(i + 1)
+                        .and_then(|t| self.lookup(&t.core))
+                        .is_some();
+                if joins {
+                    i += 1;
+                    continue;
+                }
+            }
+            let Some((value, kind, is_ordinal)) = self.lookup(word) else {
+                break;
+            };
+            let allowed = match kind {
+                Kind::Zero => previous.is_none(),
+                Kind::Unit => matches!(
+                    previous,
+                    None | Some(Kind::Tens | Kind::Hundred | Kind::Hundreds | Kind::Scale)
+                ),
+                Kind::Teen | Kind::Tens => matches!(
+                    previous,
+                    None | Some(Kind::Hundred | Kind::Hundreds | Kind::Scale)
+                ),
+                Kind::Hundreds => matches!(previous, None | Some(Kind::Scale)),
+                // "nineteen hundred", but not "one hundred five hundred"
+                Kind::Hundred => matches!(previous, Some(Kind::Unit | Kind::Teen)) && current < 20,
+                Kind::Scale => {
+                    let counted = matches!(
+                        previous,
+                        Some(Kind::Unit | Kind::Teen | Kind::Tens | Kind::Hundred | Kind::Hundreds)
+                    );
+                    (counted || (previous.is_none() && self.bare_scales)) && value < last_scale
+                }
+            };
+            if !allowed {
+                break;
+            }
+
+            match kind {
+                Kind::Hundred => current *= 100,
+                Kind::Scale => {
+                    total += current.max(1) * value;
+                    current = 0;
+                    last_scale = value;
+                }
+                _ => current += value,
+            }
+            previous = Some(kind);
+            i += 1;
+            len = i;
+            if is_ordinal {
+                ordinal = true;
+                break;
+            }
+        }
+        if len == 0 {
+            return None;
+        }
+
+        // "three point one four"
+        let mut decimals = String::new();
+        if !ordinal
+            && tokens
+                .get(len)
+                .is_some_and(|t| t.core == self.decimal_point)
+        {
+            let digits: String = tokens[len + 1..]
+                .iter()
+                .map_while(|t| match self.lookup(&t.core) {
+                    Some((value, Kind::Zero | Kind::Unit, false)) => {
+                        char::from_digit(value as u32, 10)
+                    }
+                    _ => None,
+                })
+                .collect();
+            if !digits.is_empty() {
+                len += 1 + digits.chars().count();
+                decimals = digits;
+            }
+        }
+
+        Some(ParsedNumber {
+            value: total + current,
+            decimals,
+            ordinal,
+            len,
+        })
+    }
+
+    fn format_number(&self, number: &ParsedNumber, language_ordinals: bool) -> String {
+        let mut text = number.value.to_string();
+        if !number.decimals.is_empty() {
+            text.push(self.decimal_separator);
+            text.push_str(&number.decimals);
+        }
+        if number.ordinal && language_ordinals {
+            text.push_str(ordinal_suffix(number.value));
+        }
+        text
+    }
+
+    // Unit words right after a number, returning the symbol and how many tokens it spans
+    fn match_unit(&self, tokens: &[Token]) -> Option<(&'static str, Placement, usize)> {
+        self.units.iter().find_map(|(words, symbol, placement)| {
+            let matches =
+                words.len() <= tokens.len() && words.iter().zip(tokens).all(|(w, t)| *w == t.core);
+            matches.then_some((*symbol, *placement, words.len()))
+        })
+    }
+}
+
+// "am", "a.m." or "a m"
+fn match_meridiem(tokens: &[Token]) -> Option<(&'static str, usize)> {
+    let first = tokens.first()?.core.as_str();
+    let second = tokens.get(1).map(|t| t.core.as_str());
+    match (first, second) {
+        ("am", _) => Some(("AM", 1)),
+        ("pm", _) => Some(("PM", 1)),
+        ("a", Some("m")) => Some(("AM", 2)),
+        ("p", Some("m")) => Some(("PM", 2)),
+        _ => None,
+    }
+}
+
+// A clock time starting at a parsed hour: "three p m", "three thirty p m", "three oh five p m"
+fn match_time(
+    rules: &LanguageRules,
+    hour: &ParsedNumber,
+    tokens: &[Token],
+) -> Option<(String, usize)> {
+    if !rules.meridiem || hour.ordinal || !hour.decimals.is_empty() {
+        return None;
+    }
+    if !(1..=12).contains(&hour.value) {
+        return None;
+    }
+    let rest = &tokens[hour.len..];
+    if let Some((meridiem, len)) = match_meridiem(rest) {
+        return Some((format!("{} {}", hour.value, meridiem), hour.len + len));
+    }
+
+    let (minutes, minutes_len) = match rest.first().map(|t| t.core.as_str()) {
+        Some("oh") => {
+            let digit = rules.parse_number(&rest[1..])?;
+            if digit.len != 1 || digit.value > 9 {
+                return None;
+            }
+            (digit.value, 2)
+        }
+        _ => {
+            let minutes = rules.parse_number(rest)?;
+            if minutes.ordinal || !minutes.decimals.is_empty() || !(10..60).contains(&minutes.value)
+            {
+                return None;
+            }
+            (minutes.value, minutes.len)
+        }
+    };
+    let (meridiem, len) = match_meridiem(&rest[minutes_len..])?;
+    Some((
+        format!("{}:{:02} {}", hour.value, minutes, meridiem),
+        hour.len + minutes_len + len,
+    ))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextNormalizer {
+    pub enabled: bool,
+    // Also turn lone "one".."nine" into digits; typed text usually spells them out
+    pub convert_small_numbers: bool,
+}
+
+impl Default for TextNormalizer {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            convert_small_numbers: false,
+        }
+    }
+}
+
+impl TextNormalizer {
+    pub fn apply(&self, text: &str, language: &str) -> String {
+        if !self.enabled {
+            return text.to_string();
+        }
+        let Some(rules) = rules_for(language) else {
+            return text.to_string();
+        };
+
+        let tokens = tokenize(text, rules);
+        let mut output: Vec<String> = Vec::new();
+        let mut changed = false;
+        let mut i = 0;
+        while i < tokens.len() {
+            let Some(number) = rules.parse_number(&tokens[i..]) else {
+                output.push(tokens[i].original.to_string());
+                i += 1;
+                continue;
+            };
+            let rest = &tokens[i + number.len..];
+
+            let (text, len) = if let Some((time, len)) = match_time(rules, &number, &tokens[i..]) {
+                (time, len)
+            } else if let Some((symbol, placement, unit_len)) =
+                rules.match_unit(rest).filter(|_| !number.ordinal)
+            {
+                let value = rules.format_number(&number, false);
+                let text = match placement {
+                    Placement::Attached => format!("{}{}", value, symbol),
+                    Placement::Spaced => format!("{} {}", value, symbol),
+                    Placement::Prefix => format!("{}{}", symbol, value),
+                };
+                (text, number.len + unit_len)
+            } else {
+                // Lone small numbers read better spelled out ("one of them", "first time")
+                let small = number.len == 1 && number.value < 10 && number.decimals.is_empty();
+                if small && !self.convert_small_numbers {
+                    output.push(tokens[i].original.to_string());
+                    i += 1;
+                    continue;
+                }
+                (
+                    rules.format_number(&number, !rules.ordinals.is_empty()),
+                    number.len,
+                )
+            };
+
+            let last = &tokens[i + len - 1];
+            output.push(format!("{}{}{}", tokens[i].leading, text, last.trailing));
+            changed = true;
+            i += len;
+        }
+        // Keep the original spacing when there was nothing to rewrite
+        if changed {
+            output.join(" ")
+        } else {
+            text.to_string()
+        }
+    }
+
+    // Normalize the transcript and each segment in its own language (English when
+    // Whisper translated). Word-level entries keep their spoken form so they still
+    // line up with their timings.
+    pub fn apply_to_result(&self, result: &mut TranscriptionResult, translated: bool) {
+        if !self.enabled {
+            return;
+        }
+        let language_of = |language: &str| {
+            if translated {
+                "en".to_string()
+            } else {
+                language.to_string()
+            }
+        };
+        result.text = self.apply(&result.text, &language_of(&result.language));
+        for segment in &mut result.segments {
+            segment.text = self.apply(&segment.text, &language_of(&segment.language));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(text: &str, language: &str) -> String {
+        TextNormalizer::default().apply(text, language)
+    }
+
+    #[test]
+    fn percentages() {
+        assert_eq!(normalize("twenty three percent", "en"), "23%");
+        assert_eq!(normalize("veintitrés por ciento", "es"), "23%");
+    }
+
+    #[test]
+    fn clock_times() {
+        assert_eq!(normalize("three thirty p m", "en"), "3:30 PM");
+        assert_eq!(
+            normalize("meet at three oh five pm.", "en"),
+            "meet at 3:05 PM."
+        );
+        assert_eq!(normalize("five a m", "en"), "5 AM");
+    }
+
+    #[test]
+    fn compound_numbers_and_decimals() {
+        assert_eq!(normalize("one hundred and five", "en-US"), "105");
+        assert_eq!(normalize("twenty-three dollars", "en"), "$23");
+        assert_eq!(normalize("three point one four", "en"), "3.14");
+        assert_eq!(normalize("the twenty first", "en"), "the 21st");
+    }
+
+    #[test]
+    fn lone_small_numbers_stay_words() {
+        assert_eq!(normalize("no one", "en"), "no one");
+        assert_eq!(normalize("wait a second", "en"), "wait a second");
+        assert_eq!(normalize("one of them", "en"), "one of them");
+    }
+
+    #[test]
+    fn small_numbers_convert_when_asked() {
+        let normalizer = TextNormalizer {
+            convert_small_numbers: true,
+            ..TextNormalizer::default()
+        };
+        assert_eq!(normalizer.apply("I have two cats", "en"), "I have 2 cats");
+    }
+
+    #[test]
+    fn unchanged_without_rules_or_when_disabled() {
+        assert_eq!(normalize("twenty three", "ja"), "twenty three");
+        let disabled = TextNormalizer {
+            enabled: false,
+            ..TextNormalizer::default()
+        };
+        assert_eq!(disabled.apply("twenty three", "en"), "twenty three");
+    }
+}
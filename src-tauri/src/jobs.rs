@@ -0,0 +1,530 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::debug_recording::record_segment;
+use crate::quantization::{catalog_variants, downloaded_variants, parse_model_id};
+use crate::whisper::{
+    register_job, transcribe_audio, InferenceOptions, TranscribeOptions, TranscriptionResult,
+    WhisperAppState, TRANSCRIPTION_CANCELLED,
+};
+
+const DEFAULT_MAX_CONCURRENCY: usize = 1;
+const DEFAULT_MAX_PENDING: usize = 4;
+// Finished jobs kept around so the frontend can still fetch their results
+const MAX_FINISHED_JOBS: usize = 50;
+// Model sizes from smallest to largest, for escalating low-confidence transcriptions
+const MODEL_SIZES: [&str; 5] = ["tiny", "base", "small", "medium", "large"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+    // Evicted from the queue by a newer job under the drop-oldest policy
+    Dropped,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStage {
+    // A standalone transcription
+    Full,
+    // Low-latency pass with the fast model of a dual-model submission
+    Partial,
+    // Accurate re-transcription of the same audio; its text replaces the partial
+    Final,
+    // Low-confidence result re-transcribed with a larger model, correcting it if different
+    Retry,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JobInfo {
+    pub id: String,
+    pub model: String,
+    pub stage: JobStage,
+    // The other half of a dual-model submission
+    pub linked_job: Option<String>,
+    pub status: JobStatus,
+    pub submitted_at: i64,
+    pub finished_at: Option<i64>,
+    pub result: Option<TranscriptionResult>,
+    pub error: Option<String>,
+}
+
+struct PendingJob {
+    id: String,
+    audio_data: Vec<u8>,
+    model: String,
+    options: InferenceOptions,
+    stage: JobStage,
+    linked_job: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DualJob {
+    pub partial_id: String,
+    pub final_id: String,
+}
+
+#[derive(Serialize)]
+pub struct RetryJob {
+    pub id: String,
+    pub model: String,
+}
+
+struct QueueInner {
+    pending: VecDeque<PendingJob>,
+    jobs: HashMap<String, JobInfo>,
+    // Finished job IDs, oldest first, used to prune `jobs`
+    finished: VecDeque<String>,
+    running: usize,
+    max_concurrency: usize,
+    max_pending: usize,
+    next_id: u64,
+}
+
+// Bounded transcription queue. Bursts of audio segments beyond `max_pending` evict
+// the oldest queued segment, since stale speech is worth less than fresh speech.
+pub struct JobQueueState {
+    inner: Arc<Mutex<QueueInner>>,
+}
+
+impl Default for JobQueueState {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(QueueInner {
+                pending: VecDeque::new(),
+                jobs: HashMap::new(),
+                finished: VecDeque::new(),
+                running: 0,
+                max_concurrency: DEFAULT_MAX_CONCURRENCY,
+                max_pending: DEFAULT_MAX_PENDING,
+                next_id: 1,
+            })),
+        }
+    }
+}
+
+fn lock_queue(inner: &Mutex<QueueInner>) -> Result<MutexGuard<'_, QueueInner>, String> {
+    inner.lock().map_err(|e| format!("Mutex poisoned: {:?}", e))
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+fn emit_job(app: &AppHandle, job: &JobInfo) {
+    let _ = app.emit("transcription-job", job);
+}
+
+impl QueueInner {
+    fn finish(&mut self, id: &str, status: JobStatus) -> Option<JobInfo> {
+        let job = self.jobs.get_mut(id)?;
+        job.status = status;
+        job.finished_at = Some(now_ms());
+        let snapshot = job.clone();
+
+        self.finished.push_back(id.to_string());
+        while self.finished.len() > MAX_FINISHED_JOBS {
+            if let Some(old) = self.finished.pop_front() {
+                self.jobs.remove(&old);
+            }
+        }
+        Some(snapshot)
+    }
+
+    fn enqueue(&mut self, job: PendingJob) -> JobInfo {
+        let info = JobInfo {
+            id: job.id.clone(),
+            model: job.model.clone(),
+            stage: job.stage,
+            linked_job: job.linked_job.clone(),
+            status: JobStatus::Queued,
+            submitted_at: now_ms(),
+            finished_at: None,
+            result: None,
+            error: None,
+        };
+        self.jobs.insert(job.id.clone(), info.clone());
+        // Partials exist for latency, so they go ahead of everything already waiting
+        if job.stage == JobStage::Partial {
+            self.pending.push_front(job);
+        } else {
+            self.pending.push_back(job);
+        }
+        info
+    }
+
+    fn next_id(&mut self) -> String {
+        let id = format!("queued-{}", self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    // Drop-oldest: keep the queue bounded so bursts can't pile up unbounded. Retries
+    // only refine text that was already sent, so they go first; partials are evicted
+    // last since they are the quick feedback the user is waiting on.
+    fn evict_overflow(&mut self) -> Vec<JobInfo> {
+        let mut dropped = Vec::new();
+        while self.pending.len() > self.max_pending.max(1) {
+            let index = self
+                .pending
+                .iter()
+                .position(|job| job.stage == JobStage::Retry)
+                .or_else(|| {
+                    self.pending
+                        .iter()
+                        .position(|job| job.stage != JobStage::Partial)
+                })
+                .unwrap_or(0);
+            if let Some(old) = self.pending.remove(index) {
+                println!("Transcription queue full, dropping job {}", old.id);
+                if let Some(snapshot) = self.finish(&old.id, JobStatus::Dropped) {
+                    dropped.push(snapshot);
+                }
+            }
+        }
+        dropped
+    }
+
+    // A finished final makes its partial pointless: drop it if it hasn't started yet
+    fn supersede(&mut self, partial_id: &str) -> Option<JobInfo> {
+        let index = self.pending.iter().position(|job| job.id == partial_id)?;
+        self.pending.remove(index);
+        self.finish(partial_id, JobStatus::Cancelled)
+    }
+}
+
+// Start as many queued jobs as the concurrency limit allows
+fn pump(app: &AppHandle, inner: &Arc<Mutex<QueueInner>>) {
+    loop {
+        let (job, registration, snapshot) = {
+            let Ok(mut queue) = lock_queue(inner) else {
+                return;
+            };
+            if queue.running >= queue.max_concurrency.max(1) {
+                return;
+            }
+            let Some(job) = queue.pending.pop_front() else {
+                return;
+            };
+            // Registered while the job is still under the queue lock, so a cancel
+            // arriving during audio prep or model load finds its flag
+            let registration =
+                match register_job(app, &app.state::<WhisperAppState>(), Some(job.id.clone())) {
+                    Ok(registration) => registration,
+                    Err(e) => {
+                        if let Some(info) = queue.jobs.get_mut(&job.id) {
+                            info.error = Some(e);
+                        }
+                        if let Some(snapshot) = queue.finish(&job.id, JobStatus::Failed) {
+                            emit_job(app, &snapshot);
+                        }
+                        continue;
+                    }
+                };
+            queue.running += 1;
+            let Some(info) = queue.jobs.get_mut(&job.id) else {
+                queue.running -= 1;
+                continue;
+            };
+            info.status = JobStatus::Running;
+            (job, registration, info.clone())
+        };
+        emit_job(app, &snapshot);
+
+        let app = app.clone();
+        let inner = inner.clone();
+        tauri::async_runtime::spawn(async move {
+            let whisper_state = app.state::<WhisperAppState>();
+            record_segment(&app, &job.audio_data, &job.options.source);
+            let superseded = match job.stage {
+                JobStage::Final => job.linked_job.clone(),
+                _ => None,
+            };
+            let result = transcribe_audio(
+                app.clone(),
+                &whisper_state,
+                job.audio_data,
+                job.model,
+                job.options,
+                registration,
+            )
+            .await;
+
+            let succeeded = result.is_ok();
+            let (snapshot, superseded_snapshot) = match lock_queue(&inner) {
+                Ok(mut queue) => {
+                    queue.running = queue.running.saturating_sub(1);
+                    let status = match &result {
+                        Ok(_) => JobStatus::Completed,
+                        Err(e) if e == TRANSCRIPTION_CANCELLED => JobStatus::Cancelled,
+                        Err(_) => JobStatus::Failed,
+                    };
+                    if let Some(info) = queue.jobs.get_mut(&job.id) {
+                        match result {
+                            Ok(transcription) => info.result = Some(transcription),
+                            Err(e) => info.error = Some(e),
+                        }
+                    }
+                    let superseded_snapshot = superseded
+                        .as_deref()
+                        .filter(|_| succeeded)
+                        .and_then(|partial_id| queue.supersede(partial_id));
+                    (queue.finish(&job.id, status), superseded_snapshot)
+                }
+                Err(_) => (None, None),
+            };
+            if let Some(snapshot) = snapshot {
+                emit_job(&app, &snapshot);
+            }
+            match superseded_snapshot {
+                Some(snapshot) => emit_job(&app, &snapshot),
+                // Already decoding (concurrency > 1), abort it instead
+                None if succeeded => {
+                    if let Some(partial_id) = &superseded {
+                        let _ = whisper_state.jobs.cancel(partial_id);
+                    }
+                }
+                None => {}
+            }
+
+            pump(&app, &inner);
+        });
+    }
+}
+
+#[tauri::command]
+pub fn whisper_submit_job(
+    app: AppHandle,
+    state: State<'_, JobQueueState>,
+    whisper_state: State<'_, WhisperAppState>,
+    audio_data: Vec<u8>,
+    model: String,
+    language: String,
+    options: Option<TranscribeOptions>,
+) -> Result<String, String> {
+    let options = options
+        .unwrap_or_default()
+        .into_inference_options(&whisper_state, &language)?;
+
+    let (id, dropped, snapshot) = {
+        let mut queue = lock_queue(&state.inner)?;
+        let id = queue.next_id();
+        let info = queue.enqueue(PendingJob {
+            id: id.clone(),
+            audio_data,
+            model,
+            options,
+            stage: JobStage::Full,
+            linked_job: None,
+        });
+        let dropped = queue.evict_overflow();
+        (id, dropped, info)
+    };
+
+    emit_job(&app, &snapshot);
+    for job in &dropped {
+        emit_job(&app, job);
+    }
+    pump(&app, &state.inner);
+
+    Ok(id)
+}
+
+// Dual-model transcription of one utterance: `fast_model` (tiny/base) runs first for a
+// low-latency partial, then `model` re-transcribes the same audio and its result
+// replaces the partial. Both are tracked through `transcription-job` events.
+#[tauri::command]
+pub fn whisper_submit_dual_job(
+    app: AppHandle,
+    state: State<'_, JobQueueState>,
+    audio_data: Vec<u8>,
+    model: String,
+    fast_model: String,
+    language: String,
+    options: Option<TranscribeOptions>,
+) -> Result<DualJob, String> {
+    let whisper_state = app.state::<WhisperAppState>();
+    let final_options = options
+        .unwrap_or_default()
+        .into_inference_options(&whisper_state, &language)?;
+    // The partial only has to be quick, so decode it greedily
+    let mut partial_options = final_options.for_language(&language);
+    partial_options.decoding.beam_size = 1;
+
+    let (partial_id, final_id, dropped, snapshots) = {
+        let mut queue = lock_queue(&state.inner)?;
+        let partial_id = queue.next_id();
+        let final_id = queue.next_id();
+        let partial = queue.enqueue(PendingJob {
+            id: partial_id.clone(),
+            audio_data: audio_data.clone(),
+            model: fast_model,
+            options: partial_options,
+            stage: JobStage::Partial,
+            linked_job: Some(final_id.clone()),
+        });
+        let accurate = queue.enqueue(PendingJob {
+            id: final_id.clone(),
+            audio_data,
+            model,
+            options: final_options,
+            stage: JobStage::Final,
+            linked_job: Some(partial_id.clone()),
+        });
+        let dropped = queue.evict_overflow();
+        (partial_id, final_id, dropped, [partial, accurate])
+    };
+
+    for job in snapshots.iter().chain(&dropped) {
+        emit_job(&app, job);
+    }
+    pump(&app, &state.inner);
+
+    Ok(DualJob {
+        partial_id,
+        final_id,
+    })
+}
+
+// Next larger model size with a downloaded variant, preferring the same quantization
+async fn next_model_size(app: &AppHandle, model: &str) -> Option<String> {
+    let (size, quantization) = parse_model_id(model);
+    let position = MODEL_SIZES.iter().position(|s| *s == size)?;
+    for larger in &MODEL_SIZES[position + 1..] {
+        let downloaded = downloaded_variants(app, catalog_variants(larger)).await;
+        let same_quantization = downloaded.iter().find(|(_, q)| *q == quantization);
+        if let Some((id, _)) = same_quantization.or_else(|| downloaded.first()) {
+            return Some(id.clone());
+        }
+    }
+    None
+}
+
+// Queue a low-confidence utterance for re-transcription by the next larger downloaded
+// model. Returns None when there is no larger model to escalate to.
+#[tauri::command]
+pub async fn whisper_submit_retry_job(
+    app: AppHandle,
+    state: State<'_, JobQueueState>,
+    audio_data: Vec<u8>,
+    model: String,
+    language: String,
+    options: Option<TranscribeOptions>,
+) -> Result<Option<RetryJob>, String> {
+    let Some(retry_model) = next_model_size(&app, &model).await else {
+        println!(
+            "No model larger than {} downloaded, not re-transcribing",
+            model
+        );
+        return Ok(None);
+    };
+    let whisper_state = app.state::<WhisperAppState>();
+    let options = options
+        .unwrap_or_default()
+        .into_inference_options(&whisper_state, &language)?;
+
+    let (id, dropped, snapshot) = {
+        let mut queue = lock_queue(&state.inner)?;
+        let id = queue.next_id();
+        let info = queue.enqueue(PendingJob {
+            id: id.clone(),
+            audio_data,
+            model: retry_model.clone(),
+            options,
+            stage: JobStage::Retry,
+            linked_job: None,
+        });
+        let dropped = queue.evict_overflow();
+        (id, dropped, info)
+    };
+    println!(
+        "Low-confidence {} transcription queued for {} as job {}",
+        model, retry_model, id
+    );
+
+    emit_job(&app, &snapshot);
+    for job in &dropped {
+        emit_job(&app, job);
+    }
+    pump(&app, &state.inner);
+
+    Ok(Some(RetryJob {
+        id,
+        model: retry_model,
+    }))
+}
+
+#[tauri::command]
+pub fn whisper_job_status(
+    state: State<'_, JobQueueState>,
+    job_id: String,
+) -> Result<JobInfo, String> {
+    lock_queue(&state.inner)?
+        .jobs
+        .get(&job_id)
+        .cloned()
+        .ok_or_else(|| format!("Unknown job: {}", job_id))
+}
+
+#[tauri::command]
+pub fn whisper_list_jobs(state: State<'_, JobQueueState>) -> Result<Vec<JobInfo>, String> {
+    let mut jobs: Vec<JobInfo> = lock_queue(&state.inner)?.jobs.values().cloned().collect();
+    jobs.sort_by_key(|job| job.submitted_at);
+    Ok(jobs)
+}
+
+#[tauri::command]
+pub fn whisper_cancel_job(
+    app: AppHandle,
+    state: State<'_, JobQueueState>,
+    whisper_state: State<'_, WhisperAppState>,
+    job_id: String,
+) -> Result<bool, String> {
+    let snapshot = {
+        let mut queue = lock_queue(&state.inner)?;
+        match queue.pending.iter().position(|job| job.id == job_id) {
+            Some(index) => {
+                queue.pending.remove(index);
+                queue.finish(&job_id, JobStatus::Cancelled)
+            }
+            None => None,
+        }
+    };
+
+    if let Some(snapshot) = snapshot {
+        emit_job(&app, &snapshot);
+        return Ok(true);
+    }
+
+    // Not queued any more, abort it if it's currently decoding
+    whisper_state.jobs.cancel(&job_id)
+}
+
+#[tauri::command]
+pub fn whisper_set_queue_limits(
+    app: AppHandle,
+    state: State<'_, JobQueueState>,
+    max_concurrency: usize,
+    max_pending: usize,
+) -> Result<(), String> {
+    {
+        let mut queue = lock_queue(&state.inner)?;
+        queue.max_concurrency = max_concurrency.max(1);
+        queue.max_pending = max_pending.max(1);
+    }
+    println!(
+        "Transcription queue limits: concurrency {}, pending {}",
+        max_concurrency, max_pending
+    );
+
+    // A higher concurrency limit may let queued jobs start right away
+    pump(&app, &state.inner);
+    Ok(())
+}
@@ -0,0 +1,56 @@
+// Language identification for transcripts translated with the source set to "auto",
+// so bilingual speakers don't have to switch the source language by hand. Knowing the
+// language lets per-pair providers apply and skips translating into the same language.
+use crate::translate::AUTO_LANGUAGE;
+
+// whatlang's ISO 639-3 codes to the app's language codes
+const LANGUAGE_CODES: [(&str, &str); 20] = [
+    ("eng", "en"),
+    ("jpn", "ja"),
+    ("kor", "ko"),
+    ("cmn", "zh"),
+    ("spa", "es"),
+    ("fra", "fr"),
+    ("deu", "de"),
+    ("rus", "ru"),
+    ("ind", "id"),
+    ("ara", "ar"),
+    ("ita", "it"),
+    ("por", "pt"),
+    ("tha", "th"),
+    ("vie", "vi"),
+    ("ukr", "uk"),
+    ("pol", "pl"),
+    ("nld", "nl"),
+    ("tur", "tr"),
+    ("tgl", "tl"),
+    ("swe", "sv"),
+];
+
+// The language of `text`, when it can be told with confidence
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let info = whatlang::detect(text)?;
+    if !info.is_reliable() {
+        return None;
+    }
+    let code = info.lang().code();
+    LANGUAGE_CODES
+        .iter()
+        .find(|(whatlang, _)| *whatlang == code)
+        .map(|(_, app)| *app)
+}
+
+// The source language to translate from: `source` itself unless it is "auto" and
+// the text's language can be identified
+pub fn resolve_source(text: &str, source: &str) -> String {
+    if source != AUTO_LANGUAGE {
+        return source.to_string();
+    }
+    match detect_language(text) {
+        Some(detected) => {
+            println!("Detected source language {}", detected);
+            detected.to_string()
+        }
+        None => source.to_string(),
+    }
+}
@@ -0,0 +1,276 @@
+// Named language setups (source, target, provider, chatbox formatting) for flipping
+// between e.g. a JP and a KR instance in one step, from the UI or a global hotkey.
+// These are presets, not state: the settings store owns the live languages, provider
+// and formatting (and keeps them in the active settings profile), so switching edits
+// the settings through it, applies the provider and formatting here and emits
+// `language-profile` with the new settings for the frontend. Saved to the app data
+// directory.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, State};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::chatbox::ChatboxAppState;
+use crate::chatbox_format::ChatboxFormat;
+use crate::config::SettingsState;
+use crate::translate::{TranslateAppState, TranslatorConfig};
+
+const LANGUAGE_PROFILES_FILE: &str = "language_profiles.json";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LanguageProfile {
+    pub name: String,
+    pub source: String,
+    pub target: String,
+    // Provider and formatting to switch to; unset keeps the current ones
+    #[serde(default)]
+    pub provider: Option<TranslatorConfig>,
+    #[serde(default)]
+    pub format: Option<ChatboxFormat>,
+    // Global hotkey in accelerator syntax ("Ctrl+Alt+1") that switches to this profile
+    #[serde(default)]
+    pub hotkey: Option<String>,
+}
+
+impl LanguageProfile {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Language profiles need a name".to_string());
+        }
+        if self.source.trim().is_empty() || self.target.trim().is_empty() {
+            return Err(format!(
+                "Language profile '{}' needs a source and target language",
+                self.name
+            ));
+        }
+        if let Some(provider) = &self.provider {
+            provider.validate()?;
+        }
+        if let Some(format) = &self.format {
+            format.validate()?;
+        }
+        if let Some(hotkey) = &self.hotkey {
+            parse_hotkey(hotkey)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LanguageProfiles {
+    pub profiles: Vec<LanguageProfile>,
+    // Name of the profile switched to last
+    pub active: Option<String>,
+}
+
+#[derive(Default)]
+pub struct LanguageProfilesState {
+    profiles: Mutex<LanguageProfiles>,
+}
+
+fn parse_hotkey(hotkey: &str) -> Result<Shortcut, String> {
+    hotkey
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid language profile hotkey '{}': {}", hotkey, e))
+}
+
+fn profiles_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join(LANGUAGE_PROFILES_FILE))
+}
+
+fn save_language_profiles(
+    app_handle: &tauri::AppHandle,
+    profiles: &LanguageProfiles,
+) -> Result<(), String> {
+    let path = profiles_path(app_handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let data = serde_json::to_string_pretty(profiles)
+        .map_err(|e| format!("Failed to serialize language profiles: {}", e))?;
+    fs::write(&path, data).map_err(|e| format!("Failed to save language profiles: {}", e))
+}
+
+// Move the profile hotkeys from `previous` to `profiles`
+fn register_hotkeys(
+    app_handle: &tauri::AppHandle,
+    previous: &[LanguageProfile],
+    profiles: &[LanguageProfile],
+) -> Result<(), String> {
+    let shortcuts = app_handle.global_shortcut();
+    for hotkey in previous.iter().filter_map(|p| p.hotkey.as_deref()) {
+        if let Ok(shortcut) = parse_hotkey(hotkey) {
+            let _ = shortcuts.unregister(shortcut);
+        }
+    }
+    for profile in profiles {
+        let Some(hotkey) = &profile.hotkey else {
+            continue;
+        };
+        let name = profile.name.clone();
+        shortcuts
+            .on_shortcut(parse_hotkey(hotkey)?, move |app_handle, _, event| {
+                if event.state() != ShortcutState::Pressed {
+                    return;
+                }
+                if let Err(e) = apply_profile(app_handle, &name) {
+                    println!("Failed to switch language profile: {}", e);
+                }
+            })
+            .map_err(|e| format!("Failed to register hotkey '{}': {}", hotkey, e))?;
+    }
+    Ok(())
+}
+
+// Restore the saved profiles and their hotkeys at startup; the active profile's
+// settings come back with the settings store, so nothing is re-applied
+pub fn load_language_profiles(app_handle: &tauri::AppHandle) {
+    let Ok(path) = profiles_path(app_handle) else {
+        return;
+    };
+    let Ok(data) = fs::read_to_string(&path) else {
+        return;
+    };
+    let profiles = match serde_json::from_str::<LanguageProfiles>(&data) {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            println!("Ignoring invalid {}: {}", LANGUAGE_PROFILES_FILE, e);
+            return;
+        }
+    };
+    println!("Loaded {} language profile(s)", profiles.profiles.len());
+    if let Err(e) = register_hotkeys(app_handle, &[], &profiles.profiles) {
+        println!("{}", e);
+    }
+    let state = app_handle.state::<LanguageProfilesState>();
+    if let Ok(mut current) = state.profiles.lock() {
+        *current = profiles;
+    }
+}
+
+fn apply_profile(app_handle: &tauri::AppHandle, name: &str) -> Result<LanguageProfile, String> {
+    let state = app_handle.state::<LanguageProfilesState>();
+    let mut profiles = state
+        .profiles
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    let profile = profiles
+        .profiles
+        .iter()
+        .find(|p| p.name == name)
+        .cloned()
+        .ok_or_else(|| format!("No language profile named '{}'", name))?;
+
+    // Saved into the active settings profile too, like any other edit
+    let settings = app_handle
+        .state::<SettingsState>()
+        .modify(app_handle, |settings| {
+            settings.source_language = profile.source.clone();
+            settings.target_language = profile.target.clone();
+            if let Some(provider) = &profile.provider {
+                settings.translator = provider.name().to_string();
+            }
+            if let Some(format) = &profile.format {
+                settings.chatbox_format = format.clone();
+            }
+            Ok(())
+        })?;
+    if let Some(provider) = &profile.provider {
+        *app_handle
+            .state::<TranslateAppState>()
+            .provider
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))? = provider.clone();
+    }
+    if let Some(format) = &profile.format {
+        *app_handle
+            .state::<ChatboxAppState>()
+            .format
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))? = format.clone();
+    }
+    profiles.active = Some(profile.name.clone());
+    if let Err(e) = save_language_profiles(app_handle, &profiles) {
+        println!("{}", e);
+    }
+
+    println!(
+        "Switched to language profile '{}' ({} -> {})",
+        profile.name, profile.source, profile.target
+    );
+    let _ = app_handle.emit(
+        "language-profile",
+        serde_json::json!({
+            "name": profile.name,
+            "source": profile.source,
+            "target": profile.target,
+            "provider": profile.provider.as_ref().map(|p| p.name()),
+            "settings": settings,
+        }),
+    );
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn get_language_profiles(
+    state: State<'_, LanguageProfilesState>,
+) -> Result<LanguageProfiles, String> {
+    Ok(state
+        .profiles
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+// Replace the stored profiles, moving their hotkeys along
+#[tauri::command]
+pub fn set_language_profiles(
+    app_handle: tauri::AppHandle,
+    state: State<'_, LanguageProfilesState>,
+    mut profiles: Vec<LanguageProfile>,
+) -> Result<(), String> {
+    for profile in &mut profiles {
+        profile.name = profile.name.trim().to_string();
+        profile.hotkey = profile
+            .hotkey
+            .take()
+            .map(|hotkey| hotkey.trim().to_string())
+            .filter(|hotkey| !hotkey.is_empty());
+        profile.validate()?;
+    }
+    for (index, profile) in profiles.iter().enumerate() {
+        if profiles[..index].iter().any(|p| p.name == profile.name) {
+            return Err(format!("Duplicate language profile '{}'", profile.name));
+        }
+    }
+
+    let mut current = state
+        .profiles
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    register_hotkeys(&app_handle, &current.profiles, &profiles)?;
+    let active = current
+        .active
+        .take()
+        .filter(|active| profiles.iter().any(|p| &p.name == active));
+    *current = LanguageProfiles { profiles, active };
+    println!("Saved {} language profile(s)", current.profiles.len());
+    save_language_profiles(&app_handle, &current)
+}
+
+// Apply a profile's provider and formatting; the frontend follows its languages
+#[tauri::command]
+pub fn switch_language_profile(
+    app_handle: tauri::AppHandle,
+    name: String,
+) -> Result<LanguageProfile, String> {
+    apply_profile(&app_handle, name.trim())
+}
@@ -1,14 +1,104 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 use rosc::encoder;
 use rosc::{OscMessage, OscPacket, OscType};
+use std::io::ErrorKind;
 use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::thread;
+use std::time::{Duration, Instant};
 use tauri::AppHandle;
 use tauri::Emitter;
 
+mod aec;
+mod agc;
+mod agreement;
+mod audio;
+mod audio_decode;
+mod azure_translate;
+mod benchmark;
+mod cancel;
+mod captions;
+mod chatbox;
+mod chatbox_format;
+mod config;
+mod corrections;
+mod debug_recording;
+mod deepl_translate;
+mod denoise;
+mod download;
+mod file_transcribe;
+mod glossary;
+mod google_translate;
+mod gpu;
+mod hallucination;
+mod hardware;
+mod hotword;
+mod itn;
+mod jobs;
+mod language_id;
+mod language_profiles;
+mod libre_translate;
+mod llm_translate;
+mod local_translate;
+mod manifest;
+mod mixer;
+mod model_manager;
+mod noise_gate;
+mod onnx_stt;
+#[cfg(feature = "onnx")]
+mod onnx_whisper;
+mod openai_stt;
+mod openai_tts;
+mod piper_tts;
+mod profanity;
+mod pronunciation;
+mod push_to_talk;
+mod quantization;
+mod sapi_tts;
+mod server_stt;
+mod settings_profiles;
+mod stream_translate;
+mod stt;
+mod translate;
+mod translation_cache;
+mod translation_usage;
+mod transliterate;
+mod tts;
+mod utterance;
+mod vad;
+mod visemes;
+mod watch_folder;
 mod whisper;
+use audio::*;
+use benchmark::*;
+use captions::*;
+use chatbox::*;
+use chatbox_format::*;
+use config::*;
+use corrections::*;
+use debug_recording::*;
+use download::load_download_settings;
+use file_transcribe::*;
+use glossary::*;
+use hardware::*;
+use jobs::*;
+use language_profiles::*;
+use local_translate::*;
+use manifest::*;
+use piper_tts::*;
+use pronunciation::*;
+use push_to_talk::*;
+use quantization::*;
+use sapi_tts::*;
+use settings_profiles::*;
+use stream_translate::*;
+use stt::*;
+use translate::*;
+use translation_cache::*;
+use translation_usage::*;
+use transliterate::*;
+use tts::*;
+use watch_folder::*;
 use whisper::*;
 
 static LISTENER_STARTED: AtomicBool = AtomicBool::new(false);
@@ -31,26 +121,95 @@ fn send_typing(address: String, port: String) -> Result<(), String> {
     Ok(())
 }
 
-#[tauri::command]
-fn send_message(msg: String, address: String, port: String) -> Result<(), String> {
-    let sock = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
-        .map_err(|e| format!("Failed to bind socket: {}", e))?;
+// Default time a MuteSelf value must stay unchanged before `vrchat-mute` fires
+const DEFAULT_MUTE_DEBOUNCE_MS: u64 = 150;
 
-    let msg_buf = encoder::encode(&OscPacket::Message(OscMessage {
-        addr: "/chatbox/input".to_string(),
-        args: vec![OscType::String(msg), OscType::Bool(true)],
-    }))
-    .map_err(|e| format!("Failed to encode OSC message: {}", e))?;
+static MUTE_DEBOUNCE_MS: AtomicU64 = AtomicU64::new(DEFAULT_MUTE_DEBOUNCE_MS);
 
-    let target = format!("{}:{}", address, port);
-    sock.send_to(&msg_buf, &target)
-        .map_err(|e| format!("Failed to send OSC message: {}", e))?;
+// Filters duplicate MuteSelf updates and only reports states that stayed stable
+// for the configured debounce window. VRChat tends to resend the same value
+// (and occasionally flap) when avatars reload or the mic toggle is spammed.
+struct MuteDebouncer {
+    last_emitted: Option<bool>,
+    pending: Option<(bool, Instant)>,
+}
+
+impl MuteDebouncer {
+    fn new() -> Self {
+        Self {
+            last_emitted: None,
+            pending: None,
+        }
+    }
+
+    fn update(&mut self, mute: bool) {
+        match self.pending {
+            // Duplicate of a value we're already waiting on, keep the original timestamp
+            Some((pending, _)) if pending == mute => {}
+            _ => {
+                if self.last_emitted == Some(mute) {
+                    // Flapped back to the already reported state, nothing to emit
+                    self.pending = None;
+                } else {
+                    self.pending = Some((mute, Instant::now()));
+                }
+            }
+        }
+    }
+
+    // Returns the state to emit once the pending value has been stable long enough
+    fn poll(&mut self, debounce: Duration) -> Option<bool> {
+        let (mute, since) = self.pending?;
+        if since.elapsed() < debounce {
+            return None;
+        }
+        self.pending = None;
+        self.last_emitted = Some(mute);
+        Some(mute)
+    }
+
+    // Time left until the pending value settles, used as the socket read timeout
+    fn time_until_settled(&self, debounce: Duration) -> Option<Duration> {
+        self.pending
+            .map(|(_, since)| debounce.saturating_sub(since.elapsed()))
+    }
+}
+
+fn handle_osc_packet(packet: OscPacket, debouncer: &mut MuteDebouncer) {
+    match packet {
+        OscPacket::Message(msg) => {
+            if msg.addr.as_str() == "/avatar/parameters/MuteSelf" {
+                if let Some(mute) = msg.args.first().and_then(|arg| arg.clone().bool()) {
+                    debouncer.update(mute);
+                }
+            }
+        }
+        OscPacket::Bundle(bundle) => {
+            // Process messages in bundle
+            for message in bundle.content {
+                handle_osc_packet(message, debouncer);
+            }
+        }
+    }
+}
+
+fn mute_debounce() -> Duration {
+    Duration::from_millis(MUTE_DEBOUNCE_MS.load(Ordering::SeqCst))
+}
 
+#[tauri::command]
+fn set_mute_debounce(debounce_ms: u64) -> Result<(), String> {
+    MUTE_DEBOUNCE_MS.store(debounce_ms, Ordering::SeqCst);
+    println!("Mute debounce set to {}ms", debounce_ms);
     Ok(())
 }
 
 #[tauri::command]
-fn start_vrc_listener(app: AppHandle) -> Result<(), String> {
+fn start_vrc_listener(app: AppHandle, mute_debounce_ms: Option<u64>) -> Result<(), String> {
+    if let Some(debounce_ms) = mute_debounce_ms {
+        MUTE_DEBOUNCE_MS.store(debounce_ms, Ordering::SeqCst);
+    }
+
     // Only start the listener once
     if LISTENER_STARTED.swap(true, Ordering::SeqCst) {
         return Ok(());
@@ -64,46 +223,27 @@ fn start_vrc_listener(app: AppHandle) -> Result<(), String> {
                 let _ = app.emit("vrchat-status", "connected");
 
                 let mut buf = [0u8; rosc::decoder::MTU];
+                let mut debouncer = MuteDebouncer::new();
 
                 loop {
+                    // Wake up in time to flush a pending mute change even if VRChat goes quiet
+                    let debounce = mute_debounce();
+                    let _ = sock.set_read_timeout(
+                        debouncer
+                            .time_until_settled(debounce)
+                            .map(|d| d.max(Duration::from_millis(1))),
+                    );
+
                     match sock.recv_from(&mut buf) {
-                        Ok((size, _)) => {
-                            match rosc::decoder::decode_udp(&buf[..size]) {
-                                Ok((_, packet)) => {
-                                    match packet {
-                                        OscPacket::Message(msg) => {
-                                            if msg.addr.as_str() == "/avatar/parameters/MuteSelf" {
-                                                if let Some(arg) = msg.args.first() {
-                                                    if let Some(mute) = arg.clone().bool() {
-                                                        let _ = app.emit("vrchat-mute", mute);
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        OscPacket::Bundle(bundle) => {
-                                            // Process messages in bundle
-                                            for message in bundle.content {
-                                                if let OscPacket::Message(msg) = message {
-                                                    if msg.addr.as_str()
-                                                        == "/avatar/parameters/MuteSelf"
-                                                    {
-                                                        if let Some(arg) = msg.args.first() {
-                                                            if let Some(mute) = arg.clone().bool() {
-                                                                let _ =
-                                                                    app.emit("vrchat-mute", mute);
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    println!("Error decoding OSC packet: {}", e);
-                                }
+                        Ok((size, _)) => match rosc::decoder::decode_udp(&buf[..size]) {
+                            Ok((_, packet)) => handle_osc_packet(packet, &mut debouncer),
+                            Err(e) => {
+                                println!("Error decoding OSC packet: {}", e);
                             }
-                        }
+                        },
+                        Err(e)
+                            if e.kind() == ErrorKind::WouldBlock
+                                || e.kind() == ErrorKind::TimedOut => {}
                         Err(e) => {
                             println!("Error receiving from socket: {}", e);
                             let _ = app.emit("vrchat-status", "disconnected");
@@ -126,6 +266,10 @@ fn start_vrc_listener(app: AppHandle) -> Result<(), String> {
                             }
                         }
                     }
+
+                    if let Some(mute) = debouncer.poll(mute_debounce()) {
+                        let _ = app.emit("vrchat-mute", mute);
+                    }
                 }
             }
             Err(e) => {
@@ -147,18 +291,224 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_log::Builder::new().build())
-        .manage(WhisperAppState {
-            state: Arc::new(Mutex::new(None)),
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .manage(SettingsState::default())
+        .manage(ChatboxAppState::default())
+        .manage(WhisperAppState::new())
+        .manage(JobQueueState::default())
+        .manage(SttAppState::default())
+        .manage(TranslateAppState::default())
+        .manage(CaptionsAppState::default())
+        .manage(StreamTranslateState::default())
+        .manage(LanguageProfilesState::default())
+        .manage(LocalTranslateState::default())
+        .manage(WatchFolderState::default())
+        .manage(AudioCaptureState::default())
+        .manage(PushToTalkState::default())
+        .manage(DebugRecordingState::default())
+        .manage(TtsAppState::default())
+        .manage(PiperState::default())
+        .setup(|app| {
+            load_settings(app.handle());
+            load_custom_models(app.handle());
+            load_download_settings(app.handle());
+            load_corrections(app.handle());
+            load_translation_cache(app.handle());
+            load_glossary(app.handle());
+            load_translation_usage(app.handle());
+            load_language_profiles(app.handle());
+            load_pronunciations(app.handle());
+            report_backends(app.handle());
+            // Pick up newly published models without shipping a new build
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                refresh_model_manifest(&handle).await;
+            });
+            Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             send_typing,
+            get_settings,
+            update_settings,
+            list_settings_profiles,
+            get_settings_profile,
+            create_settings_profile,
+            clone_settings_profile,
+            delete_settings_profile,
+            switch_settings_profile,
             send_message,
+            set_chatbox_preview,
+            get_chatbox_preview,
+            get_chatbox_format,
+            set_chatbox_format,
+            format_chatbox_message,
+            transliterate_text,
             start_vrc_listener,
+            set_mute_debounce,
             whisper_download_model,
+            whisper_cancel_download,
+            whisper_get_download_settings,
+            whisper_set_download_settings,
+            whisper_get_decoding_options,
+            whisper_set_decoding_options,
+            whisper_get_denoise_sources,
+            whisper_set_denoise,
+            whisper_get_vad_settings,
+            whisper_set_vad_settings,
+            whisper_get_debug_recording,
+            whisper_set_debug_recording,
+            whisper_get_hallucination_filter,
+            whisper_set_hallucination_filter,
+            whisper_get_profanity_filter,
+            whisper_set_profanity_filter,
+            whisper_get_text_normalization,
+            whisper_set_text_normalization,
+            whisper_get_corrections,
+            whisper_set_corrections,
             whisper_is_model_downloaded,
             whisper_get_downloaded_models,
-            whisper_transcribe
+            whisper_get_model_catalog,
+            whisper_add_custom_model,
+            whisper_remove_custom_model,
+            whisper_delete_model,
+            whisper_import_model,
+            whisper_repair_model,
+            whisper_get_storage_info,
+            whisper_transcribe,
+            whisper_transcribe_pcm,
+            whisper_transcribe_file,
+            whisper_transcribe_files,
+            whisper_start_watch_folder,
+            whisper_stop_watch_folder,
+            whisper_get_watch_folder,
+            audio_start_capture,
+            audio_stop_capture,
+            audio_get_capture,
+            audio_get_agc,
+            audio_set_agc,
+            audio_get_noise_gate,
+            audio_set_noise_gate,
+            audio_calibrate_noise_gate,
+            audio_get_echo_cancellation,
+            audio_set_echo_cancellation,
+            audio_get_sources,
+            audio_set_source,
+            list_audio_devices,
+            set_audio_device,
+            audio_get_input_mix,
+            audio_set_input_mix,
+            audio_get_push_to_talk,
+            audio_set_push_to_talk,
+            audio_set_push_to_talk_pressed,
+            stt_get_provider,
+            stt_set_provider,
+            stt_get_hotword,
+            stt_set_hotword,
+            stt_transcribe,
+            translate_get_provider,
+            translate_set_provider,
+            translate_get_pair_providers,
+            translate_set_pair_providers,
+            translate_get_fallback_providers,
+            translate_set_fallback_providers,
+            translate_deepl_usage,
+            translate_clear_cache,
+            translate_get_glossary,
+            translate_set_glossary,
+            get_translation_usage,
+            translate_list_local_models,
+            translate_download_model,
+            translate_delete_local_model,
+            captions_get_settings,
+            captions_set_settings,
+            captions_history,
+            get_language_profiles,
+            set_language_profiles,
+            switch_language_profile,
+            tts_get_settings,
+            tts_set_settings,
+            tts_list_output_devices,
+            tts_speak,
+            tts_stop,
+            tts_skip,
+            tts_clear_queue,
+            tts_get_queue,
+            tts_set_priority,
+            tts_list_piper_voices,
+            tts_download_piper_voice,
+            tts_delete_piper_voice,
+            tts_list_sapi_voices,
+            tts_get_pronunciations,
+            tts_set_pronunciations,
+            translate_text,
+            whisper_detect_language,
+            whisper_cancel,
+            whisper_submit_job,
+            whisper_submit_dual_job,
+            whisper_submit_retry_job,
+            whisper_job_status,
+            whisper_list_jobs,
+            whisper_cancel_job,
+            whisper_set_queue_limits,
+            whisper_transcribe_stream,
+            whisper_end_stream,
+            whisper_get_backends,
+            whisper_set_backend,
+            whisper_get_quantization,
+            whisper_set_quantization,
+            whisper_preload_model,
+            whisper_get_loaded_models,
+            whisper_unload_model,
+            whisper_benchmark,
+            whisper_compare,
+            get_hardware_profile
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mute_debouncer_emits_once_settled() {
+        let mut debouncer = MuteDebouncer::new();
+        debouncer.update(true);
+        assert_eq!(debouncer.poll(Duration::from_secs(60)), None);
+        assert_eq!(debouncer.poll(Duration::ZERO), Some(true));
+        assert_eq!(debouncer.poll(Duration::ZERO), None);
+    }
+
+    #[test]
+    fn mute_debouncer_ignores_repeats_of_reported_state() {
+        let mut debouncer = MuteDebouncer::new();
+        debouncer.update(true);
+        assert_eq!(debouncer.poll(Duration::ZERO), Some(true));
+        debouncer.update(true);
+        debouncer.update(true);
+        assert_eq!(debouncer.poll(Duration::ZERO), None);
+        assert_eq!(debouncer.time_until_settled(Duration::ZERO), None);
+    }
+
+    #[test]
+    fn mute_debouncer_drops_flap_back_to_reported_state() {
+        let mut debouncer = MuteDebouncer::new();
+        debouncer.update(false);
+        assert_eq!(debouncer.poll(Duration::ZERO), Some(false));
+        debouncer.update(true);
+        debouncer.update(false);
+        assert_eq!(debouncer.poll(Duration::ZERO), None);
+    }
+
+    #[test]
+    fn mute_debouncer_reports_latest_pending_state() {
+        let mut debouncer = MuteDebouncer::new();
+        debouncer.update(true);
+        debouncer.update(false);
+        assert!(debouncer
+            .time_until_settled(Duration::from_secs(60))
+            .is_some());
+        assert_eq!(debouncer.poll(Duration::ZERO), Some(false));
+    }
+}
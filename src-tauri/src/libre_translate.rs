@@ -0,0 +1,125 @@
+// Self-hosted LibreTranslate instances, so translation traffic can stay on the LAN.
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::translate::{TranslateError, TranslationProvider};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LibreTranslateConfig {
+    // e.g. "http://192.168.1.20:5000"
+    pub url: String,
+    // Only needed when the instance is started with API keys required
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+impl LibreTranslateConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let url = self.url.trim();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!("Invalid LibreTranslate URL: '{}'", url));
+        }
+        Ok(())
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/translate", self.url.trim().trim_end_matches('/'))
+    }
+}
+
+#[derive(Serialize)]
+struct LibreRequest<'a> {
+    q: &'a [String],
+    source: String,
+    target: String,
+    format: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct LibreResponse {
+    #[serde(rename = "translatedText")]
+    translated_text: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct LibreError {
+    error: String,
+}
+
+// LibreTranslate uses bare codes, with "zt" for traditional Chinese
+fn language_code(code: &str) -> String {
+    let code = code.to_lowercase();
+    match code.as_str() {
+        "zh-tw" | "zh-hk" | "zh-hant" => "zt".to_string(),
+        code => code.split('-').next().unwrap_or(code).to_string(),
+    }
+}
+
+impl TranslationProvider for LibreTranslateConfig {
+    fn name(&self) -> &'static str {
+        "libretranslate"
+    }
+
+    async fn translate(
+        &self,
+        texts: &[String],
+        source: &str,
+        target: &str,
+    ) -> Result<Vec<String>, TranslateError> {
+        self.validate().map_err(TranslateError::Config)?;
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| TranslateError::Config(format!("Failed to create HTTP client: {}", e)))?;
+
+        let request = LibreRequest {
+            q: texts,
+            source: language_code(source),
+            target: language_code(target),
+            format: "text",
+            api_key: self
+                .api_key
+                .as_deref()
+                .map(str::trim)
+                .filter(|k| !k.is_empty()),
+        };
+        let response = client
+            .post(self.endpoint())
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                TranslateError::Unavailable(format!("LibreTranslate request failed: {}", e))
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = response
+                .json::<LibreError>()
+                .await
+                .map(|body| body.error)
+                .unwrap_or_else(|_| status.to_string());
+            return Err(match status.as_u16() {
+                403 => TranslateError::Config(format!("LibreTranslate refused: {}", message)),
+                429 => TranslateError::RateLimited(message),
+                _ if status.is_server_error() => TranslateError::Unavailable(message),
+                _ => TranslateError::Failed(message),
+            });
+        }
+        let response: LibreResponse = response.json().await.map_err(|e| {
+            TranslateError::Failed(format!("Invalid LibreTranslate response: {}", e))
+        })?;
+        if response.translated_text.len() != texts.len() {
+            return Err(TranslateError::Failed(format!(
+                "LibreTranslate returned {} translations for {} texts",
+                response.translated_text.len(),
+                texts.len()
+            )));
+        }
+        Ok(response.translated_text)
+    }
+}
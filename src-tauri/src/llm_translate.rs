@@ -0,0 +1,279 @@
+// Translation by a chat model behind any OpenAI-compatible `/v1/chat/completions`
+// endpoint (OpenAI, Groq, OpenRouter, Ollama, LM Studio, ...). The system prompt is
+// configurable; the default one asks for casual, chatbox-sized small talk.
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use crate::translate::{TranslateError, TranslationProvider, AUTO_LANGUAGE};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+// VRChat's chatbox limit
+const DEFAULT_MAX_LENGTH: usize = 144;
+// {source}, {target} and {max_length} are filled in per request
+const DEFAULT_PROMPT: &str = "You translate messages spoken in VRChat voice chat from {source} to {target}. \
+Keep the casual tone, slang, jokes and emoticons, and don't make the message more formal than it is. \
+Keep the translation under {max_length} characters. \
+Reply with the translation only, without quotes, notes or explanations.";
+
+const LANGUAGE_NAMES: [(&str, &str); 20] = [
+    ("en", "English"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("zh-cn", "Simplified Chinese"),
+    ("zh-tw", "Traditional Chinese"),
+    ("zh", "Chinese"),
+    ("es", "Spanish"),
+    ("fr", "French"),
+    ("de", "German"),
+    ("ru", "Russian"),
+    ("id", "Indonesian"),
+    ("ms", "Malay"),
+    ("ar", "Arabic"),
+    ("it", "Italian"),
+    ("pt", "Portuguese"),
+    ("th", "Thai"),
+    ("vi", "Vietnamese"),
+    ("uk", "Ukrainian"),
+    ("pl", "Polish"),
+    ("tr", "Turkish"),
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LlmTranslateConfig {
+    // e.g. "https://api.openai.com" or "http://localhost:11434"; "/v1" is optional
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    // e.g. "gpt-4o-mini" or "llama3.1:8b"
+    pub model: String,
+    // System prompt, the default one when unset
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default = "default_max_length")]
+    pub max_length: usize,
+}
+
+fn default_max_length() -> usize {
+    DEFAULT_MAX_LENGTH
+}
+
+impl LlmTranslateConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let base_url = self.base_url.trim();
+        if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+            return Err(format!("Invalid LLM server URL: '{}'", base_url));
+        }
+        if self.model.trim().is_empty() {
+            return Err("LLM translation needs a model name".to_string());
+        }
+        if self.max_length == 0 {
+            return Err("LLM translation length limit must be above 0".to_string());
+        }
+        Ok(())
+    }
+
+    fn endpoint(&self) -> String {
+        let base_url = self.base_url.trim().trim_end_matches('/');
+        if base_url.ends_with("/v1") {
+            format!("{}/chat/completions", base_url)
+        } else {
+            format!("{}/v1/chat/completions", base_url)
+        }
+    }
+
+    fn prompt(&self) -> &str {
+        self.prompt
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .unwrap_or(DEFAULT_PROMPT)
+    }
+
+    fn system_prompt(&self, source: &str, target: &str) -> String {
+        let source = if source == AUTO_LANGUAGE {
+            "whatever language it is in".to_string()
+        } else {
+            language_name(source)
+        };
+        self.prompt()
+            .replace("{source}", &source)
+            .replace("{target}", &language_name(target))
+            .replace("{max_length}", &self.max_length.to_string())
+    }
+
+    // Identifies the model and prompt, so editing the prompt doesn't serve old translations
+    pub fn cache_key(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.prompt().hash(&mut hasher);
+        self.max_length.hash(&mut hasher);
+        format!(
+            "llm:{}:{}:{:x}",
+            self.base_url.trim(),
+            self.model.trim(),
+            hasher.finish()
+        )
+    }
+}
+
+fn language_name(code: &str) -> String {
+    let lower = code.to_lowercase();
+    let base = lower.split('-').next().unwrap_or(&lower);
+    LANGUAGE_NAMES
+        .iter()
+        .find(|(c, _)| *c == lower)
+        .or_else(|| LANGUAGE_NAMES.iter().find(|(c, _)| *c == base))
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| code.to_string())
+}
+
+// Models sometimes wrap the answer in quotes despite the prompt
+fn clean_reply(reply: &str) -> String {
+    let reply = reply.trim();
+    for (open, close) in [("\"", "\""), ("“", "”"), ("「", "」")] {
+        if let Some(inner) = reply.strip_prefix(open).and_then(|r| r.strip_suffix(close)) {
+            if !inner.contains(open) && !inner.contains(close) {
+                return inner.trim().to_string();
+            }
+        }
+    }
+    reply.to_string()
+}
+
+#[derive(Serialize)]
+struct ChatMessage<'a> {
+    role: &'static str,
+    content: &'a str,
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: [ChatMessage<'a>; 2],
+    temperature: f32,
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatReply,
+}
+
+#[derive(Deserialize)]
+struct ChatReply {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChatErrorBody {
+    error: ChatError,
+}
+
+#[derive(Deserialize)]
+struct ChatError {
+    message: String,
+}
+
+impl TranslationProvider for LlmTranslateConfig {
+    fn name(&self) -> &'static str {
+        "llm"
+    }
+
+    async fn translate(
+        &self,
+        texts: &[String],
+        source: &str,
+        target: &str,
+    ) -> Result<Vec<String>, TranslateError> {
+        self.validate().map_err(TranslateError::Config)?;
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| TranslateError::Config(format!("Failed to create HTTP client: {}", e)))?;
+        let system_prompt = self.system_prompt(source, target);
+        let api_key = self
+            .api_key
+            .as_deref()
+            .map(str::trim)
+            .filter(|k| !k.is_empty());
+
+        // One request per text, so a model can't merge or drop lines of a batch
+        let mut translated = Vec::with_capacity(texts.len());
+        for text in texts {
+            let request = ChatRequest {
+                model: self.model.trim(),
+                messages: [
+                    ChatMessage {
+                        role: "system",
+                        content: &system_prompt,
+                    },
+                    ChatMessage {
+                        role: "user",
+                        content: text,
+                    },
+                ],
+                temperature: 0.3,
+                stream: false,
+            };
+            let mut builder = client.post(self.endpoint()).json(&request);
+            if let Some(api_key) = api_key {
+                builder = builder.bearer_auth(api_key);
+            }
+            let response = builder.send().await.map_err(|e| {
+                if e.is_timeout() {
+                    TranslateError::Unavailable("LLM server timed out".to_string())
+                } else {
+                    TranslateError::Unavailable(format!("LLM request failed: {}", e))
+                }
+            })?;
+
+            let status = response.status();
+            if !status.is_success() {
+                let message = response
+                    .json::<ChatErrorBody>()
+                    .await
+                    .map(|body| body.error.message)
+                    .unwrap_or_else(|_| status.to_string());
+                return Err(match status.as_u16() {
+                    401 | 403 => TranslateError::Config(format!("LLM server refused: {}", message)),
+                    404 => TranslateError::Config(format!(
+                        "Unknown model '{}': {}",
+                        self.model.trim(),
+                        message
+                    )),
+                    429 => TranslateError::RateLimited(message),
+                    _ if status.is_server_error() => TranslateError::Unavailable(message),
+                    _ => TranslateError::Failed(format!("LLM returned {}: {}", status, message)),
+                });
+            }
+            let response: ChatResponse = response
+                .json()
+                .await
+                .map_err(|e| TranslateError::Failed(format!("Invalid LLM response: {}", e)))?;
+            let reply = response
+                .choices
+                .into_iter()
+                .next()
+                .and_then(|choice| choice.message.content)
+                .map(|content| clean_reply(&content))
+                .filter(|reply| !reply.is_empty())
+                .ok_or_else(|| TranslateError::Failed("LLM returned an empty reply".to_string()))?;
+            if reply.chars().count() > self.max_length {
+                println!(
+                    "LLM translation is {} characters, over the {} asked for",
+                    reply.chars().count(),
+                    self.max_length
+                );
+            }
+            translated.push(reply);
+        }
+        Ok(translated)
+    }
+}
@@ -0,0 +1,340 @@
+// Fully offline translation with NLLB-200 converted for CTranslate2, so translation
+// keeps working with no internet and no API quota. Models are fetched with the same
+// download pipeline (progress events, resumable chunks, checksums, cancellation via
+// `whisper_cancel_download`) as Whisper models. Running them needs the native
+// CTranslate2 library and is only built with the `nmt` feature.
+use futures_util::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+#[cfg(feature = "nmt")]
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager, State};
+
+use crate::download::{ModelDownload, DOWNLOAD_CANCELLED};
+use crate::manifest::{fetch_file_size, ModelConfig, ModelFile};
+use crate::translate::{TranslateError, TranslationProvider};
+use crate::whisper::{download_model_file, WhisperAppState};
+
+const TRANSLATION_MODELS_DIR: &str = "translation_models";
+
+#[cfg(feature = "nmt")]
+type NllbTranslator = ct2rs::Translator<ct2rs::tokenizers::auto::Tokenizer>;
+
+// Downloadable models: (ID, Hugging Face repository, files)
+const LOCAL_MODELS: [(&str, &str, &[&str]); 2] = [
+    (
+        "nllb-200-600m",
+        "JustFrederik/nllb-200-distilled-600M-ct2-int8",
+        &[
+            "config.json",
+            "model.bin",
+            "shared_vocabulary.txt",
+            "tokenizer.json",
+        ],
+    ),
+    (
+        "nllb-200-1.3b",
+        "JustFrederik/nllb-200-distilled-1.3B-ct2-int8",
+        &[
+            "config.json",
+            "model.bin",
+            "shared_vocabulary.txt",
+            "tokenizer.json",
+        ],
+    ),
+];
+
+// App language codes to the FLORES-200 codes NLLB uses
+const NLLB_LANGUAGES: [(&str, &str); 22] = [
+    ("en", "eng_Latn"),
+    ("ja", "jpn_Jpan"),
+    ("ko", "kor_Hang"),
+    ("zh", "zho_Hans"),
+    ("zh-cn", "zho_Hans"),
+    ("zh-tw", "zho_Hant"),
+    ("es", "spa_Latn"),
+    ("fr", "fra_Latn"),
+    ("de", "deu_Latn"),
+    ("ru", "rus_Cyrl"),
+    ("id", "ind_Latn"),
+    ("ms", "zsm_Latn"),
+    ("ar", "arb_Arab"),
+    ("it", "ita_Latn"),
+    ("pt", "por_Latn"),
+    ("th", "tha_Thai"),
+    ("vi", "vie_Latn"),
+    ("uk", "ukr_Cyrl"),
+    ("pl", "pol_Latn"),
+    ("nl", "nld_Latn"),
+    ("tr", "tur_Latn"),
+    ("tl", "tgl_Latn"),
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LocalTranslateConfig {
+    // ID of a downloaded model from `translate_list_local_models`
+    pub model: String,
+}
+
+impl LocalTranslateConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !cfg!(feature = "nmt") {
+            return Err("This build of VRCTalk doesn't include local translation".to_string());
+        }
+        find_local_model(&self.model).map(|_| ())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LocalModelInfo {
+    pub id: String,
+    pub repo_id: String,
+    pub downloaded: bool,
+}
+
+// The loaded model, kept until another one is needed
+#[derive(Default)]
+pub struct LocalTranslateState {
+    #[cfg(feature = "nmt")]
+    loaded: Mutex<Option<(String, Arc<NllbTranslator>)>>,
+}
+
+fn find_local_model(id: &str) -> Result<(&'static str, &'static [&'static str]), String> {
+    LOCAL_MODELS
+        .iter()
+        .find(|(model, _, _)| *model == id)
+        .map(|(_, repo_id, files)| (*repo_id, *files))
+        .ok_or_else(|| format!("Unknown translation model '{}'", id))
+}
+
+fn models_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join(TRANSLATION_MODELS_DIR))
+}
+
+fn is_downloaded(model_path: &std::path::Path, files: &[&str]) -> bool {
+    files.iter().all(|file| {
+        fs::metadata(model_path.join(file))
+            .map(|m| m.len() > 0)
+            .unwrap_or(false)
+    })
+}
+
+fn nllb_language(code: &str) -> Option<&'static str> {
+    let code = code.to_lowercase();
+    let base = code.split('-').next().unwrap_or(&code);
+    NLLB_LANGUAGES
+        .iter()
+        .find(|(app, _)| *app == code)
+        .or_else(|| NLLB_LANGUAGES.iter().find(|(app, _)| *app == base))
+        .map(|(_, nllb)| *nllb)
+}
+
+// A local model bound to the app, so it can find and cache the model files
+pub struct LocalTranslator<'a> {
+    pub app_handle: &'a tauri::AppHandle,
+    pub config: &'a LocalTranslateConfig,
+}
+
+// The cached translator for `model`, loading it from disk if another one (or
+// none) is loaded. Blocks while loading.
+#[cfg(feature = "nmt")]
+fn load_model(
+    app_handle: &tauri::AppHandle,
+    model: &str,
+) -> Result<Arc<NllbTranslator>, TranslateError> {
+    let state = app_handle.state::<LocalTranslateState>();
+    let mut loaded = state
+        .loaded
+        .lock()
+        .map_err(|e| TranslateError::Failed(format!("Mutex poisoned: {:?}", e)))?;
+    if let Some((id, translator)) = loaded.as_ref() {
+        if id == model {
+            return Ok(translator.clone());
+        }
+    }
+
+    let (_, files) = find_local_model(model).map_err(TranslateError::Config)?;
+    let model_path = models_dir(app_handle)
+        .map_err(TranslateError::Failed)?
+        .join(model);
+    if !is_downloaded(&model_path, files) {
+        return Err(TranslateError::Config(format!(
+            "Translation model {} is not downloaded",
+            model
+        )));
+    }
+    println!("Loading translation model {}", model);
+    let translator = ct2rs::Translator::new(&model_path, &ct2rs::Config::default())
+        .map(Arc::new)
+        .map_err(|e| TranslateError::Failed(format!("Failed to load model: {}", e)))?;
+    *loaded = Some((model.to_string(), translator.clone()));
+    Ok(translator)
+}
+
+impl TranslationProvider for LocalTranslator<'_> {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    // NLLB's tokenizer marks the source as English by default; the encoder copes well
+    // with other source languages, only the target prefix is essential
+    async fn translate(
+        &self,
+        texts: &[String],
+        _source: &str,
+        target: &str,
+    ) -> Result<Vec<String>, TranslateError> {
+        let target = nllb_language(target).ok_or_else(|| {
+            TranslateError::Config(format!("Local translation doesn't support '{}'", target))
+        })?;
+
+        #[cfg(feature = "nmt")]
+        {
+            let app_handle = self.app_handle.clone();
+            let model = self.config.model.clone();
+            let texts = texts.to_vec();
+            tokio::task::spawn_blocking(move || {
+                let translator = load_model(&app_handle, &model)?;
+                let prefixes = vec![vec![target.to_string()]; texts.len()];
+                translator
+                    .translate_batch_with_target_prefix(
+                        &texts,
+                        &prefixes,
+                        &ct2rs::TranslationOptions::<String, String>::default(),
+                        None,
+                    )
+                    .map(|results| {
+                        results
+                            .into_iter()
+                            .map(|(text, _)| text.trim().to_string())
+                            .collect()
+                    })
+                    .map_err(|e| TranslateError::Failed(format!("Local translation failed: {}", e)))
+            })
+            .await
+            .map_err(|e| TranslateError::Failed(format!("Translation task failed: {}", e)))?
+        }
+        #[cfg(not(feature = "nmt"))]
+        {
+            let _ = (self.app_handle, texts, target);
+            Err(TranslateError::Config(
+                "This build of VRCTalk doesn't include local translation".to_string(),
+            ))
+        }
+    }
+}
+
+#[tauri::command]
+pub fn translate_list_local_models(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<LocalModelInfo>, String> {
+    let dir = models_dir(&app_handle)?;
+    Ok(LOCAL_MODELS
+        .iter()
+        .map(|(id, repo_id, files)| LocalModelInfo {
+            id: id.to_string(),
+            repo_id: repo_id.to_string(),
+            downloaded: is_downloaded(&dir.join(id), files),
+        })
+        .collect())
+}
+
+// Download a translation model, reporting progress through the same
+// `download-progress` events as Whisper models
+#[tauri::command]
+pub async fn translate_download_model(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    model: String,
+) -> Result<bool, String> {
+    let (repo_id, files) = find_local_model(&model)?;
+    let registration = state
+        .downloads
+        .register(&model)
+        .map_err(|_| format!("Model {} is already being downloaded", model))?;
+    let cancel = registration.flag();
+    let download_settings = state
+        .download_settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone();
+
+    let mut model_info = ModelConfig {
+        id: model.clone(),
+        repo_id: repo_id.to_string(),
+        revision: None,
+        base_url: None,
+        language: None,
+        custom: false,
+        files: Vec::new(),
+    };
+    // Sizes aren't pinned for these models, ask the server for them
+    for file in files {
+        let url = model_info.file_url(&download_settings, file);
+        model_info.files.push(ModelFile {
+            name: file.to_string(),
+            size: fetch_file_size(&download_settings, &url).await?,
+            sha256: None,
+        });
+    }
+
+    let model_path = models_dir(&app_handle)?.join(&model);
+    fs::create_dir_all(&model_path)
+        .map_err(|e| format!("Failed to create translation model directory: {}", e))?;
+
+    println!("=== TRANSLATION MODEL DOWNLOAD START ({}) ===", model);
+    let total_bytes = model_info.files.iter().map(|f| f.size).sum();
+    let download = ModelDownload::new(
+        &app_handle,
+        &download_settings,
+        &model,
+        total_bytes,
+        &cancel,
+    )?;
+    let result =
+        try_join_all(model_info.files.iter().map(|model_file| {
+            download_model_file(&download, &model_info, model_file, &model_path)
+        }))
+        .await;
+
+    if let Err(e) = result {
+        if registration.is_cancelled() {
+            let _ = app_handle.emit("download-cancelled", serde_json::json!({ "model": model }));
+            return Err(DOWNLOAD_CANCELLED.to_string());
+        }
+        return Err(e);
+    }
+    println!("Translation model {} downloaded successfully", model);
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn translate_delete_local_model(
+    app_handle: tauri::AppHandle,
+    model: String,
+) -> Result<(), String> {
+    find_local_model(&model)?;
+    #[cfg(feature = "nmt")]
+    {
+        let state = app_handle.state::<LocalTranslateState>();
+        let mut loaded = state
+            .loaded
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        if loaded.as_ref().is_some_and(|(id, _)| *id == model) {
+            *loaded = None;
+        }
+    }
+    let model_path = models_dir(&app_handle)?.join(&model);
+    if model_path.exists() {
+        fs::remove_dir_all(&model_path)
+            .map_err(|e| format!("Failed to delete translation model: {}", e))?;
+    }
+    println!("Deleted translation model {}", model);
+    Ok(())
+}
@@ -0,0 +1,484 @@
+use base64::Engine;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+use tauri::{Emitter, Manager, State};
+
+use crate::download::DownloadSettings;
+use crate::whisper::{is_weights_file, whisper_language_code, WhisperAppState};
+
+// Catalog shipped with the app, used until (or if) the remote manifest is verified
+const BUNDLED_MANIFEST: &str = include_str!("../models/manifest.json");
+
+const MANIFEST_URL: &str =
+    "https://raw.githubusercontent.com/KannaCS/VRCTalk/main/src-tauri/models/manifest.json";
+
+// User-registered models (fine-tunes etc.), kept in the app data directory
+const CUSTOM_MODELS_FILE: &str = "custom_models.json";
+
+// Ed25519 public key the remote manifest must be signed with. The detached,
+// base64-encoded signature is published next to the manifest as `manifest.json.sig`;
+// re-sign after every manifest change:
+//   openssl pkeyutl -sign -inkey <key.pem> -rawin -in manifest.json | base64 -w0 > manifest.json.sig
+const MANIFEST_PUBLIC_KEY: [u8; 32] = [
+    0x6d, 0xf9, 0x00, 0xcf, 0x5f, 0xae, 0x17, 0xb1, 0xac, 0xf6, 0x7d, 0x4a, 0x5a, 0x83, 0x69, 0x00,
+    0x68, 0xbd, 0x4b, 0x6f, 0x41, 0xc8, 0x00, 0x51, 0x8d, 0xb3, 0x8f, 0x53, 0xb1, 0x05, 0x1b, 0xc5,
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModelFile {
+    pub name: String,
+    // Exact size of the file as published upstream, used to validate downloads
+    pub size: u64,
+    // Expected SHA-256, looked up from the Hugging Face LFS metadata when not pinned here
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModelConfig {
+    pub id: String,
+    pub repo_id: String,
+    // Commit of `repo_id` the files are pinned to, so upstream changes to the repo
+    // can't leave users with files from different revisions. Unpinned models track main.
+    #[serde(default)]
+    pub revision: Option<String>,
+    // Models hosted outside Hugging Face are fetched from `{base_url}/{file name}`
+    #[serde(default)]
+    pub base_url: Option<String>,
+    // Language a fine-tuned model was trained for
+    #[serde(default)]
+    pub language: Option<String>,
+    // Registered by the user rather than listed in the manifest
+    #[serde(default)]
+    pub custom: bool,
+    pub files: Vec<ModelFile>,
+}
+
+impl ModelConfig {
+    pub fn file_url(&self, settings: &DownloadSettings, filename: &str) -> String {
+        match &self.base_url {
+            Some(base_url) => format!("{}/{}", base_url.trim_end_matches('/'), filename),
+            None => settings.resolve_url(&self.repo_id, self.revision.as_deref(), filename),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModelManifest {
+    pub version: u32,
+    pub models: Vec<ModelConfig>,
+}
+
+static MODEL_CATALOG: OnceLock<RwLock<ModelManifest>> = OnceLock::new();
+
+fn catalog() -> &'static RwLock<ModelManifest> {
+    MODEL_CATALOG.get_or_init(|| {
+        let manifest: ModelManifest =
+            serde_json::from_str(BUNDLED_MANIFEST).expect("bundled model manifest is invalid");
+        RwLock::new(manifest)
+    })
+}
+
+static CUSTOM_MODELS: OnceLock<RwLock<Vec<ModelConfig>>> = OnceLock::new();
+
+fn custom_models() -> &'static RwLock<Vec<ModelConfig>> {
+    CUSTOM_MODELS.get_or_init(|| RwLock::new(Vec::new()))
+}
+
+// Manifest models followed by the user's custom ones
+pub fn model_catalog() -> Vec<ModelConfig> {
+    let mut models = catalog()
+        .read()
+        .map(|manifest| manifest.models.clone())
+        .unwrap_or_default();
+    if let Ok(custom) = custom_models().read() {
+        models.extend(custom.iter().cloned());
+    }
+    models
+}
+
+pub fn find_model_config(model_id: &str) -> Result<ModelConfig, String> {
+    model_catalog()
+        .into_iter()
+        .find(|config| config.id == model_id)
+        .ok_or_else(|| format!("Unknown model: {}", model_id))
+}
+
+fn verify_manifest(manifest: &[u8], signature: &str) -> Result<(), String> {
+    let key = VerifyingKey::from_bytes(&MANIFEST_PUBLIC_KEY)
+        .map_err(|e| format!("Invalid manifest public key: {}", e))?;
+    let signature = base64::engine::general_purpose::STANDARD
+        .decode(signature.trim())
+        .map_err(|e| format!("Invalid manifest signature encoding: {}", e))?;
+    let signature = Signature::from_slice(&signature)
+        .map_err(|e| format!("Invalid manifest signature: {}", e))?;
+
+    key.verify_strict(manifest, &signature)
+        .map_err(|e| format!("Manifest signature verification failed: {}", e))
+}
+
+async fn fetch_remote_manifest(settings: &DownloadSettings) -> Result<ModelManifest, String> {
+    let client = settings
+        .client_builder()?
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let manifest = client
+        .get(MANIFEST_URL)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch model manifest: {}", e))?
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read model manifest: {}", e))?;
+
+    let signature = client
+        .get(format!("{}.sig", MANIFEST_URL))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch manifest signature: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read manifest signature: {}", e))?;
+
+    verify_manifest(&manifest, &signature)?;
+
+    serde_json::from_slice(&manifest).map_err(|e| format!("Failed to parse model manifest: {}", e))
+}
+
+// Replace the bundled catalog with the signed remote manifest if it is newer.
+// Any failure leaves the current catalog untouched.
+pub async fn refresh_model_manifest(app_handle: &tauri::AppHandle) {
+    let settings = app_handle
+        .state::<WhisperAppState>()
+        .download_settings
+        .lock()
+        .map(|s| s.clone())
+        .unwrap_or_default();
+
+    let remote = match fetch_remote_manifest(&settings).await {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            println!("Using bundled model manifest: {}", e);
+            return;
+        }
+    };
+
+    let Ok(mut current) = catalog().write() else {
+        return;
+    };
+    if remote.version <= current.version {
+        println!(
+            "Remote model manifest v{} is not newer than v{}",
+            remote.version, current.version
+        );
+        return;
+    }
+
+    println!(
+        "Updated model manifest from v{} to v{} ({} models)",
+        current.version,
+        remote.version,
+        remote.models.len()
+    );
+    *current = remote;
+    let _ = app_handle.emit("model-manifest-updated", current.version);
+}
+
+#[tauri::command]
+pub fn whisper_get_model_catalog() -> Result<Vec<ModelConfig>, String> {
+    Ok(model_catalog())
+}
+
+fn custom_models_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join(CUSTOM_MODELS_FILE))
+}
+
+// Load the user's custom models at startup; a missing or unreadable file just
+// means there are none
+pub fn load_custom_models(app_handle: &tauri::AppHandle) {
+    let Ok(path) = custom_models_path(app_handle) else {
+        return;
+    };
+    let Ok(data) = fs::read_to_string(&path) else {
+        return;
+    };
+    match serde_json::from_str::<Vec<ModelConfig>>(&data) {
+        Ok(models) => {
+            println!("Loaded {} custom model(s)", models.len());
+            if let Ok(mut custom) = custom_models().write() {
+                *custom = models;
+            }
+        }
+        Err(e) => println!("Ignoring invalid {}: {}", CUSTOM_MODELS_FILE, e),
+    }
+}
+
+fn save_custom_models(app_handle: &tauri::AppHandle, models: &[ModelConfig]) -> Result<(), String> {
+    let path = custom_models_path(app_handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let data = serde_json::to_string_pretty(models)
+        .map_err(|e| format!("Failed to serialize custom models: {}", e))?;
+    fs::write(&path, data).map_err(|e| format!("Failed to save custom models: {}", e))
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CustomModelFile {
+    pub name: String,
+    // Looked up from the server when omitted
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+// A fine-tuned model to register, from a Hugging Face repo or a direct URL
+#[derive(Clone, Debug, Deserialize)]
+pub struct CustomModel {
+    pub id: String,
+    #[serde(default)]
+    pub repo_id: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    // Only applies to Hugging Face repos
+    #[serde(default)]
+    pub revision: Option<String>,
+    pub files: Vec<CustomModelFile>,
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+// The model ID doubles as its directory name, so keep it to a safe character set
+fn validate_model_id(id: &str) -> Result<(), String> {
+    let valid = !id.is_empty()
+        && id.len() <= 64
+        && !id.starts_with('.')
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(format!(
+            "Invalid model ID '{}': use letters, digits, '-', '_' and '.'",
+            id
+        ));
+    }
+    Ok(())
+}
+
+// Branch, tag or commit hash; it becomes a URL path segment
+fn validate_revision(revision: &str) -> Result<(), String> {
+    let valid = !revision.is_empty()
+        && revision
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(format!("Invalid revision '{}'", revision));
+    }
+    Ok(())
+}
+
+fn validate_file_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid model file name '{}'", name));
+    }
+    Ok(())
+}
+
+// Size of a remote file from its Content-Length, following redirects to the CDN
+pub async fn fetch_file_size(settings: &DownloadSettings, url: &str) -> Result<u64, String> {
+    let client = settings
+        .client_builder()?
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let response = settings
+        .authorize(client.head(url), url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to query {}: {}", url, e))?;
+    response
+        .content_length()
+        .filter(|&size| size > 0)
+        .ok_or_else(|| format!("Server did not report the size of {}", url))
+}
+
+// Register (or update) a custom model. Files without a size are looked up on the server,
+// which also checks that they exist before anything is downloaded.
+#[tauri::command]
+pub async fn whisper_add_custom_model(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    model: CustomModel,
+) -> Result<ModelConfig, String> {
+    let id = model.id.trim().to_string();
+    validate_model_id(&id)?;
+    let bundled = catalog()
+        .read()
+        .map(|manifest| manifest.models.iter().any(|m| m.id == id))
+        .unwrap_or(false);
+    if bundled {
+        return Err(format!(
+            "Model ID '{}' is already used by a built-in model",
+            id
+        ));
+    }
+
+    let repo_id = model
+        .repo_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty());
+    let base_url = model
+        .url
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty());
+    let (repo_id, base_url) = match (repo_id, base_url) {
+        (Some(repo_id), None) => {
+            if repo_id.split('/').count() != 2 || repo_id.split('/').any(str::is_empty) {
+                return Err(format!(
+                    "Invalid repository '{}', expected 'owner/name'",
+                    repo_id
+                ));
+            }
+            (repo_id.to_string(), None)
+        }
+        (None, Some(url)) => {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err(format!("Invalid model URL '{}'", url));
+            }
+            (String::new(), Some(url.trim_end_matches('/').to_string()))
+        }
+        _ => return Err("Specify either a repository ID or a URL".to_string()),
+    };
+    let revision = model
+        .revision
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty());
+    if let Some(revision) = revision {
+        if base_url.is_some() {
+            return Err("A revision can only be pinned for Hugging Face repositories".to_string());
+        }
+        validate_revision(revision)?;
+    }
+
+    if model.files.is_empty() {
+        return Err("A custom model needs at least one file".to_string());
+    }
+    for file in &model.files {
+        validate_file_name(&file.name)?;
+    }
+    if !model.files.iter().any(|f| is_weights_file(&f.name)) {
+        return Err("A custom model needs a GGML/GGUF weights file (.bin or .gguf)".to_string());
+    }
+    let language = match model.language.as_deref().map(str::trim) {
+        Some(language) if !language.is_empty() => Some(
+            whisper_language_code(language)
+                .ok_or_else(|| format!("Unsupported language '{}'", language))?,
+        ),
+        _ => None,
+    };
+
+    let settings = state
+        .download_settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone();
+    let mut config = ModelConfig {
+        id: id.clone(),
+        repo_id,
+        revision: revision.map(str::to_string),
+        base_url,
+        language,
+        custom: true,
+        files: Vec::new(),
+    };
+    for file in model.files {
+        let size = match file.size {
+            Some(size) => size,
+            None => fetch_file_size(&settings, &config.file_url(&settings, &file.name)).await?,
+        };
+        config.files.push(ModelFile {
+            name: file.name,
+            size,
+            sha256: file.sha256.map(|sha| sha.trim().to_lowercase()),
+        });
+    }
+
+    let models = {
+        let mut custom = custom_models()
+            .write()
+            .map_err(|e| format!("Lock poisoned: {:?}", e))?;
+        custom.retain(|m| m.id != id);
+        custom.push(config.clone());
+        custom.clone()
+    };
+    save_custom_models(&app_handle, &models)?;
+
+    println!(
+        "Registered custom model {} ({} files)",
+        id,
+        config.files.len()
+    );
+    let _ = app_handle.emit("model-catalog-updated", &id);
+    Ok(config)
+}
+
+// Forget a custom model. Downloaded files are left alone; use whisper_delete_model for those.
+#[tauri::command]
+pub fn whisper_remove_custom_model(
+    app_handle: tauri::AppHandle,
+    model_id: String,
+) -> Result<bool, String> {
+    let (removed, models) = {
+        let mut custom = custom_models()
+            .write()
+            .map_err(|e| format!("Lock poisoned: {:?}", e))?;
+        let before = custom.len();
+        custom.retain(|m| m.id != model_id);
+        (custom.len() != before, custom.clone())
+    };
+    if removed {
+        save_custom_models(&app_handle, &models)?;
+        println!("Removed custom model {}", model_id);
+        let _ = app_handle.emit("model-catalog-updated", &model_id);
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUNDLED_SIGNATURE: &str = include_str!("../models/manifest.json.sig");
+
+    #[test]
+    fn bundled_manifest_is_signed() {
+        assert!(verify_manifest(BUNDLED_MANIFEST.as_bytes(), BUNDLED_SIGNATURE).is_ok());
+    }
+
+    #[test]
+    fn tampered_manifest_is_rejected() {
+        let tampered = BUNDLED_MANIFEST.replacen("ggml-tiny.bin", "ggml-evil.bin", 1);
+        assert!(verify_manifest(tampered.as_bytes(), BUNDLED_SIGNATURE).is_err());
+    }
+
+    #[test]
+    fn malformed_signature_is_rejected() {
+        assert!(verify_manifest(BUNDLED_MANIFEST.as_bytes(), "not base64!").is_err());
+        assert!(verify_manifest(BUNDLED_MANIFEST.as_bytes(), "AAAA").is_err());
+    }
+}
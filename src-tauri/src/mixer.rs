@@ -0,0 +1,112 @@
+// Mixing several input devices into the one microphone stream, e.g. a VR headset mic
+// plus a desk mic. The primary device drives the timing; audio from the others is
+// buffered and added in as the primary delivers, each with its own gain.
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+// Extra devices beyond the primary one
+const MAX_MIX_INPUTS: usize = 4;
+// Audio kept per extra device at 16kHz (250ms); devices run on their own clocks, so
+// anything beyond this is dropped from the front rather than left to drift
+const MAX_BUFFERED: usize = 4000;
+const MAX_GAIN: f32 = 4.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MixInput {
+    // Input device ID from `list_audio_devices`
+    pub device: String,
+    pub gain: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputMix {
+    // Gain of the selected (primary) input device
+    pub primary_gain: f32,
+    // Devices mixed in alongside it
+    pub inputs: Vec<MixInput>,
+}
+
+impl Default for InputMix {
+    fn default() -> Self {
+        Self {
+            primary_gain: 1.0,
+            inputs: Vec::new(),
+        }
+    }
+}
+
+impl InputMix {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.inputs.len() > MAX_MIX_INPUTS {
+            return Err(format!(
+                "At most {} extra input devices can be mixed",
+                MAX_MIX_INPUTS
+            ));
+        }
+        let gains = std::iter::once(self.primary_gain).chain(self.inputs.iter().map(|i| i.gain));
+        for gain in gains {
+            if !(0.0..=MAX_GAIN).contains(&gain) {
+                return Err(format!("Input gain must be between 0 and {}", MAX_GAIN));
+            }
+        }
+        for (index, input) in self.inputs.iter().enumerate() {
+            if input.device.trim().is_empty() {
+                return Err("Mixed input device name is empty".to_string());
+            }
+            if self.inputs[..index]
+                .iter()
+                .any(|i| i.device == input.device)
+            {
+                return Err(format!("Input device '{}' is mixed twice", input.device));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Per-capture mixing state, owned by the capture thread; one buffer per extra device
+pub struct Mixer {
+    buffers: Vec<VecDeque<f32>>,
+}
+
+impl Mixer {
+    pub fn new(inputs: usize) -> Self {
+        Self {
+            buffers: (0..inputs)
+                .map(|_| VecDeque::with_capacity(MAX_BUFFERED))
+                .collect(),
+        }
+    }
+
+    // Queue 16kHz audio from extra device `index`
+    pub fn push(&mut self, index: usize, samples: &[f32]) {
+        let Some(buffer) = self.buffers.get_mut(index) else {
+            return;
+        };
+        buffer.extend(samples);
+        let excess = buffer.len().saturating_sub(MAX_BUFFERED);
+        buffer.drain(..excess);
+    }
+
+    // Forget a device's pending audio, e.g. after it disappeared
+    pub fn clear(&mut self, index: usize) {
+        if let Some(buffer) = self.buffers.get_mut(index) {
+            buffer.clear();
+        }
+    }
+
+    // Mix the buffered audio into a block of primary samples in place. Devices that
+    // have fallen behind simply contribute silence for the missing part.
+    pub fn process(&mut self, mix: &InputMix, primary: &mut [f32]) {
+        for sample in primary.iter_mut() {
+            let mut mixed = *sample * mix.primary_gain;
+            for (buffer, input) in self.buffers.iter_mut().zip(&mix.inputs) {
+                if let Some(extra) = buffer.pop_front() {
+                    mixed += extra * input.gain;
+                }
+            }
+            *sample = mixed.clamp(-1.0, 1.0);
+        }
+    }
+}
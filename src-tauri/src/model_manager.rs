@@ -0,0 +1,91 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use whisper_rs::WhisperContext;
+
+use crate::gpu::WhisperBackend;
+
+// How many models may stay resident at once (e.g. a fast partials model plus an
+// accurate finals model). Loading beyond this evicts the least recently used one.
+const DEFAULT_MAX_RESIDENT_MODELS: usize = 2;
+
+pub struct LoadedModel {
+    pub model: String,
+    pub ctx: WhisperContext,
+    pub backend: WhisperBackend,
+}
+
+// Keeps loaded Whisper contexts in memory so each transcription reuses them
+// instead of paying a multi-second load. Contexts are shared via Arc; every
+// inference creates its own WhisperState so callers don't block each other.
+pub struct ModelManager {
+    // Most recently used last
+    models: Mutex<Vec<Arc<LoadedModel>>>,
+    // Serializes loads so concurrent requests don't read the same weights twice
+    load_lock: Mutex<()>,
+    max_resident: usize,
+}
+
+impl ModelManager {
+    pub fn new() -> Self {
+        Self {
+            models: Mutex::new(Vec::new()),
+            load_lock: Mutex::new(()),
+            max_resident: DEFAULT_MAX_RESIDENT_MODELS,
+        }
+    }
+
+    fn lock_models(&self) -> Result<MutexGuard<'_, Vec<Arc<LoadedModel>>>, String> {
+        self.models
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))
+    }
+
+    pub fn lock_loading(&self) -> Result<MutexGuard<'_, ()>, String> {
+        self.load_lock
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))
+    }
+
+    pub fn get(&self, model: &str) -> Option<Arc<LoadedModel>> {
+        let mut models = self.lock_models().ok()?;
+        let index = models.iter().position(|m| m.model == model)?;
+        let loaded = models.remove(index);
+        models.push(loaded.clone());
+        Some(loaded)
+    }
+
+    pub fn insert(&self, loaded: LoadedModel) -> Result<Arc<LoadedModel>, String> {
+        let loaded = Arc::new(loaded);
+        let mut models = self.lock_models()?;
+        models.retain(|m| m.model != loaded.model);
+        while models.len() >= self.max_resident.max(1) {
+            let evicted = models.remove(0);
+            println!("Evicting model '{}' from memory", evicted.model);
+        }
+        models.push(loaded.clone());
+        Ok(loaded)
+    }
+
+    pub fn remove(&self, model: &str) -> Result<bool, String> {
+        let mut models = self.lock_models()?;
+        let before = models.len();
+        models.retain(|m| m.model != model);
+        Ok(models.len() != before)
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        self.lock_models()?.clear();
+        Ok(())
+    }
+
+    pub fn loaded_models(&self) -> Vec<String> {
+        self.lock_models()
+            .map(|models| models.iter().map(|m| m.model.clone()).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl Default for ModelManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,127 @@
+// Noise gate for native capture. Constant background hum (fans, PC coil whine, an
+// idle headset link) below the threshold is silenced before AGC and VAD, so it is
+// neither boosted nor mistaken for the start of speech.
+use serde::{Deserialize, Serialize};
+
+// Gate decisions are made per 10ms block at 16kHz
+const BLOCK_LEN: usize = 160;
+// Per-sample step of the gain towards open/closed, about 6ms to fade, avoiding clicks
+const GAIN_SMOOTHING: f32 = 0.01;
+// Headroom added above the measured noise floor when suggesting a threshold
+const CALIBRATION_MARGIN_DB: f32 = 6.0;
+const SILENCE_DB: f32 = -100.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoiseGateSettings {
+    pub enabled: bool,
+    // Blocks quieter than this (dBFS RMS) are muted
+    pub threshold_db: f32,
+    // How long the gate stays open after the level drops, so word endings survive
+    pub hold_ms: u32,
+}
+
+impl Default for NoiseGateSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_db: -50.0,
+            hold_ms: 200,
+        }
+    }
+}
+
+impl NoiseGateSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-90.0..=-10.0).contains(&self.threshold_db) {
+            return Err("Noise gate threshold must be between -90dB and -10dB".to_string());
+        }
+        if self.hold_ms > 2000 {
+            return Err("Noise gate hold must be at most 2000ms".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn block_rms(block: &[f32]) -> f32 {
+    if block.is_empty() {
+        return 0.0;
+    }
+    (block.iter().map(|s| s * s).sum::<f32>() / block.len() as f32).sqrt()
+}
+
+fn to_db(level: f32) -> f32 {
+    if level > 0.0 {
+        (20.0 * level.log10()).max(SILENCE_DB)
+    } else {
+        SILENCE_DB
+    }
+}
+
+// Per-capture gate state, owned by the capture thread
+pub struct NoiseGate {
+    gain: f32,
+    hold_remaining: usize,
+}
+
+impl Default for NoiseGate {
+    fn default() -> Self {
+        Self {
+            gain: 1.0,
+            hold_remaining: 0,
+        }
+    }
+}
+
+impl NoiseGate {
+    // Mute 16kHz samples in place wherever they stay below the threshold
+    pub fn process(&mut self, settings: &NoiseGateSettings, samples: &mut [f32]) {
+        if !settings.enabled {
+            self.gain = 1.0;
+            return;
+        }
+        let hold = (settings.hold_ms as usize * BLOCK_LEN) / 10;
+
+        for block in samples.chunks_mut(BLOCK_LEN) {
+            let target = if to_db(block_rms(block)) >= settings.threshold_db {
+                self.hold_remaining = hold;
+                1.0
+            } else if self.hold_remaining > 0 {
+                self.hold_remaining = self.hold_remaining.saturating_sub(block.len());
+                1.0
+            } else {
+                0.0
+            };
+            for sample in block.iter_mut() {
+                self.gain += (target - self.gain) * GAIN_SMOOTHING;
+                *sample *= self.gain;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NoiseCalibration {
+    // Level the background stays under 90% of the time
+    pub noise_floor_db: f32,
+    pub peak_db: f32,
+    pub suggested_threshold_db: f32,
+}
+
+// Measure the background level of 16kHz samples recorded while the user stays quiet
+pub fn calibrate(samples: &[f32]) -> NoiseCalibration {
+    let mut levels: Vec<f32> = samples.chunks(BLOCK_LEN).map(block_rms).collect();
+    levels.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let noise_floor = levels
+        .get(levels.len() * 9 / 10)
+        .copied()
+        .unwrap_or_default();
+    let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+
+    let noise_floor_db = to_db(noise_floor);
+    NoiseCalibration {
+        noise_floor_db,
+        peak_db: to_db(peak),
+        suggested_threshold_db: (noise_floor_db + CALIBRATION_MARGIN_DB).clamp(-90.0, -10.0),
+    }
+}
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::utterance::group_utterances;
+use crate::whisper::{
+    prepare_audio, whisper_language_code, InferenceOptions, TranscriptionResult,
+    WHISPER_SAMPLE_RATE,
+};
+
+// Files of a Whisper model exported to ONNX (e.g. with Hugging Face Optimum)
+pub const ONNX_ENCODER_FILE: &str = "encoder_model.onnx";
+pub const ONNX_DECODER_FILE: &str = "decoder_model.onnx";
+pub const ONNX_TOKENIZER_FILE: &str = "tokenizer.json";
+
+// ONNX Runtime execution provider. DirectML runs on any DirectX 12 GPU, which
+// covers AMD and Intel cards that whisper.cpp can only use through Vulkan.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnnxDevice {
+    #[default]
+    Cpu,
+    DirectMl,
+    Cuda,
+}
+
+impl OnnxDevice {
+    // Whether ONNX Runtime was built with this execution provider
+    pub fn is_compiled(self) -> bool {
+        match self {
+            OnnxDevice::Cpu => cfg!(feature = "onnx"),
+            OnnxDevice::DirectMl => cfg!(all(feature = "directml", target_os = "windows")),
+            OnnxDevice::Cuda => cfg!(feature = "onnx-cuda"),
+        }
+    }
+}
+
+// Local Whisper inference through ONNX Runtime instead of whisper.cpp
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OnnxSttConfig {
+    // Directory holding the exported encoder, decoder and tokenizer
+    pub model_dir: String,
+    #[serde(default)]
+    pub device: OnnxDevice,
+}
+
+impl OnnxSttConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.device.is_compiled() {
+            return Err(format!(
+                "This build of VRCTalk doesn't include the ONNX Runtime {:?} backend",
+                self.device
+            ));
+        }
+        let model_dir = Path::new(self.model_dir.trim());
+        for file in [ONNX_ENCODER_FILE, ONNX_DECODER_FILE, ONNX_TOKENIZER_FILE] {
+            if !model_dir.join(file).is_file() {
+                return Err(format!(
+                    "ONNX model directory '{}' is missing {}",
+                    model_dir.display(),
+                    file
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub async fn transcribe_onnx(
+    config: &OnnxSttConfig,
+    audio_data: &[u8],
+    options: &InferenceOptions,
+) -> Result<TranscriptionResult, String> {
+    config.validate()?;
+
+    let Some(speech) = prepare_audio(audio_data, options.denoise, &options.vad)? else {
+        return Ok(TranscriptionResult::default());
+    };
+
+    // Initial prompts aren't supported here, only the language and task
+    let language = whisper_language_code(&options.language);
+    let started = std::time::Instant::now();
+    let mut result = run_onnx(
+        config.clone(),
+        speech.samples.clone(),
+        language,
+        options.translate,
+    )
+    .await?;
+    println!(
+        "ONNX transcription finished in {}ms",
+        started.elapsed().as_millis()
+    );
+    options
+        .hallucination_filter
+        .apply_to_result(&mut result, &speech.samples);
+    group_utterances(
+        &mut result,
+        &speech.region_starts_ms(WHISPER_SAMPLE_RATE),
+        &options.source,
+    );
+    Ok(result)
+}
+
+#[cfg(feature = "onnx")]
+async fn run_onnx(
+    config: OnnxSttConfig,
+    samples: Vec<f32>,
+    language: Option<String>,
+    translate: bool,
+) -> Result<TranscriptionResult, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::onnx_whisper::transcribe(&config, &samples, language.as_deref(), translate)
+    })
+    .await
+    .map_err(|e| format!("ONNX inference task failed: {}", e))?
+}
+
+#[cfg(not(feature = "onnx"))]
+async fn run_onnx(
+    _config: OnnxSttConfig,
+    _samples: Vec<f32>,
+    _language: Option<String>,
+    _translate: bool,
+) -> Result<TranscriptionResult, String> {
+    Err("This build of VRCTalk doesn't include ONNX Runtime support".to_string())
+}
@@ -0,0 +1,467 @@
+// Whisper inference on ONNX Runtime for models exported with Hugging Face Optimum.
+// Audio is decoded in 30 second windows with greedy sampling, computing Whisper's
+// log-mel features here since the exported encoder expects them as input.
+
+use ort::execution_providers::{
+    CPUExecutionProvider, CUDAExecutionProvider, DirectMLExecutionProvider,
+};
+use ort::session::builder::GraphOptimizationLevel;
+use ort::session::Session;
+use ort::value::Tensor;
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::onnx_stt::{
+    OnnxDevice, OnnxSttConfig, ONNX_DECODER_FILE, ONNX_ENCODER_FILE, ONNX_TOKENIZER_FILE,
+};
+use crate::whisper::{
+    clean_transcript, TranscriptionResult, TranscriptionSegment, WHISPER_SAMPLE_RATE,
+};
+
+const N_FFT: usize = 400;
+const HOP_LENGTH: usize = 160;
+const CHUNK_SAMPLES: usize = 30 * WHISPER_SAMPLE_RATE as usize;
+const N_FRAMES: usize = CHUNK_SAMPLES / HOP_LENGTH;
+// Half of Whisper's 448 token context, as in the reference implementation
+const MAX_NEW_TOKENS: usize = 224;
+// English-only checkpoints have a smaller vocabulary and no language/task tokens
+const ENGLISH_ONLY_VOCAB_SIZE: u64 = 51864;
+
+// Byte-level BPE vocabulary plus Whisper's special tokens, read from tokenizer.json
+struct WhisperTokenizer {
+    tokens: HashMap<i64, String>,
+    byte_decoder: HashMap<char, u8>,
+    languages: HashMap<String, i64>,
+    eot: i64,
+    sot: i64,
+    transcribe: i64,
+    translate: i64,
+    no_timestamps: i64,
+}
+
+// Inverse of GPT-2's bytes_to_unicode, mapping vocabulary characters back to bytes
+fn byte_decoder() -> HashMap<char, u8> {
+    let mut bytes: Vec<u8> = (b'!'..=b'~')
+        .chain(0xA1..=0xAC)
+        .chain(0xAE..=0xFF)
+        .collect();
+    let mut chars: Vec<u32> = bytes.iter().map(|&b| b as u32).collect();
+    let mut extra = 0;
+    for b in 0..=255u8 {
+        if !bytes.contains(&b) {
+            bytes.push(b);
+            chars.push(256 + extra);
+            extra += 1;
+        }
+    }
+    chars
+        .into_iter()
+        .zip(bytes)
+        .filter_map(|(c, b)| char::from_u32(c).map(|c| (c, b)))
+        .collect()
+}
+
+impl WhisperTokenizer {
+    fn load(path: &Path) -> Result<Self, String> {
+        let data = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let json: serde_json::Value = serde_json::from_str(&data)
+            .map_err(|e| format!("Invalid tokenizer {}: {}", path.display(), e))?;
+
+        let mut tokens: HashMap<i64, String> = json["model"]["vocab"]
+            .as_object()
+            .ok_or_else(|| format!("{} has no vocabulary", path.display()))?
+            .iter()
+            .filter_map(|(token, id)| id.as_i64().map(|id| (id, token.clone())))
+            .collect();
+        let mut special = HashMap::new();
+        for added in json["added_tokens"].as_array().into_iter().flatten() {
+            if let (Some(id), Some(content)) = (added["id"].as_i64(), added["content"].as_str()) {
+                special.insert(content.to_string(), id);
+                tokens.insert(id, content.to_string());
+            }
+        }
+
+        let special_token = |name: &str| {
+            special
+                .get(name)
+                .copied()
+                .ok_or_else(|| format!("Tokenizer is missing {}", name))
+        };
+        let sot = special_token("<|startoftranscript|>")?;
+        let translate = special_token("<|translate|>")?;
+        // Language tokens sit between <|startoftranscript|> and <|translate|>
+        let languages = special
+            .iter()
+            .filter(|(_, &id)| id > sot && id < translate)
+            .map(|(content, &id)| {
+                let code = content.trim_start_matches("<|").trim_end_matches("|>");
+                (code.to_string(), id)
+            })
+            .collect();
+
+        Ok(Self {
+            tokens,
+            byte_decoder: byte_decoder(),
+            languages,
+            eot: special_token("<|endoftext|>")?,
+            sot,
+            transcribe: special_token("<|transcribe|>")?,
+            translate,
+            no_timestamps: special_token("<|notimestamps|>")?,
+        })
+    }
+
+    // Text tokens only; special and timestamp tokens are skipped
+    fn decode(&self, ids: &[i64]) -> String {
+        let bytes: Vec<u8> = ids
+            .iter()
+            .filter(|&&id| id < self.eot)
+            .filter_map(|id| self.tokens.get(id))
+            .flat_map(|token| token.chars())
+            .filter_map(|c| self.byte_decoder.get(&c).copied())
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+// Slaney-style mel filterbank as built by librosa.filters.mel, which Whisper uses
+fn mel_filters(n_mels: usize) -> Vec<f32> {
+    const F_SP: f64 = 200.0 / 3.0;
+    const MIN_LOG_HZ: f64 = 1000.0;
+    const MIN_LOG_MEL: f64 = MIN_LOG_HZ / F_SP;
+    let log_step = 6.4f64.ln() / 27.0;
+    let hz_to_mel = |hz: f64| {
+        if hz < MIN_LOG_HZ {
+            hz / F_SP
+        } else {
+            MIN_LOG_MEL + (hz / MIN_LOG_HZ).ln() / log_step
+        }
+    };
+    let mel_to_hz = |mel: f64| {
+        if mel < MIN_LOG_MEL {
+            mel * F_SP
+        } else {
+            MIN_LOG_HZ * (log_step * (mel - MIN_LOG_MEL)).exp()
+        }
+    };
+
+    let n_bins = N_FFT / 2 + 1;
+    let max_mel = hz_to_mel(WHISPER_SAMPLE_RATE as f64 / 2.0);
+    let points: Vec<f64> = (0..n_mels + 2)
+        .map(|i| mel_to_hz(max_mel * i as f64 / (n_mels + 1) as f64))
+        .collect();
+
+    let mut filters = vec![0f32; n_mels * n_bins];
+    for m in 0..n_mels {
+        let (low, center, high) = (points[m], points[m + 1], points[m + 2]);
+        let norm = 2.0 / (high - low);
+        for bin in 0..n_bins {
+            let hz = bin as f64 * WHISPER_SAMPLE_RATE as f64 / N_FFT as f64;
+            let rising = (hz - low) / (center - low);
+            let falling = (high - hz) / (high - center);
+            filters[m * n_bins + bin] = (rising.min(falling).max(0.0) * norm) as f32;
+        }
+    }
+    filters
+}
+
+fn load_session(path: &Path, device: OnnxDevice) -> Result<Session, String> {
+    let ort_error = |e: ort::Error| format!("Failed to load {}: {}", path.display(), e);
+    let builder = Session::builder()
+        .and_then(|b| b.with_optimization_level(GraphOptimizationLevel::Level3))
+        .map_err(ort_error)?;
+    let builder = match device {
+        OnnxDevice::Cpu => {
+            builder.with_execution_providers([CPUExecutionProvider::default().build()])
+        }
+        // DirectML doesn't support memory patterns or parallel execution
+        OnnxDevice::DirectMl => builder
+            .with_memory_pattern(false)
+            .and_then(|b| b.with_parallel_execution(false))
+            .and_then(|b| {
+                b.with_execution_providers([DirectMLExecutionProvider::default()
+                    .build()
+                    .error_on_failure()])
+            }),
+        OnnxDevice::Cuda => builder.with_execution_providers([CUDAExecutionProvider::default()
+            .build()
+            .error_on_failure()]),
+    }
+    .map_err(ort_error)?;
+    builder.commit_from_file(path).map_err(ort_error)
+}
+
+struct OnnxWhisper {
+    model_dir: PathBuf,
+    device: OnnxDevice,
+    encoder: Session,
+    decoder: Session,
+    tokenizer: WhisperTokenizer,
+    n_mels: usize,
+    mel_filters: Vec<f32>,
+    english_only: bool,
+}
+
+impl OnnxWhisper {
+    fn load(model_dir: &Path, device: OnnxDevice) -> Result<Self, String> {
+        println!(
+            "Loading ONNX Whisper model from {} ({:?})",
+            model_dir.display(),
+            device
+        );
+        // config.json is optional; without it assume an 80-bin multilingual model
+        let config: serde_json::Value = fs::read_to_string(model_dir.join("config.json"))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        let n_mels = config["num_mel_bins"].as_u64().unwrap_or(80) as usize;
+        let english_only = config["vocab_size"].as_u64() == Some(ENGLISH_ONLY_VOCAB_SIZE);
+
+        Ok(Self {
+            model_dir: model_dir.to_path_buf(),
+            device,
+            encoder: load_session(&model_dir.join(ONNX_ENCODER_FILE), device)?,
+            decoder: load_session(&model_dir.join(ONNX_DECODER_FILE), device)?,
+            tokenizer: WhisperTokenizer::load(&model_dir.join(ONNX_TOKENIZER_FILE))?,
+            n_mels,
+            mel_filters: mel_filters(n_mels),
+            english_only,
+        })
+    }
+
+    // Whisper's log-mel spectrogram of one window, zero-padded to 30 seconds
+    fn log_mel_spectrogram(&self, samples: &[f32]) -> Vec<f32> {
+        let mut audio = samples.to_vec();
+        audio.resize(CHUNK_SAMPLES, 0.0);
+
+        // Reflect-pad by half a window, like torch.stft(center=True)
+        let pad = N_FFT / 2;
+        let mut padded = Vec::with_capacity(CHUNK_SAMPLES + 2 * pad);
+        padded.extend((1..=pad).rev().map(|i| audio[i]));
+        padded.extend_from_slice(&audio);
+        padded.extend((1..=pad).map(|i| audio[CHUNK_SAMPLES - 1 - i]));
+
+        let window: Vec<f32> = (0..N_FFT)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / N_FFT as f32).cos())
+            .collect();
+        let fft = FftPlanner::new().plan_fft_forward(N_FFT);
+        let n_bins = N_FFT / 2 + 1;
+
+        let mut mel = vec![0f32; self.n_mels * N_FRAMES];
+        let mut buffer = vec![Complex::default(); N_FFT];
+        let mut power = vec![0f32; n_bins];
+        for frame in 0..N_FRAMES {
+            let start = frame * HOP_LENGTH;
+            for (i, value) in buffer.iter_mut().enumerate() {
+                *value = Complex::new(padded[start + i] * window[i], 0.0);
+            }
+            fft.process(&mut buffer);
+            for (p, value) in power.iter_mut().zip(&buffer) {
+                *p = value.norm_sqr();
+            }
+            for m in 0..self.n_mels {
+                let filter = &self.mel_filters[m * n_bins..(m + 1) * n_bins];
+                let energy: f32 = filter.iter().zip(&power).map(|(f, p)| f * p).sum();
+                mel[m * N_FRAMES + frame] = energy.max(1e-10).log10();
+            }
+        }
+
+        // Clamp to 80dB below the peak and scale to roughly [-1, 1]
+        let max = mel.iter().copied().fold(f32::MIN, f32::max);
+        for value in &mut mel {
+            *value = (value.max(max - 8.0) + 4.0) / 4.0;
+        }
+        mel
+    }
+
+    fn encode(&mut self, features: Vec<f32>) -> Result<(Vec<usize>, Vec<f32>), String> {
+        let input = Tensor::from_array(([1, self.n_mels, N_FRAMES], features))
+            .map_err(|e| format!("Failed to build encoder input: {}", e))?;
+        let outputs = self
+            .encoder
+            .run(ort::inputs!["input_features" => input])
+            .map_err(|e| format!("ONNX encoder failed: {}", e))?;
+        let (shape, hidden) = outputs["last_hidden_state"]
+            .try_extract_tensor::<f32>()
+            .map_err(|e| format!("Invalid encoder output: {}", e))?;
+        Ok((shape.iter().map(|&d| d as usize).collect(), hidden.to_vec()))
+    }
+
+    // Logits for the token following `tokens`
+    fn next_logits(
+        &mut self,
+        tokens: &[i64],
+        hidden_shape: &[usize],
+        hidden: &[f32],
+    ) -> Result<Vec<f32>, String> {
+        let input_ids = Tensor::from_array(([1, tokens.len()], tokens.to_vec()))
+            .map_err(|e| format!("Failed to build decoder input: {}", e))?;
+        let states = Tensor::from_array((hidden_shape.to_vec(), hidden.to_vec()))
+            .map_err(|e| format!("Failed to build decoder input: {}", e))?;
+        let outputs = self
+            .decoder
+            .run(ort::inputs![
+                "input_ids" => input_ids,
+                "encoder_hidden_states" => states
+            ])
+            .map_err(|e| format!("ONNX decoder failed: {}", e))?;
+        let (shape, logits) = outputs["logits"]
+            .try_extract_tensor::<f32>()
+            .map_err(|e| format!("Invalid decoder output: {}", e))?;
+        let vocab = shape[shape.len() - 1] as usize;
+        Ok(logits[logits.len() - vocab..].to_vec())
+    }
+
+    fn detect_language(
+        &mut self,
+        hidden_shape: &[usize],
+        hidden: &[f32],
+    ) -> Result<String, String> {
+        let logits = self.next_logits(&[self.tokenizer.sot], hidden_shape, hidden)?;
+        self.tokenizer
+            .languages
+            .iter()
+            .filter(|(_, &id)| (id as usize) < logits.len())
+            .max_by(|(_, &a), (_, &b)| logits[a as usize].total_cmp(&logits[b as usize]))
+            .map(|(code, _)| code.clone())
+            .ok_or_else(|| "Tokenizer has no language tokens".to_string())
+    }
+
+    fn transcribe(
+        &mut self,
+        samples: &[f32],
+        language: Option<&str>,
+        translate: bool,
+    ) -> Result<TranscriptionResult, String> {
+        let mut segments = Vec::new();
+        for (index, chunk) in samples.chunks(CHUNK_SAMPLES).enumerate() {
+            let features = self.log_mel_spectrogram(chunk);
+            let (hidden_shape, hidden) = self.encode(features)?;
+
+            let mut tokens = vec![self.tokenizer.sot];
+            let chunk_language = if self.english_only {
+                "en".to_string()
+            } else {
+                // Detected per window, so a language switch between windows is followed
+                let chunk_language = match language {
+                    Some(language) => language.to_string(),
+                    None => self.detect_language(&hidden_shape, &hidden)?,
+                };
+                let language_token = self
+                    .tokenizer
+                    .languages
+                    .get(&chunk_language)
+                    .copied()
+                    .ok_or_else(|| {
+                        format!("Model doesn't support language '{}'", chunk_language)
+                    })?;
+                tokens.push(language_token);
+                tokens.push(if translate {
+                    self.tokenizer.translate
+                } else {
+                    self.tokenizer.transcribe
+                });
+                chunk_language
+            };
+            tokens.push(self.tokenizer.no_timestamps);
+            let prompt_len = tokens.len();
+
+            // Greedy decoding over text tokens, stopping at end-of-text
+            let eot = self.tokenizer.eot;
+            let mut logprob_sum = 0.0f32;
+            let mut decoded = 0;
+            while decoded < MAX_NEW_TOKENS {
+                let logits = self.next_logits(&tokens, &hidden_shape, &hidden)?;
+                let text_logits = &logits[..=(eot as usize).min(logits.len() - 1)];
+                let max = text_logits.iter().copied().fold(f32::MIN, f32::max);
+                let log_sum = text_logits
+                    .iter()
+                    .map(|l| (l - max).exp())
+                    .sum::<f32>()
+                    .ln()
+                    + max;
+                let (best, best_logit) = text_logits
+                    .iter()
+                    .copied()
+                    .enumerate()
+                    .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .unwrap_or((eot as usize, 0.0));
+                logprob_sum += best_logit - log_sum;
+                decoded += 1;
+                if best as i64 == eot {
+                    break;
+                }
+                tokens.push(best as i64);
+            }
+
+            let text = clean_transcript(&self.tokenizer.decode(&tokens[prompt_len..]));
+            if text.is_empty() {
+                continue;
+            }
+            let avg_logprob = logprob_sum / decoded.max(1) as f32;
+            let start_ms = (index * CHUNK_SAMPLES) as i64 * 1000 / WHISPER_SAMPLE_RATE as i64;
+            segments.push(TranscriptionSegment {
+                text,
+                start_ms,
+                end_ms: start_ms + chunk.len() as i64 * 1000 / WHISPER_SAMPLE_RATE as i64,
+                probability: avg_logprob.exp(),
+                avg_logprob,
+                no_speech_probability: 0.0,
+                language: chunk_language,
+                utterance: 0,
+                words: Vec::new(),
+            });
+        }
+
+        let language = segments
+            .first()
+            .map(|s| s.language.clone())
+            .or_else(|| language.map(str::to_string))
+            .unwrap_or_else(|| "auto".to_string());
+        Ok(TranscriptionResult {
+            text: segments
+                .iter()
+                .map(|s| s.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            language,
+            segments,
+            utterances: Vec::new(),
+        })
+    }
+}
+
+// The loaded model is kept between calls and swapped when the config changes
+static ENGINE: OnceLock<Mutex<Option<OnnxWhisper>>> = OnceLock::new();
+
+pub fn transcribe(
+    config: &OnnxSttConfig,
+    samples: &[f32],
+    language: Option<&str>,
+    translate: bool,
+) -> Result<TranscriptionResult, String> {
+    let mut engine = ENGINE
+        .get_or_init(|| Mutex::new(None))
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+
+    let model_dir = PathBuf::from(config.model_dir.trim());
+    let loaded = matches!(
+        engine.as_ref(),
+        Some(e) if e.model_dir == model_dir && e.device == config.device
+    );
+    if !loaded {
+        // Free the previous model before loading the next one
+        *engine = None;
+        *engine = Some(OnnxWhisper::load(&model_dir, config.device)?);
+    }
+    let Some(engine) = engine.as_mut() else {
+        return Err("ONNX model is not loaded".to_string());
+    };
+    engine.transcribe(samples, language, translate)
+}
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::time::Duration;
+
+use crate::utterance::group_utterances;
+use crate::whisper::{
+    clean_transcript, prepare_audio, whisper_language_code, InferenceOptions, TranscriptionResult,
+    TranscriptionSegment, WHISPER_SAMPLE_RATE,
+};
+
+const DEFAULT_OPENAI_STT_MODEL: &str = "whisper-1";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Any server implementing OpenAI's `/v1/audio/transcriptions` API (OpenAI, Groq,
+// LocalAI, ...). The base URL may or may not include the `/v1` suffix.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OpenAiSttConfig {
+    pub base_url: String,
+    pub api_key: Option<String>,
+    // Remote model name, e.g. "whisper-1" or "whisper-large-v3"
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+impl OpenAiSttConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let base_url = self.base_url.trim();
+        if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+            return Err(format!("Invalid transcription server URL: '{}'", base_url));
+        }
+        Ok(())
+    }
+
+    fn endpoint(&self, translate: bool) -> String {
+        let base_url = self.base_url.trim().trim_end_matches('/');
+        let path = if translate {
+            "audio/translations"
+        } else {
+            "audio/transcriptions"
+        };
+        if base_url.ends_with("/v1") {
+            format!("{}/{}", base_url, path)
+        } else {
+            format!("{}/v1/{}", base_url, path)
+        }
+    }
+
+    fn model(&self) -> &str {
+        self.model
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .unwrap_or(DEFAULT_OPENAI_STT_MODEL)
+    }
+}
+
+// Subset of the `verbose_json` response. Servers that only return `text` still parse.
+// whisper.cpp's server uses the same shape.
+#[derive(Deserialize)]
+pub struct VerboseTranscription {
+    text: String,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    segments: Vec<VerboseSegment>,
+}
+
+#[derive(Deserialize)]
+struct VerboseSegment {
+    text: String,
+    start: f64,
+    end: f64,
+    #[serde(default)]
+    avg_logprob: f32,
+    #[serde(default)]
+    no_speech_prob: f32,
+}
+
+impl VerboseTranscription {
+    pub fn into_result(self, fallback_language: &str) -> TranscriptionResult {
+        let language = normalize_language(self.language, fallback_language);
+        let segments = self
+            .segments
+            .into_iter()
+            .map(|segment| TranscriptionSegment {
+                text: clean_transcript(&segment.text),
+                start_ms: (segment.start * 1000.0) as i64,
+                end_ms: (segment.end * 1000.0) as i64,
+                probability: segment.avg_logprob.exp(),
+                avg_logprob: segment.avg_logprob,
+                no_speech_probability: segment.no_speech_prob,
+                language: language.clone(),
+                utterance: 0,
+                words: Vec::new(),
+            })
+            .filter(|segment| !segment.text.is_empty())
+            .collect();
+
+        TranscriptionResult {
+            text: clean_transcript(&self.text),
+            language,
+            segments,
+            utterances: Vec::new(),
+        }
+    }
+}
+
+// Re-encode the VAD-trimmed speech as 16kHz mono WAV so only speech is uploaded
+pub fn encode_wav(samples: &[f32]) -> Result<Vec<u8>, String> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: WHISPER_SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut cursor = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut cursor, spec)
+        .map_err(|e| format!("Failed to create WAV writer: {}", e))?;
+    for &sample in samples {
+        writer
+            .write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+            .map_err(|e| format!("Failed to encode WAV: {}", e))?;
+    }
+    writer
+        .finalize()
+        .map_err(|e| format!("Failed to finalize WAV: {}", e))?;
+    Ok(cursor.into_inner())
+}
+
+// Remote servers report full language names ("english"), normalize them to codes
+pub fn normalize_language(language: Option<String>, fallback: &str) -> String {
+    language
+        .and_then(|name| whisper_rs::get_lang_id(&name.to_lowercase()))
+        .and_then(whisper_rs::get_lang_str)
+        .map(|code| code.to_string())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+pub async fn transcribe_openai(
+    config: &OpenAiSttConfig,
+    audio_data: &[u8],
+    options: &InferenceOptions,
+) -> Result<TranscriptionResult, String> {
+    config.validate()?;
+
+    let Some(speech) = prepare_audio(audio_data, options.denoise, &options.vad)? else {
+        return Ok(TranscriptionResult::default());
+    };
+    let wav = encode_wav(&speech.samples)?;
+
+    let file = reqwest::multipart::Part::bytes(wav)
+        .file_name("audio.wav")
+        .mime_str("audio/wav")
+        .map_err(|e| format!("Failed to build upload: {}", e))?;
+    let mut form = reqwest::multipart::Form::new()
+        .part("file", file)
+        .text("model", config.model().to_string())
+        .text("response_format", "verbose_json")
+        .text("temperature", options.decoding.temperature.to_string());
+
+    let language = whisper_language_code(&options.language);
+    // The translations endpoint always outputs English and takes no language
+    if let (Some(language), false) = (&language, options.translate) {
+        form = form.text("language", language.clone());
+    }
+    if let Some(prompt) = &options.initial_prompt {
+        form = form.text("prompt", prompt.clone());
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let mut request = client
+        .post(config.endpoint(options.translate))
+        .multipart(form);
+    if let Some(key) = config.api_key.as_deref().filter(|k| !k.trim().is_empty()) {
+        request = request.bearer_auth(key.trim());
+    }
+
+    let started = std::time::Instant::now();
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Transcription request failed: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "Transcription server returned {}: {}",
+            status, body
+        ));
+    }
+    let transcription: VerboseTranscription = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse transcription response: {}", e))?;
+    println!(
+        "Remote transcription finished in {}ms",
+        started.elapsed().as_millis()
+    );
+
+    let mut result = transcription.into_result(language.as_deref().unwrap_or("auto"));
+    // Segment timestamps refer to the uploaded speech, so filter before grouping
+    options
+        .hallucination_filter
+        .apply_to_result(&mut result, &speech.samples);
+    group_utterances(
+        &mut result,
+        &speech.region_starts_ms(WHISPER_SAMPLE_RATE),
+        &options.source,
+    );
+    Ok(result)
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::audio_decode::decode_wav;
+
+const DEFAULT_OPENAI_TTS_MODEL: &str = "tts-1";
+const DEFAULT_OPENAI_TTS_VOICE: &str = "alloy";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Any server implementing OpenAI's `/v1/audio/speech` API (OpenAI, LocalAI, openedai-speech,
+// Kokoro-FastAPI, ...). The base URL may or may not include the `/v1` suffix.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OpenAiTtsConfig {
+    pub base_url: String,
+    pub api_key: Option<String>,
+    // Remote model name, e.g. "tts-1" or "kokoro"
+    #[serde(default)]
+    pub model: Option<String>,
+    // e.g. "alloy" or "nova"
+    #[serde(default)]
+    pub voice: Option<String>,
+    // 0.25 to 4.0, 1.0 being normal speed
+    #[serde(default)]
+    pub speed: Option<f32>,
+}
+
+impl OpenAiTtsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let base_url = self.base_url.trim();
+        if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+            return Err(format!("Invalid speech server URL: '{}'", base_url));
+        }
+        if let Some(speed) = self.speed {
+            if !(0.25..=4.0).contains(&speed) {
+                return Err(format!("Speech speed must be 0.25 to 4.0, got {}", speed));
+            }
+        }
+        Ok(())
+    }
+
+    fn endpoint(&self) -> String {
+        let base_url = self.base_url.trim().trim_end_matches('/');
+        if base_url.ends_with("/v1") {
+            format!("{}/audio/speech", base_url)
+        } else {
+            format!("{}/v1/audio/speech", base_url)
+        }
+    }
+
+    fn model(&self) -> &str {
+        self.model
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .unwrap_or(DEFAULT_OPENAI_TTS_MODEL)
+    }
+
+    pub fn voice(&self) -> &str {
+        self.voice
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .unwrap_or(DEFAULT_OPENAI_TTS_VOICE)
+    }
+}
+
+#[derive(Serialize)]
+struct SpeechRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+    voice: &'a str,
+    response_format: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speed: Option<f32>,
+}
+
+// Synthesize `text`, returning mono samples and their sample rate
+pub async fn synthesize_openai(
+    config: &OpenAiTtsConfig,
+    text: &str,
+) -> Result<(Vec<f32>, u32), String> {
+    config.validate()?;
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let mut request = client.post(config.endpoint()).json(&SpeechRequest {
+        model: config.model(),
+        input: text,
+        voice: config.voice(),
+        // WAV so no MP3/Opus decoder is needed
+        response_format: "wav",
+        speed: config.speed,
+    });
+    if let Some(key) = config.api_key.as_deref().filter(|k| !k.trim().is_empty()) {
+        request = request.bearer_auth(key.trim());
+    }
+
+    let started = std::time::Instant::now();
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Speech request failed: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Speech server returned {}: {}", status, body));
+    }
+    let audio = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read speech audio: {}", e))?;
+    println!(
+        "Remote speech synthesis finished in {}ms",
+        started.elapsed().as_millis()
+    );
+    decode_wav(&audio)
+}
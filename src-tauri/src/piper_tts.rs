@@ -0,0 +1,313 @@
+// Offline text-to-speech with Piper voices (VITS models exported to ONNX). Voices are
+// fetched from the rhasspy/piper-voices repository with the same download pipeline
+// (progress events, resumable chunks, checksums, cancellation via
+// `whisper_cancel_download`) as Whisper models. Running them needs ONNX Runtime and
+// espeak-ng for phonemes, and is only built with the `piper` feature.
+use futures_util::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "piper")]
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager, State};
+
+use crate::download::{ModelDownload, DOWNLOAD_CANCELLED};
+use crate::manifest::{fetch_file_size, ModelConfig, ModelFile};
+use crate::whisper::{download_model_file, WhisperAppState};
+
+const PIPER_VOICES_DIR: &str = "piper_voices";
+const PIPER_VOICES_REPO: &str = "rhasspy/piper-voices";
+// Tagged release, so voice files don't change under a finished download
+const PIPER_VOICES_REVISION: &str = "v1.0.0";
+
+// Downloadable voices: (ID as "<locale>-<name>-<quality>", language)
+const PIPER_VOICES: [(&str, &str); 10] = [
+    ("en_US-lessac-medium", "en"),
+    ("en_US-amy-medium", "en"),
+    ("en_GB-alba-medium", "en"),
+    ("zh_CN-huayan-medium", "zh"),
+    ("es_ES-davefx-medium", "es"),
+    ("fr_FR-siwis-medium", "fr"),
+    ("de_DE-thorsten-medium", "de"),
+    ("ru_RU-irina-medium", "ru"),
+    ("pt_BR-faber-medium", "pt"),
+    ("uk_UA-ukrainian_tts-medium", "uk"),
+];
+
+#[cfg(feature = "piper")]
+type PiperSynthesizer = piper_rs::synth::PiperSpeechSynthesizer;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PiperConfig {
+    // ID of a downloaded voice from `tts_list_piper_voices`
+    pub voice: String,
+}
+
+impl PiperConfig {
+    // Builds without Piper still accept the setting on Windows, speech falls back to
+    // the Windows voice there
+    pub fn validate(&self) -> Result<(), String> {
+        if !cfg!(feature = "piper") && !cfg!(target_os = "windows") {
+            return Err("This build of VRCTalk doesn't include Piper voices".to_string());
+        }
+        find_voice(&self.voice).map(|_| ())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PiperVoiceInfo {
+    pub id: String,
+    pub language: String,
+    pub downloaded: bool,
+}
+
+// The loaded voice, kept until another one is needed
+#[derive(Default)]
+pub struct PiperState {
+    #[cfg(feature = "piper")]
+    loaded: Mutex<Option<(String, Arc<PiperSynthesizer>)>>,
+}
+
+// Subset of the voice's `.onnx.json`
+#[derive(Deserialize)]
+struct VoiceConfig {
+    audio: VoiceAudio,
+}
+
+#[derive(Deserialize)]
+struct VoiceAudio {
+    sample_rate: u32,
+}
+
+fn find_voice(id: &str) -> Result<&'static str, String> {
+    PIPER_VOICES
+        .iter()
+        .find(|(voice, _)| *voice == id)
+        .map(|(_, language)| *language)
+        .ok_or_else(|| format!("Unknown Piper voice '{}'", id))
+}
+
+// Files of a voice in the repository: "en/en_US/lessac/medium/en_US-lessac-medium.onnx"
+// and its config next to it
+fn voice_files(id: &str) -> [String; 2] {
+    let mut parts = id.splitn(3, '-');
+    let locale = parts.next().unwrap_or_default();
+    let name = parts.next().unwrap_or_default();
+    let quality = parts.next().unwrap_or_default();
+    let family = locale.split('_').next().unwrap_or(locale);
+    let dir = format!("{}/{}/{}/{}", family, locale, name, quality);
+    [
+        format!("{}/{}.onnx", dir, id),
+        format!("{}/{}.onnx.json", dir, id),
+    ]
+}
+
+fn voices_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join(PIPER_VOICES_DIR))
+}
+
+fn is_downloaded(voice_path: &Path, id: &str) -> bool {
+    voice_files(id).iter().all(|file| {
+        fs::metadata(voice_path.join(file))
+            .map(|m| m.len() > 0)
+            .unwrap_or(false)
+    })
+}
+
+// Whether `voice` can be spoken: Piper is built in and the voice is downloaded
+pub fn piper_voice_available(app_handle: &tauri::AppHandle, voice: &str) -> bool {
+    cfg!(feature = "piper")
+        && voices_dir(app_handle).is_ok_and(|dir| is_downloaded(&dir.join(voice), voice))
+}
+
+// The cached synthesizer for `voice`, loading it from disk if another one (or none)
+// is loaded. Blocks while loading.
+#[cfg(feature = "piper")]
+fn load_voice(app_handle: &tauri::AppHandle, voice: &str) -> Result<Arc<PiperSynthesizer>, String> {
+    let state = app_handle.state::<PiperState>();
+    let mut loaded = state
+        .loaded
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    if let Some((id, synthesizer)) = loaded.as_ref() {
+        if id == voice {
+            return Ok(synthesizer.clone());
+        }
+    }
+
+    let voice_path = voices_dir(app_handle)?.join(voice);
+    if !is_downloaded(&voice_path, voice) {
+        return Err(format!("Piper voice {} is not downloaded", voice));
+    }
+    println!("Loading Piper voice {}", voice);
+    let [_, config_file] = voice_files(voice);
+    let model = piper_rs::from_config_path(&voice_path.join(config_file))
+        .map_err(|e| format!("Failed to load Piper voice: {}", e))?;
+    let synthesizer = PiperSynthesizer::new(model)
+        .map(Arc::new)
+        .map_err(|e| format!("Failed to load Piper voice: {}", e))?;
+    *loaded = Some((voice.to_string(), synthesizer.clone()));
+    Ok(synthesizer)
+}
+
+// Synthesize `text` with a downloaded voice, returning mono samples and their rate
+pub async fn synthesize_piper(
+    app_handle: &tauri::AppHandle,
+    config: &PiperConfig,
+    text: &str,
+) -> Result<(Vec<f32>, u32), String> {
+    config.validate()?;
+    let voice_path = voices_dir(app_handle)?.join(&config.voice);
+    let [_, config_file] = voice_files(&config.voice);
+    let voice_config: VoiceConfig = fs::read_to_string(voice_path.join(config_file))
+        .map_err(|e| format!("Piper voice {} is not downloaded: {}", config.voice, e))
+        .and_then(|data| {
+            serde_json::from_str(&data).map_err(|e| format!("Invalid Piper voice config: {}", e))
+        })?;
+
+    #[cfg(feature = "piper")]
+    {
+        let app_handle = app_handle.clone();
+        let voice = config.voice.clone();
+        let text = text.to_string();
+        let samples = tokio::task::spawn_blocking(move || {
+            let synthesizer = load_voice(&app_handle, &voice)?;
+            let started = std::time::Instant::now();
+            let mut samples = Vec::new();
+            let chunks = synthesizer
+                .synthesize_parallel(text, None)
+                .map_err(|e| format!("Piper synthesis failed: {}", e))?;
+            for chunk in chunks {
+                let chunk = chunk.map_err(|e| format!("Piper synthesis failed: {}", e))?;
+                samples.extend(chunk.into_vec());
+            }
+            println!(
+                "Piper synthesis finished in {}ms",
+                started.elapsed().as_millis()
+            );
+            Ok::<_, String>(samples)
+        })
+        .await
+        .map_err(|e| format!("Synthesis task failed: {}", e))??;
+        Ok((samples, voice_config.audio.sample_rate))
+    }
+    #[cfg(not(feature = "piper"))]
+    {
+        let _ = (text, voice_config.audio.sample_rate);
+        Err("This build of VRCTalk doesn't include Piper voices".to_string())
+    }
+}
+
+#[tauri::command]
+pub fn tts_list_piper_voices(app_handle: tauri::AppHandle) -> Result<Vec<PiperVoiceInfo>, String> {
+    let dir = voices_dir(&app_handle)?;
+    Ok(PIPER_VOICES
+        .iter()
+        .map(|(id, language)| PiperVoiceInfo {
+            id: id.to_string(),
+            language: language.to_string(),
+            downloaded: is_downloaded(&dir.join(id), id),
+        })
+        .collect())
+}
+
+// Download a Piper voice, reporting progress through the same `download-progress`
+// events as Whisper models
+#[tauri::command]
+pub async fn tts_download_piper_voice(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    voice: String,
+) -> Result<bool, String> {
+    find_voice(&voice)?;
+    let registration = state
+        .downloads
+        .register(&voice)
+        .map_err(|_| format!("Voice {} is already being downloaded", voice))?;
+    let cancel = registration.flag();
+    let download_settings = state
+        .download_settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone();
+
+    let mut voice_info = ModelConfig {
+        id: voice.clone(),
+        repo_id: PIPER_VOICES_REPO.to_string(),
+        revision: Some(PIPER_VOICES_REVISION.to_string()),
+        base_url: None,
+        language: None,
+        custom: false,
+        files: Vec::new(),
+    };
+    // Sizes aren't pinned for voices, ask the server for them
+    for file in voice_files(&voice) {
+        let url = voice_info.file_url(&download_settings, &file);
+        voice_info.files.push(ModelFile {
+            size: fetch_file_size(&download_settings, &url).await?,
+            name: file,
+            sha256: None,
+        });
+    }
+
+    // Files keep the repository's layout under the voice's directory
+    let voice_path = voices_dir(&app_handle)?.join(&voice);
+    for file in &voice_info.files {
+        if let Some(parent) = voice_path.join(&file.name).parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create voice directory: {}", e))?;
+        }
+    }
+
+    println!("=== PIPER VOICE DOWNLOAD START ({}) ===", voice);
+    let total_bytes = voice_info.files.iter().map(|f| f.size).sum();
+    let download = ModelDownload::new(
+        &app_handle,
+        &download_settings,
+        &voice,
+        total_bytes,
+        &cancel,
+    )?;
+    let result =
+        try_join_all(voice_info.files.iter().map(|voice_file| {
+            download_model_file(&download, &voice_info, voice_file, &voice_path)
+        }))
+        .await;
+
+    if let Err(e) = result {
+        if registration.is_cancelled() {
+            let _ = app_handle.emit("download-cancelled", serde_json::json!({ "model": voice }));
+            return Err(DOWNLOAD_CANCELLED.to_string());
+        }
+        return Err(e);
+    }
+    println!("Piper voice {} downloaded successfully", voice);
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn tts_delete_piper_voice(app_handle: tauri::AppHandle, voice: String) -> Result<(), String> {
+    find_voice(&voice)?;
+    #[cfg(feature = "piper")]
+    {
+        let state = app_handle.state::<PiperState>();
+        let mut loaded = state
+            .loaded
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        if loaded.as_ref().is_some_and(|(id, _)| *id == voice) {
+            *loaded = None;
+        }
+    }
+    let voice_path = voices_dir(&app_handle)?.join(&voice);
+    if voice_path.exists() {
+        fs::remove_dir_all(&voice_path)
+            .map_err(|e| format!("Failed to delete Piper voice: {}", e))?;
+    }
+    println!("Deleted Piper voice {}", voice);
+    Ok(())
+}
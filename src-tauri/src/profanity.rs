@@ -0,0 +1,136 @@
+// Optional filter for words users don't want showing up in the (publicly visible)
+// chatbox, e.g. while streaming. Entries ending in '*' match any word with that prefix.
+use serde::{Deserialize, Serialize};
+
+use crate::whisper::TranscriptionResult;
+
+const DEFAULT_WORDS: &[&str] = &[
+    "fuck*",
+    "motherfuck*",
+    "shit*",
+    "bullshit*",
+    "bitch*",
+    "cunt*",
+    "asshole*",
+    "bastard*",
+    "dick",
+    "dicks",
+    "cock",
+    "cocks",
+    "pussy",
+    "whore*",
+    "slut*",
+    "nigg*",
+    "fag*",
+    "retard*",
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfanityMode {
+    // Replace the word with asterisks
+    #[default]
+    Mask,
+    // Remove the word entirely
+    Drop,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfanityFilter {
+    pub enabled: bool,
+    pub mode: ProfanityMode,
+    pub use_default_list: bool,
+    // User additions, same syntax as the default list
+    pub custom_words: Vec<String>,
+}
+
+impl Default for ProfanityFilter {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: ProfanityMode::Mask,
+            use_default_list: true,
+            custom_words: Vec::new(),
+        }
+    }
+}
+
+impl ProfanityFilter {
+    fn entries(&self) -> Vec<String> {
+        let mut entries: Vec<String> = if self.use_default_list {
+            DEFAULT_WORDS.iter().map(|w| w.to_string()).collect()
+        } else {
+            Vec::new()
+        };
+        entries.extend(
+            self.custom_words
+                .iter()
+                .map(|w| w.trim().to_lowercase())
+                // A bare "*" would match every word
+                .filter(|w| !w.is_empty() && w != "*"),
+        );
+        entries
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        if !self.enabled {
+            return text.to_string();
+        }
+        let entries = self.entries();
+        if entries.is_empty() {
+            return text.to_string();
+        }
+
+        let mut filtered = Vec::new();
+        for token in text.split_whitespace() {
+            // Keep surrounding punctuation ("shit!" -> "****!")
+            let core = token.trim_matches(|c: char| !c.is_alphanumeric());
+            if core.is_empty() || !matches_any(&core.to_lowercase(), &entries) {
+                filtered.push(token.to_string());
+                continue;
+            }
+            if self.mode == ProfanityMode::Mask {
+                let mask = "*".repeat(core.chars().count());
+                filtered.push(token.replacen(core, &mask, 1));
+            }
+        }
+        let filtered = filtered.join(" ");
+
+        // Languages written without spaces (Japanese, Chinese, ...) can only be
+        // matched as substrings
+        entries
+            .iter()
+            .filter(|entry| !entry.is_ascii())
+            .map(|entry| entry.trim_end_matches('*'))
+            .fold(filtered, |text, entry| {
+                let replacement = match self.mode {
+                    ProfanityMode::Mask => "*".repeat(entry.chars().count()),
+                    ProfanityMode::Drop => String::new(),
+                };
+                text.replace(entry, &replacement)
+            })
+    }
+
+    // Filter the transcript text and every segment, leaving timings untouched
+    pub fn apply_to_result(&self, result: &mut TranscriptionResult) {
+        if !self.enabled {
+            return;
+        }
+        result.text = self.apply(&result.text);
+        for segment in &mut result.segments {
+            segment.text = self.apply(&segment.text);
+            for word in &mut segment.words {
+                word.text = self.apply(&word.text);
+            }
+            segment.words.retain(|w| !w.text.is_empty());
+        }
+    }
+}
+
+fn matches_any(word: &str, entries: &[String]) -> bool {
+    entries.iter().any(|entry| match entry.strip_suffix('*') {
+        Some(prefix) => word.starts_with(prefix),
+        None => word == entry,
+    })
+}
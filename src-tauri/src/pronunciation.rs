@@ -0,0 +1,508 @@
+// Getting TTS to say usernames and coined words right: a user dictionary of how words
+// should be spoken, optionally per voice, and a small subset of SSML in the text to
+// speak (<break>, <emphasis>, <phoneme>, <sub>). Windows voices understand SSML and get
+// it passed on; for the others breaks become silence between separately synthesized
+// runs and phonemes fall back to their written text.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{Manager, State};
+
+use crate::tts::TtsAppState;
+
+const PRONUNCIATIONS_FILE: &str = "pronunciations.json";
+// Longest pause a single <break> may ask for
+const MAX_BREAK_MS: u32 = 5000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Pronunciation {
+    // Word or phrase as written, matched case-insensitively as whole words
+    pub word: String,
+    // Spelled the way it should sound ("Kanna" -> "Kahnna")
+    pub say: String,
+    // IPA, used instead of `say` by voices that take phonemes
+    #[serde(default)]
+    pub phonemes: Option<String>,
+    // Voice it applies to (Piper voice ID, OpenAI voice or Windows voice name), None
+    // for every voice
+    #[serde(default)]
+    pub voice: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PronunciationDictionary {
+    pub enabled: bool,
+    pub entries: Vec<Pronunciation>,
+}
+
+impl Default for PronunciationDictionary {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            entries: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpeechPart {
+    Text { text: String, emphasis: bool },
+    // Pause in milliseconds
+    Break(u32),
+    // Spoken as the IPA `phonemes` where supported, as `text` elsewhere
+    Phoneme { text: String, phonemes: String },
+}
+
+// Text synthesized in one request, followed by a pause
+#[derive(Debug)]
+pub struct PlainRun {
+    pub text: String,
+    pub pause_ms: u32,
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Value of `name="..."` (or single-quoted) inside a tag
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    while let Some(index) = rest.find(name) {
+        let before = rest[..index].chars().next_back();
+        let after = rest[index + name.len()..].trim_start();
+        rest = &rest[index + name.len()..];
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=').map(str::trim_start) else {
+            continue;
+        };
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let value = &value[1..];
+        return value.find(quote).map(|end| unescape(&value[..end]));
+    }
+    None
+}
+
+// "500ms", "1.5s" or a named strength
+fn break_ms(tag: &str) -> u32 {
+    let ms = if let Some(time) = attribute(tag, "time") {
+        let time = time.trim();
+        if let Some(ms) = time.strip_suffix("ms") {
+            ms.trim().parse::<f32>().unwrap_or(0.0)
+        } else if let Some(seconds) = time.strip_suffix('s') {
+            seconds.trim().parse::<f32>().unwrap_or(0.0) * 1000.0
+        } else {
+            0.0
+        }
+    } else {
+        match attribute(tag, "strength").as_deref() {
+            Some("none") => 0.0,
+            Some("x-weak") => 100.0,
+            Some("weak") => 250.0,
+            Some("strong") => 750.0,
+            Some("x-strong") => 1000.0,
+            _ => 500.0,
+        }
+    };
+    (ms.max(0.0) as u32).min(MAX_BREAK_MS)
+}
+
+fn push_text(parts: &mut Vec<SpeechPart>, text: &str, emphasis: bool) {
+    if text.is_empty() {
+        return;
+    }
+    if let Some(SpeechPart::Text {
+        text: last,
+        emphasis: last_emphasis,
+    }) = parts.last_mut()
+    {
+        if *last_emphasis == emphasis {
+            last.push_str(text);
+            return;
+        }
+    }
+    parts.push(SpeechPart::Text {
+        text: text.to_string(),
+        emphasis,
+    });
+}
+
+// Split text into speech parts. Only text wrapped in <speak> is read as SSML, anything
+// else is spoken as written. Unknown tags are dropped and their content kept.
+pub fn parse_speech(text: &str) -> Vec<SpeechPart> {
+    let text = text.trim();
+    if !text.starts_with("<speak") {
+        return vec![SpeechPart::Text {
+            text: text.to_string(),
+            emphasis: false,
+        }];
+    }
+
+    let mut parts = Vec::new();
+    let mut emphasis = 0usize;
+    // Open <phoneme>: its IPA and the text read so far
+    let mut phoneme: Option<(String, String)> = None;
+    // Inside <sub>, whose content is replaced by the alias
+    let mut substituting = false;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (raw, tag, next) = match rest.find('<') {
+            Some(start) => match rest[start..].find('>') {
+                Some(len) => (
+                    &rest[..start],
+                    Some(&rest[start + 1..start + len]),
+                    &rest[start + len + 1..],
+                ),
+                // Unterminated tag, dropped
+                None => (&rest[..start], None, ""),
+            },
+            None => (rest, None, ""),
+        };
+        rest = next;
+        let content = unescape(raw);
+        if let Some((_, spoken)) = phoneme.as_mut() {
+            spoken.push_str(&content);
+        } else if !substituting {
+            push_text(&mut parts, &content, emphasis > 0);
+        }
+        let Some(tag) = tag else {
+            continue;
+        };
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+        match (name, closing) {
+            ("break", false) => parts.push(SpeechPart::Break(break_ms(tag))),
+            ("emphasis", false) if !tag.ends_with('/') => emphasis += 1,
+            ("emphasis", true) => emphasis = emphasis.saturating_sub(1),
+            ("phoneme", false) if !tag.ends_with('/') => {
+                phoneme = Some((attribute(tag, "ph").unwrap_or_default(), String::new()))
+            }
+            ("phoneme", true) => {
+                if let Some((phonemes, text)) = phoneme.take() {
+                    if phonemes.trim().is_empty() {
+                        push_text(&mut parts, &text, emphasis > 0);
+                    } else {
+                        parts.push(SpeechPart::Phoneme { text, phonemes });
+                    }
+                }
+            }
+            ("sub", false) => {
+                if let Some(alias) = attribute(tag, "alias") {
+                    push_text(&mut parts, &alias, emphasis > 0);
+                    substituting = !tag.ends_with('/');
+                }
+            }
+            ("sub", true) => substituting = false,
+            _ => {}
+        }
+    }
+    parts
+}
+
+// Byte range of the first whole-word, ASCII-case-insensitive match of `word` in `text`.
+// Words in scripts written without spaces match anywhere.
+fn find_word(text: &str, word: &str) -> Option<(usize, usize)> {
+    let haystack = text.to_ascii_lowercase();
+    let needle = word.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(index) = haystack[from..].find(&needle) {
+        let start = from + index;
+        let end = start + needle.len();
+        let bounded = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric());
+        if !word.is_ascii()
+            || (bounded(text[..start].chars().next_back()) && bounded(text[end..].chars().next()))
+        {
+            return Some((start, end));
+        }
+        from = start + haystack[start..].chars().next().map_or(1, char::len_utf8);
+    }
+    None
+}
+
+impl PronunciationDictionary {
+    // Replace dictionary words in the text parts for `voice`. With `phonemes`, entries
+    // that have IPA become phoneme parts instead of respellings.
+    pub fn apply(&self, parts: Vec<SpeechPart>, voice: &str, phonemes: bool) -> Vec<SpeechPart> {
+        if !self.enabled || self.entries.is_empty() {
+            return parts;
+        }
+        // Longest words first so "Kanna CS" wins over "Kanna"
+        let mut entries: Vec<&Pronunciation> = self
+            .entries
+            .iter()
+            .filter(|entry| !entry.word.trim().is_empty())
+            .filter(|entry| {
+                entry
+                    .voice
+                    .as_deref()
+                    .is_none_or(|v| v.trim().eq_ignore_ascii_case(voice.trim()))
+            })
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.word.trim().len()));
+
+        let mut output = Vec::new();
+        for part in parts {
+            let SpeechPart::Text { text, emphasis } = part else {
+                output.push(part);
+                continue;
+            };
+            let mut rest = text.as_str();
+            while !rest.is_empty() {
+                // Earliest match, longest word on ties
+                let found = entries
+                    .iter()
+                    .filter_map(|entry| {
+                        find_word(rest, entry.word.trim()).map(|range| (range, *entry))
+                    })
+                    .min_by_key(|((start, _), _)| *start);
+                let Some(((start, end), entry)) = found else {
+                    push_text(&mut output, rest, emphasis);
+                    break;
+                };
+                push_text(&mut output, &rest[..start], emphasis);
+                let written = &rest[start..end];
+                match entry.phonemes.as_deref().map(str::trim) {
+                    Some(ipa) if phonemes && !ipa.is_empty() => output.push(SpeechPart::Phoneme {
+                        text: written.to_string(),
+                        phonemes: ipa.to_string(),
+                    }),
+                    _ if !entry.say.trim().is_empty() => {
+                        push_text(&mut output, entry.say.trim(), emphasis)
+                    }
+                    _ => push_text(&mut output, written, emphasis),
+                }
+                rest = &rest[end..];
+            }
+        }
+        output
+    }
+}
+
+// SSML body (without the <speak> element) for voices that read SSML
+pub fn to_ssml(parts: &[SpeechPart]) -> String {
+    parts
+        .iter()
+        .map(|part| match part {
+            SpeechPart::Text {
+                text,
+                emphasis: false,
+            } => escape(text),
+            SpeechPart::Text {
+                text,
+                emphasis: true,
+            } => format!("<emphasis>{}</emphasis>", escape(text)),
+            SpeechPart::Break(ms) => format!("<break time=\"{}ms\"/>", ms),
+            SpeechPart::Phoneme { text, phonemes } => format!(
+                "<phoneme alphabet=\"ipa\" ph=\"{}\">{}</phoneme>",
+                escape(phonemes),
+                escape(text)
+            ),
+        })
+        .collect()
+}
+
+// Runs of plain text between pauses, for voices that only read plain text
+pub fn plain_runs(parts: &[SpeechPart]) -> Vec<PlainRun> {
+    let mut runs = vec![PlainRun {
+        text: String::new(),
+        pause_ms: 0,
+    }];
+    for part in parts {
+        let Some(run) = runs.last_mut() else {
+            continue;
+        };
+        match part {
+            SpeechPart::Text { text, .. } | SpeechPart::Phoneme { text, .. } => {
+                if run.pause_ms > 0 {
+                    runs.push(PlainRun {
+                        text: text.clone(),
+                        pause_ms: 0,
+                    });
+                } else {
+                    run.text.push_str(text);
+                }
+            }
+            SpeechPart::Break(ms) => run.pause_ms += ms,
+        }
+    }
+    runs
+}
+
+fn pronunciations_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join(PRONUNCIATIONS_FILE))
+}
+
+// Restore the saved dictionary at startup; a missing file means no entries yet
+pub fn load_pronunciations(app_handle: &tauri::AppHandle) {
+    let Ok(path) = pronunciations_path(app_handle) else {
+        return;
+    };
+    let Ok(data) = fs::read_to_string(&path) else {
+        return;
+    };
+    match serde_json::from_str::<PronunciationDictionary>(&data) {
+        Ok(dictionary) => {
+            println!("Loaded {} pronunciation(s)", dictionary.entries.len());
+            let state = app_handle.state::<TtsAppState>();
+            if let Ok(mut pronunciations) = state.pronunciations.lock() {
+                *pronunciations = dictionary;
+            }
+        }
+        Err(e) => println!("Ignoring invalid {}: {}", PRONUNCIATIONS_FILE, e),
+    }
+}
+
+fn save_pronunciations(
+    app_handle: &tauri::AppHandle,
+    dictionary: &PronunciationDictionary,
+) -> Result<(), String> {
+    let path = pronunciations_path(app_handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let data = serde_json::to_string_pretty(dictionary)
+        .map_err(|e| format!("Failed to serialize pronunciations: {}", e))?;
+    fs::write(&path, data).map_err(|e| format!("Failed to save pronunciations: {}", e))
+}
+
+#[tauri::command]
+pub fn tts_get_pronunciations(
+    state: State<'_, TtsAppState>,
+) -> Result<PronunciationDictionary, String> {
+    Ok(state
+        .pronunciations
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+#[tauri::command]
+pub fn tts_set_pronunciations(
+    app_handle: tauri::AppHandle,
+    state: State<'_, TtsAppState>,
+    mut dictionary: PronunciationDictionary,
+) -> Result<(), String> {
+    dictionary
+        .entries
+        .retain(|entry| !entry.word.trim().is_empty());
+    save_pronunciations(&app_handle, &dictionary)?;
+    println!(
+        "Updated pronunciation dictionary: enabled {}, {} entries",
+        dictionary.enabled,
+        dictionary.entries.len()
+    );
+    *state
+        .pronunciations
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = dictionary;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> SpeechPart {
+        SpeechPart::Text {
+            text: text.to_string(),
+            emphasis: false,
+        }
+    }
+
+    #[test]
+    fn plain_text_is_not_parsed_as_ssml() {
+        assert_eq!(parse_speech("a <b> & c"), vec![text("a <b> & c")]);
+    }
+
+    #[test]
+    fn entities_are_unescaped_and_escaped_again() {
+        let parts = parse_speech("<speak>Tom &amp; Jerry &lt;3 &quot;hi&quot;</speak>");
+        assert_eq!(parts, vec![text("Tom & Jerry <3 \"hi\"")]);
+        assert_eq!(to_ssml(&parts), "Tom &amp; Jerry &lt;3 &quot;hi&quot;");
+    }
+
+    #[test]
+    fn unknown_tags_are_dropped_and_their_content_kept() {
+        let parts =
+            parse_speech("<speak><prosody rate=\"slow\">slowly</prosody> <p>now</p></speak>");
+        assert_eq!(parts, vec![text("slowly now")]);
+    }
+
+    #[test]
+    fn tags_are_understood() {
+        let parts = parse_speech(
+            "<speak>Hi <break time=\"1.5s\"/><emphasis>there</emphasis> \
+             <phoneme ph=\"ˈkɑnə\">Kanna</phoneme> <sub alias=\"VR Chat\">VRC</sub></speak>",
+        );
+        assert_eq!(
+            parts,
+            vec![
+                text("Hi "),
+                SpeechPart::Break(1500),
+                SpeechPart::Text {
+                    text: "there".to_string(),
+                    emphasis: true
+                },
+                text(" "),
+                SpeechPart::Phoneme {
+                    text: "Kanna".to_string(),
+                    phonemes: "ˈkɑnə".to_string()
+                },
+                text(" VR Chat"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_quotes_fall_back_to_defaults() {
+        // A break whose time never closes gets the default medium pause
+        assert_eq!(
+            parse_speech("<speak>a<break time=\"300ms/>b</speak>"),
+            vec![text("a"), SpeechPart::Break(500), text("b")]
+        );
+        // A phoneme without usable IPA is read as its text
+        assert_eq!(
+            parse_speech("<speak><phoneme ph='kɑ>Kanna</phoneme></speak>"),
+            vec![text("Kanna")]
+        );
+    }
+
+    #[test]
+    fn unterminated_tags_are_dropped() {
+        assert_eq!(parse_speech("<speak>hello <break"), vec![text("hello ")]);
+    }
+
+    #[test]
+    fn breaks_are_capped() {
+        assert_eq!(
+            parse_speech("<speak><break time=\"60s\"/></speak>"),
+            vec![SpeechPart::Break(MAX_BREAK_MS)]
+        );
+    }
+}
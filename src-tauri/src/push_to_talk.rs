@@ -0,0 +1,177 @@
+// Push-to-talk for native microphone capture. While enabled, captured audio is only
+// passed on for transcription while the hotkey (or a controller binding forwarded by
+// the frontend) is held. A short pre-roll of audio from just before the press is kept
+// so the first syllable isn't clipped by a late finger.
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, State};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::whisper::WHISPER_SAMPLE_RATE;
+
+const DEFAULT_PRE_ROLL_MS: u32 = 300;
+const MAX_PRE_ROLL_MS: u32 = 2000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PushToTalkConfig {
+    pub enabled: bool,
+    // Global hotkey in accelerator syntax ("Ctrl+Shift+Space"). None leaves pressing
+    // to `audio_set_push_to_talk_pressed`, e.g. for a controller button.
+    pub hotkey: Option<String>,
+    // Audio from before the press that is kept and transcribed with the utterance
+    pub pre_roll_ms: u32,
+}
+
+impl Default for PushToTalkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hotkey: None,
+            pre_roll_ms: DEFAULT_PRE_ROLL_MS,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PushToTalkState {
+    config: Mutex<PushToTalkConfig>,
+    held: AtomicBool,
+}
+
+// What the capture thread should do with a buffer of 16kHz samples
+pub enum GateOutput {
+    // Keep buffering for transcription
+    Pass(Vec<f32>),
+    // Key is up, drop the audio (it only feeds the pre-roll)
+    Hold,
+    // Key was just released: buffer these and flush the utterance now
+    Release(Vec<f32>),
+}
+
+// Per-capture gate state, owned by the capture thread
+#[derive(Default)]
+pub struct PushToTalkGate {
+    was_held: bool,
+    pre_roll: VecDeque<f32>,
+}
+
+impl PushToTalkGate {
+    pub fn process(&mut self, state: &PushToTalkState, samples: Vec<f32>) -> GateOutput {
+        let (enabled, pre_roll_ms) = match state.config.lock() {
+            Ok(config) => (config.enabled, config.pre_roll_ms),
+            Err(_) => return GateOutput::Pass(samples),
+        };
+        if !enabled {
+            self.was_held = false;
+            self.pre_roll.clear();
+            return GateOutput::Pass(samples);
+        }
+
+        let held = state.held.load(Ordering::SeqCst);
+        match (self.was_held, held) {
+            (false, true) => {
+                self.was_held = true;
+                let mut output: Vec<f32> = self.pre_roll.drain(..).collect();
+                output.extend(samples);
+                GateOutput::Pass(output)
+            }
+            (true, true) => GateOutput::Pass(samples),
+            (true, false) => {
+                self.was_held = false;
+                GateOutput::Release(samples)
+            }
+            (false, false) => {
+                let capacity = (WHISPER_SAMPLE_RATE as u64 * pre_roll_ms as u64 / 1000) as usize;
+                self.pre_roll.extend(samples);
+                let excess = self.pre_roll.len().saturating_sub(capacity);
+                self.pre_roll.drain(..excess);
+                GateOutput::Hold
+            }
+        }
+    }
+}
+
+fn set_pressed(app_handle: &tauri::AppHandle, state: &PushToTalkState, pressed: bool) {
+    if state.held.swap(pressed, Ordering::SeqCst) != pressed {
+        let _ = app_handle.emit("push-to-talk", serde_json::json!({ "pressed": pressed }));
+    }
+}
+
+fn parse_hotkey(hotkey: &str) -> Result<Shortcut, String> {
+    hotkey
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid push-to-talk hotkey '{}': {}", hotkey, e))
+}
+
+#[tauri::command]
+pub fn audio_get_push_to_talk(
+    state: State<'_, PushToTalkState>,
+) -> Result<PushToTalkConfig, String> {
+    Ok(state
+        .config
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+// Apply push-to-talk settings, moving the global hotkey registration if it changed
+#[tauri::command]
+pub fn audio_set_push_to_talk(
+    app_handle: tauri::AppHandle,
+    state: State<'_, PushToTalkState>,
+    mut config: PushToTalkConfig,
+) -> Result<(), String> {
+    config.pre_roll_ms = config.pre_roll_ms.min(MAX_PRE_ROLL_MS);
+    config.hotkey = config
+        .hotkey
+        .map(|hotkey| hotkey.trim().to_string())
+        .filter(|hotkey| !hotkey.is_empty());
+    let shortcut = match (&config.hotkey, config.enabled) {
+        (Some(hotkey), true) => Some(parse_hotkey(hotkey)?),
+        _ => None,
+    };
+
+    let mut current = state
+        .config
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    let shortcuts = app_handle.global_shortcut();
+    if let (Some(hotkey), true) = (&current.hotkey, current.enabled) {
+        if let Ok(previous) = parse_hotkey(hotkey) {
+            let _ = shortcuts.unregister(previous);
+        }
+    }
+    if let Some(shortcut) = shortcut {
+        shortcuts
+            .on_shortcut(shortcut, |app_handle, _, event| {
+                let state = app_handle.state::<PushToTalkState>();
+                set_pressed(app_handle, &state, event.state() == ShortcutState::Pressed);
+            })
+            .map_err(|e| format!("Failed to register push-to-talk hotkey: {}", e))?;
+    }
+
+    println!(
+        "Push-to-talk enabled: {} (hotkey: {}, pre-roll {}ms)",
+        config.enabled,
+        config.hotkey.as_deref().unwrap_or("none"),
+        config.pre_roll_ms
+    );
+    *current = config;
+    // Don't leave the gate open if it was disabled mid-press
+    set_pressed(&app_handle, &state, false);
+    Ok(())
+}
+
+// Press or release push-to-talk from outside the global hotkey (controller bindings)
+#[tauri::command]
+pub fn audio_set_push_to_talk_pressed(
+    app_handle: tauri::AppHandle,
+    state: State<'_, PushToTalkState>,
+    pressed: bool,
+) -> Result<(), String> {
+    set_pressed(&app_handle, &state, pressed);
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{Emitter, State};
+
+use crate::gpu::WhisperBackend;
+use crate::hardware::{detect_vram, model_footprint, VRCHAT_VRAM_RESERVE};
+use crate::manifest::model_catalog;
+use crate::whisper::{whisper_is_model_downloaded, WhisperAppState};
+
+// Weight precision of a catalog model, best quality first
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quantization {
+    // Pick the best variant that fits in VRAM on GPU backends
+    #[default]
+    Auto,
+    Full,
+    #[serde(rename = "q8_0")]
+    Q8,
+    // q5_0 or q5_1, whichever the model is published in
+    #[serde(rename = "q5")]
+    Q5,
+}
+
+impl Quantization {
+    fn as_str(self) -> &'static str {
+        match self {
+            Quantization::Auto => "auto",
+            Quantization::Full => "full",
+            Quantization::Q8 => "q8_0",
+            Quantization::Q5 => "q5",
+        }
+    }
+}
+
+// Split a model ID into its size and precision: "medium-q5_0" -> ("medium", Q5)
+pub fn parse_model_id(model: &str) -> (&str, Quantization) {
+    match model.rsplit_once('-') {
+        Some((size, "q8_0")) => (size, Quantization::Q8),
+        Some((size, "q5_0" | "q5_1")) => (size, Quantization::Q5),
+        _ => (model, Quantization::Full),
+    }
+}
+
+#[derive(Default)]
+pub struct QuantizationState {
+    pub preference: Quantization,
+    // VRAM probed on first use; probing shells out to vendor tools so it's done once
+    vram: Option<Option<u64>>,
+    // Last variant chosen per requested model, to only announce changes
+    choices: HashMap<String, String>,
+}
+
+// Model substituted for a requested one, reported through the `model-quantization` event
+#[derive(Clone, Debug, Serialize)]
+pub struct QuantizationChoice {
+    pub requested: String,
+    pub selected: String,
+    pub quantization: Quantization,
+    pub backend: WhisperBackend,
+    pub vram_bytes: Option<u64>,
+    pub required_bytes: u64,
+    pub reason: String,
+}
+
+// Variants of `size` in the built-in catalog, best quality first
+pub fn catalog_variants(size: &str) -> Vec<(String, Quantization)> {
+    let mut variants: Vec<(String, Quantization)> = model_catalog()
+        .into_iter()
+        .filter(|m| !m.custom)
+        .filter_map(|m| {
+            let (variant_size, quantization) = parse_model_id(&m.id);
+            (variant_size == size).then(|| (m.id.clone(), quantization))
+        })
+        .collect();
+    variants.sort_by_key(|(_, quantization)| *quantization);
+    variants
+}
+
+pub async fn downloaded_variants(
+    app_handle: &tauri::AppHandle,
+    variants: Vec<(String, Quantization)>,
+) -> Vec<(String, Quantization)> {
+    let mut downloaded = Vec::new();
+    for (id, quantization) in variants {
+        if let Ok(true) = whisper_is_model_downloaded(app_handle.clone(), id.clone()).await {
+            downloaded.push((id, quantization));
+        }
+    }
+    downloaded
+}
+
+fn lock_state(
+    state: &WhisperAppState,
+) -> Result<std::sync::MutexGuard<'_, QuantizationState>, String> {
+    state
+        .quantization
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))
+}
+
+// Map a requested model to the downloaded variant of the same size to load. On GPU
+// backends the best quality that fits in spare VRAM is used, so a model too large
+// for the card is swapped for a quantized one instead of failing with out-of-memory.
+pub async fn resolve_quantization(
+    app_handle: &tauri::AppHandle,
+    state: &WhisperAppState,
+    model: &str,
+) -> Result<String, String> {
+    let (size, _) = parse_model_id(model);
+    let variants = catalog_variants(size);
+    if variants.len() < 2 {
+        return Ok(model.to_string());
+    }
+
+    let backend = *state
+        .backend
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    let preference = lock_state(state)?.preference;
+    if preference == Quantization::Auto && !backend.uses_gpu() {
+        return Ok(model.to_string());
+    }
+
+    let cached_vram = lock_state(state)?.vram;
+    let vram = match cached_vram {
+        Some(vram) => vram,
+        None => {
+            let vram = tokio::task::spawn_blocking(move || detect_vram(backend))
+                .await
+                .map_err(|e| format!("VRAM probe failed: {:?}", e))?;
+            lock_state(state)?.vram = Some(vram);
+            vram
+        }
+    };
+
+    let downloaded = downloaded_variants(app_handle, variants).await;
+    let (selected, reason) = match preference {
+        Quantization::Auto => {
+            let Some(vram) = vram else {
+                return Ok(model.to_string());
+            };
+            let spare = vram.saturating_sub(VRCHAT_VRAM_RESERVE);
+            // Best downloaded variant that fits, else the smallest one downloaded
+            let fitting = downloaded
+                .iter()
+                .find(|(id, _)| model_footprint(id) <= spare)
+                .or_else(|| downloaded.last());
+            let Some((selected, _)) = fitting else {
+                return Ok(model.to_string());
+            };
+            let reason = if model_footprint(selected) <= spare {
+                format!(
+                    "best downloaded variant fitting {:.1} GB of spare VRAM",
+                    spare as f64 / (1024.0 * 1024.0 * 1024.0)
+                )
+            } else {
+                "no downloaded variant fits in VRAM, using the smallest".to_string()
+            };
+            (selected.clone(), reason)
+        }
+        forced => {
+            let Some((selected, _)) = downloaded.iter().find(|(_, q)| *q == forced) else {
+                println!(
+                    "No downloaded {} variant of {}, using {}",
+                    forced.as_str(),
+                    size,
+                    model
+                );
+                return Ok(model.to_string());
+            };
+            (
+                selected.clone(),
+                format!("{} forced in settings", forced.as_str()),
+            )
+        }
+    };
+
+    let previous = lock_state(state)?
+        .choices
+        .insert(model.to_string(), selected.clone());
+    if previous.as_deref() != Some(selected.as_str()) {
+        let choice = QuantizationChoice {
+            requested: model.to_string(),
+            selected: selected.clone(),
+            quantization: parse_model_id(&selected).1,
+            backend,
+            vram_bytes: vram,
+            required_bytes: model_footprint(&selected),
+            reason,
+        };
+        println!(
+            "Quantization for {}: using {} ({})",
+            model, selected, choice.reason
+        );
+        let _ = app_handle.emit("model-quantization", &choice);
+    }
+    Ok(selected)
+}
+
+#[tauri::command]
+pub fn whisper_get_quantization(state: State<'_, WhisperAppState>) -> Result<Quantization, String> {
+    Ok(lock_state(&state)?.preference)
+}
+
+#[tauri::command]
+pub fn whisper_set_quantization(
+    state: State<'_, WhisperAppState>,
+    quantization: Quantization,
+) -> Result<(), String> {
+    {
+        let mut quantization_state = lock_state(&state)?;
+        if quantization_state.preference == quantization {
+            return Ok(());
+        }
+        quantization_state.preference = quantization;
+        quantization_state.choices.clear();
+    }
+    // Resident variants may no longer match the preference
+    state.models.clear()?;
+    println!("Model quantization set to {}", quantization.as_str());
+    Ok(())
+}
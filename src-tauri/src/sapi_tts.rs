@@ -0,0 +1,185 @@
+// Text-to-speech with the voices installed in Windows (SAPI, through .NET's
+// System.Speech in PowerShell), so there is a voice without downloading anything.
+// Also used when the chosen Piper voice isn't available.
+use serde::{Deserialize, Serialize};
+
+#[cfg(target_os = "windows")]
+use crate::audio_decode::decode_wav;
+
+// Speech rate range of SpeechSynthesizer.Rate
+const MIN_RATE: i32 = -10;
+const MAX_RATE: i32 = 10;
+const MAX_VOLUME: u32 = 100;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SapiConfig {
+    // Installed voice name, e.g. "Microsoft Haruka Desktop"; None for the system default
+    pub voice: Option<String>,
+    // -10 (slowest) to 10 (fastest)
+    pub rate: i32,
+    // 0 to 100
+    pub volume: u32,
+}
+
+impl Default for SapiConfig {
+    fn default() -> Self {
+        Self {
+            voice: None,
+            rate: 0,
+            volume: MAX_VOLUME,
+        }
+    }
+}
+
+impl SapiConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !cfg!(target_os = "windows") {
+            return Err("Windows voices are only available on Windows".to_string());
+        }
+        if !(MIN_RATE..=MAX_RATE).contains(&self.rate) {
+            return Err(format!(
+                "Speech rate must be {} to {}, got {}",
+                MIN_RATE, MAX_RATE, self.rate
+            ));
+        }
+        if self.volume > MAX_VOLUME {
+            return Err(format!(
+                "Speech volume must be 0 to {}, got {}",
+                MAX_VOLUME, self.volume
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SapiVoice {
+    pub name: String,
+    // e.g. "ja-JP"
+    pub culture: String,
+}
+
+// Run a PowerShell script without flashing a console window, feeding `input` on stdin
+#[cfg(target_os = "windows")]
+fn run_powershell(script: &str, input: &str, env: &[(&str, &str)]) -> Result<String, String> {
+    use std::io::Write;
+    use std::os::windows::process::CommandExt;
+    use std::process::{Command, Stdio};
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let mut child = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .creation_flags(CREATE_NO_WINDOW)
+        .spawn()
+        .map_err(|e| format!("Failed to start PowerShell: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| format!("Failed to pass text to PowerShell: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("PowerShell failed: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Windows speech failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// Synthesize `text` with a Windows voice, returning mono samples and their rate. With
+// `ssml` the text is the body of a <speak> element, wrapped here in the voice's language.
+// Text and voice name go through stdin and the environment, never into the script.
+pub async fn synthesize_sapi(
+    config: &SapiConfig,
+    text: &str,
+    ssml: bool,
+) -> Result<(Vec<f32>, u32), String> {
+    config.validate()?;
+    #[cfg(target_os = "windows")]
+    {
+        use base64::Engine as _;
+
+        let script = format!(
+            "[Console]::InputEncoding = [Text.Encoding]::UTF8; \
+             Add-Type -AssemblyName System.Speech; \
+             $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+             if ($env:VRCTALK_SAPI_VOICE) {{ $s.SelectVoice($env:VRCTALK_SAPI_VOICE) }}; \
+             $s.Rate = {}; $s.Volume = {}; \
+             $m = New-Object System.IO.MemoryStream; \
+             $s.SetOutputToWaveStream($m); \
+             $t = [Console]::In.ReadToEnd(); \
+             if ($env:VRCTALK_SAPI_SSML) {{ \
+               $s.SpeakSsml('<speak version=''1.0'' xmlns=''http://www.w3.org/2001/10/synthesis'' xml:lang=''' + \
+                 $s.Voice.Culture.Name + '''>' + $t + '</speak>') \
+             }} else {{ $s.Speak($t) }}; \
+             [Convert]::ToBase64String($m.ToArray())",
+            config.rate, config.volume
+        );
+        let voice = config.voice.clone().unwrap_or_default();
+        let text = text.to_string();
+        let output = tokio::task::spawn_blocking(move || {
+            let ssml = if ssml { "1" } else { "" };
+            run_powershell(
+                &script,
+                &text,
+                &[
+                    ("VRCTALK_SAPI_VOICE", voice.trim()),
+                    ("VRCTALK_SAPI_SSML", ssml),
+                ],
+            )
+        })
+        .await
+        .map_err(|e| format!("Speech task failed: {}", e))??;
+        let wav = base64::engine::general_purpose::STANDARD
+            .decode(output.trim())
+            .map_err(|e| format!("Invalid Windows speech output: {}", e))?;
+        decode_wav(&wav)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = (text, ssml);
+        Err("Windows voices are only available on Windows".to_string())
+    }
+}
+
+// `<name>|<culture>` lines of the installed voices, nothing on other platforms
+async fn installed_voices() -> Result<String, String> {
+    #[cfg(target_os = "windows")]
+    {
+        let script = "Add-Type -AssemblyName System.Speech; \
+             (New-Object System.Speech.Synthesis.SpeechSynthesizer).GetInstalledVoices() | \
+             Where-Object Enabled | \
+             ForEach-Object { \"$($_.VoiceInfo.Name)|$($_.VoiceInfo.Culture.Name)\" }";
+        tokio::task::spawn_blocking(move || run_powershell(script, "", &[]))
+            .await
+            .map_err(|e| format!("Voice enumeration failed: {}", e))?
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Ok(String::new())
+    }
+}
+
+// Voices installed in Windows; empty on other platforms
+#[tauri::command]
+pub async fn tts_list_sapi_voices() -> Result<Vec<SapiVoice>, String> {
+    Ok(installed_voices()
+        .await?
+        .lines()
+        .filter_map(|line| {
+            let (name, culture) = line.trim().rsplit_once('|')?;
+            Some(SapiVoice {
+                name: name.to_string(),
+                culture: culture.to_string(),
+            })
+        })
+        .collect())
+}
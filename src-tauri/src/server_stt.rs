@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use crate::openai_stt::{encode_wav, normalize_language, VerboseTranscription};
+use crate::utterance::group_utterances;
+use crate::whisper::{
+    clean_transcript, prepare_audio, whisper_language_code, InferenceOptions, TranscriptionResult,
+    WHISPER_SAMPLE_RATE,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+// Audio is streamed to Wyoming servers in one-second chunks
+const WYOMING_CHUNK_SAMPLES: usize = WHISPER_SAMPLE_RATE as usize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerProtocol {
+    // whisper.cpp's bundled `server` example (HTTP, POST /inference)
+    WhisperCpp,
+    // Wyoming protocol over TCP, as spoken by wyoming-faster-whisper
+    Wyoming,
+}
+
+// A Whisper server on the LAN, so inference can run on another machine while
+// VRCTalk stays the OSC frontend.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WhisperServerConfig {
+    pub protocol: ServerProtocol,
+    // http://host:8080 for whisper.cpp, host:10300 for Wyoming
+    pub address: String,
+}
+
+impl WhisperServerConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let address = self.address.trim();
+        match self.protocol {
+            ServerProtocol::WhisperCpp => {
+                if !address.starts_with("http://") && !address.starts_with("https://") {
+                    return Err(format!("Invalid whisper.cpp server URL: '{}'", address));
+                }
+            }
+            ServerProtocol::Wyoming => {
+                let (host, port) = address.rsplit_once(':').ok_or_else(|| {
+                    format!("Wyoming address must be host:port, got '{}'", address)
+                })?;
+                if host.is_empty() || port.parse::<u16>().is_err() {
+                    return Err(format!("Invalid Wyoming address: '{}'", address));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub async fn transcribe_server(
+    config: &WhisperServerConfig,
+    audio_data: &[u8],
+    options: &InferenceOptions,
+) -> Result<TranscriptionResult, String> {
+    config.validate()?;
+
+    let Some(speech) = prepare_audio(audio_data, options.denoise, &options.vad)? else {
+        return Ok(TranscriptionResult::default());
+    };
+
+    let started = Instant::now();
+    let mut result = match config.protocol {
+        ServerProtocol::WhisperCpp => {
+            transcribe_whisper_cpp(config, &speech.samples, options).await
+        }
+        ServerProtocol::Wyoming => tokio::time::timeout(
+            REQUEST_TIMEOUT,
+            transcribe_wyoming(config, &speech.samples, options),
+        )
+        .await
+        .map_err(|_| "Wyoming server timed out".to_string())?,
+    }?;
+    println!(
+        "Server transcription finished in {}ms",
+        started.elapsed().as_millis()
+    );
+    options
+        .hallucination_filter
+        .apply_to_result(&mut result, &speech.samples);
+    group_utterances(
+        &mut result,
+        &speech.region_starts_ms(WHISPER_SAMPLE_RATE),
+        &options.source,
+    );
+    Ok(result)
+}
+
+async fn transcribe_whisper_cpp(
+    config: &WhisperServerConfig,
+    speech: &[f32],
+    options: &InferenceOptions,
+) -> Result<TranscriptionResult, String> {
+    let file = reqwest::multipart::Part::bytes(encode_wav(speech)?)
+        .file_name("audio.wav")
+        .mime_str("audio/wav")
+        .map_err(|e| format!("Failed to build upload: {}", e))?;
+
+    let language = whisper_language_code(&options.language);
+    let mut form = reqwest::multipart::Form::new()
+        .part("file", file)
+        .text("response_format", "verbose_json")
+        .text(
+            "language",
+            language.clone().unwrap_or_else(|| "auto".to_string()),
+        )
+        .text("translate", options.translate.to_string())
+        .text("temperature", options.decoding.temperature.to_string())
+        .text(
+            "temperature_inc",
+            options.decoding.temperature_increment.to_string(),
+        );
+    if let Some(prompt) = &options.initial_prompt {
+        form = form.text("prompt", prompt.clone());
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let url = format!("{}/inference", config.address.trim().trim_end_matches('/'));
+    let response = client
+        .post(url)
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| format!("whisper.cpp server request failed: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("whisper.cpp server returned {}: {}", status, body));
+    }
+
+    let transcription: VerboseTranscription = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse whisper.cpp response: {}", e))?;
+    Ok(transcription.into_result(language.as_deref().unwrap_or("auto")))
+}
+
+// Wyoming events are a JSON header line, optionally followed by extra JSON data
+// and a binary payload whose lengths the header announces.
+async fn write_wyoming_event(
+    stream: &mut TcpStream,
+    event_type: &str,
+    data: serde_json::Value,
+    payload: &[u8],
+) -> Result<(), String> {
+    let mut header = serde_json::json!({
+        "type": event_type,
+        "data": data
+    });
+    if !payload.is_empty() {
+        header["payload_length"] = payload.len().into();
+    }
+
+    let mut buffer = serde_json::to_vec(&header)
+        .map_err(|e| format!("Failed to encode Wyoming event: {}", e))?;
+    buffer.push(b'\n');
+    buffer.extend_from_slice(payload);
+    stream
+        .write_all(&buffer)
+        .await
+        .map_err(|e| format!("Failed to send to Wyoming server: {}", e))
+}
+
+async fn read_wyoming_event<R: AsyncBufReadExt + Unpin>(
+    reader: &mut R,
+) -> Result<(String, serde_json::Value), String> {
+    let mut line = String::new();
+    let read = reader
+        .read_line(&mut line)
+        .await
+        .map_err(|e| format!("Failed to read from Wyoming server: {}", e))?;
+    if read == 0 {
+        return Err("Wyoming server closed the connection".to_string());
+    }
+
+    let header: serde_json::Value =
+        serde_json::from_str(&line).map_err(|e| format!("Invalid Wyoming event: {}", e))?;
+    let event_type = header["type"].as_str().unwrap_or_default().to_string();
+    let mut data = header
+        .get("data")
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}));
+
+    if let Some(length) = header["data_length"].as_u64().filter(|&l| l > 0) {
+        let mut buffer = vec![0u8; length as usize];
+        reader
+            .read_exact(&mut buffer)
+            .await
+            .map_err(|e| format!("Failed to read Wyoming event data: {}", e))?;
+        let extra: serde_json::Value = serde_json::from_slice(&buffer)
+            .map_err(|e| format!("Invalid Wyoming event data: {}", e))?;
+        if let (Some(data), Some(extra)) = (data.as_object_mut(), extra.as_object()) {
+            data.extend(extra.clone());
+        }
+    }
+    if let Some(length) = header["payload_length"].as_u64().filter(|&l| l > 0) {
+        // Payloads (e.g. audio) aren't needed from the server, just skip them
+        let mut buffer = vec![0u8; length as usize];
+        reader
+            .read_exact(&mut buffer)
+            .await
+            .map_err(|e| format!("Failed to read Wyoming payload: {}", e))?;
+    }
+
+    Ok((event_type, data))
+}
+
+async fn transcribe_wyoming(
+    config: &WhisperServerConfig,
+    speech: &[f32],
+    options: &InferenceOptions,
+) -> Result<TranscriptionResult, String> {
+    if options.translate {
+        return Err("Wyoming servers don't support the translate task".to_string());
+    }
+
+    let mut stream = TcpStream::connect(config.address.trim())
+        .await
+        .map_err(|e| format!("Failed to connect to Wyoming server: {}", e))?;
+
+    let language = whisper_language_code(&options.language);
+    let transcribe = match &language {
+        Some(language) => serde_json::json!({ "language": language }),
+        None => serde_json::json!({}),
+    };
+    let format = serde_json::json!({
+        "rate": WHISPER_SAMPLE_RATE,
+        "width": 2,
+        "channels": 1
+    });
+
+    write_wyoming_event(&mut stream, "transcribe", transcribe, &[]).await?;
+    write_wyoming_event(&mut stream, "audio-start", format.clone(), &[]).await?;
+    for chunk in speech.chunks(WYOMING_CHUNK_SAMPLES) {
+        let pcm: Vec<u8> = chunk
+            .iter()
+            .flat_map(|&s| ((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+            .collect();
+        write_wyoming_event(&mut stream, "audio-chunk", format.clone(), &pcm).await?;
+    }
+    write_wyoming_event(&mut stream, "audio-stop", serde_json::json!({}), &[]).await?;
+
+    let mut reader = BufReader::new(stream);
+    loop {
+        let (event_type, data) = read_wyoming_event(&mut reader).await?;
+        if event_type == "transcript" {
+            let text = data["text"].as_str().unwrap_or_default();
+            let detected = data["language"].as_str().map(|l| l.to_string());
+            return Ok(TranscriptionResult {
+                text: clean_transcript(text),
+                language: normalize_language(detected, language.as_deref().unwrap_or("auto")),
+                segments: Vec::new(),
+                utterances: Vec::new(),
+            });
+        }
+    }
+}
@@ -0,0 +1,245 @@
+// Named settings profiles ("JP friends", "Streaming", "Quest") kept in the settings
+// store. Each carries the model, languages, OSC target and chatbox formatting; switching
+// copies them over the live settings and emits `settings-profile-changed`. While a
+// profile is active, edits to those settings are saved into it as well. That includes
+// switching a language profile (`language_profiles`), which is just such an edit.
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, State};
+
+use crate::chatbox_format::ChatboxFormat;
+use crate::config::{current_settings, Settings, SettingsState, VrchatSettings};
+
+pub const DEFAULT_PROFILE_NAME: &str = "Default";
+
+// The part of the settings a profile switches
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileSettings {
+    pub source_language: String,
+    pub target_language: String,
+    pub secondary_target_language: Option<String>,
+    pub recognizer: String,
+    pub whisper_model: String,
+    pub translator: String,
+    pub chatbox_format: ChatboxFormat,
+    // OSC target and chatbox behaviour
+    pub vrchat_settings: VrchatSettings,
+}
+
+impl Default for ProfileSettings {
+    fn default() -> Self {
+        ProfileSettings::from(&Settings::default())
+    }
+}
+
+impl From<&Settings> for ProfileSettings {
+    fn from(settings: &Settings) -> Self {
+        Self {
+            source_language: settings.source_language.clone(),
+            target_language: settings.target_language.clone(),
+            secondary_target_language: settings.secondary_target_language.clone(),
+            recognizer: settings.recognizer.clone(),
+            whisper_model: settings.whisper_model.clone(),
+            translator: settings.translator.clone(),
+            chatbox_format: settings.chatbox_format.clone(),
+            vrchat_settings: settings.vrchat_settings.clone(),
+        }
+    }
+}
+
+impl ProfileSettings {
+    pub fn apply_to(&self, settings: &mut Settings) {
+        settings.source_language = self.source_language.clone();
+        settings.target_language = self.target_language.clone();
+        settings.secondary_target_language = self.secondary_target_language.clone();
+        settings.recognizer = self.recognizer.clone();
+        settings.whisper_model = self.whisper_model.clone();
+        settings.translator = self.translator.clone();
+        settings.chatbox_format = self.chatbox_format.clone();
+        settings.vrchat_settings = self.vrchat_settings.clone();
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SettingsProfile {
+    pub name: String,
+    #[serde(flatten)]
+    pub settings: ProfileSettings,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ProfileList {
+    pub profiles: Vec<String>,
+    pub active: String,
+}
+
+impl Settings {
+    fn profile(&self, name: &str) -> Option<&SettingsProfile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    // Save the live values into the active profile
+    pub fn sync_active_profile(&mut self) {
+        let current = ProfileSettings::from(&*self);
+        if let Some(profile) = self
+            .profiles
+            .iter_mut()
+            .find(|profile| profile.name == self.active_profile)
+        {
+            profile.settings = current;
+        }
+    }
+
+    pub fn validate_profiles(&self) -> Result<(), String> {
+        for (index, profile) in self.profiles.iter().enumerate() {
+            if profile.name.trim().is_empty() {
+                return Err("Settings profiles need a name".to_string());
+            }
+            if self.profiles[..index]
+                .iter()
+                .any(|p| p.name == profile.name)
+            {
+                return Err(format!("Duplicate settings profile '{}'", profile.name));
+            }
+        }
+        if self.profile(&self.active_profile).is_none() {
+            return Err(format!(
+                "Active settings profile '{}' doesn't exist",
+                self.active_profile
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn profile_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Settings profiles need a name".to_string());
+    }
+    Ok(name.to_string())
+}
+
+fn profile_list(settings: &Settings) -> ProfileList {
+    ProfileList {
+        profiles: settings.profiles.iter().map(|p| p.name.clone()).collect(),
+        active: settings.active_profile.clone(),
+    }
+}
+
+// Add a profile with `profile` under `name`, which must be new
+fn add_profile(
+    app_handle: &tauri::AppHandle,
+    state: &SettingsState,
+    name: &str,
+    profile: ProfileSettings,
+) -> Result<ProfileList, String> {
+    let name = profile_name(name)?;
+    let settings = state.modify(app_handle, |settings| {
+        if settings.profile(&name).is_some() {
+            return Err(format!(
+                "A settings profile named '{}' already exists",
+                name
+            ));
+        }
+        settings.profiles.push(SettingsProfile {
+            name: name.clone(),
+            settings: profile,
+        });
+        Ok(())
+    })?;
+    println!("Created settings profile '{}'", name);
+    Ok(profile_list(&settings))
+}
+
+#[tauri::command]
+pub fn list_settings_profiles(state: State<'_, SettingsState>) -> Result<ProfileList, String> {
+    Ok(profile_list(&current_settings(&state)?))
+}
+
+// Settings stored in a profile, without switching to it
+#[tauri::command]
+pub fn get_settings_profile(
+    state: State<'_, SettingsState>,
+    name: String,
+) -> Result<SettingsProfile, String> {
+    current_settings(&state)?
+        .profile(name.trim())
+        .cloned()
+        .ok_or_else(|| format!("No settings profile named '{}'", name.trim()))
+}
+
+// New profile starting from the defaults
+#[tauri::command]
+pub fn create_settings_profile(
+    app_handle: tauri::AppHandle,
+    state: State<'_, SettingsState>,
+    name: String,
+) -> Result<ProfileList, String> {
+    add_profile(&app_handle, &state, &name, ProfileSettings::default())
+}
+
+// New profile with the settings of `source`
+#[tauri::command]
+pub fn clone_settings_profile(
+    app_handle: tauri::AppHandle,
+    state: State<'_, SettingsState>,
+    source: String,
+    name: String,
+) -> Result<ProfileList, String> {
+    let profile = current_settings(&state)?
+        .profile(source.trim())
+        .map(|profile| profile.settings.clone())
+        .ok_or_else(|| format!("No settings profile named '{}'", source.trim()))?;
+    add_profile(&app_handle, &state, &name, profile)
+}
+
+// The active profile can't be deleted, switch away from it first
+#[tauri::command]
+pub fn delete_settings_profile(
+    app_handle: tauri::AppHandle,
+    state: State<'_, SettingsState>,
+    name: String,
+) -> Result<ProfileList, String> {
+    let name = name.trim();
+    let settings = state.modify(&app_handle, |settings| {
+        if settings.active_profile == name {
+            return Err(format!("'{}' is the active settings profile", name));
+        }
+        let count = settings.profiles.len();
+        settings.profiles.retain(|profile| profile.name != name);
+        if settings.profiles.len() == count {
+            return Err(format!("No settings profile named '{}'", name));
+        }
+        Ok(())
+    })?;
+    println!("Deleted settings profile '{}'", name);
+    Ok(profile_list(&settings))
+}
+
+// Make `name` the active profile, applying its settings
+#[tauri::command]
+pub fn switch_settings_profile(
+    app_handle: tauri::AppHandle,
+    state: State<'_, SettingsState>,
+    name: String,
+) -> Result<Settings, String> {
+    let name = name.trim();
+    // Applied and made active in one step, so the profile receives its own values back
+    let settings = state.modify(&app_handle, |settings| {
+        let profile = settings
+            .profile(name)
+            .map(|profile| profile.settings.clone())
+            .ok_or_else(|| format!("No settings profile named '{}'", name))?;
+        profile.apply_to(settings);
+        settings.active_profile = name.to_string();
+        Ok(())
+    })?;
+
+    println!("Switched to settings profile '{}'", name);
+    let _ = app_handle.emit(
+        "settings-profile-changed",
+        serde_json::json!({ "name": name, "settings": settings }),
+    );
+    Ok(settings)
+}
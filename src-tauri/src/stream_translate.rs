@@ -0,0 +1,198 @@
+// Translation of streaming transcripts while the user is still talking. Streams opened
+// with a target language get their committed (stabilized) text translated each time it
+// grows, one request at a time with only the newest text queued, and the full text
+// once more when the stream ends so the final translation replaces the partial ones.
+// Results go out as `translation-stream` events.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::translate::{translate_transcript, TranslateAppState};
+
+struct StreamTranslation {
+    source: String,
+    target: String,
+    // Newest committed text, and the text last sent for translation
+    latest: String,
+    translated: String,
+    in_flight: bool,
+    // Full text of the newest finished decode, translated when the stream ends
+    final_text: String,
+}
+
+#[derive(Default)]
+pub struct StreamTranslateState {
+    streams: Mutex<HashMap<String, StreamTranslation>>,
+}
+
+// Start translating a stream's partials into `target`; a no-op if already open
+pub fn open_stream_translation(
+    app_handle: &tauri::AppHandle,
+    stream_id: &str,
+    source: &str,
+    target: &str,
+) {
+    let state = app_handle.state::<StreamTranslateState>();
+    let Ok(mut streams) = state.streams.lock() else {
+        return;
+    };
+    streams
+        .entry(stream_id.to_string())
+        .or_insert_with(|| StreamTranslation {
+            source: source.to_string(),
+            target: target.to_string(),
+            latest: String::new(),
+            translated: String::new(),
+            in_flight: false,
+            final_text: String::new(),
+        });
+}
+
+// Queue the stream's committed text for translation if it changed
+pub fn translate_committed(app_handle: &tauri::AppHandle, stream_id: &str, committed: &str) {
+    let state = app_handle.state::<StreamTranslateState>();
+    let Ok(mut streams) = state.streams.lock() else {
+        return;
+    };
+    let Some(stream) = streams.get_mut(stream_id) else {
+        return;
+    };
+    if committed.trim().is_empty() || committed == stream.latest {
+        return;
+    }
+    stream.latest = committed.to_string();
+    if stream.in_flight {
+        return;
+    }
+    stream.in_flight = true;
+    let app_handle = app_handle.clone();
+    let stream_id = stream_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        translate_partials(app_handle, stream_id).await;
+    });
+}
+
+// Remember the full text of a finished decode for the final translation
+pub fn set_stream_final_text(app_handle: &tauri::AppHandle, stream_id: &str, text: &str) {
+    let state = app_handle.state::<StreamTranslateState>();
+    if let Ok(mut streams) = state.streams.lock() {
+        if let Some(stream) = streams.get_mut(stream_id) {
+            stream.final_text = text.trim().to_string();
+        }
+    }
+}
+
+// Translate the newest committed text until it stops changing. Results for a stream
+// that ended in the meantime are dropped, the final translation supersedes them.
+async fn translate_partials(app_handle: tauri::AppHandle, stream_id: String) {
+    let state = app_handle.state::<StreamTranslateState>();
+    let translate_state = app_handle.state::<TranslateAppState>();
+    loop {
+        let (text, source, target) = {
+            let Ok(mut streams) = state.streams.lock() else {
+                return;
+            };
+            let Some(stream) = streams.get_mut(&stream_id) else {
+                return;
+            };
+            if stream.latest == stream.translated {
+                stream.in_flight = false;
+                return;
+            }
+            stream.translated = stream.latest.clone();
+            (
+                stream.latest.clone(),
+                stream.source.clone(),
+                stream.target.clone(),
+            )
+        };
+
+        let result = translate_transcript(
+            &app_handle,
+            &translate_state,
+            text.clone(),
+            &source,
+            &target,
+            None,
+        )
+        .await;
+        let open = state
+            .streams
+            .lock()
+            .is_ok_and(|streams| streams.contains_key(&stream_id));
+        match result {
+            Ok(translation) if open => {
+                let _ = app_handle.emit(
+                    "translation-stream",
+                    serde_json::json!({
+                        "stream_id": stream_id,
+                        "text": text,
+                        "translation": translation.text,
+                        "provider": translation.provider,
+                        "final": false
+                    }),
+                );
+            }
+            Ok(_) => return,
+            Err(e) => println!("Partial translation failed: {}", e),
+        }
+    }
+}
+
+// Stop translating a stream and translate its final text, replacing the partials
+pub fn finish_stream_translation(app_handle: &tauri::AppHandle, stream_id: &str) {
+    let state = app_handle.state::<StreamTranslateState>();
+    let Some(stream) = state
+        .streams
+        .lock()
+        .ok()
+        .and_then(|mut streams| streams.remove(stream_id))
+    else {
+        return;
+    };
+    let StreamTranslation {
+        source,
+        target,
+        latest,
+        final_text,
+        ..
+    } = stream;
+    let text = if final_text.is_empty() {
+        latest
+    } else {
+        final_text
+    };
+    if text.is_empty() {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    let stream_id = stream_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let translate_state = app_handle.state::<TranslateAppState>();
+        match translate_transcript(
+            &app_handle,
+            &translate_state,
+            text.clone(),
+            &source,
+            &target,
+            None,
+        )
+        .await
+        {
+            Ok(translation) => {
+                let _ = app_handle.emit(
+                    "translation-stream",
+                    serde_json::json!({
+                        "stream_id": stream_id,
+                        "text": text,
+                        "translation": translation.text,
+                        "provider": translation.provider,
+                        "final": true
+                    }),
+                );
+            }
+            Err(e) => println!("Final stream translation failed: {}", e),
+        }
+    });
+}
@@ -0,0 +1,286 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, State};
+
+use crate::audio::LOOPBACK_AUDIO_SOURCE;
+use crate::debug_recording::{record_samples, record_segment};
+use crate::hotword::{GateOutcome, HotwordConfig, HotwordGate};
+use crate::onnx_stt::{transcribe_onnx, OnnxSttConfig};
+use crate::openai_stt::{encode_wav, transcribe_openai, OpenAiSttConfig};
+use crate::server_stt::{transcribe_server, WhisperServerConfig};
+use crate::whisper::{
+    postprocess, register_job, transcribe_audio, transcribe_captured, TranscribeOptions,
+    TranscriptionResult, WhisperAppState,
+};
+
+// Audio handed to a provider: an encoded upload from the webview, or 16kHz mono PCM
+// from native capture, which is only encoded for providers that need a file
+pub enum SttAudio {
+    Encoded(Vec<u8>),
+    Pcm(Vec<f32>),
+}
+
+impl SttAudio {
+    fn encoded(self) -> Result<Vec<u8>, String> {
+        match self {
+            SttAudio::Encoded(audio_data) => Ok(audio_data),
+            SttAudio::Pcm(samples) => encode_wav(&samples),
+        }
+    }
+}
+
+// Where speech gets transcribed. Local runs the downloaded whisper.cpp models;
+// remote providers offload inference for machines too slow to run them.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SttProvider {
+    #[default]
+    Local,
+    #[serde(rename = "openai")]
+    OpenAi(OpenAiSttConfig),
+    // Self-hosted whisper.cpp or faster-whisper server, typically on the LAN
+    WhisperServer(WhisperServerConfig),
+    // Whisper exported to ONNX, run locally on ONNX Runtime (DirectML for AMD/Intel GPUs)
+    Onnx(OnnxSttConfig),
+}
+
+impl SttProvider {
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            SttProvider::Local => Ok(()),
+            SttProvider::OpenAi(config) => config.validate(),
+            SttProvider::WhisperServer(config) => config.validate(),
+            SttProvider::Onnx(config) => config.validate(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            SttProvider::Local => "local",
+            SttProvider::OpenAi(_) => "openai",
+            SttProvider::WhisperServer(_) => "whisper_server",
+            SttProvider::Onnx(_) => "onnx",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SttAppState {
+    provider: Arc<Mutex<SttProvider>>,
+    hotword: Arc<Mutex<HotwordGate>>,
+}
+
+#[tauri::command]
+pub fn stt_get_provider(state: State<'_, SttAppState>) -> Result<SttProvider, String> {
+    Ok(state
+        .provider
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+#[tauri::command]
+pub fn stt_set_provider(
+    state: State<'_, SttAppState>,
+    provider: SttProvider,
+) -> Result<(), String> {
+    provider.validate()?;
+    println!("Speech-to-text provider set to {}", provider.name());
+    *state
+        .provider
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = provider;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stt_get_hotword(state: State<'_, SttAppState>) -> Result<HotwordConfig, String> {
+    Ok(state
+        .hotword
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .config
+        .clone())
+}
+
+#[tauri::command]
+pub fn stt_set_hotword(state: State<'_, SttAppState>, config: HotwordConfig) -> Result<(), String> {
+    config.validate()?;
+    println!(
+        "Wake-word gating {} ({:?})",
+        if config.enabled {
+            "enabled"
+        } else {
+            "disabled"
+        },
+        config.phrases
+    );
+    state
+        .hotword
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .set_config(config);
+    Ok(())
+}
+
+// Run a finished transcript through the wake-word gate, emitting `hotword-detected`
+// when the wake phrase arms transcription
+fn apply_hotword_gate(
+    app_handle: &tauri::AppHandle,
+    state: &SttAppState,
+    mut result: TranscriptionResult,
+) -> Result<TranscriptionResult, String> {
+    let outcome = state
+        .hotword
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .process(&result.text);
+
+    match outcome {
+        GateOutcome::Forward(text) => result.text = text,
+        GateOutcome::Armed { phrase, remainder } => {
+            println!("Wake phrase '{}' detected, transcription armed", phrase);
+            let _ = app_handle.emit("hotword-detected", serde_json::json!({ "phrase": phrase }));
+            result.text = remainder;
+            // Segments would still contain the wake phrase itself
+            result.segments.clear();
+        }
+        GateOutcome::Suppressed => {
+            println!("Transcript suppressed, waiting for wake phrase");
+            result.text.clear();
+            result.segments.clear();
+        }
+    }
+    Ok(result)
+}
+
+// Transcribe with whichever provider is configured. `model` only applies to the
+// local provider; remote providers use the model from their own config.
+pub async fn transcribe_with_provider(
+    app_handle: &tauri::AppHandle,
+    state: &SttAppState,
+    whisper_state: &WhisperAppState,
+    audio: SttAudio,
+    model: String,
+    language: &str,
+    mut options: TranscribeOptions,
+) -> Result<TranscriptionResult, String> {
+    let provider = state
+        .provider
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone();
+
+    // Only the user's own microphone is wake-word gated; other players saying the
+    // phrase in loopback audio must neither arm the gate nor get their captions muted
+    let gated = options.source.as_deref() != Some(LOOPBACK_AUDIO_SOURCE);
+
+    // While waiting for the wake phrase, listen with the cheaper detector model and
+    // bias Whisper towards spelling the phrase the way it is configured
+    let mut model = model;
+    if gated {
+        let gate = state
+            .hotword
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        if gate.config.enabled && !gate.is_armed() {
+            if let Some(detector) = gate.config.detector_model.clone() {
+                model = detector;
+            }
+            let phrases = gate.config.phrases.join(", ");
+            options.initial_prompt = Some(match options.initial_prompt.take() {
+                Some(prompt) => format!("{} {}", prompt, phrases),
+                None => phrases,
+            });
+        }
+    }
+    let options = options.into_inference_options(whisper_state, language)?;
+    match &audio {
+        SttAudio::Encoded(audio_data) => record_segment(app_handle, audio_data, &options.source),
+        SttAudio::Pcm(samples) => record_samples(app_handle, samples, &options.source),
+    }
+
+    // Local transcriptions are filtered during inference, remote ones once they return
+    let result = match provider {
+        SttProvider::Local => {
+            let job = register_job(app_handle, whisper_state, None)?;
+            match audio {
+                SttAudio::Encoded(audio_data) => {
+                    transcribe_audio(
+                        app_handle.clone(),
+                        whisper_state,
+                        audio_data,
+                        model,
+                        options,
+                        job,
+                    )
+                    .await?
+                }
+                SttAudio::Pcm(samples) => {
+                    transcribe_captured(
+                        app_handle.clone(),
+                        whisper_state,
+                        samples,
+                        model,
+                        options,
+                        job,
+                    )
+                    .await?
+                }
+            }
+        }
+        SttProvider::OpenAi(config) => {
+            println!("=== REMOTE TRANSCRIPTION START ({}) ===", config.base_url);
+            let audio_data = audio.encoded()?;
+            let mut result = transcribe_openai(&config, &audio_data, &options).await?;
+            postprocess(&options, &mut result);
+            result
+        }
+        SttProvider::WhisperServer(config) => {
+            println!(
+                "=== SERVER TRANSCRIPTION START ({:?} @ {}) ===",
+                config.protocol, config.address
+            );
+            let audio_data = audio.encoded()?;
+            let mut result = transcribe_server(&config, &audio_data, &options).await?;
+            postprocess(&options, &mut result);
+            result
+        }
+        SttProvider::Onnx(config) => {
+            println!(
+                "=== ONNX TRANSCRIPTION START ({:?} @ {}) ===",
+                config.device, config.model_dir
+            );
+            let audio_data = audio.encoded()?;
+            let mut result = transcribe_onnx(&config, &audio_data, &options).await?;
+            postprocess(&options, &mut result);
+            result
+        }
+    };
+    if !gated {
+        return Ok(result);
+    }
+    apply_hotword_gate(app_handle, state, result)
+}
+
+#[tauri::command]
+pub async fn stt_transcribe(
+    app_handle: tauri::AppHandle,
+    state: State<'_, SttAppState>,
+    whisper_state: State<'_, WhisperAppState>,
+    audio_data: Vec<u8>,
+    model: String,
+    language: String,
+    options: Option<TranscribeOptions>,
+) -> Result<TranscriptionResult, String> {
+    transcribe_with_provider(
+        &app_handle,
+        &state,
+        &whisper_state,
+        SttAudio::Encoded(audio_data),
+        model,
+        &language,
+        options.unwrap_or_default(),
+    )
+    .await
+}
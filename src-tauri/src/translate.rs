@@ -0,0 +1,561 @@
+// Backend translation of transcripts. Each service implements `TranslationProvider`;
+// the one used is picked in settings (or per call), so requests go out from here
+// instead of ad-hoc fetches in the webview.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, State};
+
+use crate::azure_translate::{AzureTranslatorConfig, AZURE_FREE_CHARACTERS};
+use crate::deepl_translate::{deepl_usage, DeepLConfig, DeepLUsage, DEEPL_FREE_CHARACTERS};
+use crate::glossary::Glossary;
+use crate::google_translate::GoogleTranslate;
+use crate::language_id::resolve_source;
+use crate::libre_translate::LibreTranslateConfig;
+use crate::llm_translate::LlmTranslateConfig;
+use crate::local_translate::{LocalTranslateConfig, LocalTranslator};
+use crate::translation_cache::{save_translation_cache, TranslationCache};
+use crate::translation_usage::{record_translation_usage, TranslationUsage};
+
+// Source language that lets the provider detect it
+pub const AUTO_LANGUAGE: &str = "auto";
+// Language pair pattern matching any language
+const ANY_LANGUAGE: &str = "*";
+// How long a throttled provider is passed over while fallbacks are available
+const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+pub enum TranslateError {
+    // Settings the provider can't work with, e.g. a missing API key
+    Config(String),
+    // The service couldn't be reached, didn't answer in time or had a server error
+    Unavailable(String),
+    // The service is throttling us (HTTP 429 and the like)
+    RateLimited(String),
+    // The service answered, but with an error or something unreadable
+    Failed(String),
+}
+
+impl TranslateError {
+    // Errors another provider may not have, worth falling back on
+    fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            TranslateError::Unavailable(_) | TranslateError::RateLimited(_)
+        )
+    }
+}
+
+impl fmt::Display for TranslateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranslateError::Config(message) => {
+                write!(f, "Translation is misconfigured: {}", message)
+            }
+            TranslateError::Unavailable(message) => {
+                write!(f, "Translation service unavailable: {}", message)
+            }
+            TranslateError::RateLimited(message) => {
+                write!(f, "Translation rate limited: {}", message)
+            }
+            TranslateError::Failed(message) => write!(f, "Translation failed: {}", message),
+        }
+    }
+}
+
+pub trait TranslationProvider {
+    fn name(&self) -> &'static str;
+
+    // Translate each text from `source` (or `AUTO_LANGUAGE`) to `target`, returning
+    // one translation per text, in order
+    async fn translate(
+        &self,
+        texts: &[String],
+        source: &str,
+        target: &str,
+    ) -> Result<Vec<String>, TranslateError>;
+}
+
+// Which service translates. Settings for a provider live in its variant.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranslatorConfig {
+    // Free web endpoint, no key needed
+    #[default]
+    Google,
+    #[serde(rename = "deepl")]
+    DeepL(DeepLConfig),
+    // Self-hosted instance
+    #[serde(rename = "libretranslate")]
+    LibreTranslate(LibreTranslateConfig),
+    // Offline NLLB model, see `translate_download_model`
+    Local(LocalTranslateConfig),
+    Azure(AzureTranslatorConfig),
+    // Chat model behind an OpenAI-compatible endpoint, with a custom prompt
+    Llm(LlmTranslateConfig),
+}
+
+impl TranslatorConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            TranslatorConfig::Google => Ok(()),
+            TranslatorConfig::DeepL(config) => config.validate(),
+            TranslatorConfig::LibreTranslate(config) => config.validate(),
+            TranslatorConfig::Local(config) => config.validate(),
+            TranslatorConfig::Azure(config) => config.validate(),
+            TranslatorConfig::Llm(config) => config.validate(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TranslatorConfig::Google => GoogleTranslate.name(),
+            TranslatorConfig::DeepL(config) => config.name(),
+            TranslatorConfig::LibreTranslate(config) => config.name(),
+            TranslatorConfig::Local(_) => "local",
+            TranslatorConfig::Azure(config) => config.name(),
+            TranslatorConfig::Llm(config) => config.name(),
+        }
+    }
+
+    // Identifies the provider and the settings that change its output, for caching
+    fn cache_key(&self) -> String {
+        match self {
+            TranslatorConfig::Google => self.name().to_string(),
+            TranslatorConfig::DeepL(config) => format!("deepl:{:?}", config.formality),
+            TranslatorConfig::LibreTranslate(config) => {
+                format!("libretranslate:{}", config.url.trim())
+            }
+            TranslatorConfig::Local(config) => format!("local:{}", config.model),
+            TranslatorConfig::Azure(config) => {
+                format!("azure:{}", config.category().unwrap_or_default())
+            }
+            TranslatorConfig::Llm(config) => config.cache_key(),
+        }
+    }
+
+    // Characters per month the provider's free tier allows
+    fn free_tier_limit(&self) -> Option<u64> {
+        match self {
+            TranslatorConfig::DeepL(config) if config.is_free() => Some(DEEPL_FREE_CHARACTERS),
+            TranslatorConfig::Azure(config) if config.free_tier => Some(AZURE_FREE_CHARACTERS),
+            _ => None,
+        }
+    }
+}
+
+// Provider used for one language pair instead of the default, e.g. DeepL for
+// Japanese to English only
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LanguagePairProvider {
+    // Language codes, or "*" for any
+    pub source: String,
+    pub target: String,
+    pub provider: TranslatorConfig,
+}
+
+impl LanguagePairProvider {
+    fn matches(&self, source: &str, target: &str) -> bool {
+        let matches = |pattern: &str, code: &str| {
+            pattern == ANY_LANGUAGE || language_base(pattern) == language_base(code)
+        };
+        matches(&self.source, source) && matches(&self.target, target)
+    }
+}
+
+#[derive(Default)]
+pub struct TranslateAppState {
+    pub provider: Mutex<TranslatorConfig>,
+    // Checked in order, the first matching pair wins
+    pairs: Mutex<Vec<LanguagePairProvider>>,
+    // Tried in order when the chosen provider is throttled or unreachable
+    fallbacks: Mutex<Vec<TranslatorConfig>>,
+    // Rate-limited providers (by cache key) and when they were throttled
+    rate_limited: Mutex<HashMap<String, Instant>>,
+    pub cache: Mutex<TranslationCache>,
+    pub glossary: Mutex<Glossary>,
+    pub usage: Mutex<TranslationUsage>,
+}
+
+impl TranslateAppState {
+    // Provider configured for a language pair, falling back to the default one
+    fn provider_for(&self, source: &str, target: &str) -> Result<TranslatorConfig, String> {
+        let pairs = self
+            .pairs
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        if let Some(pair) = pairs.iter().find(|pair| pair.matches(source, target)) {
+            return Ok(pair.provider.clone());
+        }
+        Ok(self
+            .provider
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+            .clone())
+    }
+
+    // The provider for a language pair followed by the fallbacks, without repeats
+    fn provider_chain(&self, source: &str, target: &str) -> Result<Vec<TranslatorConfig>, String> {
+        let mut chain = vec![self.provider_for(source, target)?];
+        let fallbacks = self
+            .fallbacks
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        for fallback in fallbacks.iter() {
+            if !chain.iter().any(|c| c.cache_key() == fallback.cache_key()) {
+                chain.push(fallback.clone());
+            }
+        }
+        Ok(chain)
+    }
+
+    fn is_rate_limited(&self, config: &TranslatorConfig) -> bool {
+        self.rate_limited
+            .lock()
+            .map(|limited| {
+                limited
+                    .get(&config.cache_key())
+                    .is_some_and(|since| since.elapsed() < RATE_LIMIT_COOLDOWN)
+            })
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Translation {
+    pub text: String,
+    // Provider that produced the text, empty when nothing needed translating
+    pub provider: String,
+    // Answered from the translation cache
+    pub cached: bool,
+    // Language translated from, identified from the text when the source was "auto"
+    pub source: String,
+}
+
+// "en-US" and "en" are the same language, "zh-CN" and "zh-TW" are not
+fn language_base(code: &str) -> String {
+    let code = code.to_lowercase();
+    match code.split_once('-') {
+        Some(("zh", _)) => code,
+        Some((base, _)) => base.to_string(),
+        None => code,
+    }
+}
+
+fn same_language(source: &str, target: &str) -> bool {
+    source != AUTO_LANGUAGE && language_base(source) == language_base(target)
+}
+
+// Translate texts with the given provider
+pub async fn translate_with(
+    app_handle: &tauri::AppHandle,
+    config: &TranslatorConfig,
+    texts: &[String],
+    source: &str,
+    target: &str,
+) -> Result<Vec<String>, TranslateError> {
+    match config {
+        TranslatorConfig::Google => GoogleTranslate.translate(texts, source, target).await,
+        TranslatorConfig::DeepL(config) => config.translate(texts, source, target).await,
+        TranslatorConfig::LibreTranslate(config) => config.translate(texts, source, target).await,
+        TranslatorConfig::Local(config) => {
+            LocalTranslator { app_handle, config }
+                .translate(texts, source, target)
+                .await
+        }
+        TranslatorConfig::Azure(config) => config.translate(texts, source, target).await,
+        TranslatorConfig::Llm(config) => config.translate(texts, source, target).await,
+    }
+}
+
+#[tauri::command]
+pub fn translate_get_provider(
+    state: State<'_, TranslateAppState>,
+) -> Result<TranslatorConfig, String> {
+    Ok(state
+        .provider
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+#[tauri::command]
+pub fn translate_set_provider(
+    state: State<'_, TranslateAppState>,
+    provider: TranslatorConfig,
+) -> Result<(), String> {
+    provider.validate()?;
+    println!("Translation provider set to {}", provider.name());
+    *state
+        .provider
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = provider;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn translate_get_pair_providers(
+    state: State<'_, TranslateAppState>,
+) -> Result<Vec<LanguagePairProvider>, String> {
+    Ok(state
+        .pairs
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+#[tauri::command]
+pub fn translate_set_pair_providers(
+    state: State<'_, TranslateAppState>,
+    pairs: Vec<LanguagePairProvider>,
+) -> Result<(), String> {
+    for pair in &pairs {
+        pair.provider.validate()?;
+    }
+    println!("{} language pair translation override(s) set", pairs.len());
+    *state
+        .pairs
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = pairs;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn translate_get_fallback_providers(
+    state: State<'_, TranslateAppState>,
+) -> Result<Vec<TranslatorConfig>, String> {
+    Ok(state
+        .fallbacks
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+#[tauri::command]
+pub fn translate_set_fallback_providers(
+    state: State<'_, TranslateAppState>,
+    providers: Vec<TranslatorConfig>,
+) -> Result<(), String> {
+    for provider in &providers {
+        provider.validate()?;
+    }
+    println!(
+        "Translation fallbacks: {}",
+        providers
+            .iter()
+            .map(|p| p.name())
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    );
+    *state
+        .fallbacks
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = providers;
+    state
+        .rate_limited
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clear();
+    Ok(())
+}
+
+// DeepL characters used this billing period, for `config` or the DeepL provider
+// in use by default
+#[tauri::command]
+pub async fn translate_deepl_usage(
+    state: State<'_, TranslateAppState>,
+    config: Option<DeepLConfig>,
+) -> Result<DeepLUsage, String> {
+    let config = match config {
+        Some(config) => config,
+        None => {
+            let provider = state
+                .provider
+                .lock()
+                .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+                .clone();
+            match provider {
+                TranslatorConfig::DeepL(config) => config,
+                _ => return Err("DeepL is not the selected translation provider".to_string()),
+            }
+        }
+    };
+    deepl_usage(&config).await.map_err(|e| e.to_string())
+}
+
+// Translate a transcript with the provider set for its language pair (or the
+// default one), or `provider` when given
+#[tauri::command]
+pub async fn translate_text(
+    app_handle: tauri::AppHandle,
+    state: State<'_, TranslateAppState>,
+    text: String,
+    source: String,
+    target: String,
+    provider: Option<TranslatorConfig>,
+) -> Result<Translation, String> {
+    translate_transcript(&app_handle, &state, text, &source, &target, provider).await
+}
+
+// `translate_text` for callers in the backend
+pub async fn translate_transcript(
+    app_handle: &tauri::AppHandle,
+    state: &TranslateAppState,
+    text: String,
+    source: &str,
+    target: &str,
+    provider: Option<TranslatorConfig>,
+) -> Result<Translation, String> {
+    let source = resolve_source(&text, source);
+    if text.trim().is_empty() || same_language(&source, target) {
+        return Ok(Translation {
+            text,
+            provider: String::new(),
+            cached: false,
+            source,
+        });
+    }
+    let chain = match provider {
+        Some(provider) => {
+            provider.validate()?;
+            vec![provider]
+        }
+        None => state.provider_chain(&source, target)?,
+    };
+
+    let mut last_error = None;
+    for (index, config) in chain.iter().enumerate() {
+        let next = chain.get(index + 1);
+        if next.is_some() && state.is_rate_limited(config) {
+            println!("Skipping {}, still rate limited", config.name());
+            continue;
+        }
+        match translate_one(app_handle, state, config, &text, &source, target).await {
+            Ok(translation) => return Ok(translation),
+            Err(e) if e.is_transient() => {
+                if let TranslateError::RateLimited(_) = e {
+                    if let Ok(mut limited) = state.rate_limited.lock() {
+                        limited.insert(config.cache_key(), Instant::now());
+                    }
+                }
+                if let Some(next) = next {
+                    println!(
+                        "{} failed ({}), falling back to {}",
+                        config.name(),
+                        e,
+                        next.name()
+                    );
+                    let _ = app_handle.emit(
+                        "translation-fallback",
+                        serde_json::json!({
+                            "from": config.name(),
+                            "to": next.name(),
+                            "error": e.to_string()
+                        }),
+                    );
+                }
+                last_error = Some(e);
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Err(last_error
+        .map(|e| e.to_string())
+        .unwrap_or_else(|| "No translation provider available".to_string()))
+}
+
+// Translate with one provider, answering from and filling the cache
+async fn translate_one(
+    app_handle: &tauri::AppHandle,
+    state: &TranslateAppState,
+    config: &TranslatorConfig,
+    text: &str,
+    source: &str,
+    target: &str,
+) -> Result<Translation, TranslateError> {
+    let cache_key = config.cache_key();
+    let cached = state
+        .cache
+        .lock()
+        .map_err(|e| TranslateError::Failed(format!("Mutex poisoned: {:?}", e)))?
+        .get(&cache_key, source, target, text);
+    if let Some(translated) = cached {
+        println!("Translated {} -> {} from cache", source, target);
+        return Ok(Translation {
+            text: translated,
+            provider: config.name().to_string(),
+            cached: true,
+            source: source.to_string(),
+        });
+    }
+
+    let protected = state
+        .glossary
+        .lock()
+        .map_err(|e| TranslateError::Failed(format!("Mutex poisoned: {:?}", e)))?
+        .protect(text, target);
+    let started = Instant::now();
+    let characters = protected.text.chars().count() as u64;
+    let translated = translate_with(
+        app_handle,
+        config,
+        &[protected.text.clone()],
+        source,
+        target,
+    )
+    .await?
+    .into_iter()
+    .next()
+    .ok_or_else(|| TranslateError::Failed("Translation returned no text".to_string()))?;
+    let translated = if protected.is_empty() {
+        translated
+    } else {
+        protected.restore(&translated)
+    };
+    println!(
+        "Translated {} -> {} with {} in {}ms",
+        source,
+        target,
+        config.name(),
+        started.elapsed().as_millis()
+    );
+    record_translation_usage(
+        app_handle,
+        config.name(),
+        characters,
+        config.free_tier_limit(),
+    );
+
+    let mut cache = state
+        .cache
+        .lock()
+        .map_err(|e| TranslateError::Failed(format!("Mutex poisoned: {:?}", e)))?;
+    if cache.insert(&cache_key, source, target, text, &translated) {
+        save_translation_cache(app_handle, &cache);
+    }
+    Ok(Translation {
+        text: translated,
+        provider: config.name().to_string(),
+        cached: false,
+        source: source.to_string(),
+    })
+}
+
+// Forget all cached translations, returning how many there were
+#[tauri::command]
+pub fn translate_clear_cache(
+    app_handle: tauri::AppHandle,
+    state: State<'_, TranslateAppState>,
+) -> Result<usize, String> {
+    let mut cache = state
+        .cache
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    let cleared = cache.len();
+    cache.clear();
+    save_translation_cache(&app_handle, &cache);
+    println!("Cleared {} cached translation(s)", cleared);
+    Ok(cleared)
+}
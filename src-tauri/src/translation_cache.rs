@@ -0,0 +1,178 @@
+// Remembers recent translations keyed by (provider, source, target, text), so
+// phrases that come up again and again ("hello", "nice to meet you") are answered
+// instantly without spending API quota. Least recently used entries are evicted,
+// and the cache is saved to the app data directory so it survives restarts.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::translate::TranslateAppState;
+
+const TRANSLATION_CACHE_FILE: &str = "translation_cache.json";
+const MAX_ENTRIES: usize = 2000;
+// Long texts are rarely repeated word for word and would bloat the file
+const MAX_CACHED_TEXT_LEN: usize = 200;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct CacheKey {
+    provider: String,
+    source: String,
+    target: String,
+    text: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheRecord {
+    #[serde(flatten)]
+    key: CacheKey,
+    translation: String,
+}
+
+#[derive(Default)]
+pub struct TranslationCache {
+    // Translation and when it was last used
+    entries: HashMap<CacheKey, (String, u64)>,
+    clock: u64,
+}
+
+impl TranslationCache {
+    fn key(provider: &str, source: &str, target: &str, text: &str) -> CacheKey {
+        CacheKey {
+            provider: provider.to_string(),
+            source: source.to_lowercase(),
+            target: target.to_lowercase(),
+            text: text.trim().to_string(),
+        }
+    }
+
+    pub fn get(
+        &mut self,
+        provider: &str,
+        source: &str,
+        target: &str,
+        text: &str,
+    ) -> Option<String> {
+        self.clock += 1;
+        let clock = self.clock;
+        let (translation, last_used) = self
+            .entries
+            .get_mut(&Self::key(provider, source, target, text))?;
+        *last_used = clock;
+        Some(translation.clone())
+    }
+
+    // Returns whether the entry was stored
+    pub fn insert(
+        &mut self,
+        provider: &str,
+        source: &str,
+        target: &str,
+        text: &str,
+        translation: &str,
+    ) -> bool {
+        if text.trim().len() > MAX_CACHED_TEXT_LEN || translation.trim().is_empty() {
+            return false;
+        }
+        self.clock += 1;
+        self.entries.insert(
+            Self::key(provider, source, target, text),
+            (translation.to_string(), self.clock),
+        );
+        while self.entries.len() > MAX_ENTRIES {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => self.entries.remove(&key),
+                None => break,
+            };
+        }
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    // Entries from least to most recently used
+    fn records(&self) -> Vec<CacheRecord> {
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by_key(|(_, (_, last_used))| *last_used);
+        entries
+            .into_iter()
+            .map(|(key, (translation, _))| CacheRecord {
+                key: key.clone(),
+                translation: translation.clone(),
+            })
+            .collect()
+    }
+}
+
+fn cache_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join(TRANSLATION_CACHE_FILE))
+}
+
+// Restore the saved cache at startup; a missing file means an empty cache
+pub fn load_translation_cache(app_handle: &tauri::AppHandle) {
+    let Ok(path) = cache_path(app_handle) else {
+        return;
+    };
+    let Ok(data) = fs::read_to_string(&path) else {
+        return;
+    };
+    match serde_json::from_str::<Vec<CacheRecord>>(&data) {
+        Ok(records) => {
+            let state = app_handle.state::<TranslateAppState>();
+            if let Ok(mut cache) = state.cache.lock() {
+                for record in records {
+                    let CacheKey {
+                        provider,
+                        source,
+                        target,
+                        text,
+                    } = record.key;
+                    cache.insert(&provider, &source, &target, &text, &record.translation);
+                }
+                println!("Loaded {} cached translation(s)", cache.len());
+            }
+        }
+        Err(e) => println!("Ignoring invalid {}: {}", TRANSLATION_CACHE_FILE, e),
+    }
+}
+
+// Write the cache out in the background
+pub fn save_translation_cache(app_handle: &tauri::AppHandle, cache: &TranslationCache) {
+    let records = cache.records();
+    let path = match cache_path(app_handle) {
+        Ok(path) => path,
+        Err(e) => {
+            println!("Translation cache not saved: {}", e);
+            return;
+        }
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = serde_json::to_string(&records)
+            .map_err(|e| e.to_string())
+            .and_then(|data| {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                fs::write(&path, data).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            println!("Failed to save translation cache: {}", e);
+        }
+    });
+}
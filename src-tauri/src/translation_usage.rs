@@ -0,0 +1,175 @@
+// Characters sent to each translation provider per calendar month, so users on free
+// tiers see how close they are to the limit before a provider starts refusing.
+// Saved to the app data directory; months are in UTC like the providers' billing.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{Emitter, Manager, State};
+
+use crate::translate::TranslateAppState;
+
+const TRANSLATION_USAGE_FILE: &str = "translation_usage.json";
+// Share of a free-tier limit that triggers a `translation-quota-warning`
+const WARNING_THRESHOLDS: [f64; 3] = [0.8, 0.9, 1.0];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProviderUsage {
+    // "2026-10"
+    pub month: String,
+    pub provider: String,
+    pub characters: u64,
+    pub requests: u64,
+    // Monthly free-tier allowance when the provider has one
+    #[serde(default)]
+    pub limit: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct TranslationUsage {
+    records: Vec<ProviderUsage>,
+}
+
+fn current_month() -> String {
+    chrono::Utc::now().format("%Y-%m").to_string()
+}
+
+impl TranslationUsage {
+    // Add a request's characters to this month's total, returning the warning
+    // threshold it crossed, if any, and the new total
+    fn record(
+        &mut self,
+        provider: &str,
+        characters: u64,
+        limit: Option<u64>,
+    ) -> Option<(f64, u64)> {
+        let month = current_month();
+        let index = match self
+            .records
+            .iter()
+            .position(|r| r.month == month && r.provider == provider)
+        {
+            Some(index) => index,
+            None => {
+                self.records.push(ProviderUsage {
+                    month,
+                    provider: provider.to_string(),
+                    characters: 0,
+                    requests: 0,
+                    limit,
+                });
+                self.records.len() - 1
+            }
+        };
+        let usage = &mut self.records[index];
+        let before = usage.characters;
+        usage.characters += characters;
+        usage.requests += 1;
+        usage.limit = limit;
+
+        let limit = limit? as f64;
+        WARNING_THRESHOLDS
+            .iter()
+            .rev()
+            .find(|&&threshold| {
+                (before as f64) < limit * threshold && usage.characters as f64 >= limit * threshold
+            })
+            .map(|&threshold| (threshold, usage.characters))
+    }
+}
+
+fn usage_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data.join(TRANSLATION_USAGE_FILE))
+}
+
+// Restore the saved usage at startup; a missing file means nothing was translated yet
+pub fn load_translation_usage(app_handle: &tauri::AppHandle) {
+    let Ok(path) = usage_path(app_handle) else {
+        return;
+    };
+    let Ok(data) = fs::read_to_string(&path) else {
+        return;
+    };
+    match serde_json::from_str::<Vec<ProviderUsage>>(&data) {
+        Ok(records) => {
+            let state = app_handle.state::<TranslateAppState>();
+            if let Ok(mut usage) = state.usage.lock() {
+                usage.records = records;
+            }
+        }
+        Err(e) => println!("Ignoring invalid {}: {}", TRANSLATION_USAGE_FILE, e),
+    }
+}
+
+fn save_translation_usage(app_handle: &tauri::AppHandle, usage: &TranslationUsage) {
+    let records = usage.records.clone();
+    let path = match usage_path(app_handle) {
+        Ok(path) => path,
+        Err(e) => {
+            println!("Translation usage not saved: {}", e);
+            return;
+        }
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = serde_json::to_string_pretty(&records)
+            .map_err(|e| e.to_string())
+            .and_then(|data| {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                fs::write(&path, data).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            println!("Failed to save translation usage: {}", e);
+        }
+    });
+}
+
+// Count characters sent to `provider`, warning when a free-tier limit gets close
+pub fn record_translation_usage(
+    app_handle: &tauri::AppHandle,
+    provider: &str,
+    characters: u64,
+    limit: Option<u64>,
+) {
+    let state = app_handle.state::<TranslateAppState>();
+    let Ok(mut usage) = state.usage.lock() else {
+        return;
+    };
+    let crossed = usage.record(provider, characters, limit);
+    save_translation_usage(app_handle, &usage);
+
+    if let (Some((threshold, used)), Some(limit)) = (crossed, limit) {
+        println!(
+            "{} has used {} of {} free characters this month",
+            provider, used, limit
+        );
+        let _ = app_handle.emit(
+            "translation-quota-warning",
+            serde_json::json!({
+                "provider": provider,
+                "characters": used,
+                "limit": limit,
+                "percent": (threshold * 100.0).round() as u32
+            }),
+        );
+    }
+}
+
+// Characters and requests per provider and month, most recent month first
+#[tauri::command]
+pub fn get_translation_usage(
+    state: State<'_, TranslateAppState>,
+) -> Result<Vec<ProviderUsage>, String> {
+    let mut records = state
+        .usage
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .records
+        .clone();
+    records.sort_by(|a, b| b.month.cmp(&a.month).then(a.provider.cmp(&b.provider)));
+    Ok(records)
+}
@@ -0,0 +1,238 @@
+// Latin-script readings of Japanese (romaji), Chinese (pinyin) and Korean (Revised
+// Romanization), for language learners and people who can't read the script. Kanji
+// readings come from kakasi's dictionary, so rare names may be read wrong; the
+// original text is kept alongside by default.
+use pinyin::ToPinyin;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::chatbox::ChatboxAppState;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransliterationMode {
+    #[default]
+    Off,
+    // "こんにちは (konnichiha)"
+    Append,
+    // "konnichiha"
+    Replace,
+}
+
+// Languages with a transliteration
+const SUPPORTED_LANGUAGES: [&str; 3] = ["ja", "zh", "ko"];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transliteration {
+    pub mode: TransliterationMode,
+    // Languages to transliterate, as base codes ("ja", "zh", "ko")
+    pub languages: Vec<String>,
+}
+
+impl Default for Transliteration {
+    fn default() -> Self {
+        Self {
+            mode: TransliterationMode::default(),
+            languages: vec!["ja".to_string()],
+        }
+    }
+}
+
+impl Transliteration {
+    pub fn validate(&self) -> Result<(), String> {
+        for language in &self.languages {
+            if !SUPPORTED_LANGUAGES.contains(&language.as_str()) {
+                return Err(format!("No transliteration for '{}'", language));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn language_base(language: &str) -> String {
+    language
+        .split('-')
+        .next()
+        .unwrap_or(language)
+        .to_lowercase()
+}
+
+fn romaji(text: &str) -> Option<String> {
+    if matches!(kakasi::is_japanese(text), kakasi::IsJapanese::False) {
+        return None;
+    }
+    Some(kakasi::convert(text).romaji)
+}
+
+// Chinese punctuation that should read as its ASCII counterpart among pinyin
+fn ascii_punctuation(c: char) -> Option<&'static str> {
+    match c {
+        '，' | '、' => Some(", "),
+        '。' => Some(". "),
+        '？' => Some("? "),
+        '！' => Some("! "),
+        '：' => Some(": "),
+        '；' => Some("; "),
+        _ => None,
+    }
+}
+
+// Tone-marked pinyin, one syllable per character ("nǐ hǎo")
+fn pinyin(text: &str) -> Option<String> {
+    let mut output = String::new();
+    let mut found = false;
+    for c in text.chars() {
+        if let Some(syllable) = c.to_pinyin() {
+            output.push(' ');
+            output.push_str(syllable.with_tone());
+            output.push(' ');
+            found = true;
+        } else if let Some(punctuation) = ascii_punctuation(c) {
+            output.push_str(punctuation);
+        } else {
+            output.push(c);
+        }
+    }
+    found.then_some(output)
+}
+
+const HANGUL_START: u32 = 0xAC00;
+const HANGUL_END: u32 = 0xD7A3;
+const INITIALS: [&str; 19] = [
+    "g", "kk", "n", "d", "tt", "r", "m", "b", "pp", "s", "ss", "", "j", "jj", "ch", "k", "t", "p",
+    "h",
+];
+const MEDIALS: [&str; 21] = [
+    "a", "ae", "ya", "yae", "eo", "e", "yeo", "ye", "o", "wa", "wae", "oe", "yo", "u", "wo", "we",
+    "wi", "yu", "eu", "ui", "i",
+];
+// Final consonant before a consonant or a pause, and when carried over to a
+// following vowel ("한국어" is "hangugeo", not "hangukeo")
+const FINALS: [(&str, &str); 28] = [
+    ("", ""),
+    ("k", "g"),
+    ("k", "kk"),
+    ("k", "ks"),
+    ("n", "n"),
+    ("n", "nj"),
+    ("n", "n"),
+    ("t", "d"),
+    ("l", "r"),
+    ("k", "lg"),
+    ("m", "lm"),
+    ("l", "lb"),
+    ("l", "ls"),
+    ("l", "lt"),
+    ("p", "lp"),
+    ("l", "r"),
+    ("m", "m"),
+    ("p", "b"),
+    ("p", "ps"),
+    ("t", "s"),
+    ("t", "ss"),
+    ("ng", "ng"),
+    ("t", "j"),
+    ("t", "ch"),
+    ("k", "k"),
+    ("t", "t"),
+    ("p", "p"),
+    ("t", ""),
+];
+// Indices of ㄴ, ㄹ, ㅁ and the silent ㅇ among the initials
+const NIEUN_INITIAL: usize = 2;
+const RIEUL_INITIAL: usize = 5;
+const MIEUM_INITIAL: usize = 6;
+const SILENT_INITIAL: usize = 11;
+
+// (initial, medial, final) indices of a Hangul syllable
+fn hangul_jamo(c: char) -> Option<(usize, usize, usize)> {
+    let code = c as u32;
+    if !(HANGUL_START..=HANGUL_END).contains(&code) {
+        return None;
+    }
+    let index = (code - HANGUL_START) as usize;
+    Some((index / 588, (index % 588) / 28, index % 28))
+}
+
+// Revised Romanization with consonant carry-over between syllables of a word
+fn romaja(text: &str) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::new();
+    let mut found = false;
+    for (i, &c) in chars.iter().enumerate() {
+        let Some((initial, medial, final_)) = hangul_jamo(c) else {
+            output.push(c);
+            continue;
+        };
+        found = true;
+        let previous_final = i
+            .checked_sub(1)
+            .and_then(|p| hangul_jamo(chars[p]))
+            .map(|(_, _, f)| f)
+            .unwrap_or(0);
+        // "몰라" is "molla", not "molra"
+        if initial == RIEUL_INITIAL && FINALS[previous_final].0 == "l" {
+            output.push('l');
+        } else {
+            output.push_str(INITIALS[initial]);
+        }
+        output.push_str(MEDIALS[medial]);
+
+        let (coda, carried) = FINALS[final_];
+        let next_initial = chars
+            .get(i + 1)
+            .and_then(|&n| hangul_jamo(n))
+            .map(|(n, _, _)| n);
+        let coda = match (coda, next_initial) {
+            (_, Some(SILENT_INITIAL)) => carried,
+            // Stops become nasal before ㄴ and ㅁ ("감사합니다" is "gamsahamnida")
+            ("k", Some(NIEUN_INITIAL | MIEUM_INITIAL)) => "ng",
+            ("t", Some(NIEUN_INITIAL | MIEUM_INITIAL)) => "n",
+            ("p", Some(NIEUN_INITIAL | MIEUM_INITIAL)) => "m",
+            _ => coda,
+        };
+        output.push_str(coda);
+    }
+    found.then_some(output)
+}
+
+// Latin-script reading of text in `language`, None when there is nothing to transliterate
+pub fn romanize(text: &str, language: &str) -> Option<String> {
+    let romanized = match language_base(language).as_str() {
+        "ja" => romaji(text),
+        "zh" => pinyin(text),
+        "ko" => romaja(text),
+        _ => None,
+    }?;
+    let romanized = romanized.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!romanized.is_empty() && romanized != text).then_some(romanized)
+}
+
+impl Transliteration {
+    pub fn apply(&self, text: &str, language: &str) -> String {
+        let base = language_base(language);
+        if self.mode == TransliterationMode::Off || !self.languages.contains(&base) {
+            return text.to_string();
+        }
+        match romanize(text, language) {
+            Some(romaji) if self.mode == TransliterationMode::Replace => romaji,
+            Some(romaji) => format!("{} ({})", text, romaji),
+            None => text.to_string(),
+        }
+    }
+}
+
+// Transliterate text with the chatbox settings, e.g. for showing incoming speech
+#[tauri::command]
+pub fn transliterate_text(
+    state: State<'_, ChatboxAppState>,
+    text: String,
+    language: String,
+) -> Result<String, String> {
+    let format = state
+        .format
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    Ok(format.transliteration.apply(&text, &language))
+}
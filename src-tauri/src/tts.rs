@@ -0,0 +1,867 @@
+// Text-to-speech for users who don't talk: typed or translated text is synthesized and
+// played on a chosen output device. Pointing that at a virtual cable (VB-Cable and the
+// like) whose other end is VRChat's microphone makes the voice heard in game. Speech
+// only meant for the user (headphones) is routed to a device of its own, and the
+// microphone capture can be ducked or paused while anything is spoken so the
+// synthetic voice isn't transcribed back. Speech on the virtual mic can also move the
+// avatar's mouth over OSC (see `visemes`). Utterances wait in a queue per route and
+// are spoken one after another, by priority, so quick typing doesn't talk over itself.
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SizedSample};
+use rubato::{FftFixedIn, Resampler as _};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager, State};
+
+use crate::audio::find_output_device;
+use crate::openai_tts::{synthesize_openai, OpenAiTtsConfig};
+use crate::piper_tts::{piper_voice_available, synthesize_piper, PiperConfig};
+use crate::pronunciation::{
+    parse_speech, plain_runs, to_ssml, PronunciationDictionary, SpeechPart,
+};
+use crate::sapi_tts::{synthesize_sapi, SapiConfig};
+use crate::visemes::{LipSync, VisemeSettings};
+
+// How often playback checks whether it finished or was stopped
+const PLAYBACK_POLL_INTERVAL: Duration = Duration::from_millis(20);
+// Played after the last sample so the device buffer drains before the stream closes
+const PLAYBACK_TAIL: Duration = Duration::from_millis(150);
+// Resampler input block, 10ms of synthesized audio
+const RESAMPLER_CHUNKS_PER_SECOND: u32 = 100;
+// Microphone gain while ducked, about -14dB
+const DEFAULT_DUCK_GAIN: f32 = 0.2;
+// Rate reported for speech that is nothing but pauses
+const SILENCE_SAMPLE_RATE: u32 = 16000;
+// Utterances waiting per route; beyond this the least important, oldest one is dropped
+// so speech never lags minutes behind the typing
+const MAX_QUEUED_UTTERANCES: usize = 8;
+
+// Which engine speaks
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TtsProvider {
+    #[serde(rename = "openai")]
+    OpenAi(OpenAiTtsConfig),
+    // Downloaded Piper voice, runs offline
+    Piper(PiperConfig),
+    // Voices installed in Windows, nothing to download
+    Sapi(SapiConfig),
+}
+
+impl TtsProvider {
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            TtsProvider::OpenAi(config) => config.validate(),
+            TtsProvider::Piper(config) => config.validate(),
+            TtsProvider::Sapi(config) => config.validate(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            TtsProvider::OpenAi(_) => "openai",
+            TtsProvider::Piper(_) => "piper",
+            TtsProvider::Sapi(_) => "sapi",
+        }
+    }
+
+    // Key of the voice in the pronunciation dictionary
+    fn voice(&self) -> &str {
+        match self {
+            TtsProvider::OpenAi(config) => config.voice(),
+            TtsProvider::Piper(config) => &config.voice,
+            TtsProvider::Sapi(config) => config.voice.as_deref().unwrap_or_default(),
+        }
+    }
+
+    // Whether the voice reads SSML itself, phonemes included
+    fn reads_ssml(&self) -> bool {
+        matches!(self, TtsProvider::Sapi(_))
+    }
+
+    // Mono samples and their sample rate
+    async fn synthesize(
+        &self,
+        app_handle: &tauri::AppHandle,
+        text: &str,
+    ) -> Result<(Vec<f32>, u32), String> {
+        match self {
+            TtsProvider::OpenAi(config) => synthesize_openai(config, text).await,
+            TtsProvider::Piper(config) => synthesize_piper(app_handle, config, text).await,
+            TtsProvider::Sapi(config) => synthesize_sapi(config, text, false).await,
+        }
+    }
+
+    // Speak parsed speech: as SSML where the voice reads it, otherwise one request per
+    // run of text with silence for the pauses in between
+    async fn synthesize_parts(
+        &self,
+        app_handle: &tauri::AppHandle,
+        parts: &[SpeechPart],
+    ) -> Result<(Vec<f32>, u32), String> {
+        if let TtsProvider::Sapi(config) = self {
+            return synthesize_sapi(config, &to_ssml(parts), true).await;
+        }
+        let mut pieces = Vec::new();
+        for run in plain_runs(parts) {
+            let audio = if run.text.trim().is_empty() {
+                None
+            } else {
+                Some(self.synthesize(app_handle, &run.text).await?)
+            };
+            pieces.push((audio, run.pause_ms));
+        }
+        let Some(sample_rate) = pieces
+            .iter()
+            .find_map(|(audio, _)| audio.as_ref().map(|(_, rate)| *rate))
+        else {
+            return Ok((Vec::new(), SILENCE_SAMPLE_RATE));
+        };
+        let mut samples = Vec::new();
+        for (audio, pause_ms) in pieces {
+            if let Some((piece, rate)) = audio {
+                samples.extend(resample(&piece, rate, sample_rate)?);
+            }
+            samples.resize(
+                samples.len() + (sample_rate as u64 * pause_ms as u64 / 1000) as usize,
+                0.0,
+            );
+        }
+        Ok((samples, sample_rate))
+    }
+}
+
+// Who the speech is for, which picks the output device
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TtsRoute {
+    // Heard in game, through the virtual cable feeding VRChat's microphone
+    #[default]
+    VirtualMic,
+    // Only the user hears it
+    Headphones,
+}
+
+impl TtsRoute {
+    fn label(self) -> &'static str {
+        match self {
+            TtsRoute::VirtualMic => "virtual_mic",
+            TtsRoute::Headphones => "headphones",
+        }
+    }
+}
+
+// What happens to microphone transcription while speech plays
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MicDucking {
+    #[default]
+    Off,
+    // Lower the microphone by `duck_gain`
+    Duck,
+    // Transcribe nothing until speech ends
+    Pause,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TtsSettings {
+    // None until a voice is set up
+    pub provider: Option<TtsProvider>,
+    // Output device name (see `tts_list_output_devices`) for the virtual mic route,
+    // None for the system default
+    pub output_device: Option<String>,
+    // Output device for the headphones route, None for the system default
+    pub headphones_device: Option<String>,
+    // Gain applied to the synthesized audio, 0.0 to 2.0
+    pub volume: f32,
+    pub mic_ducking: MicDucking,
+    // Microphone gain while ducked, 0.0 to 1.0
+    pub duck_gain: f32,
+    // Avatar lip-sync while speaking on the virtual mic
+    pub visemes: VisemeSettings,
+}
+
+impl Default for TtsSettings {
+    fn default() -> Self {
+        Self {
+            provider: None,
+            output_device: None,
+            headphones_device: None,
+            volume: 1.0,
+            mic_ducking: MicDucking::default(),
+            duck_gain: DEFAULT_DUCK_GAIN,
+            visemes: VisemeSettings::default(),
+        }
+    }
+}
+
+impl TtsSettings {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(provider) = &self.provider {
+            provider.validate()?;
+        }
+        if !(0.0..=2.0).contains(&self.volume) {
+            return Err(format!(
+                "TTS volume must be 0.0 to 2.0, got {}",
+                self.volume
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.duck_gain) {
+            return Err(format!(
+                "Ducked microphone gain must be 0.0 to 1.0, got {}",
+                self.duck_gain
+            ));
+        }
+        self.visemes.validate()?;
+        Ok(())
+    }
+
+    fn device(&self, route: TtsRoute) -> Option<String> {
+        match route {
+            TtsRoute::VirtualMic => self.output_device.clone(),
+            TtsRoute::Headphones => self.headphones_device.clone(),
+        }
+    }
+}
+
+// Order in the queue. Higher priorities are spoken first, equal ones in order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TtsPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    // Cuts off what is being spoken and clears the queue
+    Interrupt,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct QueuedUtterance {
+    pub id: String,
+    pub text: String,
+    pub route: TtsRoute,
+    pub priority: TtsPriority,
+    pub queued_at: i64,
+}
+
+#[derive(Default)]
+struct RouteQueue {
+    pending: VecDeque<QueuedUtterance>,
+    // A task is speaking from this queue
+    running: bool,
+}
+
+impl RouteQueue {
+    // Behind everything of the same or higher priority
+    fn insert(&mut self, utterance: QueuedUtterance) {
+        let index = self
+            .pending
+            .iter()
+            .position(|queued| queued.priority < utterance.priority)
+            .unwrap_or(self.pending.len());
+        self.pending.insert(index, utterance);
+    }
+
+    // Oldest utterance of the lowest priority, which sits at the start of the last run
+    fn evict_overflow(&mut self) -> Vec<QueuedUtterance> {
+        let mut dropped = Vec::new();
+        while self.pending.len() > MAX_QUEUED_UTTERANCES {
+            let Some(lowest) = self.pending.back().map(|queued| queued.priority) else {
+                break;
+            };
+            let index = self
+                .pending
+                .iter()
+                .position(|queued| queued.priority == lowest)
+                .unwrap_or(0);
+            dropped.extend(self.pending.remove(index));
+        }
+        dropped
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OutputDevice {
+    pub name: String,
+    pub is_default: bool,
+}
+
+#[derive(Default)]
+pub struct TtsAppState {
+    settings: Mutex<TtsSettings>,
+    // Stop flag of the utterance playing now on each route
+    playback: Mutex<HashMap<TtsRoute, Arc<AtomicBool>>>,
+    // Utterances playing right now, on any route
+    playing: AtomicUsize,
+    // Output devices those utterances are playing on
+    playing_devices: Mutex<Vec<String>>,
+    queues: Mutex<HashMap<TtsRoute, RouteQueue>>,
+    next_id: AtomicUsize,
+    pub pronunciations: Mutex<PronunciationDictionary>,
+}
+
+impl TtsAppState {
+    // How the microphone capture should treat its input right now: None while
+    // nothing is spoken or ducking is off, otherwise the gain to apply (0.0 pauses)
+    pub fn mic_duck_gain(&self) -> Option<f32> {
+        if self.playing.load(Ordering::SeqCst) == 0 {
+            return None;
+        }
+        let settings = self.settings.lock().ok()?;
+        match settings.mic_ducking {
+            MicDucking::Off => None,
+            MicDucking::Duck => Some(settings.duck_gain),
+            MicDucking::Pause => Some(0.0),
+        }
+    }
+
+    // Whether speech is playing on the output device `name` right now. Loopback capture
+    // of that device would otherwise hear it as another player (e.g. spoken captions on
+    // headphones that are also the default output).
+    pub fn is_playing_on(&self, name: &str) -> bool {
+        self.playing_devices
+            .lock()
+            .is_ok_and(|devices| devices.iter().any(|device| device == name))
+    }
+
+    // Cut off the utterance playing on `route`, or on every route
+    fn skip(&self, route: Option<TtsRoute>) -> Result<(), String> {
+        let mut playback = self
+            .playback
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        let stopped: Vec<Arc<AtomicBool>> = match route {
+            Some(route) => playback.remove(&route).into_iter().collect(),
+            None => playback.drain().map(|(_, stop)| stop).collect(),
+        };
+        for stop in stopped {
+            stop.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    // Drop the utterances waiting on `route`, or on every route
+    fn clear_queue(&self, route: Option<TtsRoute>) -> Result<Vec<QueuedUtterance>, String> {
+        let mut queues = self
+            .queues
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        Ok(queues
+            .iter_mut()
+            .filter(|(queue_route, _)| route.is_none_or(|route| route == **queue_route))
+            .flat_map(|(_, queue)| queue.pending.drain(..).collect::<Vec<_>>())
+            .collect())
+    }
+
+    fn queued(&self) -> Result<Vec<QueuedUtterance>, String> {
+        let queues = self
+            .queues
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        Ok(queues
+            .values()
+            .flat_map(|queue| queue.pending.iter().cloned())
+            .collect())
+    }
+}
+
+fn emit_queue(app_handle: &tauri::AppHandle, state: &TtsAppState) {
+    if let Ok(queued) = state.queued() {
+        let _ = app_handle.emit("tts-queue", &queued);
+    }
+}
+
+// Counts an utterance as playing, on `device` when known, until dropped
+struct PlayingGuard<'a> {
+    state: &'a TtsAppState,
+    device: Option<String>,
+}
+
+impl<'a> PlayingGuard<'a> {
+    fn new(state: &'a TtsAppState, device: Option<String>) -> Self {
+        state.playing.fetch_add(1, Ordering::SeqCst);
+        if let (Some(device), Ok(mut devices)) = (&device, state.playing_devices.lock()) {
+            devices.push(device.clone());
+        }
+        Self { state, device }
+    }
+}
+
+impl Drop for PlayingGuard<'_> {
+    fn drop(&mut self) {
+        self.state.playing.fetch_sub(1, Ordering::SeqCst);
+        if let (Some(device), Ok(mut devices)) = (&self.device, self.state.playing_devices.lock()) {
+            if let Some(index) = devices.iter().position(|d| d == device) {
+                devices.remove(index);
+            }
+        }
+    }
+}
+
+// Convert synthesized audio to the output device's rate
+fn resample(samples: &[f32], from: u32, to: u32) -> Result<Vec<f32>, String> {
+    if from == to || samples.is_empty() {
+        return Ok(samples.to_vec());
+    }
+    let chunk_len = (from / RESAMPLER_CHUNKS_PER_SECOND).max(1) as usize;
+    let mut resampler = FftFixedIn::<f32>::new(from as usize, to as usize, chunk_len, 1, 1)
+        .map_err(|e| format!("Failed to create resampler for {}Hz: {}", from, e))?;
+
+    let expected = (samples.len() as u64 * to as u64 / from as u64) as usize;
+    let mut output = Vec::with_capacity(expected + chunk_len);
+    // Pad the last chunk, and push one more so the resampler's delay is flushed
+    let mut input = samples.to_vec();
+    input.resize(input.len().div_ceil(chunk_len) * chunk_len + chunk_len, 0.0);
+    for chunk in input.chunks(chunk_len) {
+        let resampled = resampler
+            .process(&[chunk], None)
+            .map_err(|e| format!("Resampling failed: {}", e))?;
+        output.extend_from_slice(&resampled[0]);
+    }
+    let delay = resampler.output_delay();
+    Ok(output.into_iter().skip(delay).take(expected).collect())
+}
+
+// Open an output stream that plays `samples` on every channel from `position` on
+fn build_output_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: Arc<Vec<f32>>,
+    position: Arc<AtomicUsize>,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                for frame in data.chunks_mut(channels) {
+                    let index = position.fetch_add(1, Ordering::Relaxed);
+                    let sample = samples.get(index).copied().unwrap_or(0.0);
+                    for out in frame.iter_mut() {
+                        *out = T::from_sample(sample);
+                    }
+                }
+            },
+            |e| println!("TTS output stream error: {}", e),
+            None,
+        )
+        .map_err(|e| format!("Failed to open output stream: {}", e))
+}
+
+// Play mono audio on an output device, blocking until it finished or `stop` is raised,
+// and lip-sync the avatar to it with `visemes`.
+// Runs on a blocking thread: cpal streams aren't Send on every platform.
+fn play_blocking(
+    device_name: Option<&str>,
+    samples: &[f32],
+    sample_rate: u32,
+    stop: &AtomicBool,
+    visemes: Option<VisemeSettings>,
+) -> Result<(), String> {
+    let device = find_output_device(device_name)?;
+    let supported = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to get output config: {}", e))?;
+    let config: cpal::StreamConfig = supported.config();
+    let samples = Arc::new(resample(samples, sample_rate, config.sample_rate.0)?);
+    let position = Arc::new(AtomicUsize::new(0));
+
+    let stream = match supported.sample_format() {
+        cpal::SampleFormat::F32 => {
+            build_output_stream::<f32>(&device, &config, samples.clone(), position.clone())?
+        }
+        cpal::SampleFormat::I16 => {
+            build_output_stream::<i16>(&device, &config, samples.clone(), position.clone())?
+        }
+        cpal::SampleFormat::U16 => {
+            build_output_stream::<u16>(&device, &config, samples.clone(), position.clone())?
+        }
+        format => return Err(format!("Unsupported output sample format {:?}", format)),
+    };
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start playback: {}", e))?;
+    let mut lip_sync = visemes.and_then(|settings| {
+        LipSync::new(settings, samples.clone(), config.sample_rate.0)
+            .map_err(|e| println!("Lip-sync unavailable: {}", e))
+            .ok()
+    });
+    let mut stopped = false;
+    loop {
+        let played = position.load(Ordering::Relaxed);
+        if played >= samples.len() {
+            break;
+        }
+        if stop.load(Ordering::SeqCst) {
+            stopped = true;
+            break;
+        }
+        if let Some(lip_sync) = lip_sync.as_mut() {
+            lip_sync.update(played);
+        }
+        std::thread::sleep(PLAYBACK_POLL_INTERVAL);
+    }
+    if let Some(lip_sync) = lip_sync.as_mut() {
+        lip_sync.finish();
+    }
+    if !stopped {
+        std::thread::sleep(PLAYBACK_TAIL);
+    }
+    Ok(())
+}
+
+// Synthesize an utterance and play it on the device of its route, blocking until it
+// finished or was skipped
+async fn speak_utterance(
+    app_handle: &tauri::AppHandle,
+    state: &TtsAppState,
+    utterance: &QueuedUtterance,
+) -> Result<(), String> {
+    let text = utterance.text.as_str();
+    let route = utterance.route;
+    let settings = state
+        .settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone();
+    let provider = settings
+        .provider
+        .ok_or_else(|| "No text-to-speech voice is set up".to_string())?;
+    // On Windows, speak with the default system voice until a Piper voice is downloaded
+    let provider = match provider {
+        TtsProvider::Piper(config)
+            if cfg!(target_os = "windows") && !piper_voice_available(app_handle, &config.voice) =>
+        {
+            println!(
+                "Piper voice {} isn't available, using the Windows voice",
+                config.voice
+            );
+            TtsProvider::Sapi(SapiConfig::default())
+        }
+        provider => provider,
+    };
+
+    // Registered before synthesis so skipping also cancels an utterance still being
+    // synthesized
+    let stop = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = state
+        .playback
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .insert(route, stop.clone())
+    {
+        previous.store(true, Ordering::SeqCst);
+    }
+
+    let parts = match state.pronunciations.lock() {
+        Ok(dictionary) => {
+            dictionary.apply(parse_speech(text), provider.voice(), provider.reads_ssml())
+        }
+        Err(_) => parse_speech(text),
+    };
+    let (mut samples, sample_rate) = provider.synthesize_parts(app_handle, &parts).await?;
+    if stop.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    for sample in &mut samples {
+        *sample = (*sample * settings.volume).clamp(-1.0, 1.0);
+    }
+
+    println!(
+        "Speaking with {} to {}: '{}'",
+        provider.name(),
+        route.label(),
+        text
+    );
+    let _ = app_handle.emit(
+        "tts",
+        serde_json::json!({
+            "speaking": true,
+            "id": utterance.id,
+            "text": text,
+            "provider": provider.name(),
+            "route": route
+        }),
+    );
+    let device = settings.device(route);
+    let playback_stop = stop.clone();
+    // Only speech heard in game comes out of the avatar's mouth
+    let visemes = Some(settings.visemes.clone())
+        .filter(|visemes| visemes.enabled && route == TtsRoute::VirtualMic);
+    let result = {
+        // Resolved here too so "default" is matched against the device loopback records
+        let device_name = find_output_device(device.as_deref())
+            .ok()
+            .and_then(|device| device.name().ok());
+        let _playing = PlayingGuard::new(state, device_name);
+        tauri::async_runtime::spawn_blocking(move || {
+            play_blocking(
+                device.as_deref(),
+                &samples,
+                sample_rate,
+                &playback_stop,
+                visemes,
+            )
+        })
+        .await
+        .map_err(|e| format!("Playback task failed: {}", e))?
+    };
+
+    if let Ok(mut playback) = state.playback.lock() {
+        if playback
+            .get(&route)
+            .is_some_and(|current| Arc::ptr_eq(current, &stop))
+        {
+            playback.remove(&route);
+        }
+    }
+    let _ = app_handle.emit(
+        "tts",
+        serde_json::json!({
+            "speaking": false,
+            "id": utterance.id,
+            "text": text,
+            "provider": provider.name(),
+            "route": route
+        }),
+    );
+    result
+}
+
+// Speak a route's queue until it is empty
+async fn run_queue(app_handle: tauri::AppHandle, route: TtsRoute) {
+    let state = app_handle.state::<TtsAppState>();
+    loop {
+        let next = match state.queues.lock() {
+            Ok(mut queues) => {
+                let queue = queues.entry(route).or_default();
+                let next = queue.pending.pop_front();
+                queue.running = next.is_some();
+                next
+            }
+            Err(_) => None,
+        };
+        let Some(utterance) = next else {
+            break;
+        };
+        emit_queue(&app_handle, &state);
+        if let Err(e) = speak_utterance(&app_handle, &state, &utterance).await {
+            println!("ERROR: Speaking '{}' failed: {}", utterance.text, e);
+            let _ = app_handle.emit(
+                "tts-error",
+                serde_json::json!({ "id": utterance.id, "route": route, "error": e }),
+            );
+        }
+    }
+}
+
+// Queue `text` to be spoken on `route`, returning its ID. The queue is worked off in
+// the background; `tts` events report when each utterance starts and ends.
+pub fn speak(
+    app_handle: &tauri::AppHandle,
+    state: &TtsAppState,
+    text: &str,
+    route: TtsRoute,
+    priority: TtsPriority,
+) -> Result<String, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Nothing to speak".to_string());
+    }
+    if state
+        .settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .provider
+        .is_none()
+    {
+        return Err("No text-to-speech voice is set up".to_string());
+    }
+
+    let utterance = QueuedUtterance {
+        id: format!("tts-{}", state.next_id.fetch_add(1, Ordering::SeqCst) + 1),
+        text: text.to_string(),
+        route,
+        priority,
+        queued_at: chrono::Utc::now().timestamp_millis(),
+    };
+    let id = utterance.id.clone();
+    let (dropped, start) = {
+        let mut queues = state
+            .queues
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        let queue = queues.entry(route).or_default();
+        let mut dropped = Vec::new();
+        if priority == TtsPriority::Interrupt {
+            dropped.extend(queue.pending.drain(..));
+        }
+        queue.insert(utterance);
+        dropped.extend(queue.evict_overflow());
+        let start = !queue.running;
+        queue.running = true;
+        (dropped, start)
+    };
+    if priority == TtsPriority::Interrupt {
+        state.skip(Some(route))?;
+    }
+    for utterance in &dropped {
+        println!("TTS queue full, dropping '{}'", utterance.text);
+    }
+    if start {
+        tauri::async_runtime::spawn(run_queue(app_handle.clone(), route));
+    }
+    emit_queue(app_handle, state);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn tts_get_settings(state: State<'_, TtsAppState>) -> Result<TtsSettings, String> {
+    Ok(state
+        .settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+#[tauri::command]
+pub fn tts_set_settings(
+    state: State<'_, TtsAppState>,
+    settings: TtsSettings,
+) -> Result<(), String> {
+    settings.validate()?;
+    println!(
+        "TTS voice: {}, output: {}, headphones: {}, volume {}, mic ducking {:?}",
+        settings.provider.as_ref().map_or("none", |p| p.name()),
+        settings.output_device.as_deref().unwrap_or("default"),
+        settings.headphones_device.as_deref().unwrap_or("default"),
+        settings.volume,
+        settings.mic_ducking
+    );
+    *state
+        .settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = settings;
+    Ok(())
+}
+
+// Output devices TTS can play on; virtual cables show up here under their own names
+#[tauri::command]
+pub async fn tts_list_output_devices() -> Result<Vec<OutputDevice>, String> {
+    // Enumeration can block on some drivers
+    tokio::task::spawn_blocking(|| {
+        let host = cpal::default_host();
+        let default_name = host.default_output_device().and_then(|d| d.name().ok());
+        let devices = host
+            .output_devices()
+            .map_err(|e| format!("Failed to list output devices: {}", e))?
+            .filter_map(|device| device.name().ok())
+            .map(|name| OutputDevice {
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                name,
+            })
+            .collect();
+        Ok(devices)
+    })
+    .await
+    .map_err(|e| format!("Device enumeration failed: {}", e))?
+}
+
+// Queue speech on the virtual mic unless another route is given, returning its ID
+#[tauri::command]
+pub fn tts_speak(
+    app_handle: tauri::AppHandle,
+    state: State<'_, TtsAppState>,
+    text: String,
+    route: Option<TtsRoute>,
+    priority: Option<TtsPriority>,
+) -> Result<String, String> {
+    speak(
+        &app_handle,
+        &state,
+        &text,
+        route.unwrap_or_default(),
+        priority.unwrap_or_default(),
+    )
+}
+
+// Cut off the utterance being spoken on one route, or on all of them; the queue
+// carries on with the next one
+#[tauri::command]
+pub fn tts_skip(state: State<'_, TtsAppState>, route: Option<TtsRoute>) -> Result<(), String> {
+    state.skip(route)
+}
+
+// Drop the utterances waiting on one route, or on all of them
+#[tauri::command]
+pub fn tts_clear_queue(
+    app_handle: tauri::AppHandle,
+    state: State<'_, TtsAppState>,
+    route: Option<TtsRoute>,
+) -> Result<(), String> {
+    state.clear_queue(route)?;
+    emit_queue(&app_handle, &state);
+    Ok(())
+}
+
+// Clear the queue and stop speaking, on one route or on all of them
+#[tauri::command]
+pub fn tts_stop(
+    app_handle: tauri::AppHandle,
+    state: State<'_, TtsAppState>,
+    route: Option<TtsRoute>,
+) -> Result<(), String> {
+    state.clear_queue(route)?;
+    state.skip(route)?;
+    emit_queue(&app_handle, &state);
+    Ok(())
+}
+
+// Utterances waiting to be spoken, in the order they will be
+#[tauri::command]
+pub fn tts_get_queue(state: State<'_, TtsAppState>) -> Result<Vec<QueuedUtterance>, String> {
+    state.queued()
+}
+
+// Move a queued utterance by giving it another priority. Interrupt isn't accepted
+// here, queue a new utterance for that.
+#[tauri::command]
+pub fn tts_set_priority(
+    app_handle: tauri::AppHandle,
+    state: State<'_, TtsAppState>,
+    id: String,
+    priority: TtsPriority,
+) -> Result<(), String> {
+    if priority == TtsPriority::Interrupt {
+        return Err("Only new utterances can interrupt".to_string());
+    }
+    {
+        let mut queues = state
+            .queues
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+        let queue = queues
+            .values_mut()
+            .find(|queue| queue.pending.iter().any(|queued| queued.id == id))
+            .ok_or_else(|| format!("No queued utterance {}", id))?;
+        let index = queue
+            .pending
+            .iter()
+            .position(|queued| queued.id == id)
+            .unwrap_or(0);
+        if let Some(mut utterance) = queue.pending.remove(index) {
+            utterance.priority = priority;
+            queue.insert(utterance);
+        }
+    }
+    emit_queue(&app_handle, &state);
+    Ok(())
+}
@@ -0,0 +1,51 @@
+// Pause-based pseudo-diarization. There is no speaker model, but the silences the
+// VAD finds between speech regions are a good stand-in for turn boundaries, so
+// segments are grouped into utterances along them for history and subtitles.
+use serde::Serialize;
+
+use crate::whisper::TranscriptionResult;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Utterance {
+    pub index: usize,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+    // Input the audio came from ("microphone", "desktop", a file path, ...)
+    pub source: String,
+}
+
+// Group the result's segments into utterances and tag each segment with its index.
+// `region_starts_ms` are the speech region starts on the segments' timeline, sorted.
+pub fn group_utterances(result: &mut TranscriptionResult, region_starts_ms: &[i64], source: &str) {
+    let mut utterances: Vec<Utterance> = Vec::new();
+    let mut current_region = None;
+
+    for segment in &mut result.segments {
+        let midpoint = (segment.start_ms + segment.end_ms) / 2;
+        let region = region_starts_ms
+            .partition_point(|&start| start <= midpoint)
+            .saturating_sub(1);
+
+        match utterances.last_mut() {
+            Some(utterance) if current_region == Some(region) => {
+                utterance.end_ms = utterance.end_ms.max(segment.end_ms);
+                utterance.text.push(' ');
+                utterance.text.push_str(&segment.text);
+            }
+            _ => {
+                current_region = Some(region);
+                utterances.push(Utterance {
+                    index: utterances.len(),
+                    start_ms: segment.start_ms,
+                    end_ms: segment.end_ms,
+                    text: segment.text.clone(),
+                    source: source.to_string(),
+                });
+            }
+        }
+        segment.utterance = utterances.len() - 1;
+    }
+
+    result.utterances = utterances;
+}
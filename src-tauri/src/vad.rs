@@ -0,0 +1,552 @@
+// Lightweight voice activity detection used to trim non-speech before it reaches
+// Whisper. Frames are classified by energy against an adaptive noise floor, then
+// merged into segments with hangover so short pauses don't split words.
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VadConfig {
+    pub frame_ms: u32,
+    // Absolute RMS below which a frame is never considered speech
+    pub min_energy: f32,
+    // How far above the estimated noise floor a frame must be to count as speech
+    pub noise_ratio: f32,
+    // Upper bound on the estimated noise floor, so clips that are speech end to end
+    // don't raise the threshold above the speech itself
+    pub max_noise_floor: f32,
+    // Segments shorter than this are discarded as clicks/bumps
+    pub min_speech_ms: u32,
+    // Silence shorter than this between speech frames is bridged
+    pub hangover_ms: u32,
+    // Audio kept before and after each segment so word onsets aren't clipped
+    pub padding_ms: u32,
+    // Long segments are split so a single utterance can't grow unbounded
+    pub max_segment_ms: u32,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            frame_ms: 30,
+            min_energy: 0.01,
+            noise_ratio: 2.5,
+            max_noise_floor: 0.02,
+            min_speech_ms: 250,
+            hangover_ms: 300,
+            padding_ms: 200,
+            max_segment_ms: 28_000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpeechSegment {
+    // Sample offsets into the analysed buffer, end exclusive
+    pub start: usize,
+    pub end: usize,
+}
+
+fn ms_to_samples(ms: u32, sample_rate: u32) -> usize {
+    (ms as u64 * sample_rate as u64 / 1000) as usize
+}
+
+fn frame_rms(frame: &[f32]) -> f32 {
+    if frame.is_empty() {
+        return 0.0;
+    }
+    let sum_squares: f32 = frame.iter().map(|&x| x * x).sum();
+    (sum_squares / frame.len() as f32).sqrt()
+}
+
+// Estimate the background level as a low percentile of frame energies
+fn estimate_noise_floor(energies: &[f32]) -> f32 {
+    if energies.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = energies.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    sorted[sorted.len() / 10]
+}
+
+pub fn detect_speech_segments(
+    samples: &[f32],
+    sample_rate: u32,
+    config: &VadConfig,
+) -> Vec<SpeechSegment> {
+    let frame_len = ms_to_samples(config.frame_ms, sample_rate).max(1);
+    let energies: Vec<f32> = samples.chunks(frame_len).map(frame_rms).collect();
+
+    let noise_floor = estimate_noise_floor(&energies).min(config.max_noise_floor);
+    let threshold = (noise_floor * config.noise_ratio).max(config.min_energy);
+
+    let hangover_frames = (config.hangover_ms / config.frame_ms.max(1)) as usize;
+    let min_speech = ms_to_samples(config.min_speech_ms, sample_rate);
+    let padding = ms_to_samples(config.padding_ms, sample_rate);
+    let max_segment = ms_to_samples(config.max_segment_ms, sample_rate).max(frame_len);
+
+    // Group speech frames, bridging gaps up to the hangover length
+    let mut raw: Vec<(usize, usize)> = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+    let mut silent_frames = 0;
+
+    for (i, &energy) in energies.iter().enumerate() {
+        if energy > threshold {
+            silent_frames = 0;
+            current = match current {
+                Some((start, _)) => Some((start, i + 1)),
+                None => Some((i, i + 1)),
+            };
+        } else if let Some(segment) = current {
+            silent_frames += 1;
+            if silent_frames > hangover_frames {
+                raw.push(segment);
+                current = None;
+            }
+        }
+    }
+    if let Some(segment) = current {
+        raw.push(segment);
+    }
+
+    let mut segments = Vec::new();
+    for (start_frame, end_frame) in raw {
+        let start = start_frame * frame_len;
+        let end = (end_frame * frame_len).min(samples.len());
+        if end - start < min_speech {
+            continue;
+        }
+
+        let start = start.saturating_sub(padding);
+        let end = (end + padding).min(samples.len());
+
+        let mut chunk_start = start;
+        while chunk_start < end {
+            let chunk_end = (chunk_start + max_segment).min(end);
+            segments.push(SpeechSegment {
+                start: chunk_start,
+                end: chunk_end,
+            });
+            chunk_start = chunk_end;
+        }
+    }
+
+    // Padding can make neighbours overlap, merge them back together
+    let mut merged: Vec<SpeechSegment> = Vec::new();
+    for mut segment in segments {
+        if let Some(last) = merged.last_mut() {
+            if segment.start <= last.end {
+                if segment.end - last.start <= max_segment {
+                    last.end = last.end.max(segment.end);
+                    continue;
+                }
+                // Too long to merge, just drop the overlapping padding
+                segment.start = last.end;
+            }
+        }
+        merged.push(segment);
+    }
+
+    println!(
+        "VAD: noise floor {:.6}, threshold {:.6}, {} speech segment(s)",
+        noise_floor,
+        threshold,
+        merged.len()
+    );
+    merged
+}
+
+// Speech regions of a buffer joined back to back, as forwarded to Whisper
+#[derive(Default)]
+pub struct SpeechAudio {
+    pub samples: Vec<f32>,
+    // Offset in `samples` where each speech region begins; the pauses between
+    // regions mark utterance boundaries
+    pub region_starts: Vec<usize>,
+}
+
+impl SpeechAudio {
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn region_starts_ms(&self, sample_rate: u32) -> Vec<i64> {
+        self.region_starts
+            .iter()
+            .map(|&start| (start as u64 * 1000 / sample_rate as u64) as i64)
+            .collect()
+    }
+}
+
+// Concatenate only the speech regions of a buffer, separated by a short silence
+pub fn extract_speech(samples: &[f32], sample_rate: u32, config: &VadConfig) -> SpeechAudio {
+    let gap = vec![0.0f32; ms_to_samples(100, sample_rate)];
+    let mut speech = SpeechAudio::default();
+
+    for (i, segment) in detect_speech_segments(samples, sample_rate, config)
+        .iter()
+        .enumerate()
+    {
+        if i > 0 {
+            speech.samples.extend_from_slice(&gap);
+        }
+        speech.region_starts.push(speech.samples.len());
+        speech
+            .samples
+            .extend_from_slice(&samples[segment.start..segment.end]);
+    }
+
+    speech
+}
+
+// How a live stream is cut into utterances around the speech the VAD finds
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SegmentationSettings {
+    // Audio kept from before speech starts, so soft onsets aren't clipped
+    pub pre_padding_ms: u32,
+    // Trailing silence kept after speech ends
+    pub post_padding_ms: u32,
+    // Utterances running longer are cut at the quietest recent frame
+    pub max_utterance_ms: u32,
+}
+
+impl Default for SegmentationSettings {
+    fn default() -> Self {
+        Self {
+            pre_padding_ms: 300,
+            post_padding_ms: 300,
+            max_utterance_ms: 15_000,
+        }
+    }
+}
+
+impl SegmentationSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.pre_padding_ms > 2000 || self.post_padding_ms > 2000 {
+            return Err("Speech padding must be at most 2000ms".to_string());
+        }
+        if !(1000..=30_000).contains(&self.max_utterance_ms) {
+            return Err("Max utterance length must be between 1000ms and 30000ms".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Where to look for a cut point when an utterance hits its length limit
+const CUT_SEARCH_MS: u32 = 1000;
+// Weight of each silent frame in the running noise floor estimate
+const NOISE_FLOOR_ADAPTATION: f32 = 0.05;
+
+impl VadConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(10..=100).contains(&self.frame_ms) {
+            return Err("VAD frame length must be between 10ms and 100ms".to_string());
+        }
+        if !(0.0..=0.5).contains(&self.min_energy) || !(0.0..=0.5).contains(&self.max_noise_floor) {
+            return Err("VAD energy thresholds must be between 0 and 0.5".to_string());
+        }
+        if !(1.0..=10.0).contains(&self.noise_ratio) {
+            return Err("VAD noise ratio must be between 1 and 10".to_string());
+        }
+        if self.min_speech_ms > 5000 || self.hangover_ms > 5000 || self.padding_ms > 2000 {
+            return Err("VAD durations are out of range".to_string());
+        }
+        if !(1000..=30_000).contains(&self.max_segment_ms) {
+            return Err("Max segment length must be between 1000ms and 30000ms".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Streaming counterpart of `detect_speech_segments` for live capture. Audio is held
+// in a ring buffer of `pre_padding_ms` until a frame crosses the speech threshold,
+// then collected until the speaker has been quiet for the hangover time.
+pub struct StreamingSegmenter {
+    config: VadConfig,
+    settings: SegmentationSettings,
+    sample_rate: u32,
+    frame_len: usize,
+    noise_floor: f32,
+    // Samples not yet making up a whole frame
+    partial: Vec<f32>,
+    // Recent audio while nobody is speaking, at most the pre-padding long
+    ring: VecDeque<f32>,
+    // Utterance being collected, with one energy per frame for picking cut points.
+    // Frames start after the pre-padding, at `frames_start`.
+    utterance: Vec<f32>,
+    energies: Vec<f32>,
+    frames_start: usize,
+    speaking: bool,
+    speech_samples: usize,
+    silent_samples: usize,
+}
+
+impl StreamingSegmenter {
+    pub fn new(config: VadConfig, settings: SegmentationSettings, sample_rate: u32) -> Self {
+        let frame_len = ms_to_samples(config.frame_ms, sample_rate).max(1);
+        Self {
+            noise_floor: config.min_energy / config.noise_ratio.max(1.0),
+            config,
+            settings,
+            sample_rate,
+            frame_len,
+            partial: Vec::with_capacity(frame_len),
+            ring: VecDeque::new(),
+            utterance: Vec::new(),
+            energies: Vec::new(),
+            frames_start: 0,
+            speaking: false,
+            speech_samples: 0,
+            silent_samples: 0,
+        }
+    }
+
+    // Apply new tuning to a running capture; the utterance in progress carries on
+    pub fn set_config(&mut self, config: &VadConfig) {
+        self.frame_len = ms_to_samples(config.frame_ms, self.sample_rate).max(1);
+        self.config = config.clone();
+    }
+
+    // Feed 16kHz samples, returning the utterances they completed
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Vec<f32>> {
+        let mut finished = Vec::new();
+        for &sample in samples {
+            self.partial.push(sample);
+            if self.partial.len() >= self.frame_len {
+                let frame =
+                    std::mem::replace(&mut self.partial, Vec::with_capacity(self.frame_len));
+                finished.extend(self.push_frame(frame));
+            }
+        }
+        finished
+    }
+
+    // End the current utterance now (push-to-talk release, capture stopping)
+    pub fn flush(&mut self) -> Option<Vec<f32>> {
+        let partial = std::mem::take(&mut self.partial);
+        if self.speaking {
+            self.utterance.extend(partial);
+        }
+        self.finish(0)
+    }
+
+    fn push_frame(&mut self, frame: Vec<f32>) -> Option<Vec<f32>> {
+        let energy = frame_rms(&frame);
+        let threshold = (self.noise_floor * self.config.noise_ratio).max(self.config.min_energy);
+        let is_speech = energy > threshold;
+        if !is_speech {
+            self.noise_floor += (energy - self.noise_floor) * NOISE_FLOOR_ADAPTATION;
+            self.noise_floor = self.noise_floor.min(self.config.max_noise_floor);
+        }
+
+        if !self.speaking {
+            if !is_speech {
+                let pre_padding = ms_to_samples(self.settings.pre_padding_ms, self.sample_rate);
+                self.ring.extend(frame);
+                let excess = self.ring.len().saturating_sub(pre_padding);
+                self.ring.drain(..excess);
+                return None;
+            }
+            self.speaking = true;
+            self.utterance.extend(self.ring.drain(..));
+            self.energies.clear();
+            self.frames_start = self.utterance.len();
+        }
+
+        if is_speech {
+            self.speech_samples += frame.len();
+            self.silent_samples = 0;
+        } else {
+            self.silent_samples += frame.len();
+        }
+        self.utterance.extend(frame);
+        self.energies.push(energy);
+
+        let hangover = ms_to_samples(self.config.hangover_ms, self.sample_rate);
+        let post_padding = ms_to_samples(self.settings.post_padding_ms, self.sample_rate);
+        if self.silent_samples >= hangover.max(post_padding) {
+            let trailing = self.silent_samples - post_padding.min(self.silent_samples);
+            return self.finish(trailing);
+        }
+
+        let max_len = ms_to_samples(self.settings.max_utterance_ms, self.sample_rate);
+        if self.utterance.len() >= max_len {
+            return self.cut();
+        }
+        None
+    }
+
+    // Close the utterance, dropping `trailing` samples of silence from its end into
+    // the ring so they can serve as the next utterance's pre-padding
+    fn finish(&mut self, trailing: usize) -> Option<Vec<f32>> {
+        let mut utterance = std::mem::take(&mut self.utterance);
+        let tail = utterance.split_off(utterance.len() - trailing.min(utterance.len()));
+        let pre_padding = ms_to_samples(self.settings.pre_padding_ms, self.sample_rate);
+        self.ring = tail[tail.len().saturating_sub(pre_padding)..]
+            .iter()
+            .copied()
+            .collect();
+
+        let min_speech = ms_to_samples(self.config.min_speech_ms, self.sample_rate);
+        let keep = self.speaking && self.speech_samples >= min_speech;
+        self.speaking = false;
+        self.speech_samples = 0;
+        self.silent_samples = 0;
+        self.energies.clear();
+        keep.then_some(utterance)
+    }
+
+    // Split an over-long utterance at the quietest frame of its last second, so the
+    // cut lands between words where possible; the rest starts the next utterance
+    fn cut(&mut self) -> Option<Vec<f32>> {
+        let search = (CUT_SEARCH_MS / self.config.frame_ms.max(1)) as usize;
+        let first = self.energies.len().saturating_sub(search);
+        let quietest = (first..self.energies.len())
+            .min_by(|&a, &b| {
+                self.energies[a]
+                    .partial_cmp(&self.energies[b])
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .unwrap_or(self.energies.len());
+
+        let split_at = match self.frames_start + quietest * self.frame_len {
+            0 => self.utterance.len(),
+            split_at => split_at.min(self.utterance.len()),
+        };
+        let rest = self.utterance.split_off(split_at);
+        let utterance = std::mem::replace(&mut self.utterance, rest);
+        self.energies.drain(..quietest.min(self.energies.len()));
+        if self.utterance.is_empty() {
+            self.energies.clear();
+        }
+        self.frames_start = 0;
+        self.speech_samples = self.utterance.len().min(self.speech_samples);
+        Some(utterance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    // Faint deterministic hiss, well under the default speech threshold
+    fn silence(ms: u32) -> Vec<f32> {
+        let mut seed = 0x2545_f491_u32;
+        (0..ms_to_samples(ms, RATE))
+            .map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (seed >> 8) as f32 / (1u32 << 24) as f32 * 0.002 - 0.001
+            })
+            .collect()
+    }
+
+    fn speech(ms: u32) -> Vec<f32> {
+        (0..ms_to_samples(ms, RATE))
+            .map(|i| 0.3 * (2.0 * std::f32::consts::PI * 220.0 * i as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    fn clip(parts: &[Vec<f32>]) -> Vec<f32> {
+        parts.concat()
+    }
+
+    #[test]
+    fn finds_speech_between_silences() {
+        let config = VadConfig::default();
+        let samples = clip(&[silence(1000), speech(1000), silence(1000)]);
+        let segments = detect_speech_segments(&samples, RATE, &config);
+        assert_eq!(segments.len(), 1);
+
+        let padding = ms_to_samples(config.padding_ms, RATE);
+        let frame = ms_to_samples(config.frame_ms, RATE);
+        let (start, end) = (segments[0].start, segments[0].end);
+        assert!((16_000 - padding - frame..=16_000 - padding).contains(&start));
+        assert!((32_000 + padding..=32_000 + padding + frame).contains(&end));
+    }
+
+    #[test]
+    fn silence_has_no_speech() {
+        let samples = silence(3000);
+        assert!(detect_speech_segments(&samples, RATE, &VadConfig::default()).is_empty());
+        assert!(extract_speech(&samples, RATE, &VadConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn short_bursts_are_dropped() {
+        let samples = clip(&[silence(1000), speech(100), silence(1000)]);
+        assert!(detect_speech_segments(&samples, RATE, &VadConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn short_pauses_are_bridged_and_long_ones_split() {
+        let config = VadConfig::default();
+        let bridged = clip(&[
+            silence(500),
+            speech(500),
+            silence(200),
+            speech(500),
+            silence(500),
+        ]);
+        assert_eq!(detect_speech_segments(&bridged, RATE, &config).len(), 1);
+
+        let split = clip(&[
+            silence(500),
+            speech(500),
+            silence(1500),
+            speech(500),
+            silence(500),
+        ]);
+        let speech_audio = extract_speech(&split, RATE, &config);
+        assert_eq!(speech_audio.region_starts.len(), 2);
+        assert_eq!(speech_audio.region_starts[0], 0);
+        assert!(speech_audio.samples.len() < split.len());
+    }
+
+    #[test]
+    fn long_speech_is_split_at_the_segment_limit() {
+        let config = VadConfig {
+            max_segment_ms: 1000,
+            ..VadConfig::default()
+        };
+        let samples = clip(&[silence(500), speech(2500), silence(500)]);
+        let segments = detect_speech_segments(&samples, RATE, &config);
+        assert!(segments.len() >= 3);
+        assert!(segments
+            .iter()
+            .all(|s| s.end - s.start <= ms_to_samples(1000, RATE)));
+    }
+
+    #[test]
+    fn streaming_segmenter_emits_one_padded_utterance() {
+        let settings = SegmentationSettings::default();
+        let mut segmenter = StreamingSegmenter::new(VadConfig::default(), settings.clone(), RATE);
+        let samples = clip(&[silence(1000), speech(1000), silence(1000)]);
+
+        // Feed it in capture-sized chunks
+        let utterances: Vec<Vec<f32>> = samples
+            .chunks(512)
+            .flat_map(|chunk| segmenter.push(chunk))
+            .collect();
+        assert_eq!(utterances.len(), 1);
+
+        let speech_len = ms_to_samples(1000, RATE);
+        let padded = speech_len
+            + ms_to_samples(settings.pre_padding_ms, RATE)
+            + ms_to_samples(settings.post_padding_ms, RATE);
+        let frame = ms_to_samples(VadConfig::default().frame_ms, RATE);
+        assert!(utterances[0].len().abs_diff(padded) <= 2 * frame);
+        assert!(segmenter.flush().is_none());
+    }
+
+    #[test]
+    fn streaming_segmenter_flushes_speech_in_progress() {
+        let mut segmenter =
+            StreamingSegmenter::new(VadConfig::default(), SegmentationSettings::default(), RATE);
+        let samples = clip(&[silence(500), speech(800)]);
+        assert!(segmenter.push(&samples).is_empty());
+        assert!(segmenter.flush().is_some());
+    }
+}
@@ -0,0 +1,186 @@
+// Lip-sync for TTS: while speech plays on the virtual mic, its loudness envelope drives
+// avatar parameters over OSC so the mouth moves with the synthesized voice instead of
+// staying frozen. A float parameter carries how open the mouth is; an optional int
+// parameter gets a rough viseme (VRChat's Oculus viseme indices) guessed from how
+// noisy the sound is.
+use rosc::encoder;
+use rosc::{OscMessage, OscPacket, OscType};
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, UdpSocket};
+use std::sync::Arc;
+
+const DEFAULT_MOUTH_PARAMETER: &str = "TTSMouthOpen";
+// Envelope analysed around the playback position
+const WINDOW_MS: u32 = 40;
+// Changes smaller than this aren't worth a packet
+const MIN_MOUTH_CHANGE: f32 = 0.02;
+// Fraction of the way the mouth moves toward its target per update, so it doesn't
+// flutter with every syllable
+const SMOOTHING: f32 = 0.5;
+// RMS below this is a closed mouth
+const SILENCE_RMS: f32 = 0.01;
+
+// Oculus viseme indices as used by VRChat's Viseme parameter
+const VISEME_SIL: i32 = 0;
+const VISEME_SS: i32 = 7;
+const VISEME_E: i32 = 11;
+const VISEME_AA: i32 = 10;
+const VISEME_O: i32 = 13;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VisemeSettings {
+    pub enabled: bool,
+    // VRChat's OSC input
+    pub address: String,
+    pub port: String,
+    // Float avatar parameter, 0.0 (closed) to 1.0 (fully open)
+    pub mouth_parameter: String,
+    // Int avatar parameter for the estimated viseme, None to not send one
+    pub viseme_parameter: Option<String>,
+    // Multiplier from the audio envelope to mouth opening
+    pub sensitivity: f32,
+}
+
+impl Default for VisemeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1".to_string(),
+            port: "9000".to_string(),
+            mouth_parameter: DEFAULT_MOUTH_PARAMETER.to_string(),
+            viseme_parameter: None,
+            sensitivity: 4.0,
+        }
+    }
+}
+
+impl VisemeSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.mouth_parameter.trim().is_empty() {
+            return Err("Lip-sync needs a mouth parameter name".to_string());
+        }
+        if self.port.trim().parse::<u16>().is_err() {
+            return Err(format!("Invalid lip-sync OSC port '{}'", self.port));
+        }
+        if !(0.1..=20.0).contains(&self.sensitivity) {
+            return Err(format!(
+                "Lip-sync sensitivity must be 0.1 to 20.0, got {}",
+                self.sensitivity
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn parameter_address(name: &str) -> String {
+    format!("/avatar/parameters/{}", name.trim())
+}
+
+// Follows one utterance's playback and sends the mouth parameters for it
+pub struct LipSync {
+    settings: VisemeSettings,
+    socket: UdpSocket,
+    target: String,
+    samples: Arc<Vec<f32>>,
+    window: usize,
+    mouth: f32,
+    sent_mouth: f32,
+    sent_viseme: i32,
+}
+
+impl LipSync {
+    // `samples` are what is played, at `sample_rate`
+    pub fn new(
+        settings: VisemeSettings,
+        samples: Arc<Vec<f32>>,
+        sample_rate: u32,
+    ) -> Result<Self, String> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+            .map_err(|e| format!("Failed to bind socket: {}", e))?;
+        let target = format!("{}:{}", settings.address.trim(), settings.port.trim());
+        Ok(Self {
+            settings,
+            socket,
+            target,
+            samples,
+            window: (sample_rate * WINDOW_MS / 1000).max(1) as usize,
+            mouth: 0.0,
+            sent_mouth: 0.0,
+            sent_viseme: VISEME_SIL,
+        })
+    }
+
+    fn send(&self, parameter: &str, value: OscType) {
+        let packet = OscPacket::Message(OscMessage {
+            addr: parameter_address(parameter),
+            args: vec![value],
+        });
+        if let Ok(buf) = encoder::encode(&packet) {
+            let _ = self.socket.send_to(&buf, &self.target);
+        }
+    }
+
+    // Loudness and zero-crossing rate of the audio about to be heard at `position`
+    fn analyse(&self, position: usize) -> (f32, f32) {
+        let start = position.min(self.samples.len());
+        let end = (position + self.window).min(self.samples.len());
+        let window = &self.samples[start..end];
+        if window.is_empty() {
+            return (0.0, 0.0);
+        }
+        let rms = (window.iter().map(|s| s * s).sum::<f32>() / window.len() as f32).sqrt();
+        let crossings = window
+            .windows(2)
+            .filter(|pair| (pair[0] >= 0.0) != (pair[1] >= 0.0))
+            .count();
+        (rms, crossings as f32 / window.len() as f32)
+    }
+
+    // Rough mouth shape: hissing sounds cross zero often, open vowels rarely
+    fn viseme(rms: f32, zero_crossings: f32) -> i32 {
+        if rms < SILENCE_RMS {
+            VISEME_SIL
+        } else if zero_crossings > 0.25 {
+            VISEME_SS
+        } else if zero_crossings > 0.1 {
+            VISEME_E
+        } else if rms > 0.1 {
+            VISEME_AA
+        } else {
+            VISEME_O
+        }
+    }
+
+    // Called as playback advances, with the index of the next sample to be played
+    pub fn update(&mut self, position: usize) {
+        let (rms, zero_crossings) = self.analyse(position);
+        let target = (rms * self.settings.sensitivity).clamp(0.0, 1.0);
+        self.mouth += (target - self.mouth) * SMOOTHING;
+        if (self.mouth - self.sent_mouth).abs() >= MIN_MOUTH_CHANGE {
+            self.send(&self.settings.mouth_parameter, OscType::Float(self.mouth));
+            self.sent_mouth = self.mouth;
+        }
+        if let Some(parameter) = &self.settings.viseme_parameter {
+            let viseme = Self::viseme(rms, zero_crossings);
+            if viseme != self.sent_viseme {
+                self.send(parameter, OscType::Int(viseme));
+                self.sent_viseme = viseme;
+            }
+        }
+    }
+
+    // Close the mouth once the utterance ended or was cut off
+    pub fn finish(&mut self) {
+        self.mouth = 0.0;
+        self.send(&self.settings.mouth_parameter, OscType::Float(0.0));
+        self.sent_mouth = 0.0;
+        if let Some(parameter) = &self.settings.viseme_parameter {
+            self.send(parameter, OscType::Int(VISEME_SIL));
+            self.sent_viseme = VISEME_SIL;
+        }
+    }
+}
@@ -0,0 +1,222 @@
+// Drop-box captioning: watch a folder an external recorder writes into and
+// transcribe every new audio file once it has finished being written. Results are
+// announced through `watch-folder-transcribed`.
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager, State};
+
+use crate::file_transcribe::{transcribe_file, FileProgress, FileTranscript};
+use crate::whisper::{register_job, TranscribeOptions, WhisperAppState};
+
+// Polling rather than file system events, which fire while a recorder is still writing
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const AUDIO_EXTENSIONS: &[&str] = &["wav", "flac", "mp3", "ogg", "opus", "webm"];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WatchFolderConfig {
+    pub path: String,
+    pub model: String,
+    pub language: String,
+    // Also transcribe the files already in the folder when watching starts
+    #[serde(default)]
+    pub include_existing: bool,
+}
+
+#[derive(Default)]
+pub struct WatchFolderState {
+    // Folder being watched and the flag that stops its polling task
+    active: Mutex<Option<(WatchFolderConfig, Arc<AtomicBool>)>>,
+}
+
+// Audio files directly inside `folder`, with their current sizes
+fn scan_folder(folder: &Path) -> Result<HashMap<PathBuf, u64>, String> {
+    let entries =
+        fs::read_dir(folder).map_err(|e| format!("Failed to read {}: {}", folder.display(), e))?;
+    Ok(entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let extension = path.extension()?.to_str()?.to_lowercase();
+            if !AUDIO_EXTENSIONS.contains(&extension.as_str()) {
+                return None;
+            }
+            let metadata = entry.metadata().ok()?;
+            metadata.is_file().then(|| (path, metadata.len()))
+        })
+        .collect())
+}
+
+async fn transcribe_new_file(
+    app_handle: &tauri::AppHandle,
+    config: &WatchFolderConfig,
+    path: String,
+) -> Result<FileTranscript, String> {
+    let state = app_handle.state::<WhisperAppState>();
+    // Built per file so settings changed while watching apply to the next recording
+    let mut options =
+        TranscribeOptions::default().into_inference_options(&state, &config.language)?;
+    let job = register_job(app_handle, &state, None)?;
+    options.abort = Some(job.flag());
+
+    let progress = FileProgress {
+        app_handle: app_handle.clone(),
+        job_id: job.key().to_string(),
+        path,
+        file_index: 0,
+        file_count: 1,
+    };
+    transcribe_file(&state, &config.model, &options, progress).await
+}
+
+async fn watch_folder(
+    app_handle: tauri::AppHandle,
+    config: WatchFolderConfig,
+    stop: Arc<AtomicBool>,
+) {
+    let folder = PathBuf::from(&config.path);
+    let mut done: HashSet<PathBuf> = if config.include_existing {
+        HashSet::new()
+    } else {
+        scan_folder(&folder)
+            .map(|files| files.into_keys().collect())
+            .unwrap_or_default()
+    };
+    // Sizes seen on the previous poll; a file is picked up once its size stops changing
+    let mut growing: HashMap<PathBuf, u64> = HashMap::new();
+
+    while !stop.load(Ordering::SeqCst) {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let files = match scan_folder(&folder) {
+            Ok(files) => files,
+            Err(e) => {
+                println!("Watch folder scan failed: {}", e);
+                continue;
+            }
+        };
+        // Forget deleted files so a new recording reusing the name is picked up
+        done.retain(|path| files.contains_key(path));
+        growing.retain(|path, _| files.contains_key(path));
+
+        let mut ready = Vec::new();
+        for (path, size) in files {
+            if done.contains(&path) {
+                continue;
+            }
+            if size > 0 && growing.get(&path) == Some(&size) {
+                ready.push(path);
+            } else {
+                growing.insert(path, size);
+            }
+        }
+        // Recorders name files by timestamp, so this is usually recording order
+        ready.sort();
+
+        for path in ready {
+            if stop.load(Ordering::SeqCst) {
+                break;
+            }
+            growing.remove(&path);
+            done.insert(path.clone());
+
+            let path = path.to_string_lossy().to_string();
+            println!("New recording in watch folder: {}", path);
+            let transcript = match transcribe_new_file(&app_handle, &config, path.clone()).await {
+                Ok(transcript) => transcript,
+                Err(e) => {
+                    println!("ERROR: Failed to transcribe {}: {}", path, e);
+                    FileTranscript {
+                        path,
+                        duration_ms: 0,
+                        transcript: None,
+                        error: Some(e),
+                    }
+                }
+            };
+            let _ = app_handle.emit("watch-folder-transcribed", &transcript);
+        }
+    }
+    println!("Stopped watching {}", config.path);
+}
+
+fn emit_status(app_handle: &tauri::AppHandle, config: Option<&WatchFolderConfig>) {
+    let status_payload = serde_json::json!({
+        "watching": config.is_some(),
+        "path": config.map(|c| c.path.clone())
+    });
+    let _ = app_handle.emit("watch-folder-status", &status_payload);
+}
+
+// Start transcribing new audio files dropped into `config.path`, replacing any
+// folder already being watched
+#[tauri::command]
+pub fn whisper_start_watch_folder(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WatchFolderState>,
+    config: WatchFolderConfig,
+) -> Result<(), String> {
+    if !Path::new(&config.path).is_dir() {
+        return Err(format!("Watch folder {} does not exist", config.path));
+    }
+
+    let mut active = state
+        .active
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    if let Some((_, stop)) = active.take() {
+        stop.store(true, Ordering::SeqCst);
+    }
+
+    println!(
+        "Watching {} for new recordings (model {}, language {})",
+        config.path, config.model, config.language
+    );
+    let stop = Arc::new(AtomicBool::new(false));
+    tauri::async_runtime::spawn(watch_folder(
+        app_handle.clone(),
+        config.clone(),
+        stop.clone(),
+    ));
+    emit_status(&app_handle, Some(&config));
+    *active = Some((config, stop));
+    Ok(())
+}
+
+// Stop watching; a file already being transcribed still finishes
+#[tauri::command]
+pub fn whisper_stop_watch_folder(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WatchFolderState>,
+) -> Result<bool, String> {
+    let stopped = state
+        .active
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .take();
+    let Some((_, stop)) = stopped else {
+        return Ok(false);
+    };
+    stop.store(true, Ordering::SeqCst);
+    emit_status(&app_handle, None);
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn whisper_get_watch_folder(
+    state: State<'_, WatchFolderState>,
+) -> Result<Option<WatchFolderConfig>, String> {
+    Ok(state
+        .active
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .as_ref()
+        .map(|(config, _)| config.clone()))
+}
@@ -1,28 +1,343 @@
-use futures_util::StreamExt;
-use hound::WavReader;
-use reqwest;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use futures_util::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::Cursor;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
 use tauri::{Emitter, Manager};
 // Whisper imports
 use std::sync::{Arc, Mutex};
 use tauri::State;
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext};
+use whisper_rs::{
+    FullParams, SamplingStrategy, SegmentCallbackData, WhisperContext, WhisperContextParameters,
+};
+
+use crate::agreement::LocalAgreement;
+use crate::audio_decode::{decode_audio_file, decode_wav};
+use crate::cancel::{CancelRegistration, CancelRegistry};
+use crate::corrections::CorrectionDictionary;
+use crate::denoise::{denoise_samples, DEFAULT_AUDIO_SOURCE};
+use crate::download::{
+    fetch_expected_sha256, hash_file, DownloadSettings, ModelDownload, DOWNLOAD_CANCELLED,
+    MAX_DOWNLOAD_ATTEMPTS,
+};
+use crate::gpu::{detect_backends, gpu_device, gpu_devices, preferred_backend, WhisperBackend};
+use crate::hallucination::HallucinationFilter;
+use crate::itn::TextNormalizer;
+use crate::manifest::{find_model_config, ModelConfig, ModelFile};
+use crate::model_manager::{LoadedModel, ModelManager};
+use crate::profanity::ProfanityFilter;
+use crate::quantization::{resolve_quantization, QuantizationState};
+use crate::stream_translate::{
+    finish_stream_translation, open_stream_translation, set_stream_final_text, translate_committed,
+};
+use crate::utterance::{group_utterances, Utterance};
+use crate::vad::{extract_speech, SpeechAudio, VadConfig};
+
+// Whisper models expect 16kHz mono input
+pub const WHISPER_SAMPLE_RATE: u32 = 16000;
 
 pub struct WhisperAppState {
-    pub state: Arc<Mutex<Option<(WhisperContext, String)>>>,
+    pub models: Arc<ModelManager>,
+    pub backend: Arc<Mutex<WhisperBackend>>,
+    pub available_backends: Vec<WhisperBackend>,
+    // Cancellation flags for in-flight downloads, keyed by model ID
+    pub downloads: CancelRegistry,
+    // Cancellation flags for in-flight transcriptions, keyed by job ID
+    pub jobs: CancelRegistry,
+    pub download_settings: Arc<Mutex<DownloadSettings>>,
+    // Decoder settings used when a transcription call doesn't pass its own
+    pub decoding: Arc<Mutex<DecodingOptions>>,
+    // Input sources ("microphone", "others", ...) whose audio goes through RNNoise
+    pub denoise_sources: Arc<Mutex<HashSet<String>>>,
+    // Post-processing applied to every transcription before it is returned
+    pub hallucination_filter: Arc<Mutex<HallucinationFilter>>,
+    // Masks or drops configured words in final transcripts from every provider
+    pub profanity_filter: Arc<Mutex<ProfanityFilter>>,
+    // Rewrites spoken numbers, times and units as digits and symbols in final transcripts
+    pub text_normalizer: Arc<Mutex<TextNormalizer>>,
+    // User dictionary fixing misheard names and slang, persisted in the app data directory
+    pub corrections: Arc<Mutex<CorrectionDictionary>>,
+    // Partial stabilization state of open streaming sessions, keyed by stream ID
+    pub streams: Arc<Mutex<HashMap<String, LocalAgreement>>>,
+    // Which quantized variant of a model gets loaded on GPU backends
+    pub quantization: Arc<Mutex<QuantizationState>>,
+    // Speech detection tuning for VAD trimming and live capture segmentation
+    pub vad: Arc<Mutex<VadConfig>>,
 }
 
-// Model configurations with GGML files to download
-static MODEL_CONFIGS: &[(&str, &str, &[&str])] = &[
-    ("tiny", "ggerganov/whisper.cpp", &["ggml-tiny.bin"]),
-    ("base", "ggerganov/whisper.cpp", &["ggml-base.bin"]),
-    ("small", "ggerganov/whisper.cpp", &["ggml-small.bin"]),
-    ("medium", "ggerganov/whisper.cpp", &["ggml-medium.bin"]),
-    ("large", "ggerganov/whisper.cpp", &["ggml-large-v3.bin"]),
-];
+impl WhisperAppState {
+    pub fn new() -> Self {
+        let available_backends = detect_backends();
+        Self {
+            models: Arc::new(ModelManager::new()),
+            backend: Arc::new(Mutex::new(preferred_backend(&available_backends))),
+            available_backends,
+            downloads: CancelRegistry::default(),
+            jobs: CancelRegistry::default(),
+            download_settings: Arc::new(Mutex::new(DownloadSettings::default())),
+            decoding: Arc::new(Mutex::new(DecodingOptions::default())),
+            denoise_sources: Arc::new(Mutex::new(HashSet::new())),
+            hallucination_filter: Arc::new(Mutex::new(HallucinationFilter::default())),
+            profanity_filter: Arc::new(Mutex::new(ProfanityFilter::default())),
+            text_normalizer: Arc::new(Mutex::new(TextNormalizer::default())),
+            corrections: Arc::new(Mutex::new(CorrectionDictionary::default())),
+            streams: Arc::new(Mutex::new(HashMap::new())),
+            quantization: Arc::new(Mutex::new(QuantizationState::default())),
+            vad: Arc::new(Mutex::new(VadConfig::default())),
+        }
+    }
+}
+
+impl Default for WhisperAppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Create a Whisper context on the requested backend, falling back to the CPU if
+// the GPU backend fails to initialize (missing driver, out of VRAM, ...). Returns the
+// backend of the device whisper.cpp was pointed at, not just the one requested.
+pub fn load_whisper_context(
+    model_file: &str,
+    backend: WhisperBackend,
+) -> Result<(WhisperContext, WhisperBackend), String> {
+    // `use_gpu` alone takes whichever GPU ggml lists first, which is the CUDA device
+    // even when Vulkan was requested, so select the device explicitly
+    let device = if backend.uses_gpu() {
+        let device = gpu_device(backend);
+        if device.is_none() {
+            println!(
+                "Warning: No {} device found, falling back to CPU",
+                backend.as_str()
+            );
+        }
+        device
+    } else {
+        None
+    };
+
+    let mut ctx_params = WhisperContextParameters::default();
+    ctx_params.use_gpu(device.is_some());
+    if let Some(device) = &device {
+        ctx_params.gpu_device(device.index);
+    }
+
+    match WhisperContext::new_with_params(model_file, ctx_params) {
+        Ok(ctx) => match device {
+            Some(device) => {
+                println!("Whisper context initialized on {}", device.name);
+                Ok((ctx, device.backend))
+            }
+            None => Ok((ctx, WhisperBackend::Cpu)),
+        },
+        Err(e) if device.is_some() => {
+            println!(
+                "Warning: Failed to initialize {} backend ({:?}), falling back to CPU",
+                backend.as_str(),
+                e
+            );
+            let mut cpu_params = WhisperContextParameters::default();
+            cpu_params.use_gpu(false);
+            let ctx = WhisperContext::new_with_params(model_file, cpu_params)
+                .map_err(|e| format!("Failed to create WhisperContext: {:?}", e))?;
+            Ok((ctx, WhisperBackend::Cpu))
+        }
+        Err(e) => Err(format!("Failed to create WhisperContext: {:?}", e)),
+    }
+}
+
+// Tell the frontend which backends were detected at startup and which one is selected
+pub fn report_backends(app_handle: &tauri::AppHandle) {
+    let state = app_handle.state::<WhisperAppState>();
+    let Ok(selected) = state.backend.lock().map(|backend| *backend) else {
+        return;
+    };
+    let payload = serde_json::json!({
+        "available": state.available_backends,
+        "selected": selected,
+        "devices": gpu_devices()
+            .into_iter()
+            .map(|d| serde_json::json!({ "backend": d.backend, "name": d.name }))
+            .collect::<Vec<_>>()
+    });
+    let _ = app_handle.emit("whisper-backends", &payload);
+}
+
+#[tauri::command]
+pub fn whisper_get_backends(
+    state: State<'_, WhisperAppState>,
+) -> Result<serde_json::Value, String> {
+    let selected = *state
+        .backend
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+
+    Ok(serde_json::json!({
+        "available": state.available_backends,
+        "selected": selected
+    }))
+}
+
+#[tauri::command]
+pub fn whisper_set_backend(
+    state: State<'_, WhisperAppState>,
+    backend: String,
+) -> Result<(), String> {
+    let backend = WhisperBackend::parse(&backend)?;
+    if !state.available_backends.contains(&backend) {
+        return Err(format!(
+            "Backend {} is not available on this system",
+            backend.as_str()
+        ));
+    }
+
+    *state
+        .backend
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = backend;
+
+    // Drop the cached contexts so the next transcription reloads on the new backend
+    state.models.clear()?;
+
+    println!("Whisper backend set to {}", backend.as_str());
+    Ok(())
+}
+
+// Whisper.cpp accepts both legacy GGML (.bin) and GGUF weights
+pub fn is_weights_file(name: &str) -> bool {
+    name.ends_with(".bin") || name.ends_with(".gguf")
+}
+
+// Check a file on disk against the size published for it
+fn is_model_file_complete(path: &std::path::Path, file: &ModelFile) -> bool {
+    let file_size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    file_size > 0 && file_size == file.size
+}
+
+// Records which repository revision a model's files were downloaded from
+const REVISION_MARKER: &str = ".revision";
+
+// Whether the files on disk come from the revision the manifest pins. Models
+// downloaded before their revision was pinned have no marker and count as stale.
+fn is_revision_current(model_path: &std::path::Path, model_info: &ModelConfig) -> bool {
+    let Some(revision) = &model_info.revision else {
+        return true;
+    };
+    fs::read_to_string(model_path.join(REVISION_MARKER))
+        .map(|installed| installed.trim() == revision)
+        .unwrap_or(false)
+}
+
+fn record_revision(model_path: &std::path::Path, model_info: &ModelConfig) -> Result<(), String> {
+    let marker = model_path.join(REVISION_MARKER);
+    match &model_info.revision {
+        Some(revision) => fs::write(&marker, revision)
+            .map_err(|e| format!("Failed to record model revision: {}", e)),
+        None => {
+            let _ = fs::remove_file(&marker);
+            Ok(())
+        }
+    }
+}
+
+// Before moving a model to a new revision, drop the files on disk that don't match
+// the pinned revision's checksums so they are downloaded again. Files that are
+// unchanged upstream are kept.
+async fn discard_stale_files(
+    settings: &DownloadSettings,
+    model_info: &ModelConfig,
+    model_path: &std::path::Path,
+) -> Result<(), String> {
+    for model_file in &model_info.files {
+        let local_path = model_path.join(&model_file.name);
+        if !is_model_file_complete(&local_path, model_file) {
+            continue;
+        }
+        let expected_sha256 = match &model_file.sha256 {
+            Some(sha) => Some(sha.to_lowercase()),
+            None => {
+                let url = model_info.file_url(settings, &model_file.name);
+                fetch_expected_sha256(settings, &url).await
+            }
+        };
+        let current = match expected_sha256 {
+            Some(expected) => hash_file(local_path.clone()).await? == expected,
+            // Without a checksum there's no telling which revision the file is from
+            None => false,
+        };
+        if current {
+            println!("{} is unchanged in the pinned revision", model_file.name);
+        } else {
+            println!(
+                "{} is from another revision, re-downloading",
+                model_file.name
+            );
+            fs::remove_file(&local_path)
+                .map_err(|e| format!("Failed to remove {}: {}", model_file.name, e))?;
+        }
+    }
+    Ok(())
+}
+
+// Prefix of the error returned when the models volume can't hold a download
+pub const INSUFFICIENT_SPACE: &str = "Insufficient disk space";
+// Headroom left on the volume beyond the model files themselves
+const DISK_SPACE_MARGIN_BYTES: u64 = 64 * 1024 * 1024;
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+// Fail fast when the files still to be fetched won't fit, instead of running out
+// of space halfway through a multi-gigabyte download
+fn ensure_free_space<'a>(
+    app_handle: &tauri::AppHandle,
+    model_id: &str,
+    model_path: &std::path::Path,
+    files: impl Iterator<Item = &'a ModelFile>,
+) -> Result<(), String> {
+    let pending: u64 = files
+        .filter(|f| !is_model_file_complete(&model_path.join(&f.name), f))
+        .map(|f| f.size)
+        .sum();
+    if pending == 0 {
+        return Ok(());
+    }
+
+    let required_bytes = pending + DISK_SPACE_MARGIN_BYTES;
+    let available_bytes = fs2::available_space(model_path)
+        .map_err(|e| format!("Failed to query free disk space: {}", e))?;
+    if available_bytes >= required_bytes {
+        return Ok(());
+    }
+
+    let space_payload = serde_json::json!({
+        "model": model_id,
+        "required_bytes": required_bytes,
+        "available_bytes": available_bytes
+    });
+    let _ = app_handle.emit("download-insufficient-space", &space_payload);
+    let error_msg = format!(
+        "{}: need {}, have {}",
+        INSUFFICIENT_SPACE,
+        format_bytes(required_bytes),
+        format_bytes(available_bytes)
+    );
+    println!("ERROR: {}", error_msg);
+    Err(error_msg)
+}
 
 // Audio processing validation function
 fn validate_audio_data(audio_data: &[u8]) -> Result<(), String> {
@@ -30,15 +345,6 @@ fn validate_audio_data(audio_data: &[u8]) -> Result<(), String> {
         return Err("Audio data is empty".to_string());
     }
 
-    // Basic audio format validation
-    if audio_data.len() < 44 {
-        // Not necessarily an error - could be raw PCM data
-        println!(
-            "Warning: Audio data smaller than typical WAV header size ({} bytes)",
-            audio_data.len()
-        );
-    }
-
     // More reasonable audio data size check
     // Increased limit to accommodate longer recordings
     if audio_data.len() > 20_000_000 {
@@ -59,165 +365,201 @@ fn detect_audio_format(audio_data: &[u8]) -> Result<String, String> {
         return Ok("WAV".to_string());
     }
 
+    // Compressed containers, decoded with Symphonia
+    if &audio_data[0..4] == b"OggS" {
+        return Ok("OGG".to_string());
+    }
+    if audio_data[0..4] == [0x1A, 0x45, 0xDF, 0xA3] {
+        // EBML header, as written by browser MediaRecorder
+        return Ok("WEBM".to_string());
+    }
+    if &audio_data[0..4] == b"fLaC" {
+        return Ok("FLAC".to_string());
+    }
+    if &audio_data[0..3] == b"ID3" || is_mp3_frame_header(audio_data) {
+        return Ok("MP3".to_string());
+    }
+
     // Check for raw PCM (assume if no header detected)
     Ok("PCM".to_string())
 }
 
-// Convert audio data to the format expected by Whisper (16kHz mono f32)
-fn process_audio_for_whisper(audio_data: &[u8]) -> Result<Vec<f32>, String> {
-    println!("Processing audio data for Whisper inference...");
+// MPEG audio frame sync with a valid layer and bitrate, for MP3s without an ID3 tag
+fn is_mp3_frame_header(audio_data: &[u8]) -> bool {
+    audio_data[0] == 0xFF
+        && audio_data[1] & 0xE0 == 0xE0
+        && audio_data[1] & 0x06 != 0
+        && audio_data[2] & 0xF0 != 0xF0
+}
 
-    // Try to parse as WAV first
-    let cursor = Cursor::new(audio_data);
-    match WavReader::new(cursor) {
-        Ok(mut reader) => {
-            let spec = reader.spec();
-            println!(
-                "WAV format - Sample rate: {}, Channels: {}, Bits per sample: {}",
-                spec.sample_rate, spec.channels, spec.bits_per_sample
-            );
+// Taps on each side of the windowed-sinc kernel, at the lower of the two rates
+const RESAMPLE_HALF_TAPS: f64 = 8.0;
 
-            // Read samples
-            let samples: Result<Vec<i16>, _> = reader.samples::<i16>().collect();
-            let samples = samples.map_err(|e| format!("Failed to read WAV samples: {}", e))?;
+// Band-limited (Hann-windowed sinc) resampling to 16kHz. When downsampling the
+// cutoff drops to the new Nyquist so higher frequencies don't alias into speech.
+fn resample_to_whisper_rate(samples: Vec<f32>, sample_rate: u32) -> Vec<f32> {
+    if sample_rate == WHISPER_SAMPLE_RATE || samples.is_empty() {
+        return samples;
+    }
+    println!(
+        "Resampling from {}Hz to {}Hz",
+        sample_rate, WHISPER_SAMPLE_RATE
+    );
 
-            // Convert to mono if stereo
-            let mono_samples: Vec<i16> = if spec.channels == 2 {
-                samples
-                    .chunks(2)
-                    .map(|chunk| ((chunk[0] as i32 + chunk[1] as i32) / 2) as i16)
-                    .collect()
+    let ratio = sample_rate as f64 / WHISPER_SAMPLE_RATE as f64;
+    // Cutoff relative to the input Nyquist frequency
+    let cutoff = (1.0 / ratio).min(1.0);
+    let half_width = RESAMPLE_HALF_TAPS / cutoff;
+    let new_length = (samples.len() as f64 / ratio) as usize;
+
+    (0..new_length)
+        .map(|i| {
+            let center = i as f64 * ratio;
+            let first = (center - half_width).ceil().max(0.0) as usize;
+            let last = ((center + half_width).floor() as usize).min(samples.len() - 1);
+
+            let mut sum = 0.0;
+            let mut weight_sum = 0.0;
+            for (offset, &sample) in samples[first..=last].iter().enumerate() {
+                let x = (first + offset) as f64 - center;
+                let arg = std::f64::consts::PI * x * cutoff;
+                let sinc = if arg.abs() < 1e-9 {
+                    1.0
+                } else {
+                    arg.sin() / arg
+                };
+                let window = 0.5 * (1.0 + (std::f64::consts::PI * x / half_width).cos());
+                let weight = sinc * window;
+                sum += sample as f64 * weight;
+                weight_sum += weight;
+            }
+            // Normalizing keeps unity gain near the edges where the kernel is cut off
+            if weight_sum.abs() > 1e-9 {
+                (sum / weight_sum) as f32
             } else {
-                samples
-            };
-
-            // Convert to f32 and normalize
-            let mut float_samples: Vec<f32> = mono_samples
-                .iter()
-                .map(|&sample| sample as f32 / 32768.0)
-                .collect();
-
-            // Resample to 16kHz if needed
-            if spec.sample_rate != 16000 {
-                println!("Resampling from {}Hz to 16000Hz", spec.sample_rate);
-                // Simple linear interpolation resampling
-                let ratio = spec.sample_rate as f32 / 16000.0;
-                let new_length = (float_samples.len() as f32 / ratio) as usize;
-                let mut resampled = Vec::with_capacity(new_length);
-
-                for i in 0..new_length {
-                    let src_index = (i as f32 * ratio) as usize;
-                    if src_index < float_samples.len() {
-                        resampled.push(float_samples[src_index]);
-                    }
-                }
-                float_samples = resampled;
+                0.0
             }
+        })
+        .collect()
+}
 
-            println!("Processed audio: {} samples at 16kHz", float_samples.len());
-            Ok(float_samples)
-        }
-        Err(_) => {
-            // Assume raw PCM data
+// Convert audio data to the format expected by Whisper (16kHz mono f32)
+pub fn process_audio_for_whisper(audio_data: &[u8]) -> Result<Vec<f32>, String> {
+    println!("Processing audio data for Whisper inference...");
+
+    let format = detect_audio_format(audio_data).unwrap_or_else(|_| "PCM".to_string());
+    let (samples, sample_rate) = match format.as_str() {
+        "WAV" => decode_wav(audio_data)?,
+        "PCM" => {
+            // Headerless audio is assumed to be 16-bit little-endian mono at 16kHz
             println!("Treating as raw PCM data");
             if audio_data.len() % 2 != 0 {
                 return Err("Raw PCM data length must be even (16-bit samples)".to_string());
             }
-
-            let samples: Vec<i16> = audio_data
+            let samples = audio_data
                 .chunks_exact(2)
-                .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]))
-                .collect();
-
-            let float_samples: Vec<f32> = samples
-                .iter()
-                .map(|&sample| sample as f32 / 32768.0)
+                .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]) as f32 / 32768.0)
                 .collect();
-
-            println!("Processed raw PCM: {} samples", float_samples.len());
-            Ok(float_samples)
+            (samples, WHISPER_SAMPLE_RATE)
         }
-    }
+        _ => {
+            println!("Detected {} audio", format);
+            decode_audio_file(audio_data, &format.to_lowercase())?
+        }
+    };
+
+    let float_samples = resample_to_whisper_rate(samples, sample_rate);
+    println!("Processed audio: {} samples at 16kHz", float_samples.len());
+    Ok(float_samples)
 }
 
-// Run actual Whisper inference using whisper-rs
-// Run inference on an existing Whisper context
-fn run_inference_on_context(
-    ctx: &WhisperContext,
-    audio_samples: &[f32],
-    language: &str,
-) -> Result<String, String> {
-    println!("Starting inference on context...");
+// Convert a BCP-47 style tag ("ja-JP", "zh-TW", "en") into a Whisper language code.
+// Returns None for "auto" or languages Whisper doesn't know, which enables auto-detection.
+pub fn whisper_language_code(language: &str) -> Option<String> {
+    let primary = language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_lowercase();
 
-    // Use BeamSearch instead of Greedy to prevent temperature fallback
-    // BeamSearch provides better quality and doesn't retry with higher temperatures
-    let mut params = FullParams::new(SamplingStrategy::BeamSearch { 
-        beam_size: 5,      // Standard beam size for good quality
-        patience: -1.0     // Default patience
-    });
+    if primary.is_empty() || primary == "auto" {
+        return None;
+    }
 
-    // Set language (convert to Whisper 2-letter format)
-    let whisper_lang = match language {
-        "en-US" | "en" => "en",
-        "ja-JP" | "ja" => "ja",
-        "ko-KR" | "ko" => "ko",
-        "id-ID" | "id" => "id",
-        "zh-CN" | "zh" => "zh",
-        "es-ES" | "es" => "es",
-        "fr-FR" | "fr" => "fr",
-        "de-DE" | "de" => "de",
-        "it-IT" | "it" => "it",
-        "pt-PT" | "pt" => "pt",
-        "ru-RU" | "ru" => "ru",
-        "nl-NL" | "nl" => "nl",
-        "pl-PL" | "pl" => "pl",
-        "tr-TR" | "tr" => "tr",
-        "vi-VN" | "vi" => "vi",
-        "th-TH" | "th" => "th",
-        lang if lang.len() >= 2 => &lang[..2],
-        _ => "en",
-    };
+    if whisper_rs::get_lang_id(&primary).is_none() {
+        println!(
+            "Warning: Language '{}' is not supported by Whisper, falling back to auto-detection",
+            language
+        );
+        return None;
+    }
 
-    params.set_language(Some(whisper_lang));
-    params.set_print_progress(false);
-    params.set_print_realtime(false);
-    params.set_print_timestamps(false);
-    
-    // Critical parameters to prevent hallucinations and runaway generation
-    params.set_suppress_blank(true); // Suppress blank outputs
-    params.set_suppress_nst(true); // Suppress non-speech tokens
-    params.set_no_context(true); // Don't use context to prevent hallucination continuation
-    params.set_single_segment(false); // Allow multiple segments for better accuracy
-    params.set_max_tokens(100); // Limit tokens to prevent infinite loops - ~7.5 seconds of speech
-    params.set_entropy_thold(2.4); // Reject low-entropy (repetitive) outputs
-    
-    // BeamSearch doesn't use temperature fallback, so no need to set temperature params
-    // This prevents the problematic retry mechanism that causes hallucinations
+    Some(primary)
+}
 
-    // Create state
-    let mut state = ctx
-        .create_state()
-        .map_err(|e| format!("Failed to create Whisper state: {:?}", e))?;
+// Resolve the GGML/GGUF weights file for a model from the model catalog
+pub fn model_weights_file(model_id: &str) -> Result<String, String> {
+    find_model_config(model_id)?
+        .files
+        .into_iter()
+        .map(|f| f.name)
+        .find(|name| is_weights_file(name))
+        .ok_or_else(|| format!("Model {} has no GGML weights file", model_id))
+}
 
-    // Run inference
-    state
-        .full(params, audio_samples)
-        .map_err(|e| format!("Whisper inference failed: {:?}", e))?;
+#[derive(Clone, Debug, Serialize)]
+pub struct TranscriptionWord {
+    pub text: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub probability: f32,
+}
 
-    // Collect transcription
-    let num_segments = state.full_n_segments();
+#[derive(Clone, Debug, Serialize)]
+pub struct TranscriptionSegment {
+    pub text: String,
+    // Offsets are relative to the speech passed to Whisper (after VAD trimming)
+    pub start_ms: i64,
+    pub end_ms: i64,
+    // Mean token probability across the segment
+    pub probability: f32,
+    // Mean token log-probability, the usual Whisper confidence gate (< -1.0 is suspicious).
+    // NO_TOKENS_LOGPROB for segments without any text tokens.
+    pub avg_logprob: f32,
+    // Probability the segment is actually silence/noise, high values flag hallucinations
+    pub no_speech_probability: f32,
+    // Language the segment was decoded in; differs between segments when code-switching
+    pub language: String,
+    // Index into the result's utterances, segments between two pauses share one
+    pub utterance: usize,
+    pub words: Vec<TranscriptionWord>,
+}
 
-    let mut transcription = String::new();
-    for i in 0..num_segments {
-        if let Some(segment) = state.get_segment(i) {
-            if let Ok(text) = segment.to_str() {
-                transcription.push_str(text);
-            }
+impl TranscriptionSegment {
+    // Move the segment and its words later by `offset_ms`, e.g. onto a longer timeline
+    pub fn shift(&mut self, offset_ms: i64) {
+        self.start_ms += offset_ms;
+        self.end_ms += offset_ms;
+        for word in &mut self.words {
+            word.start_ms += offset_ms;
+            word.end_ms += offset_ms;
         }
     }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TranscriptionResult {
+    pub text: String,
+    pub language: String,
+    pub segments: Vec<TranscriptionSegment>,
+    // Segments grouped by the pauses between them
+    pub utterances: Vec<Utterance>,
+}
 
-    // Clean up the transcription
-    let cleaned = transcription.trim()
+// Strip Whisper's non-speech markers and artifacts from decoded text
+pub fn clean_transcript(text: &str) -> String {
+    text.trim()
         // Remove Whisper hallucination tokens
         .replace("[BLANK_AUDIO]", "")
         .replace("(BLANK_AUDIO)", "")
@@ -228,130 +570,609 @@ fn run_inference_on_context(
         // Remove common Whisper artifacts
         .replace("...", "")
         .trim()
-        .to_string();
+        .to_string()
+}
 
-    Ok(cleaned)
+// Special tokens ([_BEG_], <|endoftext|>, timestamps) aren't part of the spoken text
+fn is_special_token(text: &str) -> bool {
+    text.starts_with("[_") || text.starts_with("<|")
 }
 
-// Simple speech activity detection based on audio energy
-fn detect_speech_activity(audio_samples: &[f32]) -> Result<bool, String> {
-    if audio_samples.is_empty() {
-        return Ok(false);
+// avg_logprob of a segment with no text tokens. Finite so it survives JSON (which has no
+// -inf) and no higher than any logprob threshold the decoding options accept.
+pub const NO_TOKENS_LOGPROB: f32 = -10.0;
+
+struct SegmentTokens {
+    words: Vec<TranscriptionWord>,
+    probability: f32,
+    avg_logprob: f32,
+}
+
+// Group a segment's tokens into words. Whisper tokens that start with a space begin
+// a new word; token timestamps are in 10ms units.
+fn collect_segment_tokens(segment: &whisper_rs::WhisperSegment) -> SegmentTokens {
+    let mut words: Vec<TranscriptionWord> = Vec::new();
+    let mut probability_sum = 0.0f32;
+    let mut logprob_sum = 0.0f32;
+    let mut token_count = 0usize;
+
+    for t in 0..segment.n_tokens() {
+        let Some(token) = segment.get_token(t) else {
+            continue;
+        };
+        let text = token.to_str_lossy().to_string();
+        if is_special_token(&text) {
+            continue;
+        }
+
+        let data = token.token_data();
+        probability_sum += data.p;
+        logprob_sum += data.plog;
+        token_count += 1;
+
+        let starts_word = text.starts_with(' ') || words.is_empty();
+        if starts_word {
+            words.push(TranscriptionWord {
+                text: text.trim().to_string(),
+                start_ms: data.t0 * 10,
+                end_ms: data.t1 * 10,
+                probability: data.p,
+            });
+        } else if let Some(word) = words.last_mut() {
+            word.text.push_str(&text);
+            word.end_ms = data.t1 * 10;
+            word.probability = word.probability.min(data.p);
+        }
     }
 
-    // Calculate RMS (Root Mean Square) energy
-    let rms = {
-        let sum_squares: f32 = audio_samples.iter().map(|&x| x * x).sum();
-        (sum_squares / audio_samples.len() as f32).sqrt()
+    words.retain(|w| !w.text.is_empty());
+    let (probability, avg_logprob) = if token_count > 0 {
+        (
+            probability_sum / token_count as f32,
+            logprob_sum / token_count as f32,
+        )
+    } else {
+        (0.0, NO_TOKENS_LOGPROB)
     };
 
-    // Calculate peak amplitude
-    let peak = audio_samples
-        .iter()
-        .map(|&x| x.abs())
-        .fold(0.0f32, f32::max);
+    SegmentTokens {
+        words,
+        probability,
+        avg_logprob,
+    }
+}
 
-    println!("Audio analysis - RMS: {:.6}, Peak: {:.6}", rms, peak);
+pub const TRANSCRIPTION_CANCELLED: &str = "Transcription cancelled";
+
+// Decoder tuning exposed to advanced users. Defaults: beam search of 5, the Whisper
+// reference temperature fallback ladder (0.0, 0.2, ... 1.0), no cross-window context.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DecodingOptions {
+    // 1 decodes greedily, which is noticeably faster on slow machines
+    pub beam_size: u32,
+    // Starting temperature and the step added on each fallback retry (0 disables retries)
+    pub temperature: f32,
+    pub temperature_increment: f32,
+    // Segments whose no-speech probability exceeds this are treated as silence
+    pub no_speech_threshold: f32,
+    // Segments compressing better than this (repetition loops) are retried hotter
+    pub compression_ratio_threshold: f32,
+    // Segments with a lower average token log-probability are retried hotter
+    pub logprob_threshold: f32,
+    // Feed previous text back as context; more coherent but prone to repetition loops
+    pub condition_on_previous_text: bool,
+}
 
-    // Check minimum audio duration (at least 0.5 seconds of actual audio)
-    // At 16kHz, 0.5 seconds = 8000 samples
-    let min_samples = 8000;
-    if audio_samples.len() < min_samples {
-        println!("Audio too short ({} samples, need at least {}), skipping", audio_samples.len(), min_samples);
-        return Ok(false);
+impl Default for DecodingOptions {
+    fn default() -> Self {
+        Self {
+            beam_size: 5,
+            temperature: 0.0,
+            temperature_increment: 0.2,
+            no_speech_threshold: 0.6,
+            compression_ratio_threshold: 2.4,
+            logprob_threshold: -1.0,
+            condition_on_previous_text: false,
+        }
     }
+}
 
-    // Higher thresholds to reduce sensitivity and false positives from background noise
-    // These values require clear, intentional speech to trigger transcription
-    let rms_threshold = 0.02; // Doubled from 0.01 - requires more energy
-    let peak_threshold = 0.1; // Doubled from 0.05 - requires clearer peaks
-
-    let has_energy = rms > rms_threshold;
-    let has_peaks = peak > peak_threshold;
-
-    // Check for dynamic range (speech typically has varying amplitude)
-    let mut amplitude_changes = 0;
-    let window_size = audio_samples.len() / 10; // Divide into 10 segments
-    if window_size > 0 {
-        for i in 0..9 {
-            let start = i * window_size;
-            let end = (i + 1) * window_size;
-            let segment_rms = {
-                let sum_squares: f32 = audio_samples[start..end].iter().map(|&x| x * x).sum();
-                (sum_squares / window_size as f32).sqrt()
-            };
+impl DecodingOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=16).contains(&self.beam_size) {
+            return Err(format!(
+                "Beam size must be between 1 and 16, got {}",
+                self.beam_size
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.temperature) {
+            return Err(format!(
+                "Temperature must be between 0 and 1, got {}",
+                self.temperature
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.temperature_increment) {
+            return Err(format!(
+                "Temperature increment must be between 0 and 1, got {}",
+                self.temperature_increment
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.no_speech_threshold) {
+            return Err(format!(
+                "No-speech threshold must be between 0 and 1, got {}",
+                self.no_speech_threshold
+            ));
+        }
+        if !(1.0..=10.0).contains(&self.compression_ratio_threshold) {
+            return Err(format!(
+                "Compression ratio threshold must be between 1 and 10, got {}",
+                self.compression_ratio_threshold
+            ));
+        }
+        if !(-10.0..=0.0).contains(&self.logprob_threshold) {
+            return Err(format!(
+                "Log-probability threshold must be between -10 and 0, got {}",
+                self.logprob_threshold
+            ));
+        }
+        Ok(())
+    }
+}
 
-            let next_start = (i + 1) * window_size;
-            let next_end = if i + 2 < 10 {
-                (i + 2) * window_size
-            } else {
-                audio_samples.len()
-            };
-            let next_segment_rms = {
-                let sum_squares: f32 = audio_samples[next_start..next_end]
-                    .iter()
-                    .map(|&x| x * x)
-                    .sum();
-                (sum_squares / (next_end - next_start) as f32).sqrt()
-            };
+// Per-call settings for a Whisper inference run
+pub struct InferenceOptions {
+    pub language: String,
+    // Use Whisper's built-in translate task to emit English regardless of input language
+    pub translate: bool,
+    // Text fed to the decoder as prior context to bias spelling of names and slang
+    pub initial_prompt: Option<String>,
+    pub decoding: DecodingOptions,
+    // Input the audio came from, used to label utterances
+    pub source: String,
+    // Run RNNoise over the audio before VAD and inference
+    pub denoise: bool,
+    pub hallucination_filter: HallucinationFilter,
+    pub profanity_filter: ProfanityFilter,
+    pub text_normalizer: TextNormalizer,
+    pub corrections: CorrectionDictionary,
+    pub vad: VadConfig,
+    // Invoked for each decoded segment while inference is still running
+    pub on_segment: Option<SegmentCallback>,
+    // Set to abort decoding part-way through
+    pub abort: Option<Arc<AtomicBool>>,
+}
 
-            if (segment_rms - next_segment_rms).abs() > 0.015 {
-                // Require significant variation - speech has dynamic range, noise is static
-                amplitude_changes += 1;
-            }
+impl InferenceOptions {
+    // Same settings decoding in another language, without the streaming callback
+    pub fn for_language(&self, language: &str) -> Self {
+        Self {
+            language: language.to_string(),
+            translate: self.translate,
+            initial_prompt: self.initial_prompt.clone(),
+            decoding: self.decoding.clone(),
+            source: self.source.clone(),
+            denoise: self.denoise,
+            hallucination_filter: self.hallucination_filter.clone(),
+            profanity_filter: self.profanity_filter.clone(),
+            text_normalizer: self.text_normalizer.clone(),
+            corrections: self.corrections.clone(),
+            vad: self.vad.clone(),
+            on_segment: None,
+            abort: self.abort.clone(),
         }
     }
 
-    // Require at least 3 amplitude changes to ensure it's dynamic speech, not static noise
-    let has_variation = amplitude_changes >= 3;
+    pub fn new(language: &str) -> Self {
+        Self {
+            language: language.to_string(),
+            translate: false,
+            initial_prompt: None,
+            decoding: DecodingOptions::default(),
+            source: DEFAULT_AUDIO_SOURCE.to_string(),
+            denoise: false,
+            hallucination_filter: HallucinationFilter::default(),
+            profanity_filter: ProfanityFilter::default(),
+            text_normalizer: TextNormalizer::default(),
+            corrections: CorrectionDictionary::default(),
+            vad: VadConfig::default(),
+            on_segment: None,
+            abort: None,
+        }
+    }
+}
 
-    println!(
-        "Speech detection - Energy: {}, Peaks: {}, Variation: {} changes",
-        has_energy, has_peaks, amplitude_changes
-    );
+// Audio kept around a segment when it is re-identified and re-decoded on its own
+const UTTERANCE_PADDING_MS: i64 = 100;
+// Confidence needed before a segment is re-decoded in a different language
+const LANGUAGE_SWITCH_PROBABILITY: f32 = 0.5;
+// Language ID is unreliable on shorter segments, so they aren't worth an encoder pass
+const MIN_REIDENTIFY_MS: i64 = 1000;
+// Segments decoded with at least this mean token probability were most likely decoded
+// in the right language already
+const REIDENTIFY_BELOW_PROBABILITY: f32 = 0.7;
+
+// Run inference on an existing Whisper context. With automatic language detection,
+// segments the first pass decoded with low confidence are language-identified on their
+// own, so bilingual speakers switching languages mid-conversation get each utterance
+// decoded in the language it was spoken in without paying an extra encoder pass for
+// every segment.
+pub fn run_inference_on_context(
+    ctx: &WhisperContext,
+    audio_samples: &[f32],
+    options: InferenceOptions,
+) -> Result<TranscriptionResult, String> {
+    let auto_language = whisper_language_code(&options.language).is_none();
+    let template = options.for_language(&options.language);
+    let result = decode_on_context(ctx, audio_samples, options)?;
+    if !auto_language || result.segments.len() < 2 {
+        return Ok(result);
+    }
+
+    let samples_per_ms = WHISPER_SAMPLE_RATE as i64 / 1000;
+    let mut segments = Vec::with_capacity(result.segments.len());
+    for segment in result.segments {
+        if segment.end_ms - segment.start_ms < MIN_REIDENTIFY_MS
+            || segment.probability >= REIDENTIFY_BELOW_PROBABILITY
+        {
+            segments.push(segment);
+            continue;
+        }
+        let start_ms = (segment.start_ms - UTTERANCE_PADDING_MS).max(0);
+        let start = (start_ms * samples_per_ms) as usize;
+        let end = (((segment.end_ms + UTTERANCE_PADDING_MS) * samples_per_ms) as usize)
+            .min(audio_samples.len());
+        let Some(utterance) = audio_samples.get(start..end).filter(|u| !u.is_empty()) else {
+            segments.push(segment);
+            continue;
+        };
+
+        let detected = detect_language_on_context(ctx, utterance)?
+            .into_iter()
+            .next()
+            .filter(|top| {
+                top.language != segment.language && top.probability >= LANGUAGE_SWITCH_PROBABILITY
+            });
+        let Some(detected) = detected else {
+            segments.push(segment);
+            continue;
+        };
 
-    // Speech is detected if we have sufficient energy, peaks, AND variation
-    // All three conditions required to reduce false positives
-    let speech_detected = has_energy && has_peaks && has_variation;
+        println!(
+            "Segment '{}' identified as {} ({:.2}), re-decoding",
+            segment.text, detected.language, detected.probability
+        );
+        let redecoded =
+            decode_on_context(ctx, utterance, template.for_language(&detected.language))?;
+        segments.extend(redecoded.segments.into_iter().map(|mut s| {
+            s.shift(start_ms);
+            s
+        }));
+    }
 
-    Ok(speech_detected)
+    let text = segments
+        .iter()
+        .map(|s| s.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    // Report the language spoken for the longest total time as the overall one
+    let mut durations: HashMap<&str, i64> = HashMap::new();
+    for segment in &segments {
+        *durations.entry(segment.language.as_str()).or_default() +=
+            segment.end_ms - segment.start_ms;
+    }
+    let language = durations
+        .into_iter()
+        .max_by_key(|(_, duration)| *duration)
+        .map(|(language, _)| language.to_string())
+        .unwrap_or(result.language);
+
+    Ok(TranscriptionResult {
+        text,
+        language,
+        segments,
+        utterances: Vec::new(),
+    })
 }
 
-fn get_models_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
-    println!("Getting models directory path...");
+// Decode the whole buffer, re-decode segments that fail the quality gates at
+// increasing temperatures, then apply the post-processing filters
+fn decode_on_context(
+    ctx: &WhisperContext,
+    audio_samples: &[f32],
+    mut options: InferenceOptions,
+) -> Result<TranscriptionResult, String> {
+    println!("Starting inference on context...");
 
-    let app_data = app_handle.path().app_data_dir().map_err(|e| {
-        let error_msg = format!("Failed to get app data directory: {}", e);
-        println!("ERROR: {}", error_msg);
-        error_msg
-    })?;
+    let on_segment = options.on_segment.take();
+    let (segments, language) = decode_pass(
+        ctx,
+        audio_samples,
+        &options,
+        options.decoding.temperature,
+        on_segment,
+    )?;
+    let segments = if options.decoding.temperature_increment > 0.0 {
+        apply_temperature_fallback(ctx, audio_samples, &options, &language, segments)?
+    } else {
+        segments
+    };
 
-    println!("App data directory: {:?}", app_data);
+    let mut result = TranscriptionResult {
+        text: String::new(),
+        language,
+        segments,
+        utterances: Vec::new(),
+    };
+    // Also rebuilds the text from the segments that are kept
+    options
+        .hallucination_filter
+        .apply_to_result(&mut result, audio_samples);
+    postprocess(&options, &mut result);
+    Ok(result)
+}
 
-    let models_dir = app_data.join("whisper_models");
-    println!("Target models directory: {:?}", models_dir);
+// Text clean-up shared by every provider's final transcripts. Names are corrected
+// before normalizing, and both before masking so masked words can't hide inside a
+// correction or a number.
+pub fn postprocess(options: &InferenceOptions, result: &mut TranscriptionResult) {
+    options.corrections.apply_to_result(result);
+    options
+        .text_normalizer
+        .apply_to_result(result, options.translate);
+    options.profanity_filter.apply_to_result(result);
+}
 
-    // Ensure parent directory exists and handle conflicts
-    if let Some(parent) = models_dir.parent() {
-        if parent.exists() {
-            if parent.is_file() {
-                println!("Found conflicting file at parent directory path, removing...");
-                fs::remove_file(parent).map_err(|e| {
-                    let error_msg = format!("Failed to remove conflicting parent file: {}", e);
-                    println!("ERROR: {}", error_msg);
-                    error_msg
-                })?;
-                println!("Creating parent directory: {:?}", parent);
-                fs::create_dir_all(parent).map_err(|e| {
-                    let error_msg = format!("Failed to create parent directory: {}", e);
-                    println!("ERROR: {}", error_msg);
-                    error_msg
-                })?;
-            } else {
-                println!("Parent directory already exists: {:?}", parent);
-            }
-        } else {
-            println!("Creating parent directory: {:?}", parent);
+// `postprocess` for bare text such as streaming partials, normalized as `language`
+pub fn postprocess_text(options: &InferenceOptions, text: &str, language: &str) -> String {
+    let text = options.corrections.apply(text);
+    let text = options.text_normalizer.apply(&text, language);
+    options.profanity_filter.apply(&text)
+}
+
+// Candidates sampled per attempt once the temperature is above zero, as in the Whisper reference
+const FALLBACK_BEST_OF: i32 = 5;
+
+// Single Whisper decoding pass over the whole buffer at a fixed temperature
+fn decode_pass(
+    ctx: &WhisperContext,
+    audio_samples: &[f32],
+    options: &InferenceOptions,
+    temperature: f32,
+    on_segment: Option<SegmentCallback>,
+) -> Result<(Vec<TranscriptionSegment>, String), String> {
+    // Force the decoder to the requested language, or let Whisper detect it
+    let whisper_lang =
+        whisper_language_code(&options.language).unwrap_or_else(|| "auto".to_string());
+    println!(
+        "Decoding with language '{}' at temperature {:.1}",
+        whisper_lang, temperature
+    );
+
+    let decoding = &options.decoding;
+    let strategy = if temperature > 0.0 {
+        SamplingStrategy::Greedy {
+            best_of: FALLBACK_BEST_OF,
+        }
+    } else if decoding.beam_size > 1 {
+        SamplingStrategy::BeamSearch {
+            beam_size: decoding.beam_size as i32,
+            patience: -1.0, // Default patience
+        }
+    } else {
+        SamplingStrategy::Greedy { best_of: 1 }
+    };
+    let mut params = FullParams::new(strategy);
+
+    params.set_language(Some(&whisper_lang));
+    params.set_translate(options.translate);
+    if let Some(prompt) = &options.initial_prompt {
+        params.set_initial_prompt(prompt);
+    }
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_timestamps(false);
+    params.set_token_timestamps(true); // Per-token timing for word-level timestamps
+
+    // Critical parameters to prevent hallucinations and runaway generation
+    params.set_suppress_blank(true); // Suppress blank outputs
+    params.set_suppress_nst(true); // Suppress non-speech tokens
+    params.set_no_context(!decoding.condition_on_previous_text);
+    params.set_single_segment(false); // Allow multiple segments for better accuracy
+    params.set_max_tokens(100); // Limit tokens to prevent infinite loops - ~7.5 seconds of speech
+    params.set_entropy_thold(2.4); // Reject low-entropy (repetitive) outputs
+    params.set_no_speech_thold(decoding.no_speech_threshold);
+
+    // The fallback ladder runs per segment in apply_temperature_fallback, so
+    // whisper.cpp's own per-window retries are disabled
+    params.set_temperature(temperature);
+    params.set_temperature_inc(0.0);
+
+    if let Some(callback) = on_segment {
+        params.set_segment_callback_safe(callback);
+    }
+
+    let abort = options.abort.clone();
+    if let Some(flag) = abort.clone() {
+        params.set_abort_callback_safe(move || flag.load(Ordering::SeqCst));
+    }
+
+    // Create state
+    let mut state = ctx
+        .create_state()
+        .map_err(|e| format!("Failed to create Whisper state: {:?}", e))?;
+
+    // Run inference
+    let result = state.full(params, audio_samples);
+    if abort.is_some_and(|flag| flag.load(Ordering::SeqCst)) {
+        println!("Inference aborted");
+        return Err(TRANSCRIPTION_CANCELLED.to_string());
+    }
+    result.map_err(|e| format!("Whisper inference failed: {:?}", e))?;
+
+    // Report the language Whisper actually decoded in (relevant for auto-detection)
+    let language = whisper_rs::get_lang_str(state.full_lang_id_from_state())
+        .unwrap_or(whisper_lang.as_str())
+        .to_string();
+
+    // Collect transcription
+    let num_segments = state.full_n_segments();
+
+    let mut segments = Vec::new();
+    for i in 0..num_segments {
+        let Some(segment) = state.get_segment(i) else {
+            continue;
+        };
+        let text = clean_transcript(&segment.to_str_lossy());
+        if text.is_empty() {
+            continue;
+        }
+
+        let tokens = collect_segment_tokens(&segment);
+        segments.push(TranscriptionSegment {
+            text,
+            start_ms: segment.start_timestamp() * 10,
+            end_ms: segment.end_timestamp() * 10,
+            probability: tokens.probability,
+            avg_logprob: tokens.avg_logprob,
+            no_speech_probability: segment.no_speech_probability(),
+            language: language.clone(),
+            utterance: 0,
+            words: tokens.words,
+        });
+    }
+    Ok((segments, language))
+}
+
+// Ratio of text length to its zlib-compressed length; repetition loops compress very well
+fn compression_ratio(text: &str) -> f32 {
+    if text.is_empty() {
+        return 0.0;
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    let compressed = encoder
+        .write_all(text.as_bytes())
+        .and_then(|_| encoder.finish());
+    match compressed {
+        Ok(compressed) if !compressed.is_empty() => text.len() as f32 / compressed.len() as f32,
+        _ => 0.0,
+    }
+}
+
+// Whether a decoded segment fails the quality gates and should be retried hotter
+fn needs_fallback(segment: &TranscriptionSegment, decoding: &DecodingOptions) -> bool {
+    if compression_ratio(&segment.text) > decoding.compression_ratio_threshold {
+        return true;
+    }
+    // Low confidence on what is probably silence isn't worth retrying
+    segment.avg_logprob < decoding.logprob_threshold
+        && segment.no_speech_probability <= decoding.no_speech_threshold
+}
+
+// Temperature fallback from the Whisper reference: a segment that is too repetitive
+// (compression ratio) or too uncertain (average log-probability) is re-decoded at
+// increasing temperatures until it passes or the ladder reaches 1.0
+fn apply_temperature_fallback(
+    ctx: &WhisperContext,
+    audio_samples: &[f32],
+    options: &InferenceOptions,
+    language: &str,
+    segments: Vec<TranscriptionSegment>,
+) -> Result<Vec<TranscriptionSegment>, String> {
+    let decoding = &options.decoding;
+    let samples_per_ms = WHISPER_SAMPLE_RATE as i64 / 1000;
+    // Keep the language of the first pass so retries don't re-detect it from a short clip
+    let retry_options = options.for_language(language);
+
+    let mut kept = Vec::with_capacity(segments.len());
+    for segment in segments {
+        if !needs_fallback(&segment, decoding) {
+            kept.push(segment);
+            continue;
+        }
+
+        let start_ms = (segment.start_ms - UTTERANCE_PADDING_MS).max(0);
+        let start = (start_ms * samples_per_ms) as usize;
+        let end = (((segment.end_ms + UTTERANCE_PADDING_MS) * samples_per_ms) as usize)
+            .min(audio_samples.len());
+        let Some(utterance) = audio_samples.get(start..end).filter(|u| !u.is_empty()) else {
+            kept.push(segment);
+            continue;
+        };
+
+        println!(
+            "Segment '{}' failed quality gates (compression {:.2}, logprob {:.2}), retrying",
+            segment.text,
+            compression_ratio(&segment.text),
+            segment.avg_logprob
+        );
+
+        // When every temperature fails, keep the most confident attempt
+        let mut best_logprob = segment.avg_logprob;
+        let mut best = vec![segment];
+        let steps =
+            ((1.0 - decoding.temperature) / decoding.temperature_increment + 1e-3).floor() as u32;
+        for step in 1..=steps {
+            let temperature = decoding.temperature + decoding.temperature_increment * step as f32;
+            let (mut attempt, _) = decode_pass(ctx, utterance, &retry_options, temperature, None)?;
+            for s in &mut attempt {
+                s.shift(start_ms);
+            }
+
+            if !attempt.is_empty() && !attempt.iter().any(|s| needs_fallback(s, decoding)) {
+                println!("Segment accepted at temperature {:.1}", temperature);
+                best = attempt;
+                break;
+            }
+            let logprob = attempt
+                .iter()
+                .map(|s| s.avg_logprob)
+                .fold(f32::INFINITY, f32::min);
+            if !attempt.is_empty() && logprob > best_logprob {
+                best_logprob = logprob;
+                best = attempt;
+            }
+        }
+        kept.extend(best);
+    }
+    Ok(kept)
+}
+
+fn get_models_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    println!("Getting models directory path...");
+
+    let app_data = app_handle.path().app_data_dir().map_err(|e| {
+        let error_msg = format!("Failed to get app data directory: {}", e);
+        println!("ERROR: {}", error_msg);
+        error_msg
+    })?;
+
+    println!("App data directory: {:?}", app_data);
+
+    let models_dir = app_data.join("whisper_models");
+    println!("Target models directory: {:?}", models_dir);
+
+    // Ensure parent directory exists and handle conflicts
+    if let Some(parent) = models_dir.parent() {
+        if parent.exists() {
+            if parent.is_file() {
+                println!("Found conflicting file at parent directory path, removing...");
+                fs::remove_file(parent).map_err(|e| {
+                    let error_msg = format!("Failed to remove conflicting parent file: {}", e);
+                    println!("ERROR: {}", error_msg);
+                    error_msg
+                })?;
+                println!("Creating parent directory: {:?}", parent);
+                fs::create_dir_all(parent).map_err(|e| {
+                    let error_msg = format!("Failed to create parent directory: {}", e);
+                    println!("ERROR: {}", error_msg);
+                    error_msg
+                })?;
+            } else {
+                println!("Parent directory already exists: {:?}", parent);
+            }
+        } else {
+            println!("Creating parent directory: {:?}", parent);
             fs::create_dir_all(parent).map_err(|e| {
                 let error_msg = format!("Failed to create parent directory: {}", e);
                 println!("ERROR: {}", error_msg);
@@ -391,107 +1212,150 @@ fn get_models_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
     Ok(models_dir)
 }
 
-fn get_model_path(app_handle: &tauri::AppHandle, model_id: &str) -> Result<PathBuf, String> {
+pub fn get_model_path(app_handle: &tauri::AppHandle, model_id: &str) -> Result<PathBuf, String> {
     let models_dir = get_models_dir(app_handle)?;
     Ok(models_dir.join(model_id))
 }
 
-async fn download_file_from_huggingface(
-    app_handle: &tauri::AppHandle,
-    repo_id: &str,
-    filename: &str,
-    local_path: &PathBuf,
-    model_id: &str,
+// Download and verify one file of a model, re-fetching it if verification fails
+pub async fn download_model_file(
+    download: &ModelDownload<'_>,
+    model_info: &ModelConfig,
+    model_file: &ModelFile,
+    model_path: &std::path::Path,
 ) -> Result<(), String> {
-    let url = format!(
-        "https://huggingface.co/{}/resolve/main/{}",
-        repo_id, filename
-    );
-    println!("Downloading {} from {}", filename, url);
-
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
-
-    if !response.status().is_success() {
-        return Err(format!(
-            "HTTP error {}: {}",
-            response.status(),
-            response.status().canonical_reason().unwrap_or("Unknown")
-        ));
+    let filename = model_file.name.as_str();
+    let local_path = model_path.join(filename);
+    let url = model_info.file_url(download.settings(), filename);
+    println!("Processing file {} -> {:?}", filename, local_path);
+
+    // Skip if file already exists with the expected size
+    if local_path.exists() {
+        let file_size = fs::metadata(&local_path).map(|m| m.len()).unwrap_or(0);
+        if is_model_file_complete(&local_path, model_file) {
+            println!(
+                "File {} already exists ({} bytes), skipping",
+                filename, file_size
+            );
+            download.skip_file(filename, model_file.size);
+            return Ok(());
+        } else {
+            println!(
+                "File {} is incomplete ({} of {} bytes), re-downloading",
+                filename, file_size, model_file.size
+            );
+        }
     }
 
-    let total_size = response.content_length().unwrap_or(0);
-    println!("File size: {} bytes", total_size);
-
-    let mut file =
-        fs::File::create(local_path).map_err(|e| format!("Failed to create file: {}", e))?;
-
-    let mut stream = response.bytes_stream();
-    let mut downloaded = 0u64;
-
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| format!("Failed to read chunk: {}", e))?;
-        file.write_all(&chunk)
-            .map_err(|e| format!("Failed to write chunk: {}", e))?;
-
-        downloaded += chunk.len() as u64;
-        if total_size > 0 {
-            let progress = (downloaded as f64 / total_size as f64) * 100.0;
+    let expected_sha256 = match &model_file.sha256 {
+        Some(sha) => Some(sha.to_lowercase()),
+        None => fetch_expected_sha256(download.settings(), &url).await,
+    };
+    if expected_sha256.is_none() {
+        println!(
+            "Warning: No checksum available for {}, relying on size check only",
+            filename
+        );
+    }
 
-            // Emit progress event to frontend
-            let progress_payload = serde_json::json!({
-                "model": model_id,
-                "file": filename,
-                "progress": progress,
-                "downloaded": downloaded,
-                "total": total_size
+    // Downloaded under a temporary name and renamed once verified, so an interrupted
+    // download (chunked ones are preallocated to full size) never looks complete
+    let partial = model_path.join(format!("{}.part", filename));
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let result = download
+            .download_file(&url, filename, model_file.size, &partial)
+            .await;
+
+        let verification = result
+            .and_then(|actual_sha256| {
+                if !is_model_file_complete(&partial, model_file) {
+                    let file_size = fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+                    return Err(format!(
+                        "Downloaded {} has unexpected size ({} bytes, expected {})",
+                        filename, file_size, model_file.size
+                    ));
+                }
+                match &expected_sha256 {
+                    Some(expected) if *expected != actual_sha256 => Err(format!(
+                        "Checksum mismatch for {} (got {}, expected {})",
+                        filename, actual_sha256, expected
+                    )),
+                    _ => Ok(()),
+                }
+            })
+            .and_then(|()| {
+                fs::rename(&partial, &local_path)
+                    .map_err(|e| format!("Failed to finalize {}: {}", filename, e))
             });
 
-            let _ = app_handle.emit("download-progress", &progress_payload);
-
-            if downloaded % (1024 * 1024) == 0 || downloaded == total_size {
-                // Log every MB or at completion
+        match verification {
+            Ok(()) => {
+                println!("Successfully downloaded and verified file: {}", filename);
+                download.finish_file(filename, model_file.size);
+                return Ok(());
+            }
+            Err(e) => {
+                println!("ERROR: Failed to download {}: {}", filename, e);
+                // Remove partial or corrupted file if it exists
+                if partial.exists() {
+                    let _ = fs::remove_file(&partial);
+                }
+                if e == DOWNLOAD_CANCELLED {
+                    return Err(e);
+                }
+                if attempt >= MAX_DOWNLOAD_ATTEMPTS {
+                    return Err(format!("Failed to download {}: {}", filename, e));
+                }
                 println!(
-                    "Progress: {:.1}% ({}/{} bytes)",
-                    progress, downloaded, total_size
+                    "Retrying download of {} (attempt {}/{})",
+                    filename,
+                    attempt + 1,
+                    MAX_DOWNLOAD_ATTEMPTS
                 );
             }
         }
     }
-
-    println!(
-        "Successfully downloaded {} ({} bytes)",
-        filename, downloaded
-    );
-    Ok(())
 }
 
 #[tauri::command]
 pub async fn whisper_download_model(
     app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
     model: String,
 ) -> Result<bool, String> {
     println!("=== WHISPER MODEL DOWNLOAD START ===");
     println!("Downloading Whisper model: {}", model);
 
     // Validate model exists
-    let model_info = MODEL_CONFIGS
-        .iter()
-        .find(|(id, _, _)| *id == model)
-        .ok_or_else(|| {
-            let error_msg = format!("Unknown model: {}", model);
-            println!("ERROR: {}", error_msg);
-            error_msg
-        })?;
+    let model_info = find_model_config(&model).map_err(|e| {
+        println!("ERROR: {}", e);
+        e
+    })?;
 
-    let (model_id, repo_id, files_to_download) = *model_info;
+    let registration = state
+        .downloads
+        .register(&model_info.id)
+        .map_err(|_| format!("Model {} is already being downloaded", model_info.id))?;
+    let cancel = registration.flag();
+    let download_settings = state
+        .download_settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone();
+
+    let model_id = model_info.id.as_str();
+    let repo_id = model_info.repo_id.as_str();
+    let files_to_download = &model_info.files;
     println!(
         "Model info: id={}, repo_id={}, files={:?}",
-        model_id, repo_id, files_to_download
+        model_id,
+        repo_id,
+        files_to_download
+            .iter()
+            .map(|f| &f.name)
+            .collect::<Vec<_>>()
     );
 
     // Get model path
@@ -523,175 +1387,830 @@ pub async fn whisper_download_model(
         println!("Model directory already exists");
     }
 
-    println!(
-        "Downloading {} files from Hugging Face...",
-        files_to_download.len()
-    );
+    // Remove leftovers from the old safetensors layout, whisper.cpp can't load them
+    if let Ok(entries) = fs::read_dir(&model_path) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("safetensors") {
+                println!("Removing legacy model file: {:?}", path);
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
 
-    // Download each file
-    for (i, filename) in files_to_download.iter().enumerate() {
-        let local_path = model_path.join(filename);
+    if !is_revision_current(&model_path, &model_info) {
         println!(
-            "Processing file {}/{}: {} -> {:?}",
-            i + 1,
-            files_to_download.len(),
-            filename,
-            local_path
+            "Model {} is not at the pinned revision {:?}, verifying files",
+            model_id, model_info.revision
         );
+        // Loaded weights may come from a file about to be replaced
+        state.models.remove(model_id)?;
+        discard_stale_files(&download_settings, &model_info, &model_path).await?;
+    }
 
-        // Skip if file already exists and is not empty
-        if local_path.exists() {
-            let file_size = fs::metadata(&local_path).map(|m| m.len()).unwrap_or(0);
-            if file_size > 0 {
-                println!(
-                    "File {} already exists ({} bytes), skipping",
-                    filename, file_size
-                );
-                continue;
-            } else {
-                println!("File {} exists but is empty, re-downloading", filename);
-            }
-        }
+    ensure_free_space(&app_handle, model_id, &model_path, files_to_download.iter())?;
 
-        // Download the file
-        match download_file_from_huggingface(&app_handle, repo_id, filename, &local_path, model_id)
-            .await
-        {
-            Ok(()) => {
-                println!("Successfully downloaded file: {}", filename);
-            }
-            Err(e) => {
-                println!("ERROR: Failed to download {}: {}", filename, e);
-                // Remove partial file if it exists
-                if local_path.exists() {
-                    let _ = fs::remove_file(&local_path);
-                }
-                return Err(format!("Failed to download {}: {}", filename, e));
-            }
+    println!(
+        "Downloading {} files from Hugging Face ({} connections)...",
+        files_to_download.len(),
+        download_settings.connections()
+    );
+    let total_bytes = files_to_download.iter().map(|f| f.size).sum();
+    let download = ModelDownload::new(
+        &app_handle,
+        &download_settings,
+        model_id,
+        total_bytes,
+        &cancel,
+    )?;
+
+    // Fetch all files concurrently; the connection limit is enforced by the download
+    let result =
+        try_join_all(files_to_download.iter().map(|model_file| {
+            download_model_file(&download, &model_info, model_file, &model_path)
+        }))
+        .await;
+
+    if let Err(e) = result {
+        if registration.is_cancelled() {
+            let cancel_payload = serde_json::json!({
+                "model": model_id
+            });
+            let _ = app_handle.emit("download-cancelled", &cancel_payload);
+            return Err(DOWNLOAD_CANCELLED.to_string());
         }
+        return Err(e);
     }
+    record_revision(&model_path, &model_info)?;
 
     println!("=== WHISPER MODEL DOWNLOAD COMPLETE ===");
     println!("Model {} downloaded successfully", model_id);
     Ok(true)
 }
 
+// Check every file of a model against its published size and checksum and
+// re-download only the broken or missing ones. Returns the names of the repaired files.
 #[tauri::command]
-pub async fn whisper_is_model_downloaded(
+pub async fn whisper_repair_model(
     app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
     model: String,
-) -> Result<bool, String> {
-    let model_path = get_model_path(&app_handle, &model)?;
+) -> Result<Vec<String>, String> {
+    println!("=== WHISPER MODEL REPAIR START ===");
+    let model_info = find_model_config(&model)?;
+
+    let registration = state
+        .downloads
+        .register(&model_info.id)
+        .map_err(|_| format!("Model {} is already being downloaded", model_info.id))?;
+    let cancel = registration.flag();
+    let download_settings = state
+        .download_settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone();
+
+    let model_id = model_info.id.as_str();
+    let model_path = get_model_path(&app_handle, model_id)?;
+    fs::create_dir_all(&model_path)
+        .map_err(|e| format!("Failed to create model directory: {}", e))?;
+
+    let mut broken = Vec::new();
+    for model_file in &model_info.files {
+        let local_path = model_path.join(&model_file.name);
+        if !is_model_file_complete(&local_path, model_file) {
+            let file_size = fs::metadata(&local_path).map(|m| m.len()).unwrap_or(0);
+            println!(
+                "File {} is missing or truncated ({} of {} bytes)",
+                model_file.name, file_size, model_file.size
+            );
+            broken.push(model_file);
+            continue;
+        }
 
-    if !model_path.exists() {
-        return Ok(false);
+        let expected_sha256 = match &model_file.sha256 {
+            Some(sha) => Some(sha.to_lowercase()),
+            None => {
+                let url = model_info.file_url(&download_settings, &model_file.name);
+                fetch_expected_sha256(&download_settings, &url).await
+            }
+        };
+        let Some(expected_sha256) = expected_sha256 else {
+            println!(
+                "No checksum available for {}, size matches so keeping it",
+                model_file.name
+            );
+            continue;
+        };
+        let actual_sha256 = hash_file(local_path.clone()).await?;
+        if actual_sha256 != expected_sha256 {
+            println!(
+                "Checksum mismatch for {} (got {}, expected {})",
+                model_file.name, actual_sha256, expected_sha256
+            );
+            broken.push(model_file);
+        } else {
+            println!("Verified {}", model_file.name);
+        }
     }
 
-    // Get the model config to check required files
-    let model_info = MODEL_CONFIGS
-        .iter()
-        .find(|(id, _, _)| *id == model)
-        .ok_or_else(|| format!("Unknown model: {}", model))?;
-
-    let (_, _, required_files) = *model_info;
+    if broken.is_empty() {
+        println!("Model {} is intact, nothing to repair", model_id);
+        record_revision(&model_path, &model_info)?;
+        return Ok(Vec::new());
+    }
 
-    // Check if all required files exist and are not empty
-    for file in required_files {
-        let file_path = model_path.join(file);
-        if !file_path.exists() {
-            return Ok(false);
+    // Loaded weights may come from a file about to be replaced
+    state.models.remove(model_id)?;
+    for model_file in &broken {
+        let local_path = model_path.join(&model_file.name);
+        if local_path.exists() {
+            fs::remove_file(&local_path)
+                .map_err(|e| format!("Failed to remove {}: {}", model_file.name, e))?;
         }
+    }
 
-        // Check file size - real model files should be larger than 1KB
-        let file_size = fs::metadata(&file_path).map(|m| m.len()).unwrap_or(0);
-
-        if file_size == 0 {
-            return Ok(false);
-        }
+    ensure_free_space(&app_handle, model_id, &model_path, broken.iter().copied())?;
 
-        // For GGML model files, expect significant size (at least 10MB for tiny model)
-        if file.ends_with(".bin") && file_size < 10_000_000 {
-            return Ok(false);
+    println!(
+        "Re-downloading {} broken file(s) of {}",
+        broken.len(),
+        model_id
+    );
+    let total_bytes = broken.iter().map(|f| f.size).sum();
+    let download = ModelDownload::new(
+        &app_handle,
+        &download_settings,
+        model_id,
+        total_bytes,
+        &cancel,
+    )?;
+    let result =
+        try_join_all(broken.iter().map(|model_file| {
+            download_model_file(&download, &model_info, model_file, &model_path)
+        }))
+        .await;
+
+    if let Err(e) = result {
+        if registration.is_cancelled() {
+            let cancel_payload = serde_json::json!({
+                "model": model_id
+            });
+            let _ = app_handle.emit("download-cancelled", &cancel_payload);
+            return Err(DOWNLOAD_CANCELLED.to_string());
         }
+        return Err(e);
     }
+    record_revision(&model_path, &model_info)?;
 
-    Ok(true)
+    println!("=== WHISPER MODEL REPAIR COMPLETE ===");
+    Ok(broken.iter().map(|f| f.name.clone()).collect())
 }
 
 #[tauri::command]
-pub async fn whisper_get_downloaded_models(
-    app_handle: tauri::AppHandle,
-) -> Result<Vec<String>, String> {
-    let models_dir = get_models_dir(&app_handle)?;
-    let mut downloaded_models = Vec::new();
-
-    if !models_dir.exists() {
-        return Ok(downloaded_models);
-    }
-
-    let entries =
-        fs::read_dir(&models_dir).map_err(|e| format!("Failed to read models directory: {}", e))?;
+pub fn whisper_get_download_settings(
+    state: State<'_, WhisperAppState>,
+) -> Result<DownloadSettings, String> {
+    Ok(state
+        .download_settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
 
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-        let path = entry.path();
+#[tauri::command]
+pub fn whisper_set_download_settings(
+    state: State<'_, WhisperAppState>,
+    settings: DownloadSettings,
+) -> Result<(), String> {
+    // Validate the proxy up front instead of failing on the next download
+    settings.client_builder()?;
 
-        if path.is_dir() {
-            if let Some(model_name) = path.file_name().and_then(|n| n.to_str()) {
-                // Check if this model is fully downloaded.
-                // Use if let Ok(true) instead of ? so that unrecognized directory
-                // names (e.g. tmp folders, old model IDs, .DS_Store) return Err
-                // and are silently skipped rather than aborting the whole scan.
-                if let Ok(true) = whisper_is_model_downloaded(app_handle.clone(), model_name.to_string()).await {
-                    downloaded_models.push(model_name.to_string());
-                }
-            }
-        }
+    if let Some(limit) = settings.max_bytes_per_second.filter(|&limit| limit > 0) {
+        println!("Model downloads capped at {} KB/s", limit / 1024);
     }
+    *state
+        .download_settings
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = settings;
+    println!("Updated model download settings");
+    Ok(())
+}
 
-    Ok(downloaded_models)
+#[tauri::command]
+pub fn whisper_get_decoding_options(
+    state: State<'_, WhisperAppState>,
+) -> Result<DecodingOptions, String> {
+    Ok(state
+        .decoding
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
 }
 
 #[tauri::command]
-pub async fn whisper_transcribe(
-    app_handle: tauri::AppHandle,
+pub fn whisper_set_decoding_options(
     state: State<'_, WhisperAppState>,
-    audio_data: Vec<u8>,
-    model: String,
-    language: String,
-) -> Result<String, String> {
-    println!("=== WHISPER TRANSCRIPTION START ===");
+    options: DecodingOptions,
+) -> Result<(), String> {
+    options.validate()?;
+    println!("Updated decoding options: {:?}", options);
+    *state
+        .decoding
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = options;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn whisper_get_hallucination_filter(
+    state: State<'_, WhisperAppState>,
+) -> Result<HallucinationFilter, String> {
+    Ok(state
+        .hallucination_filter
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+#[tauri::command]
+pub fn whisper_set_hallucination_filter(
+    state: State<'_, WhisperAppState>,
+    filter: HallucinationFilter,
+) -> Result<(), String> {
+    filter.validate()?;
+    println!("Updated hallucination filter: {:?}", filter);
+    *state
+        .hallucination_filter
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = filter;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn whisper_get_profanity_filter(
+    state: State<'_, WhisperAppState>,
+) -> Result<ProfanityFilter, String> {
+    Ok(state
+        .profanity_filter
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+#[tauri::command]
+pub fn whisper_set_profanity_filter(
+    state: State<'_, WhisperAppState>,
+    filter: ProfanityFilter,
+) -> Result<(), String> {
     println!(
-        "Model: {}, Language: {}, Audio Size: {}",
-        model,
-        language,
-        audio_data.len()
+        "Updated profanity filter: enabled {}, {:?}, {} custom words",
+        filter.enabled,
+        filter.mode,
+        filter.custom_words.len()
     );
+    *state
+        .profanity_filter
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = filter;
+    Ok(())
+}
 
-    // Process audio data first
-    validate_audio_data(&audio_data)?;
-    let audio_samples = process_audio_for_whisper(&audio_data)?;
-
-    // Check speech activity (lightweight check before locking)
-    match detect_speech_activity(&audio_samples) {
-        Ok(has_speech) => {
-            if !has_speech {
-                println!("No speech detected, skipping inference");
-                return Ok("".to_string());
+#[tauri::command]
+pub fn whisper_get_text_normalization(
+    state: State<'_, WhisperAppState>,
+) -> Result<TextNormalizer, String> {
+    Ok(state
+        .text_normalizer
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+#[tauri::command]
+pub fn whisper_set_text_normalization(
+    state: State<'_, WhisperAppState>,
+    normalizer: TextNormalizer,
+) -> Result<(), String> {
+    println!(
+        "Updated text normalization: enabled {}, small numbers {}",
+        normalizer.enabled, normalizer.convert_small_numbers
+    );
+    *state
+        .text_normalizer
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = normalizer;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn whisper_get_denoise_sources(
+    state: State<'_, WhisperAppState>,
+) -> Result<Vec<String>, String> {
+    let mut sources: Vec<String> = state
+        .denoise_sources
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .iter()
+        .cloned()
+        .collect();
+    sources.sort();
+    Ok(sources)
+}
+
+#[tauri::command]
+pub fn whisper_set_denoise(
+    state: State<'_, WhisperAppState>,
+    source: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut sources = state
+        .denoise_sources
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+    if enabled {
+        sources.insert(source.clone());
+    } else {
+        sources.remove(&source);
+    }
+    println!(
+        "Denoising {} for source '{}'",
+        if enabled { "enabled" } else { "disabled" },
+        source
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn whisper_get_vad_settings(state: State<'_, WhisperAppState>) -> Result<VadConfig, String> {
+    Ok(state
+        .vad
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone())
+}
+
+// Takes effect on the next transcription and immediately on running native capture
+#[tauri::command]
+pub fn whisper_set_vad_settings(
+    state: State<'_, WhisperAppState>,
+    settings: VadConfig,
+) -> Result<(), String> {
+    settings.validate()?;
+    println!(
+        "VAD: min energy {}, min speech {}ms, hangover {}ms, max segment {}ms",
+        settings.min_energy, settings.min_speech_ms, settings.hangover_ms, settings.max_segment_ms
+    );
+    *state
+        .vad
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))? = settings;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn whisper_cancel_download(
+    state: State<'_, WhisperAppState>,
+    model: String,
+) -> Result<bool, String> {
+    let cancelled = state.downloads.cancel(&model)?;
+    if cancelled {
+        println!("Cancelling download of model {}", model);
+    }
+    Ok(cancelled)
+}
+
+#[tauri::command]
+pub async fn whisper_is_model_downloaded(
+    app_handle: tauri::AppHandle,
+    model: String,
+) -> Result<bool, String> {
+    let model_path = get_model_path(&app_handle, &model)?;
+
+    if !model_path.exists() {
+        return Ok(false);
+    }
+
+    // Get the model config to check required files
+    let model_info = find_model_config(&model)?;
+
+    // Check if all required files exist with their published sizes
+    for file in &model_info.files {
+        let file_path = model_path.join(&file.name);
+        if !file_path.exists() {
+            return Ok(false);
+        }
+
+        if !is_model_file_complete(&file_path, file) {
+            return Ok(false);
+        }
+    }
+
+    Ok(is_revision_current(&model_path, &model_info))
+}
+
+#[tauri::command]
+pub async fn whisper_get_downloaded_models(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<String>, String> {
+    let models_dir = get_models_dir(&app_handle)?;
+    let mut downloaded_models = Vec::new();
+
+    if !models_dir.exists() {
+        return Ok(downloaded_models);
+    }
+
+    let entries =
+        fs::read_dir(&models_dir).map_err(|e| format!("Failed to read models directory: {}", e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let path = entry.path();
+
+        if path.is_dir() {
+            if let Some(model_name) = path.file_name().and_then(|n| n.to_str()) {
+                // Check if this model is fully downloaded.
+                // Use if let Ok(true) instead of ? so that unrecognized directory
+                // names (e.g. tmp folders, old model IDs, .DS_Store) return Err
+                // and are silently skipped rather than aborting the whole scan.
+                if let Ok(true) =
+                    whisper_is_model_downloaded(app_handle.clone(), model_name.to_string()).await
+                {
+                    downloaded_models.push(model_name.to_string());
+                }
+            }
+        }
+    }
+
+    Ok(downloaded_models)
+}
+
+// Total size in bytes of a file or directory tree
+fn path_size(path: &std::path::Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+
+    if metadata.is_dir() {
+        fs::read_dir(path)
+            .map(|entries| entries.flatten().map(|e| path_size(&e.path())).sum())
+            .unwrap_or(0)
+    } else {
+        metadata.len()
+    }
+}
+
+#[tauri::command]
+pub async fn whisper_delete_model(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    model: String,
+) -> Result<u64, String> {
+    println!("Deleting Whisper model: {}", model);
+
+    let models_dir = get_models_dir(&app_handle)?
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve models directory: {}", e))?;
+    let model_path = models_dir.join(&model);
+
+    if !model_path.exists() {
+        return Err(format!("Model {} is not downloaded", model));
+    }
+
+    // Refuse anything that resolves outside whisper_models (e.g. "../" or symlinks)
+    let model_path = model_path
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve model path: {}", e))?;
+    if model_path == models_dir || !model_path.starts_with(&models_dir) {
+        return Err(format!(
+            "Refusing to delete '{}': not inside the models directory",
+            model_path.display()
+        ));
+    }
+
+    // Release the cached context if it belongs to this model
+    state.models.remove(&model)?;
+
+    let freed_bytes = path_size(&model_path);
+    if model_path.is_dir() {
+        fs::remove_dir_all(&model_path)
+    } else {
+        fs::remove_file(&model_path)
+    }
+    .map_err(|e| format!("Failed to delete model {}: {}", model, e))?;
+
+    println!("Deleted model {} ({} bytes freed)", model, freed_bytes);
+    Ok(freed_bytes)
+}
+
+// GGML weights start with the "ggml" magic stored little-endian, GGUF with "GGUF"
+fn has_weights_magic(path: &std::path::Path) -> bool {
+    let mut magic = [0u8; 4];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok()
+        && (&magic == b"lmgg" || &magic == b"GGUF")
+}
+
+// Find the file in a user-provided model that corresponds to a catalog file. Other
+// tools often rename the weights, so a lone file is matched by size alone.
+fn find_import_source(
+    source: &std::path::Path,
+    file: &ModelFile,
+    single_file: bool,
+) -> Result<PathBuf, String> {
+    if source.is_file() {
+        if !single_file {
+            return Err(format!(
+                "Expected a directory containing {}, got a single file",
+                file.name
+            ));
+        }
+        return Ok(source.to_path_buf());
+    }
+
+    let candidate = source.join(&file.name);
+    if candidate.is_file() {
+        return Ok(candidate);
+    }
+    if single_file {
+        let entries =
+            fs::read_dir(source).map_err(|e| format!("Failed to read directory: {}", e))?;
+        let renamed = entries.flatten().map(|entry| entry.path()).find(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(is_weights_file)
+                && fs::metadata(path).is_ok_and(|m| m.len() == file.size)
+        });
+        if let Some(path) = renamed {
+            return Ok(path);
+        }
+    }
+    Err(format!("{} not found in {}", file.name, source.display()))
+}
+
+#[cfg(unix)]
+fn link_model_file(source: &std::path::Path, target: &std::path::Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(source, target)
+}
+
+// Needs Developer Mode or admin rights on Windows; callers fall back to copying
+#[cfg(windows)]
+fn link_model_file(source: &std::path::Path, target: &std::path::Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(source, target)
+}
+
+// Import a model the user already has on disk (a GGML/GGUF file, or a directory
+// holding the model's files) instead of downloading it. Files are checked against the
+// catalog sizes, then copied into the models directory, or symlinked when `symlink`
+// is set to avoid duplicating gigabytes.
+#[tauri::command]
+pub async fn whisper_import_model(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    path: String,
+    model_id: String,
+    symlink: Option<bool>,
+) -> Result<(), String> {
+    println!("Importing Whisper model {} from {}", model_id, path);
+    let model_info = find_model_config(&model_id)?;
+
+    let source = PathBuf::from(&path)
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve '{}': {}", path, e))?;
+    let single_file = model_info.files.len() == 1;
+
+    // Validate everything before touching the models directory
+    let mut sources = Vec::with_capacity(model_info.files.len());
+    for file in &model_info.files {
+        let file_source = find_import_source(&source, file, single_file)?
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", file.name, e))?;
+        if !is_model_file_complete(&file_source, file) {
+            let size = fs::metadata(&file_source).map(|m| m.len()).unwrap_or(0);
+            return Err(format!(
+                "{} is {} bytes but {} expects {} bytes (different model, quantization or incomplete file)",
+                file_source.display(),
+                size,
+                model_id,
+                file.size
+            ));
+        }
+        if is_weights_file(&file.name) && !has_weights_magic(&file_source) {
+            return Err(format!(
+                "{} is not a GGML/GGUF model file",
+                file_source.display()
+            ));
+        }
+        sources.push((file_source, file.name.clone()));
+    }
+
+    let model_path = get_model_path(&app_handle, &model_id)?;
+    fs::create_dir_all(&model_path)
+        .map_err(|e| format!("Failed to create model directory: {}", e))?;
+    // Drop a cached context so the imported weights are used on next load
+    state.models.remove(&model_id)?;
+
+    let symlink = symlink.unwrap_or(false);
+    tokio::task::spawn_blocking(move || {
+        for (file_source, name) in sources {
+            let target = model_path.join(&name);
+            // Re-importing the file that's already in place would delete it
+            if target.canonicalize().is_ok_and(|t| t == file_source) {
+                continue;
+            }
+            if fs::symlink_metadata(&target).is_ok() {
+                fs::remove_file(&target)
+                    .map_err(|e| format!("Failed to replace {}: {}", name, e))?;
             }
+
+            if symlink {
+                match link_model_file(&file_source, &target) {
+                    Ok(()) => {
+                        println!("Linked {} -> {}", target.display(), file_source.display());
+                        continue;
+                    }
+                    Err(e) => println!("Symlink failed ({}), copying {} instead", e, name),
+                }
+            }
+
+            // Copy under a temporary name so an interrupted copy never looks complete
+            let partial = model_path.join(format!("{}.part", name));
+            fs::copy(&file_source, &partial)
+                .map_err(|e| format!("Failed to copy {}: {}", name, e))?;
+            fs::rename(&partial, &target)
+                .map_err(|e| format!("Failed to finalize {}: {}", name, e))?;
+            println!("Copied {} to {}", file_source.display(), target.display());
+        }
+        Ok::<_, String>(())
+    })
+    .await
+    .map_err(|e| format!("Task join error: {:?}", e))??;
+
+    println!("Imported model {}", model_id);
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct ModelStorage {
+    pub model: String,
+    pub bytes: u64,
+}
+
+#[derive(Serialize)]
+pub struct StorageInfo {
+    pub models: Vec<ModelStorage>,
+    pub total_bytes: u64,
+    // Space left on the volume holding whisper_models
+    pub free_bytes: u64,
+}
+
+#[tauri::command]
+pub async fn whisper_get_storage_info(app_handle: tauri::AppHandle) -> Result<StorageInfo, String> {
+    let models_dir = get_models_dir(&app_handle)?;
+
+    let mut models = Vec::new();
+    let entries =
+        fs::read_dir(&models_dir).map_err(|e| format!("Failed to read models directory: {}", e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
         }
-        Err(e) => println!("Warning: Speech detection failed: {}", e),
+        if let Some(model_name) = path.file_name().and_then(|n| n.to_str()) {
+            models.push(ModelStorage {
+                model: model_name.to_string(),
+                bytes: path_size(&path),
+            });
+        }
+    }
+    models.sort_by(|a, b| a.model.cmp(&b.model));
+
+    let total_bytes = models.iter().map(|m| m.bytes).sum();
+    let free_bytes = fs2::available_space(&models_dir)
+        .map_err(|e| format!("Failed to query free disk space: {}", e))?;
+
+    Ok(StorageInfo {
+        models,
+        total_bytes,
+        free_bytes,
+    })
+}
+
+// Callback invoked for each decoded segment while inference is still running
+type SegmentCallback = Box<dyn FnMut(SegmentCallbackData) + Send + 'static>;
+
+// Decode audio and check it contains speech. Returns None when inference can be skipped.
+pub fn prepare_audio(
+    audio_data: &[u8],
+    denoise: bool,
+    vad: &VadConfig,
+) -> Result<Option<SpeechAudio>, String> {
+    // Process audio data first
+    validate_audio_data(audio_data)?;
+    let audio_samples = process_audio_for_whisper(audio_data)?;
+    Ok(prepare_samples(&audio_samples, denoise, vad))
+}
+
+// Longest PCM buffer accepted, matching the ~20MB cap on encoded audio
+const MAX_PCM_SECONDS: usize = 600;
+
+// Validate raw f32 PCM from the capture pipeline and bring it to 16kHz
+fn process_pcm_for_whisper(samples: Vec<f32>, sample_rate: u32) -> Result<Vec<f32>, String> {
+    if samples.is_empty() {
+        return Err("Audio data is empty".to_string());
+    }
+    if !(8_000..=192_000).contains(&sample_rate) {
+        return Err(format!("Unsupported sample rate: {}Hz", sample_rate));
+    }
+    if samples.len() > sample_rate as usize * MAX_PCM_SECONDS {
+        return Err(format!(
+            "Audio data too long (>{} seconds)",
+            MAX_PCM_SECONDS
+        ));
+    }
+
+    // NaN/inf would poison the mel spectrogram, treat them as silence
+    let samples: Vec<f32> = samples
+        .into_iter()
+        .map(|s| {
+            if s.is_finite() {
+                s.clamp(-1.0, 1.0)
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    Ok(resample_to_whisper_rate(samples, sample_rate))
+}
+
+// VAD-trim 16kHz samples. Returns None when there's no speech and inference can be skipped.
+pub fn prepare_samples(
+    audio_samples: &[f32],
+    denoise: bool,
+    vad: &VadConfig,
+) -> Option<SpeechAudio> {
+    // Denoise first so fans and keyboards don't register as speech in the VAD
+    let denoised;
+    let audio_samples = if denoise {
+        let started = Instant::now();
+        denoised = denoise_samples(audio_samples, WHISPER_SAMPLE_RATE);
+        println!("Denoised audio in {}ms", started.elapsed().as_millis());
+        &denoised
+    } else {
+        audio_samples
+    };
+
+    // Only forward speech regions so Whisper never decodes long stretches of silence
+    let speech_samples = extract_speech(audio_samples, WHISPER_SAMPLE_RATE, vad);
+    if speech_samples.is_empty() {
+        println!("No speech detected, skipping inference");
+        return None;
     }
 
-    println!("Speech detected. Preparing inference...");
+    println!(
+        "Speech detected ({} of {} samples kept). Preparing inference...",
+        speech_samples.samples.len(),
+        audio_samples.len()
+    );
+    Some(speech_samples)
+}
+
+// Load (or reuse) the requested model and run `f` on it in a blocking task
+pub async fn run_with_context<T, F>(
+    app_handle: tauri::AppHandle,
+    state: &WhisperAppState,
+    model: String,
+    f: F,
+) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&WhisperContext) -> Result<T, String> + Send + 'static,
+{
+    let loaded = ensure_model_loaded(app_handle, state, model).await?;
+
+    tokio::task::spawn_blocking(move || f(&loaded.ctx))
+        .await
+        .map_err(|e| format!("Task join error: {:?}", e))?
+}
+
+// Return the resident context for a model, loading it from disk on first use
+async fn ensure_model_loaded(
+    app_handle: tauri::AppHandle,
+    state: &WhisperAppState,
+    model: String,
+) -> Result<Arc<LoadedModel>, String> {
+    // Swap in a quantized variant when the requested one won't fit in VRAM
+    let model = resolve_quantization(&app_handle, state, &model).await?;
+
+    // Fast path: the model is already resident, no disk access at all
+    if let Some(loaded) = state.models.get(&model) {
+        return Ok(loaded);
+    }
 
     // Get model path (needed if we need to load)
     let model_path = get_model_path(&app_handle, &model)?;
-    let model_name = model_path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .ok_or_else(|| "Invalid model path".to_string())?;
-    let model_file = model_path.join(format!("ggml-{}.bin", model_name));
+    let model_file = model_path.join(model_weights_file(&model)?);
 
     if !model_file.exists() {
         return Err(format!("Model file missing: {:?}", model_file));
@@ -702,56 +2221,729 @@ pub async fn whisper_transcribe(
         .ok_or_else(|| "Invalid model path".to_string())?
         .to_string();
 
-    // Run in blocking task with state lock
-    let state_arc = state.state.clone();
-    let language_clone = language.clone();
+    let backend = *state
+        .backend
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+
+    let models = state.models.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let _loading = models.lock_loading()?;
+
+        // Another request may have loaded it while we waited for the lock
+        if let Some(loaded) = models.get(&model) {
+            return Ok(loaded);
+        }
+
+        println!("Loading Whisper model '{}' from disk...", model);
+        println!("Path: {}", model_file_str);
+        let (ctx, active_backend) = load_whisper_context(&model_file_str, backend)?;
+        let loaded = models.insert(LoadedModel {
+            model: model.clone(),
+            ctx,
+            backend: active_backend,
+        })?;
+        println!(
+            "Model '{}' loaded successfully on {} and cached.",
+            model,
+            active_backend.as_str()
+        );
+
+        let backend_payload = serde_json::json!({
+            "model": model,
+            "requested": backend,
+            "active": active_backend
+        });
+        let _ = app_handle.emit("whisper-backend", &backend_payload);
+
+        Ok(loaded)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {:?}", e))?
+}
+
+// Load a model and run a throwaway inference so the first real utterance doesn't
+// pay for weight loading and backend kernel initialization.
+#[tauri::command]
+pub async fn whisper_preload_model(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    model: String,
+    language: Option<String>,
+) -> Result<(), String> {
+    println!("Preloading Whisper model '{}'...", model);
+
+    let load_start = Instant::now();
+    let loaded = ensure_model_loaded(app_handle.clone(), &state, model.clone()).await?;
+    let load_ms = load_start.elapsed().as_millis() as u64;
+
+    let warmup_start = Instant::now();
+    let language = language.unwrap_or_else(|| "en".to_string());
+    let warmup_loaded = loaded.clone();
+    tokio::task::spawn_blocking(move || {
+        // One second of silence is enough to initialize every decoder stage
+        let silence = vec![0.0f32; WHISPER_SAMPLE_RATE as usize];
+        run_inference_on_context(
+            &warmup_loaded.ctx,
+            &silence,
+            InferenceOptions::new(&language),
+        )
+    })
+    .await
+    .map_err(|e| format!("Task join error: {:?}", e))??;
+    let warmup_ms = warmup_start.elapsed().as_millis() as u64;
+
+    println!(
+        "Model '{}' ready (load {}ms, warm-up {}ms)",
+        model, load_ms, warmup_ms
+    );
+    let ready_payload = serde_json::json!({
+        "model": model,
+        "backend": loaded.backend,
+        "load_ms": load_ms,
+        "warmup_ms": warmup_ms
+    });
+    let _ = app_handle.emit("model-ready", &ready_payload);
 
-    println!("Acquiring state lock and running inference...");
+    Ok(())
+}
+
+#[tauri::command]
+pub fn whisper_get_loaded_models(state: State<'_, WhisperAppState>) -> Result<Vec<String>, String> {
+    Ok(state.models.loaded_models())
+}
 
-    let model_clone = model.clone();
-    let transcription = tokio::task::spawn_blocking(move || {
-        // Lock the mutex - this serializes all inference requests
-        let mut guard = state_arc
+#[tauri::command]
+pub fn whisper_unload_model(
+    state: State<'_, WhisperAppState>,
+    model: String,
+) -> Result<bool, String> {
+    let unloaded = state.models.remove(&model)?;
+    if unloaded {
+        println!("Unloaded model '{}' from memory", model);
+    }
+    Ok(unloaded)
+}
+
+// Load (or reuse) the requested model and run inference on the given samples
+async fn run_transcription(
+    app_handle: tauri::AppHandle,
+    state: &WhisperAppState,
+    speech: SpeechAudio,
+    model: String,
+    options: InferenceOptions,
+) -> Result<TranscriptionResult, String> {
+    let source = options.source.clone();
+    let region_starts_ms = speech.region_starts_ms(WHISPER_SAMPLE_RATE);
+    let mut result = run_with_context(app_handle, state, model, move |ctx| {
+        run_inference_on_context(ctx, &speech.samples, options)
+    })
+    .await?;
+    group_utterances(&mut result, &region_starts_ms, &source);
+    Ok(result)
+}
+
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+
+// Register a transcription so it can be aborted with whisper_cancel, and tell the
+// frontend which ID it got when it didn't supply one itself
+pub fn register_job(
+    app_handle: &tauri::AppHandle,
+    state: &WhisperAppState,
+    job_id: Option<String>,
+) -> Result<CancelRegistration, String> {
+    let job_id =
+        job_id.unwrap_or_else(|| format!("job-{}", NEXT_JOB_ID.fetch_add(1, Ordering::SeqCst)));
+    let registration = state
+        .jobs
+        .register(&job_id)
+        .map_err(|_| format!("Transcription job {} already exists", job_id))?;
+
+    let _ = app_handle.emit("transcription-started", &job_id);
+    Ok(registration)
+}
+
+#[tauri::command]
+pub fn whisper_cancel(state: State<'_, WhisperAppState>, job_id: String) -> Result<bool, String> {
+    let cancelled = state.jobs.cancel(&job_id)?;
+    if cancelled {
+        println!("Cancelling transcription job {}", job_id);
+    }
+    Ok(cancelled)
+}
+
+#[derive(Serialize)]
+pub struct LanguageProbability {
+    pub language: String,
+    pub probability: f32,
+}
+
+// Only this much speech is needed for Whisper's language-ID pass
+const LANGUAGE_DETECTION_WINDOW_SECS: usize = 10;
+
+fn inference_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get().min(8))
+        .unwrap_or(4)
+}
+
+// Run the language-ID pass on a short window and return languages ranked by probability
+fn detect_language_on_context(
+    ctx: &WhisperContext,
+    audio_samples: &[f32],
+) -> Result<Vec<LanguageProbability>, String> {
+    let window = audio_samples
+        .len()
+        .min(LANGUAGE_DETECTION_WINDOW_SECS * WHISPER_SAMPLE_RATE as usize);
+    let threads = inference_threads();
+
+    let mut state = ctx
+        .create_state()
+        .map_err(|e| format!("Failed to create Whisper state: {:?}", e))?;
+    state
+        .pcm_to_mel(&audio_samples[..window], threads)
+        .map_err(|e| format!("Failed to compute mel spectrogram: {:?}", e))?;
+    let (_, probabilities) = state
+        .lang_detect(0, threads)
+        .map_err(|e| format!("Language detection failed: {:?}", e))?;
+
+    let mut ranked: Vec<LanguageProbability> = probabilities
+        .iter()
+        .enumerate()
+        .filter_map(|(id, &probability)| {
+            whisper_rs::get_lang_str(id as i32).map(|language| LanguageProbability {
+                language: language.to_string(),
+                probability,
+            })
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.probability
+            .partial_cmp(&a.probability)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    ranked.truncate(10);
+
+    Ok(ranked)
+}
+
+#[tauri::command]
+pub async fn whisper_detect_language(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    audio_data: Vec<u8>,
+    model: String,
+) -> Result<Vec<LanguageProbability>, String> {
+    println!("=== WHISPER LANGUAGE DETECTION START ===");
+
+    let vad = state
+        .vad
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .clone();
+    let Some(audio_samples) = prepare_audio(&audio_data, false, &vad)? else {
+        return Err("No speech detected in audio".to_string());
+    };
+
+    let ranked = run_with_context(app_handle, &state, model, move |ctx| {
+        detect_language_on_context(ctx, &audio_samples.samples)
+    })
+    .await?;
+
+    if let Some(top) = ranked.first() {
+        println!(
+            "Detected language: {} ({:.1}%)",
+            top.language,
+            top.probability * 100.0
+        );
+    }
+    Ok(ranked)
+}
+
+// "transcribe" keeps the spoken language, "translate" makes Whisper output English
+fn parse_task(task: Option<&str>) -> Result<bool, String> {
+    match task.unwrap_or("transcribe") {
+        "transcribe" => Ok(false),
+        "translate" => Ok(true),
+        other => Err(format!("Unknown Whisper task: {}", other)),
+    }
+}
+
+// Whisper only looks at roughly the last 224 prompt tokens, keep the tail that fits
+const MAX_INITIAL_PROMPT_CHARS: usize = 800;
+
+// Combine a free-form prompt with a vocabulary list (usernames, avatar names, slang)
+// into a single decoder prompt. Listing the terms makes Whisper prefer those spellings.
+fn build_initial_prompt(
+    initial_prompt: Option<String>,
+    vocabulary: Option<Vec<String>>,
+) -> Option<String> {
+    let vocabulary: Vec<String> = vocabulary
+        .unwrap_or_default()
+        .into_iter()
+        .map(|term| term.trim().to_string())
+        .filter(|term| !term.is_empty())
+        .collect();
+
+    let mut parts = Vec::new();
+    if !vocabulary.is_empty() {
+        parts.push(format!("{}.", vocabulary.join(", ")));
+    }
+    if let Some(prompt) = initial_prompt {
+        let prompt = prompt.trim();
+        if !prompt.is_empty() {
+            parts.push(prompt.to_string());
+        }
+    }
+    if parts.is_empty() {
+        return None;
+    }
+
+    let prompt = parts.join(" ");
+    let char_count = prompt.chars().count();
+    if char_count <= MAX_INITIAL_PROMPT_CHARS {
+        return Some(prompt);
+    }
+    Some(
+        prompt
+            .chars()
+            .skip(char_count - MAX_INITIAL_PROMPT_CHARS)
+            .collect(),
+    )
+}
+
+// Optional per-call settings accepted by the transcription commands
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TranscribeOptions {
+    // "transcribe" (default) or "translate"
+    pub task: Option<String>,
+    pub initial_prompt: Option<String>,
+    // Usernames, avatar names and slang Whisper should spell correctly
+    pub vocabulary: Option<Vec<String>>,
+    // Overrides the stored decoding options for this call only
+    pub decoding: Option<DecodingOptions>,
+    // Input source the audio came from, selects the denoise setting ("microphone" by default)
+    pub source: Option<String>,
+    // Overrides the per-source denoise setting for this call only
+    pub denoise: Option<bool>,
+}
+
+impl TranscribeOptions {
+    pub fn into_inference_options(
+        self,
+        state: &WhisperAppState,
+        language: &str,
+    ) -> Result<InferenceOptions, String> {
+        let decoding = match self.decoding {
+            Some(decoding) => decoding,
+            None => state
+                .decoding
+                .lock()
+                .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+                .clone(),
+        };
+        decoding.validate()?;
+
+        let source = self
+            .source
+            .unwrap_or_else(|| DEFAULT_AUDIO_SOURCE.to_string());
+        let denoise = match self.denoise {
+            Some(denoise) => denoise,
+            None => state
+                .denoise_sources
+                .lock()
+                .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+                .contains(&source),
+        };
+
+        let hallucination_filter = state
+            .hallucination_filter
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+            .clone();
+        let profanity_filter = state
+            .profanity_filter
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+            .clone();
+        let text_normalizer = state
+            .text_normalizer
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+            .clone();
+        let corrections = state
+            .corrections
             .lock()
-            .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+            .clone();
+        let vad = state
+            .vad
+            .lock()
+            .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+            .clone();
+
+        let mut options = InferenceOptions::new(language);
+        options.translate = parse_task(self.task.as_deref())?;
+        options.initial_prompt = build_initial_prompt(self.initial_prompt, self.vocabulary);
+        options.decoding = decoding;
+        options.source = source;
+        options.denoise = denoise;
+        options.hallucination_filter = hallucination_filter;
+        options.profanity_filter = profanity_filter;
+        options.text_normalizer = text_normalizer;
+        options.corrections = corrections;
+        options.vad = vad;
+        Ok(options)
+    }
+}
 
-        // Check if we need to reload the model
-        let needs_reload = match guard.as_ref() {
-            None => {
-                println!("No model loaded yet, loading for first time...");
-                true
-            }
-            Some((_, cached_model)) => {
-                if cached_model != &model_clone {
-                    println!("Model changed from '{}' to '{}', reloading...", cached_model, model_clone);
-                    true
-                } else {
-                    println!("Using existing cached model '{}'", cached_model);
-                    false
+// Decode, VAD-trim and transcribe one utterance. `job` is registered by the caller
+// before any work starts, so a cancel during audio prep or model load isn't lost.
+pub async fn transcribe_audio(
+    app_handle: tauri::AppHandle,
+    state: &WhisperAppState,
+    audio_data: Vec<u8>,
+    model: String,
+    options: InferenceOptions,
+    job: CancelRegistration,
+) -> Result<TranscriptionResult, String> {
+    println!(
+        "Model: {}, Language: {}, Translate: {}, Audio Size: {}",
+        model,
+        options.language,
+        options.translate,
+        audio_data.len()
+    );
+
+    let Some(audio_samples) = prepare_audio(&audio_data, options.denoise, &options.vad)? else {
+        return Ok(TranscriptionResult::default());
+    };
+    transcribe_samples(app_handle, state, audio_samples, model, options, job).await
+}
+
+// Transcribe an utterance from native capture: 16kHz PCM the capture segmenter already
+// cut to speech, so it skips the WAV round trip and a second VAD pass
+pub async fn transcribe_captured(
+    app_handle: tauri::AppHandle,
+    state: &WhisperAppState,
+    samples: Vec<f32>,
+    model: String,
+    options: InferenceOptions,
+    job: CancelRegistration,
+) -> Result<TranscriptionResult, String> {
+    println!(
+        "Model: {}, Language: {}, Translate: {}, Samples: {}",
+        model,
+        options.language,
+        options.translate,
+        samples.len()
+    );
+
+    let samples = process_pcm_for_whisper(samples, WHISPER_SAMPLE_RATE)?;
+    let samples = if options.denoise {
+        denoise_samples(&samples, WHISPER_SAMPLE_RATE)
+    } else {
+        samples
+    };
+    let speech = SpeechAudio {
+        samples,
+        region_starts: vec![0],
+    };
+    transcribe_samples(app_handle, state, speech, model, options, job).await
+}
+
+// Transcribe VAD-trimmed 16kHz speech as the cancellable `job`
+async fn transcribe_samples(
+    app_handle: tauri::AppHandle,
+    state: &WhisperAppState,
+    audio_samples: SpeechAudio,
+    model: String,
+    mut options: InferenceOptions,
+    job: CancelRegistration,
+) -> Result<TranscriptionResult, String> {
+    if job.is_cancelled() {
+        return Err(TRANSCRIPTION_CANCELLED.to_string());
+    }
+    options.abort = Some(job.flag());
+
+    let transcription = run_transcription(app_handle, state, audio_samples, model, options).await?;
+
+    println!(
+        "Transcription result: '{}' ({} segments)",
+        transcription.text,
+        transcription.segments.len()
+    );
+    Ok(transcription)
+}
+
+#[tauri::command]
+pub async fn whisper_transcribe(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    audio_data: Vec<u8>,
+    model: String,
+    language: String,
+    job_id: Option<String>,
+    options: Option<TranscribeOptions>,
+) -> Result<TranscriptionResult, String> {
+    println!("=== WHISPER TRANSCRIPTION START ===");
+    let options = options
+        .unwrap_or_default()
+        .into_inference_options(&state, &language)?;
+    let job = register_job(&app_handle, &state, job_id)?;
+    transcribe_audio(app_handle, &state, audio_data, model, options, job).await
+}
+
+// Same as whisper_transcribe, but takes raw f32 PCM (any rate, mono) straight from the
+// capture pipeline instead of an encoded WAV buffer. Cancellable via the job ID
+// announced in `transcription-started`.
+#[tauri::command]
+pub async fn whisper_transcribe_pcm(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    samples: Vec<f32>,
+    sample_rate: u32,
+    model: String,
+    language: String,
+    options: Option<TranscribeOptions>,
+) -> Result<TranscriptionResult, String> {
+    println!("=== WHISPER PCM TRANSCRIPTION START ===");
+    println!(
+        "Model: {}, Language: {}, Samples: {} @ {}Hz",
+        model,
+        language,
+        samples.len(),
+        sample_rate
+    );
+    let options = options
+        .unwrap_or_default()
+        .into_inference_options(&state, &language)?;
+    let job = register_job(&app_handle, &state, None)?;
+
+    let audio_samples = process_pcm_for_whisper(samples, sample_rate)?;
+    let Some(speech_samples) = prepare_samples(&audio_samples, options.denoise, &options.vad)
+    else {
+        return Ok(TranscriptionResult::default());
+    };
+    transcribe_samples(app_handle, &state, speech_samples, model, options, job).await
+}
+
+// Same as whisper_transcribe, but emits `transcription-partial` as each segment is
+// decoded and `transcription-final` with the cleaned-up text once inference finishes.
+// Calls sharing a `stream_id` are treated as re-decodes of one growing buffer: their
+// partials only carry the words consecutive decodes agreed on (`text`), plus the
+// still-unstable rest (`tentative`). Close the session with whisper_end_stream.
+// With `translate_to`, a stream's committed text is also translated as it grows.
+#[tauri::command]
+pub async fn whisper_transcribe_stream(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    audio_data: Vec<u8>,
+    model: String,
+    language: String,
+    stream_id: Option<String>,
+    translate_to: Option<String>,
+    options: Option<TranscribeOptions>,
+) -> Result<TranscriptionResult, String> {
+    let mut options = options
+        .unwrap_or_default()
+        .into_inference_options(&state, &language)?;
+    println!("=== WHISPER STREAMING TRANSCRIPTION START ===");
+    println!(
+        "Model: {}, Language: {}, Audio Size: {}, Stream: {:?}",
+        model,
+        language,
+        audio_data.len(),
+        stream_id
+    );
+
+    let Some(audio_samples) = prepare_audio(&audio_data, options.denoise, &options.vad)? else {
+        let final_payload = serde_json::json!({
+            "stream_id": stream_id,
+            "text": "",
+            "segments": []
+        });
+        let _ = app_handle.emit("transcription-final", &final_payload);
+        return Ok(TranscriptionResult::default());
+    };
+
+    if let (Some(id), Some(target)) = (&stream_id, &translate_to) {
+        open_stream_translation(&app_handle, id, &language, target);
+    }
+
+    let partial_handle = app_handle.clone();
+    let partial_stream_id = stream_id.clone();
+    let partial_options = options.for_language(&options.language);
+    let partial_streams = state.streams.clone();
+    let mut raw_text = String::new();
+    let on_segment: SegmentCallback = Box::new(move |segment: SegmentCallbackData| {
+        raw_text.push(' ');
+        raw_text.push_str(segment.text.trim());
+        // Partials are cleaned up like the final text, so decodes agree on one form
+        // of each word ("23" rather than "twenty three" in one and "23" in the next)
+        let postprocessed = |language: &str| {
+            let language = if partial_options.translate {
+                "en"
+            } else if whisper_language_code(&partial_options.language).is_some() {
+                partial_options.language.as_str()
+            } else {
+                language
+            };
+            postprocess_text(&partial_options, raw_text.trim(), language)
+        };
+        let segment_text = partial_options.profanity_filter.apply(&segment.text);
+
+        // Stabilize streams: only emit when the agreed prefix grows
+        let (text, tentative) = match &partial_stream_id {
+            Some(id) => {
+                let Ok(mut streams) = partial_streams.lock() else {
+                    return;
+                };
+                let agreement = streams.entry(id.clone()).or_default();
+                // Auto-detected streams follow the language of the previous decode
+                let partial_text = postprocessed(&agreement.language);
+                if !agreement.update(&partial_text) {
+                    return;
                 }
+                translate_committed(&partial_handle, id, &agreement.committed());
+                (agreement.committed(), agreement.tentative(&partial_text))
             }
+            None => (postprocessed(""), String::new()),
         };
+        let partial_payload = serde_json::json!({
+            "stream_id": partial_stream_id,
+            "segment": segment.segment,
+            "segment_text": segment_text.trim(),
+            "text": text,
+            "tentative": tentative,
+            "start": segment.start_timestamp,
+            "end": segment.end_timestamp
+        });
+        let _ = partial_handle.emit("transcription-partial", &partial_payload);
+    });
 
-        // Load or reload model if needed
-        if needs_reload {
-            println!("Loading Whisper model from disk...");
-            println!("Path: {}", model_file_str);
-            let ctx = WhisperContext::new_with_params(&model_file_str, Default::default())
-                .map_err(|e| format!("Failed to create WhisperContext: {:?}", e))?;
-            *guard = Some((ctx, model_clone.clone()));
-            println!("Model '{}' loaded successfully and cached.", model_clone);
+    let job = register_job(&app_handle, &state, stream_id.clone())?;
+    options.on_segment = Some(on_segment);
+    options.abort = Some(job.flag());
+
+    let transcription =
+        run_transcription(app_handle.clone(), &state, audio_samples, model, options).await?;
+
+    let committed = match &stream_id {
+        Some(id) => {
+            let mut streams = state
+                .streams
+                .lock()
+                .map_err(|e| format!("Mutex poisoned: {:?}", e))?;
+            let agreement = streams.entry(id.clone()).or_default();
+            agreement.finish_decode(&transcription.text);
+            agreement.language = transcription.language.clone();
+            set_stream_final_text(&app_handle, id, &transcription.text);
+            translate_committed(&app_handle, id, &agreement.committed());
+            agreement.committed()
         }
+        None => transcription.text.clone(),
+    };
 
-        // Run inference on the locked context
-        if let Some((ctx, _)) = guard.as_ref() {
-            run_inference_on_context(ctx, &audio_samples, &language_clone)
-        } else {
-            Err("Whisper context is missing".to_string())
-        }
-    })
-    .await
-    .map_err(|e| format!("Task join error: {:?}", e))??;
+    let final_payload = serde_json::json!({
+        "stream_id": stream_id,
+        "text": transcription.text,
+        "committed": committed,
+        "language": transcription.language,
+        "segments": transcription.segments,
+        "utterances": transcription.utterances
+    });
+    let _ = app_handle.emit("transcription-final", &final_payload);
 
-    println!("Transcription result: '{}'", transcription);
+    println!("Streaming transcription result: '{}'", transcription.text);
     Ok(transcription)
 }
+
+// Close a streaming session, dropping its stabilization state. Returns the text that
+// was committed over the session; a translated stream gets its final translation.
+#[tauri::command]
+pub fn whisper_end_stream(
+    app_handle: tauri::AppHandle,
+    state: State<'_, WhisperAppState>,
+    stream_id: String,
+) -> Result<String, String> {
+    finish_stream_translation(&app_handle, &stream_id);
+    let agreement = state
+        .streams
+        .lock()
+        .map_err(|e| format!("Mutex poisoned: {:?}", e))?
+        .remove(&stream_id);
+    Ok(agreement.map(|a| a.committed()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, avg_logprob: f32, no_speech_probability: f32) -> TranscriptionSegment {
+        TranscriptionSegment {
+            text: text.to_string(),
+            start_ms: 0,
+            end_ms: 2000,
+            probability: avg_logprob.exp(),
+            avg_logprob,
+            no_speech_probability,
+            language: "en".to_string(),
+            utterance: 0,
+            words: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn compression_ratio_of_empty_text_is_zero() {
+        assert_eq!(compression_ratio(""), 0.0);
+    }
+
+    #[test]
+    fn repetition_loops_compress_well() {
+        let loop_text = "thank you ".repeat(30);
+        let sentence = "I'm heading to the store to buy some milk.";
+        assert!(
+            compression_ratio(&loop_text) > DecodingOptions::default().compression_ratio_threshold
+        );
+        assert!(
+            compression_ratio(sentence) < DecodingOptions::default().compression_ratio_threshold
+        );
+    }
+
+    #[test]
+    fn confident_speech_needs_no_fallback() {
+        let decoding = DecodingOptions::default();
+        assert!(!needs_fallback(
+            &segment("See you tomorrow.", -0.3, 0.1),
+            &decoding
+        ));
+    }
+
+    #[test]
+    fn repetitive_segment_needs_fallback() {
+        let decoding = DecodingOptions::default();
+        let text = "thank you ".repeat(30);
+        assert!(needs_fallback(&segment(&text, -0.3, 0.1), &decoding));
+    }
+
+    #[test]
+    fn uncertain_speech_needs_fallback() {
+        let decoding = DecodingOptions::default();
+        assert!(needs_fallback(
+            &segment("See you tomorrow.", -1.5, 0.1),
+            &decoding
+        ));
+    }
+
+    #[test]
+    fn uncertain_silence_is_not_retried() {
+        let decoding = DecodingOptions::default();
+        assert!(!needs_fallback(
+            &segment("See you tomorrow.", -1.5, 0.9),
+            &decoding
+        ));
+    }
+}
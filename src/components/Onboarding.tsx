@@ -1,6 +1,7 @@
-import React, { useState } from 'react';
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import { Config, saveConfig } from '../utils/config';
-import { info } from '@tauri-apps/plugin-log';
+import { info, error } from '@tauri-apps/plugin-log';
 import logo from '../assets/logo.png';
 
 type OnboardingProps = {
@@ -24,6 +25,18 @@ type TranslatorOption = {
   requiresKey: boolean;
 };
 
+// Returned by the get_hardware_profile command
+type HardwareProfile = {
+  cpu_name: string;
+  logical_cores: number;
+  simd: string[];
+  total_memory_bytes: number;
+  gpus: { name: string; vram_bytes: number | null }[];
+  recommendation: { model: string; backend: string; reason: string };
+};
+
+const formatGiB = (bytes: number) => `${(bytes / 1024 ** 3).toFixed(1)} GB`;
+
 const THEME_OPTIONS: ThemeOption[] = [
   { id: 'blue', name: 'Ocean Blue', color: '#60A5FA', bgClass: 'bg-blue-400' },
   { id: 'purple', name: 'Royal Purple', color: '#A78BFA', bgClass: 'bg-purple-400' },
@@ -74,6 +87,18 @@ const Onboarding: React.FC<OnboardingProps> = ({ config, setConfig, onComplete }
   const [selectedTheme, setSelectedTheme] = useState<string>(config.theme_color);
   const [selectedTranslator, setSelectedTranslator] = useState<string>('groq');
   const [isCompleting, setIsCompleting] = useState<boolean>(false);
+  const [hardware, setHardware] = useState<HardwareProfile | null>(null);
+  const [selectedRecognizer, setSelectedRecognizer] = useState<string>(config.recognizer);
+
+  // Probe the machine up front so the speech step can suggest a Whisper model
+  useEffect(() => {
+    invoke<HardwareProfile>('get_hardware_profile')
+      .then(profile => {
+        setHardware(profile);
+        info(`[ONBOARDING] Recommended Whisper model: ${profile.recommendation.model} (${profile.recommendation.reason})`);
+      })
+      .catch(e => error(`[ONBOARDING] Hardware probe failed: ${e}`));
+  }, []);
 
   // Get the current theme color
   const currentThemeColor = THEME_OPTIONS.find(t => t.id === selectedTheme)?.color || '#60A5FA';
@@ -87,7 +112,7 @@ const Onboarding: React.FC<OnboardingProps> = ({ config, setConfig, onComplete }
   };
 
   const handleNext = () => {
-    if (step < 1) {
+    if (step < 2) {
       setStep(step + 1);
     }
   };
@@ -105,6 +130,10 @@ const Onboarding: React.FC<OnboardingProps> = ({ config, setConfig, onComplete }
         ...config,
         theme_color: selectedTheme,
         translator: selectedTranslator,
+        recognizer: selectedRecognizer,
+        whisper_model: selectedRecognizer === 'whisper' && hardware
+          ? hardware.recommendation.model
+          : config.whisper_model,
         onboarding_completed: true,
       };
       
@@ -121,7 +150,7 @@ const Onboarding: React.FC<OnboardingProps> = ({ config, setConfig, onComplete }
 
   const renderStepIndicator = () => (
     <div className="flex items-center justify-center space-x-2 mb-4">
-      {[0, 1].map((i) => (
+      {[0, 1, 2].map((i) => (
         <div
           key={i}
           className="w-2 h-2 rounded-full transition-all duration-300"
@@ -188,6 +217,71 @@ const Onboarding: React.FC<OnboardingProps> = ({ config, setConfig, onComplete }
     </div>
   );
 
+  const renderSpeechStep = () => (
+    <div className="animate-fade-in">
+      <h2 className="text-lg font-bold text-white text-center mb-1">
+        Speech Recognition
+      </h2>
+      <p className="text-white/60 text-center text-sm mb-3">
+        Choose how your voice is turned into text
+      </p>
+
+      {hardware ? (
+        <div className="p-2.5 rounded-xl bg-white/5 border border-white/10 mb-3 text-xs text-white/60 space-y-0.5">
+          <p>CPU: {hardware.cpu_name || 'Unknown'} ({hardware.logical_cores} threads{hardware.simd.length > 0 ? `, ${hardware.simd.join('/')}` : ''})</p>
+          <p>RAM: {formatGiB(hardware.total_memory_bytes)}</p>
+          <p>GPU: {hardware.gpus.length > 0
+            ? hardware.gpus.map(g => g.vram_bytes ? `${g.name} (${formatGiB(g.vram_bytes)})` : g.name).join(', ')
+            : 'None detected'}</p>
+        </div>
+      ) : (
+        <p className="text-white/40 text-center text-xs mb-3">Checking your hardware...</p>
+      )}
+
+      <div className="space-y-2 mb-4">
+        {[
+          { id: 'webspeech', name: 'Browser Speech', description: 'Online recognition, no download needed' },
+          {
+            id: 'whisper',
+            name: 'Whisper (Local)',
+            description: hardware
+              ? `Runs on your PC. Suggested model: ${hardware.recommendation.model} (${hardware.recommendation.reason})`
+              : 'Runs on your PC, works offline'
+          }
+        ].map((option) => (
+          <button
+            key={option.id}
+            onClick={() => setSelectedRecognizer(option.id)}
+            className="relative w-full p-2.5 rounded-xl border-2 transition-all duration-300 text-left"
+            style={{
+              borderColor: selectedRecognizer === option.id ? currentThemeColor : 'rgba(255,255,255,0.1)',
+              backgroundColor: selectedRecognizer === option.id ? `${currentThemeColor}15` : 'rgba(255,255,255,0.05)'
+            }}
+          >
+            <h3 className="text-sm font-semibold text-white">{option.name}</h3>
+            <p className="text-white/50 text-xs">{option.description}</p>
+          </button>
+        ))}
+      </div>
+
+      <div className="flex space-x-3">
+        <button
+          onClick={handleBack}
+          className="flex-1 py-2.5 rounded-xl font-semibold text-white bg-white/10 border border-white/20 transition-all duration-300 hover:bg-white/20"
+        >
+          Back
+        </button>
+        <button
+          onClick={handleNext}
+          className="flex-1 py-2.5 rounded-xl font-semibold text-dark-900 transition-all duration-300 hover:scale-[1.02]"
+          style={{ backgroundColor: currentThemeColor }}
+        >
+          Continue
+        </button>
+      </div>
+    </div>
+  );
+
   const renderTranslatorStep = () => (
     <div className="animate-fade-in">
       <h2 className="text-lg font-bold text-white text-center mb-1">
@@ -331,7 +425,8 @@ const Onboarding: React.FC<OnboardingProps> = ({ config, setConfig, onComplete }
         {/* Card */}
         <div className="modern-card p-4">
           {step === 0 && renderThemeStep()}
-          {step === 1 && renderTranslatorStep()}
+          {step === 1 && renderSpeechStep()}
+          {step === 2 && renderTranslatorStep()}
         </div>
 
         {/* Skip Option */}
@@ -12,6 +12,14 @@ interface SettingsProps {
 
 type SettingsSection = 'language' | 'speech' | 'translation' | 'appearance' | 'vrchat';
 
+const formatSpeed = (bytesPerSecond: number) =>
+  bytesPerSecond >= 1024 * 1024
+    ? `${(bytesPerSecond / (1024 * 1024)).toFixed(1)} MB/s`
+    : `${Math.round(bytesPerSecond / 1024)} KB/s`;
+
+const formatEta = (seconds: number) =>
+  seconds >= 60 ? `${Math.floor(seconds / 60)}m ${seconds % 60}s` : `${seconds}s`;
+
 const Settings: React.FC<SettingsProps> = ({ config, setConfig, onClose }) => {
   const [localConfig, setLocalConfig] = useState<Config>({ ...config });
   const [hasChanges, setHasChanges] = useState(false);
@@ -20,6 +28,7 @@ const Settings: React.FC<SettingsProps> = ({ config, setConfig, onClose }) => {
   const [whisperModels, setWhisperModels] = useState<WhisperModel[]>([...WHISPER_MODELS]);
   const [downloadingModels, setDownloadingModels] = useState<Set<string>>(new Set());
   const [downloadProgress, setDownloadProgress] = useState<Map<string, number>>(new Map());
+  const [downloadStats, setDownloadStats] = useState<Map<string, { bytesPerSecond: number; etaSeconds: number | null }>>(new Map());
   const [activeSection, setActiveSection] = useState<SettingsSection>('language');
 
   // Dropdown states
@@ -154,7 +163,15 @@ const Settings: React.FC<SettingsProps> = ({ config, setConfig, onClose }) => {
   // Listen for download progress events
   useEffect(() => {
     const unlisten = listen('download-progress', (event) => {
-      const payload = event.payload as { model: string; file: string; progress: number; downloaded: number; total: number };
+      const payload = event.payload as {
+        model: string;
+        file: string;
+        progress: number;
+        downloaded: number;
+        total: number;
+        bytes_per_second: number;
+        eta_seconds: number | null;
+      };
 
       // Clear the progress check timeout if it exists (indicates ongoing download)
       if ((window as any).__progressCheckTimeout) {
@@ -179,7 +196,13 @@ const Settings: React.FC<SettingsProps> = ({ config, setConfig, onClose }) => {
         return newProgress;
       });
 
-      info(`[SETTINGS] Download progress for ${payload.model}: ${Math.round(payload.progress)}% (${payload.file})`);
+      setDownloadStats(prev => {
+        const newStats = new Map(prev);
+        newStats.set(payload.model, { bytesPerSecond: payload.bytes_per_second, etaSeconds: payload.eta_seconds });
+        return newStats;
+      });
+
+      info(`[SETTINGS] Download progress for ${payload.model}: ${Math.round(payload.progress)}% (${payload.file}, ${formatSpeed(payload.bytes_per_second)})`);
 
       // If progress reaches 100%, the download is complete
       if (payload.progress >= 100) {
@@ -196,6 +219,12 @@ const Settings: React.FC<SettingsProps> = ({ config, setConfig, onClose }) => {
             return newProgress;
           });
 
+          setDownloadStats(prev => {
+            const newStats = new Map(prev);
+            newStats.delete(payload.model);
+            return newStats;
+          });
+
           // Refresh model status to show as downloaded
           Whisper.getDownloadedModels().then(downloadedModels => {
             setWhisperModels(prevModels =>
@@ -628,6 +657,12 @@ const Settings: React.FC<SettingsProps> = ({ config, setConfig, onClose }) => {
                         <div className="settings-model-details">
                           <h4>{model.name} <span>({model.size})</span></h4>
                           <span>{model.quality}</span>
+                          {downloadStats.has(model.id) && (
+                            <span>
+                              {formatSpeed(downloadStats.get(model.id)!.bytesPerSecond)}
+                              {downloadStats.get(model.id)!.etaSeconds !== null && ` · ${formatEta(downloadStats.get(model.id)!.etaSeconds!)} left`}
+                            </span>
+                          )}
                         </div>
                       </div>
                       <button onClick={() => handleDownloadModel(model.id)} disabled={downloadingModels.has(model.id)} className="settings-model-btn">
@@ -637,6 +672,31 @@ const Settings: React.FC<SettingsProps> = ({ config, setConfig, onClose }) => {
                   ))}
                 </div>
               )}
+              {localConfig.recognizer === 'whisper' && (
+                <>
+                  <div className="settings-row">
+                    <div className="settings-row-info">
+                      <div className="settings-row-title">Download Mirror</div>
+                      <div className="settings-row-description">Hugging Face mirror if huggingface.co is blocked, e.g. https://hf-mirror.com</div>
+                    </div>
+                    <input type="text" value={localConfig.whisper_downloads.mirror_url} onChange={(e) => updateLocalConfig({ whisper_downloads: { ...localConfig.whisper_downloads, mirror_url: e.target.value } })} placeholder="huggingface.co" className="settings-input" style={{ width: '200px' }} />
+                  </div>
+                  <div className="settings-row">
+                    <div className="settings-row-info">
+                      <div className="settings-row-title">Download Proxy</div>
+                      <div className="settings-row-description">http://, https:// or socks5:// proxy for model downloads</div>
+                    </div>
+                    <input type="text" value={localConfig.whisper_downloads.proxy} onChange={(e) => updateLocalConfig({ whisper_downloads: { ...localConfig.whisper_downloads, proxy: e.target.value } })} placeholder="None" className="settings-input" style={{ width: '200px' }} />
+                  </div>
+                  <div className="settings-row">
+                    <div className="settings-row-info">
+                      <div className="settings-row-title">Hugging Face Token (Optional)</div>
+                      <div className="settings-row-description">Only needed for gated or private models</div>
+                    </div>
+                    <input type="password" value={localConfig.whisper_downloads.hf_token} onChange={(e) => updateLocalConfig({ whisper_downloads: { ...localConfig.whisper_downloads, hf_token: e.target.value } })} placeholder="hf_..." className="settings-input" style={{ width: '200px' }} />
+                  </div>
+                </>
+              )}
             </div>
           </section>
         )}
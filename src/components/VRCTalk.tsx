@@ -5,11 +5,12 @@ import { info, error, warn } from '@tauri-apps/plugin-log';
 
 import { Recognizer } from '../recognizers/recognizer';
 import { WebSpeech } from '../recognizers/WebSpeech';
-import { Whisper } from '../recognizers/Whisper';
+import { Whisper, CaptureTranscription } from '../recognizers/Whisper';
 import translateGT from '../translators/google_translate';
+import translateBackend, { TranslatorConfig } from '../translators/backend_translate';
 import translateGemini from '../translators/gemini_translate';
 import translateGroq from '../translators/groq_translate';
-import { Config, saveConfig } from '../utils/config';
+import { Config, saveConfig, validateConfig } from '../utils/config';
 import { calculateMinWaitTime, langSource, langTo, findLangSourceIndex, findLangToIndex } from '../utils/constants';
 
 type MessageItem = { src: string; tgt: string; time: number };
@@ -60,8 +61,9 @@ function isValidTranslation(
   }
 
   // Check if result is unchanged from original text when languages differ
-  // This indicates the translation likely failed
-  if (result === originalText && sourceLanguage !== targetLanguage) {
+  // This indicates the translation likely failed. With auto-detection the speaker
+  // may simply have used the target language.
+  if (result === originalText && sourceLanguage !== targetLanguage && sourceLanguage !== 'auto') {
     // Also check base language codes (e.g., 'en' vs 'en-US')
     const sourceBase = sourceLanguage.includes('-') ? sourceLanguage.split('-')[0] : sourceLanguage;
     const targetBase = targetLanguage.includes('-') ? targetLanguage.split('-')[0] : targetLanguage;
@@ -213,10 +215,10 @@ const VRCTalk: React.FC<VRCTalkProps> = ({ config, setConfig, onNewMessage, onHi
       // Create new recognizer
       let newRecognizer: Recognizer;
       if (shouldBeWhisper) {
-        newRecognizer = new Whisper(config.source_language, config.whisper_model, config.selected_microphone);
+        newRecognizer = new Whisper(config.source_language, config.whisper_model, null, config.whisper_fast_model);
         info(`[SR] Switched to Whisper recognizer with model: ${config.whisper_model}`);
       } else {
-        newRecognizer = new WebSpeech(config.source_language, config.selected_microphone);
+        newRecognizer = new WebSpeech(config.source_language);
         info(`[SR] Switched to WebSpeech recognizer`);
       }
 
@@ -292,8 +294,419 @@ const VRCTalk: React.FC<VRCTalkProps> = ({ config, setConfig, onNewMessage, onHi
         info(`[SR] Whisper model change detected: switching to ${config.whisper_model}`);
         whisperRecognizer.setModel(config.whisper_model);
       }
+      if (whisperRecognizer.fastModel !== config.whisper_fast_model) {
+        info(`[SR] Whisper fast model change detected: switching to ${config.whisper_fast_model || 'none'}`);
+        whisperRecognizer.setFastModel(config.whisper_fast_model);
+      }
+    }
+  }, [config.recognizer, config.whisper_model, config.whisper_fast_model]);
+
+  useEffect(() => {
+    if (globalSpeechRecognizer instanceof Whisper) {
+      globalSpeechRecognizer.setSegmentOverlap(config.whisper_segment_overlap_ms);
+      globalSpeechRecognizer.setRetranscribePolicy(config.whisper_retranscribe.enabled, config.whisper_retranscribe.confidence_threshold);
+      globalSpeechRecognizer.setSegmentation(config.capture_segmentation);
+      globalSpeechRecognizer.setNativeCapture(config.whisper_native_capture);
     }
-  }, [config.recognizer, config.whisper_model]);
+  }, [config.recognizer, config.whisper_segment_overlap_ms, config.whisper_retranscribe, config.whisper_native_capture, config.capture_segmentation]);
+
+  useEffect(() => {
+    invoke('set_audio_device', { id: config.audio_input_device }).catch(e => {
+      error(`[SR] Failed to select audio device ${config.audio_input_device}: ${e}`);
+    });
+  }, [config.audio_input_device]);
+
+  useEffect(() => {
+    const providerConfig = (translator: string): TranslatorConfig => {
+      switch (translator) {
+        case 'deepl':
+          return { type: 'deepl', ...config.deepl };
+        case 'libretranslate':
+          return { type: 'libretranslate', url: config.libretranslate.url, api_key: config.libretranslate.api_key || null };
+        case 'local':
+          return { type: 'local', model: config.local_translation_model };
+        case 'azure':
+          return {
+            type: 'azure',
+            api_key: config.azure.api_key,
+            region: config.azure.region || null,
+            category: config.azure.category || null,
+            free_tier: config.azure.free_tier
+          };
+        case 'llm':
+          return {
+            type: 'llm',
+            base_url: config.llm.base_url,
+            api_key: config.llm.api_key || null,
+            model: config.llm.model,
+            prompt: config.llm.prompt || null,
+            max_length: config.llm.max_length
+          };
+        default:
+          return { type: 'google' };
+      }
+    };
+    invoke('translate_set_provider', { provider: providerConfig(config.translator) }).catch(e => {
+      error(`[TRANSLATION] Failed to set translation provider: ${e}`);
+    });
+    const pairs = config.translation_pairs.map(pair => ({
+      source: pair.source,
+      target: pair.target,
+      provider: providerConfig(pair.translator)
+    }));
+    invoke('translate_set_pair_providers', { pairs }).catch(e => {
+      error(`[TRANSLATION] Failed to set language pair providers: ${e}`);
+    });
+    invoke('translate_set_fallback_providers', { providers: config.translation_fallbacks.map(providerConfig) }).catch(e => {
+      error(`[TRANSLATION] Failed to set fallback providers: ${e}`);
+    });
+  }, [config.translator, config.deepl, config.libretranslate, config.azure, config.llm, config.local_translation_model, config.translation_pairs, config.translation_fallbacks]);
+
+  useEffect(() => {
+    const unlistenFallback = listen<{ from: string; to: string; error: string }>('translation-fallback', (event) => {
+      warn(`[TRANSLATION] ${event.payload.from} failed (${event.payload.error}), using ${event.payload.to}`);
+    });
+    const unlistenQuota = listen<{ provider: string; characters: number; limit: number; percent: number }>('translation-quota-warning', (event) => {
+      const { provider, characters, limit, percent } = event.payload;
+      warn(`[TRANSLATION] ${provider} has used ${percent}% of its free tier this month (${characters}/${limit} characters)`);
+    });
+    return () => {
+      unlistenFallback.then(unlisten => unlisten()).catch(e => {
+        error(`[CLEANUP] Error cleaning up translation fallback listener: ${e}`);
+      });
+      unlistenQuota.then(unlisten => unlisten()).catch(e => {
+        error(`[CLEANUP] Error cleaning up translation quota listener: ${e}`);
+      });
+    };
+  }, []);
+
+  useEffect(() => {
+    const { openai, sapi } = config.tts;
+    // Only validated (and loaded) once TTS is turned on
+    const provider = !config.tts.enabled ? null : config.tts.provider === 'openai'
+      ? (openai.base_url ? {
+        type: 'openai',
+        base_url: openai.base_url,
+        api_key: openai.api_key || null,
+        model: openai.model || null,
+        voice: openai.voice || null
+      } : null)
+      : config.tts.provider === 'sapi'
+        ? { type: 'sapi', voice: sapi.voice || null, rate: sapi.rate, volume: sapi.volume }
+        : { type: 'piper', voice: config.tts.piper_voice };
+    const settings = {
+      provider,
+      output_device: config.tts.output_device,
+      headphones_device: config.tts.headphones_device,
+      volume: config.tts.volume,
+      mic_ducking: config.tts.mic_ducking,
+      duck_gain: config.tts.duck_gain,
+      visemes: {
+        enabled: config.tts.lip_sync.enabled,
+        address: config.vrchat_settings.osc_address,
+        port: String(config.vrchat_settings.osc_port),
+        mouth_parameter: config.tts.lip_sync.mouth_parameter,
+        viseme_parameter: config.tts.lip_sync.viseme_parameter || null,
+        sensitivity: config.tts.lip_sync.sensitivity
+      }
+    };
+    invoke('tts_set_settings', { settings }).catch(e => {
+      error(`[TTS] Failed to apply text-to-speech settings: ${e}`);
+    });
+  }, [config.tts, config.vrchat_settings.osc_address, config.vrchat_settings.osc_port]);
+
+  // Streaming transcripts are translated as they stabilize; the final one replaces the partials
+  useEffect(() => {
+    const unlistenStream = listen<{ stream_id: string; text: string; translation: string; final: boolean }>('translation-stream', (event) => {
+      const { text, translation, final } = event.payload;
+      info(`[TRANSLATION] ${final ? 'Final' : 'Partial'} stream translation: ${translation}`);
+      setSourceText(text);
+      setTranslatedText(translation);
+    });
+    return () => {
+      unlistenStream.then(unlisten => unlisten()).catch(e => {
+        error(`[CLEANUP] Error cleaning up stream translation listener: ${e}`);
+      });
+    };
+  }, []);
+
+  // Language profiles switched from the backend (e.g. by hotkey) edit the backend's settings;
+  // adopt them so the next save doesn't put the old languages back
+  useEffect(() => {
+    const unlistenProfile = listen<{ name: string; source: string; target: string; settings: Config }>('language-profile', (event) => {
+      const { name, source, target, settings } = event.payload;
+      info(`[LANGUAGE] Switched to profile ${name}: ${source} -> ${target}`);
+      setConfig(validateConfig(settings));
+      setSourceLanguage(source);
+      setTargetLanguage(target);
+    });
+    return () => {
+      unlistenProfile.then(unlisten => unlisten()).catch(e => {
+        error(`[CLEANUP] Error cleaning up language profile listener: ${e}`);
+      });
+    };
+  }, [setConfig]);
+
+  // Settings profiles switched in the backend replace the model, languages, OSC target and formatting
+  useEffect(() => {
+    const unlistenSettingsProfile = listen<{ name: string; settings: Config }>('settings-profile-changed', (event) => {
+      const { name, settings } = event.payload;
+      info(`[SETTINGS] Switched to settings profile ${name}`);
+      const newConfig = validateConfig(settings);
+      setConfig(newConfig);
+      setSourceLanguage(newConfig.source_language);
+      setTargetLanguage(newConfig.target_language);
+    });
+    return () => {
+      unlistenSettingsProfile.then(unlisten => unlisten()).catch(e => {
+        error(`[CLEANUP] Error cleaning up settings profile listener: ${e}`);
+      });
+    };
+  }, [setConfig]);
+
+  useEffect(() => {
+    const order = config.vrchat_settings.only_translation
+      ? 'translation_only'
+      : config.vrchat_settings.translation_first ? 'translation_first' : 'original_first';
+    invoke('set_chatbox_format', { format: { ...config.chatbox_format, order } }).catch(e => {
+      error(`[TRANSLATION] Failed to apply chatbox format: ${e}`);
+    });
+  }, [config.chatbox_format, config.vrchat_settings.only_translation, config.vrchat_settings.translation_first]);
+
+  useEffect(() => {
+    invoke('audio_set_input_mix', { mix: config.input_mix }).catch(e => {
+      error(`[SR] Failed to apply input mix: ${e}`);
+    });
+  }, [config.input_mix]);
+
+  // Loopback also runs as the echo reference when only echo cancellation needs it
+  const loopbackNeeded = config.loopback_capture || config.echo_cancellation.enabled;
+
+  useEffect(() => {
+    if (!loopbackNeeded) {
+      invoke('audio_stop_capture', { source: 'loopback' }).catch(e => {
+        error(`[SR] Failed to stop loopback capture: ${e}`);
+      });
+      return;
+    }
+    invoke('audio_start_capture', {
+      config: {
+        source: 'loopback',
+        device: null,
+        model: config.whisper_model,
+        language: 'auto',
+        segmentation: config.capture_segmentation
+      }
+    }).catch(e => {
+      error(`[SR] Failed to start loopback capture: ${e}`);
+    });
+  }, [loopbackNeeded, config.whisper_model, config.capture_segmentation]);
+
+  useEffect(() => {
+    for (const [source, settings] of Object.entries(config.capture_sources)) {
+      // Reference-only loopback is captured but never transcribed
+      const enabled = settings.enabled && (source !== 'loopback' || config.loopback_capture);
+      invoke('audio_set_source', { source, settings: { ...settings, enabled } }).catch(e => {
+        error(`[SR] Failed to apply ${source} capture settings: ${e}`);
+      });
+    }
+  }, [config.capture_sources, config.loopback_capture]);
+
+  useEffect(() => {
+    invoke('audio_set_echo_cancellation', { settings: config.echo_cancellation }).catch(e => {
+      error(`[SR] Failed to apply echo cancellation settings: ${e}`);
+    });
+  }, [config.echo_cancellation]);
+
+  useEffect(() => {
+    invoke('whisper_set_vad_settings', { settings: config.vad }).catch(e => {
+      error(`[SR] Failed to apply VAD settings: ${e}`);
+    });
+  }, [config.vad]);
+
+  useEffect(() => {
+    invoke('whisper_set_debug_recording', { settings: config.debug_recording }).catch(e => {
+      error(`[SR] Failed to apply debug recording settings: ${e}`);
+    });
+  }, [config.debug_recording]);
+
+  useEffect(() => {
+    invoke('audio_set_noise_gate', { settings: config.noise_gate }).catch(e => {
+      error(`[SR] Failed to apply noise gate settings: ${e}`);
+    });
+  }, [config.noise_gate]);
+
+  useEffect(() => {
+    invoke('audio_set_agc', { settings: config.agc }).catch(e => {
+      error(`[SR] Failed to apply gain control settings: ${e}`);
+    });
+  }, [config.agc]);
+
+  useEffect(() => {
+    const pushToTalk = config.push_to_talk;
+    invoke('audio_set_push_to_talk', {
+      config: {
+        enabled: pushToTalk.enabled,
+        hotkey: pushToTalk.hotkey || null,
+        pre_roll_ms: pushToTalk.pre_roll_ms
+      }
+    }).catch(e => {
+      error(`[SR] Failed to apply push-to-talk settings: ${e}`);
+    });
+  }, [config.push_to_talk]);
+
+  useEffect(() => {
+    const unlistenOthers = listen<CaptureTranscription>('capture-transcription', (event) => {
+      if (event.payload.speaker !== 'others') return;
+      const text = event.payload.text?.trim() ?? '';
+      info(`[SR] Others said: "${text}"`);
+      // With captions on, the translated caption is shown instead
+      if (text !== '' && onNewMessage && !config.captions.enabled) {
+        invoke<string>('transliterate_text', { text, language: event.payload.result.language })
+          .catch(e => {
+            error(`[SR] Failed to transliterate incoming speech: ${e}`);
+            return text;
+          })
+          .then(shown => onNewMessage(`[others] ${shown}`, ''));
+      }
+    });
+    const unlistenCaptions = listen<{ text: string; translation: string }>('caption', (event) => {
+      if (onNewMessage) {
+        onNewMessage(`[others] ${event.payload.text}`, event.payload.translation);
+      }
+    });
+    return () => {
+      unlistenOthers.then(unlisten => unlisten()).catch(e => {
+        error(`[CLEANUP] Error cleaning up loopback listener: ${e}`);
+      });
+      unlistenCaptions.then(unlisten => unlisten()).catch(e => {
+        error(`[CLEANUP] Error cleaning up captions listener: ${e}`);
+      });
+    };
+  }, [onNewMessage, config.captions.enabled]);
+
+  useEffect(() => {
+    invoke('captions_set_settings', {
+      settings: {
+        enabled: config.captions.enabled,
+        target: config.captions.target_language,
+        speak: config.captions.speak
+      }
+    }).catch(e => {
+      error(`[SR] Failed to apply caption settings: ${e}`);
+    });
+  }, [config.captions]);
+
+  // Mirror, proxy and token for model downloads; saved with the rest of the config, where the backend reads them at startup
+  useEffect(() => {
+    const downloads = config.whisper_downloads;
+    invoke('whisper_set_download_settings', {
+      settings: {
+        mirror_url: downloads.mirror_url || null,
+        proxy: downloads.proxy || null,
+        hf_token: downloads.hf_token || null,
+        max_connections: downloads.max_connections,
+        chunked: downloads.chunked,
+        max_bytes_per_second: downloads.max_bytes_per_second || null
+      }
+    }).catch(e => {
+      error(`[SR] Failed to apply model download settings: ${e}`);
+    });
+  }, [config.whisper_downloads]);
+
+  useEffect(() => {
+    invoke('whisper_set_quantization', { quantization: config.whisper_quantization }).catch(e => {
+      error(`[SR] Failed to apply Whisper quantization: ${e}`);
+    });
+  }, [config.whisper_quantization]);
+
+  // Keep the backend's Whisper decoding options in sync with the saved config
+  useEffect(() => {
+    invoke('whisper_set_decoding_options', { options: config.whisper_decoding }).catch(e => {
+      error(`[SR] Failed to apply Whisper decoding options: ${e}`);
+    });
+  }, [config.whisper_decoding]);
+
+  useEffect(() => {
+    invoke('whisper_set_denoise', { source: 'microphone', enabled: config.whisper_denoise }).catch(e => {
+      error(`[SR] Failed to apply Whisper denoise setting: ${e}`);
+    });
+  }, [config.whisper_denoise]);
+
+  useEffect(() => {
+    invoke('whisper_set_hallucination_filter', { filter: config.whisper_hallucination_filter }).catch(e => {
+      error(`[SR] Failed to apply Whisper hallucination filter: ${e}`);
+    });
+  }, [config.whisper_hallucination_filter]);
+
+  useEffect(() => {
+    invoke('whisper_set_profanity_filter', { filter: config.profanity_filter }).catch(e => {
+      error(`[SR] Failed to apply profanity filter: ${e}`);
+    });
+  }, [config.profanity_filter]);
+
+  useEffect(() => {
+    invoke('whisper_set_text_normalization', { normalizer: config.text_normalization }).catch(e => {
+      error(`[SR] Failed to apply text normalization: ${e}`);
+    });
+  }, [config.text_normalization]);
+
+  useEffect(() => {
+    const watchFolder = config.watch_folder;
+    if (!watchFolder.enabled || watchFolder.path === '') {
+      invoke('whisper_stop_watch_folder').catch(e => {
+        error(`[WATCH] Failed to stop watch folder: ${e}`);
+      });
+      return;
+    }
+    invoke('whisper_start_watch_folder', {
+      config: {
+        path: watchFolder.path,
+        model: config.whisper_model,
+        language: config.source_language,
+        include_existing: watchFolder.include_existing
+      }
+    }).catch(e => {
+      error(`[WATCH] Failed to watch ${watchFolder.path}: ${e}`);
+    });
+  }, [config.watch_folder, config.whisper_model, config.source_language]);
+
+  useEffect(() => {
+    const unlistenWatchFolder = listen<{ path: string; transcript: { text: string } | null; error: string | null }>('watch-folder-transcribed', (event) => {
+      const { path, transcript, error: transcribeError } = event.payload;
+      if (transcribeError) {
+        error(`[WATCH] Failed to transcribe ${path}: ${transcribeError}`);
+        return;
+      }
+      const text = transcript?.text.trim() ?? '';
+      info(`[WATCH] Transcribed ${path}: "${text}"`);
+      if (text !== '' && onNewMessage) {
+        onNewMessage(text, '');
+      }
+    });
+    return () => {
+      unlistenWatchFolder.then(unlisten => unlisten()).catch(e => {
+        error(`[CLEANUP] Error cleaning up watch folder listener: ${e}`);
+      });
+    };
+  }, [onNewMessage]);
+
+  useEffect(() => {
+    invoke('stt_set_hotword', { config: config.hotword }).catch(e => {
+      error(`[SR] Failed to apply wake-word settings: ${e}`);
+    });
+  }, [config.hotword]);
+
+  useEffect(() => {
+    const unlistenHotword = listen<{ phrase: string }>('hotword-detected', (event) => {
+      info(`[SR] Wake phrase "${event.payload.phrase}" detected, transcription armed`);
+    });
+    return () => {
+      unlistenHotword.then(unlisten => unlisten()).catch(e => {
+        error(`[CLEANUP] Error cleaning up hotword listener: ${e}`);
+      });
+    };
+  }, []);
 
   // Handle recognition status based on VRC mute status
   useEffect(() => {
@@ -339,15 +752,19 @@ const VRCTalk: React.FC<VRCTalkProps> = ({ config, setConfig, onNewMessage, onHi
   // Translation processing loop
   useEffect(() => {
     const processTranslation = async () => {
-      // If we are offline, wait until connection is restored to avoid useless fetches
-      if (!navigator.onLine) {
+      // If we are offline, wait until connection is restored to avoid useless fetches.
+      // Local translation doesn't need the network.
+      if (!navigator.onLine && config.translator !== 'local') {
         info("[TRANSLATION] Skipping translation because network is offline");
         // Don't lock the queue when offline - just skip processing
         return;
       }
       if (detectionQueue.length === 0 || lock) return;
 
-      const text = detectionQueue[0].replace(/%/g, "%25");
+      // Only the translators calling out from the frontend get '%' escaped; the backend
+      // builds its own requests and would otherwise see a literal "%25"
+      const frontendTranslator = (config.translator === 'gemini' && !!config.gemini_api_key) || config.translator === 'groq';
+      const text = frontendTranslator ? detectionQueue[0].replace(/%/g, "%25") : detectionQueue[0];
       detectionQueue = detectionQueue.slice(1);
 
       lock = true;
@@ -413,6 +830,15 @@ const VRCTalk: React.FC<VRCTalkProps> = ({ config, setConfig, onNewMessage, onHi
                   secondaryTranslatedResult = await translateGroq(text, sourceLanguage, config.secondary_target_language, config.groq_api_key || '', config.translation_style);
                   info("[TRANSLATION] Groq secondary translation succeeded!");
                 }
+              } else if (['deepl', 'libretranslate', 'local', 'azure', 'llm'].includes(config.translator)) {
+                translatedResult = await translateBackend(text, sourceLanguage, targetLanguage);
+                info(`[TRANSLATION] ${config.translator} primary translation succeeded!`);
+
+                // Translate to secondary language if enabled
+                if (config.secondary_target_language) {
+                  secondaryTranslatedResult = await translateBackend(text, sourceLanguage, config.secondary_target_language);
+                  info(`[TRANSLATION] ${config.translator} secondary translation succeeded!`);
+                }
               } else {
                 translatedResult = await translateGT(text, sourceLanguage, targetLanguage);
                 info("[TRANSLATION] Google primary translation succeeded!");
@@ -540,31 +966,19 @@ const VRCTalk: React.FC<VRCTalkProps> = ({ config, setConfig, onNewMessage, onHi
         let messageFormat = originalText; // default for transcription
 
         if (config.mode === 0) {
-          // Build translation message
-          const divider = ' | ';
-          const srcTag = `[${getLangTag(sourceLanguage)}]`;
-          const tgtTag = `[${getLangTag(targetLanguage)}]`;
-          
-          // Check if we have a secondary translation
+          const translations = [{ text: finalTranslation, language: targetLanguage }];
           if (config.secondary_target_language && secondaryTranslatedResult) {
-            const secondaryTgtTag = `[${getLangTag(config.secondary_target_language)}]`;
-            
-            if (config.vrchat_settings.only_translation) {
-              messageFormat = `${finalTranslation} ${tgtTag}${divider}${secondaryTranslatedResult} ${secondaryTgtTag}`;
-            } else if (config.vrchat_settings.translation_first) {
-              messageFormat = `${tgtTag} ${finalTranslation}${divider}${secondaryTgtTag} ${secondaryTranslatedResult}${divider}${originalText} ${srcTag}`;
-            } else {
-              messageFormat = `${srcTag} ${originalText}${divider}${finalTranslation} ${tgtTag}${divider}${secondaryTranslatedResult} ${secondaryTgtTag}`;
-            }
-          } else {
-            // Single translation format (original behavior)
-            messageFormat = `${srcTag} ${originalText}${divider}${finalTranslation} ${tgtTag}`;
-
-            if (config.vrchat_settings.only_translation) {
-              messageFormat = `${finalTranslation} ${tgtTag}`;
-            } else if (config.vrchat_settings.translation_first) {
-              messageFormat = `${tgtTag} ${finalTranslation}${divider}${originalText} ${srcTag}`;
-            }
+            translations.push({ text: secondaryTranslatedResult, language: config.secondary_target_language });
+          }
+          try {
+            messageFormat = await invoke<string>("format_chatbox_message", {
+              original: originalText,
+              source: sourceLanguage,
+              translations
+            });
+          } catch (formatError) {
+            error(`[TRANSLATION] Error formatting chatbox message: ${formatError}`);
+            messageFormat = `${finalTranslation} | ${originalText}`;
           }
         }
 
@@ -580,6 +994,13 @@ const VRCTalk: React.FC<VRCTalkProps> = ({ config, setConfig, onNewMessage, onHi
             onNewMessage(originalText, finalTranslation);
           }
 
+          // Speak it too, without holding up the chatbox
+          if (config.tts.enabled) {
+            invoke('tts_speak', { text: config.mode === 0 ? finalTranslation : originalText }).catch(e => {
+              error(`[TTS] Failed to speak message: ${e}`);
+            });
+          }
+
           // Wait for chatbox to process
           await new Promise(r => setTimeout(r, calculateMinWaitTime(
             finalTranslation,
@@ -682,15 +1103,8 @@ const VRCTalk: React.FC<VRCTalkProps> = ({ config, setConfig, onNewMessage, onHi
         .then((devices) => {
           const audioInputs = devices.filter(device => device.kind === "audioinput");
           if (audioInputs.length > 0) {
-            // If we have a selected microphone ID in config, use that one
-            let selectedMic;
-            if (config.selected_microphone) {
-              selectedMic = audioInputs.find(device => device.deviceId === config.selected_microphone);
-            }
-
-            // If no specific mic is selected or it's not found, use the default
-            const micToUse = selectedMic || audioInputs[0];
-            const defaultInput = micToUse.label;
+            // The webview records from the default microphone
+            const defaultInput = audioInputs[0].label;
 
             if (defaultInput) {
               // Extract mic name from format "Device name (identifier)"
@@ -738,12 +1152,7 @@ const VRCTalk: React.FC<VRCTalkProps> = ({ config, setConfig, onNewMessage, onHi
           const devices = await navigator.mediaDevices.enumerateDevices();
           const audioInputs = devices.filter(d => d.kind === 'audioinput');
           if (audioInputs.length > 0) {
-            let micToUse = audioInputs[0];
-            if (config.selected_microphone) {
-              const specific = audioInputs.find(d => d.deviceId === config.selected_microphone);
-              if (specific) micToUse = specific;
-            }
-            const label = micToUse.label?.trim();
+            const label = audioInputs[0].label?.trim();
             if (label) {
               const match = label.match(/^(.*?)(\s+\([^)]+\))?$/);
               const micName = match ? match[1] : label;
@@ -751,9 +1160,7 @@ const VRCTalk: React.FC<VRCTalkProps> = ({ config, setConfig, onNewMessage, onHi
             }
             
             // Track the default device ID
-            if (!config.selected_microphone) {
-              lastDefaultDeviceId = audioInputs[0]?.deviceId || null;
-            }
+            lastDefaultDeviceId = audioInputs[0]?.deviceId || null;
           }
         } catch (_) {
           // ignore
@@ -785,17 +1192,8 @@ const VRCTalk: React.FC<VRCTalkProps> = ({ config, setConfig, onNewMessage, onHi
                   info(`[DEVICE] Default microphone changed from ${lastDefaultDeviceId.substring(0, 8)}... to ${currentDefaultId.substring(0, 8)}..., restarting recognition`);
                   lastDefaultDeviceId = currentDefaultId;
                   
-                  // If no specific mic is selected, restart to use new default
-                  if (!config.selected_microphone) {
-                    globalSpeechRecognizer.restart();
-                  } else {
-                    // If specific mic was selected, check if it still exists
-                    const selectedStillExists = audioInputs.find(d => d.deviceId === config.selected_microphone);
-                    if (!selectedStillExists) {
-                      info(`[DEVICE] Selected microphone no longer available, switching to default`);
-                      globalSpeechRecognizer.set_microphone(null);
-                    }
-                  }
+                  // Restart to use the new default
+                  globalSpeechRecognizer.restart();
                 } else if (!lastDefaultDeviceId) {
                   // Initialize tracking if not set
                   lastDefaultDeviceId = currentDefaultId;
@@ -821,10 +1219,14 @@ const VRCTalk: React.FC<VRCTalkProps> = ({ config, setConfig, onNewMessage, onHi
     // Initialize speech recognition based on config
     let recognizer: Recognizer;
     if (config.recognizer === 'whisper') {
-      recognizer = new Whisper(config.source_language, config.whisper_model, config.selected_microphone);
+      recognizer = new Whisper(config.source_language, config.whisper_model, null, config.whisper_fast_model);
+      (recognizer as Whisper).setSegmentOverlap(config.whisper_segment_overlap_ms);
+      (recognizer as Whisper).setRetranscribePolicy(config.whisper_retranscribe.enabled, config.whisper_retranscribe.confidence_threshold);
+      (recognizer as Whisper).setSegmentation(config.capture_segmentation);
+      (recognizer as Whisper).setNativeCapture(config.whisper_native_capture);
       info(`[SR] Initializing Whisper recognizer with model: ${config.whisper_model}`);
     } else {
-      recognizer = new WebSpeech(config.source_language, config.selected_microphone);
+      recognizer = new WebSpeech(config.source_language);
       info(`[SR] Initializing WebSpeech recognizer`);
     }
     globalSpeechRecognizer = recognizer;
@@ -1033,6 +1435,10 @@ const VRCTalk: React.FC<VRCTalkProps> = ({ config, setConfig, onNewMessage, onHi
 
   // Swap languages
   const swapLanguages = () => {
+    if (sourceLanguage === 'auto') {
+      info("[LANGUAGE] Can't swap languages while the source is auto-detected");
+      return;
+    }
     info("[LANGUAGE] User swapping languages");
     // Show language change in progress
     setIsChangingLanguage(true);
@@ -1204,13 +1610,6 @@ const VRCTalk: React.FC<VRCTalkProps> = ({ config, setConfig, onNewMessage, onHi
     apply();
   }, [recognitionActive, detecting, vrcMuted, config.vrchat_settings.disable_when_muted, sr]);
 
-  // Helper to map language code to display tag
-  const getLangTag = (langCode: string): string => {
-    const base = langCode.split('-')[0].toLowerCase();
-    if (base === 'ja') return 'JP'; // Override Japanese tag from JA to JP
-    return base.toUpperCase();
-  };
-
   // Handle manual text submission
   const handleManualSubmit = async () => {
     const text = typedText.trim();
@@ -8,6 +8,9 @@ declare global {
     }
 }
 
+// Web Speech can't identify the language itself; listen in the system language instead
+const speechLang = (lang: string): string => lang === 'auto' ? navigator.language : lang;
+
 export class WebSpeech extends Recognizer {
     recognition: any;
     audioContext: AudioContext | null = null;
@@ -27,7 +30,7 @@ export class WebSpeech extends Recognizer {
     constructor(lang: string, microphoneId: string | null = null) {
         super(lang);
         this.selectedMicrophoneId = microphoneId;
-        this.lang = lang;
+        this.lang = speechLang(lang);
         
         // Use the standard SpeechRecognition object if available
         this.initRecognition();
@@ -328,6 +331,7 @@ export class WebSpeech extends Recognizer {
     }
 
     set_lang(lang: string): void {
+        lang = speechLang(lang);
         try {
             info(`[WEBSPEECH] Setting language from ${this.recognition.lang} to ${lang}`);
             
@@ -1,9 +1,145 @@
 import { Recognizer } from "./recognizer";
 import { info, error } from '@tauri-apps/plugin-log';
 import { invoke } from '@tauri-apps/api/core';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
+
+// Structured result returned by the stt_transcribe command
+type WhisperTranscription = {
+    text: string;
+    language: string;
+    segments: {
+        text: string;
+        start_ms: number;
+        end_ms: number;
+        probability: number;
+        avg_logprob: number; // -10 when the segment has no text tokens
+        no_speech_probability: number;
+        language: string; // Per-segment language, differs when code-switching with language "auto"
+        utterance: number; // Index into utterances
+        words: { text: string; start_ms: number; end_ms: number; probability: number }[];
+    }[];
+    // Segments grouped by the silence gaps between them
+    utterances: { index: number; start_ms: number; end_ms: number; text: string; source: string }[];
+};
+
+// `transcription-job` event payload from the backend job queue
+type TranscriptionJob = {
+    id: string;
+    model: string;
+    stage: 'full' | 'partial' | 'final' | 'retry';
+    linked_job: string | null;
+    status: 'queued' | 'running' | 'completed' | 'failed' | 'cancelled' | 'dropped';
+    result: WhisperTranscription | null;
+    error: string | null;
+};
+
+// Fine-tuned model to register with whisper_add_custom_model. Give either a
+// Hugging Face repo ID or a direct URL the files are served under.
+export type CustomModel = {
+    id: string;
+    repo_id?: string;
+    revision?: string; // Branch, tag or commit to pin a repo model to
+    url?: string;
+    files: { name: string; size?: number; sha256?: string }[];
+    language?: string;
+};
+
+// Replacement dictionary applied to every transcript, saved by the backend
+export type CorrectionDictionary = {
+    enabled: boolean;
+    entries: {
+        heard: string; // What Whisper writes, one or more words
+        replacement: string;
+        fuzzy?: boolean; // Also match near misses, on by default
+    }[];
+};
+
+// Capture device reported by list_audio_devices; `id` is what set_audio_device takes
+export type AudioDevice = {
+    id: string;
+    name: string;
+    is_default: boolean;
+    default_sample_rate: number | null;
+    sample_rates: number[];
+    max_channels: number;
+};
+
+// Result of native capture; `speaker` is "me" for the microphone and "others" for loopback
+export type CaptureTranscription = {
+    source: string;
+    speaker: 'me' | 'others';
+    text: string;
+    result: WhisperTranscription;
+};
+
+export type NoiseCalibration = {
+    noise_floor_db: number;
+    peak_db: number;
+    suggested_threshold_db: number;
+};
+
+export type CaptureSegmentation = {
+    pre_padding_ms: number;
+    post_padding_ms: number;
+    max_utterance_ms: number;
+};
+
+export type AudioLevel = {
+    source: string;
+    device: string;
+    rms: number;
+    peak: number;
+    rms_db: number;
+    peak_db: number;
+};
+
+const FINISHED_JOB_STATUSES = ['completed', 'failed', 'cancelled', 'dropped'];
+
+const DEFAULT_SEGMENT_OVERLAP_MS = 200;
+// Longest run of repeated words (or characters, for unspaced scripts) trimmed at a chunk boundary
+const MAX_BOUNDARY_WORDS = 5;
+const MAX_BOUNDARY_CHARS = 8;
+
+// Duration-weighted mean segment probability, null when the provider returned no segments
+function transcriptConfidence(transcription: WhisperTranscription | null | undefined): number | null {
+    const segments = transcription?.segments ?? [];
+    const totalMs = segments.reduce((sum, s) => sum + Math.max(1, s.end_ms - s.start_ms), 0);
+    if (segments.length === 0 || totalMs === 0) return null;
+    return segments.reduce((sum, s) => sum + s.probability * Math.max(1, s.end_ms - s.start_ms), 0) / totalMs;
+}
+
+// Remove the start of `text` that repeats the end of `previous`. Chunks overlap by a
+// few hundred milliseconds, so the words spoken across the boundary appear in both.
+function dedupeBoundary(previous: string, text: string): string {
+    if (!previous || !text) return text;
+
+    // Scripts without spaces (Japanese, Chinese) are compared character by character
+    if (!/\s/.test(text.trim())) {
+        const prevChars = Array.from(previous.trim());
+        const chars = Array.from(text.trim());
+        for (let k = Math.min(MAX_BOUNDARY_CHARS, prevChars.length, chars.length); k >= 2; k--) {
+            if (prevChars.slice(-k).join('') === chars.slice(0, k).join('')) {
+                return chars.slice(k).join('');
+            }
+        }
+        return text;
+    }
+
+    const normalize = (word: string) => word.toLowerCase().replace(/[^\p{L}\p{N}]/gu, '');
+    const prevWords = previous.trim().split(/\s+/).map(normalize);
+    const words = text.trim().split(/\s+/);
+    for (let k = Math.min(MAX_BOUNDARY_WORDS, prevWords.length, words.length); k >= 1; k--) {
+        const head = words.slice(0, k).map(normalize);
+        if (head.every(w => w.length > 0) && head.join(' ') === prevWords.slice(-k).join(' ')) {
+            return words.slice(k).join(' ');
+        }
+    }
+    return text;
+}
 
 export class Whisper extends Recognizer {
     public model: string; // Make it public so we can access it for comparisons
+    public fastModel: string | null; // Produces quick partials that `model` later replaces
     private selectedMicrophoneId: string | null = null;
     private audioStream: MediaStream | null = null;
     private mediaRecorder: MediaRecorder | null = null;
@@ -12,21 +148,38 @@ export class Whisper extends Recognizer {
     private resultCallback: ((result: string, final: boolean) => void) | null = null;
     private recordingInterval: number = 3000; // 3s chunks to reduce silence and hallucinations
     private intervalId: NodeJS.Timeout | null = null;
-
-    constructor(lang: string, model: string, microphoneId: string | null = null) {
+    private dualMode: boolean = false;
+    private unlistenJobs: Promise<UnlistenFn> | null = null;
+    private pendingPartials: Set<string> = new Set(); // Partial job IDs still in flight
+    private pendingFinals: Map<string, string> = new Map(); // Final job ID -> partial text shown so far
+    private segmentOverlapMs: number = DEFAULT_SEGMENT_OVERLAP_MS; // Previous chunk's tail prepended to each chunk
+    private previousTail: Float32Array | null = null; // Last segmentOverlapMs of the previous chunk at 16kHz
+    private lastFinalText: string = ''; // Boundary words of the next chunk are checked against this
+    private localProvider: boolean = false; // Retries go through the local job queue
+    private retranscribe = { enabled: false, confidenceThreshold: 0.6 };
+    private finalCount: number = 0; // Finals emitted so far, a retry only corrects the latest one
+    private pendingRetries: Map<string, { finalCount: number; text: string; previous: string }> = new Map();
+    private finalAudio: Map<string, Uint8Array> = new Map(); // Final job ID -> audio, kept for a retry
+    private nativeCapture: boolean = true; // Capture in the Rust backend instead of MediaRecorder
+    private unlistenCapture: Promise<UnlistenFn> | null = null;
+    private segmentation: CaptureSegmentation | null = null; // Native capture utterance cutting, null = backend defaults
+
+    constructor(lang: string, model: string, microphoneId: string | null = null, fastModel: string | null = null) {
         super(lang);
         this.model = model;
+        this.fastModel = fastModel;
         this.selectedMicrophoneId = microphoneId;
 
-        info(`[WHISPER] Initialized with model: ${model}, language: ${lang}`);
+        info(`[WHISPER] Initialized with model: ${model}, fast model: ${fastModel || 'none'}, language: ${lang}`);
     }
 
     async start(): Promise<void> {
         try {
             info("[WHISPER] Starting Whisper recognition");
 
-            // Check if model is downloaded
-            const isDownloaded = await this.isModelDownloaded();
+            // Check if model is downloaded (remote providers don't need a local model)
+            const provider = await invoke('stt_get_provider') as { type: string };
+            const isDownloaded = provider.type !== 'local' || await this.isModelDownloaded();
             if (!isDownloaded) {
                 const errorMsg = `Model ${this.model} is not downloaded. Please download it in Settings.`;
                 error(`[WHISPER] ${errorMsg}`);
@@ -36,6 +189,11 @@ export class Whisper extends Recognizer {
                 return;
             }
 
+            if (this.nativeCapture) {
+                await this.startNativeCapture();
+                return;
+            }
+
             // Get microphone access
             // Don't constrain sample rate - let browser use native rate, we'll resample later
             const constraints: MediaStreamConstraints = {
@@ -62,6 +220,19 @@ export class Whisper extends Recognizer {
 
             // onstop is managed by startRecordingLoop for seamless chunk chaining
 
+            // Dual-model mode and retries need the local job queue
+            this.localProvider = provider.type === 'local';
+            this.dualMode = !!this.fastModel && this.fastModel !== this.model && this.localProvider;
+            if (this.localProvider) {
+                this.unlistenJobs = listen<TranscriptionJob>('transcription-job', (event) => {
+                    if (this.pendingRetries.has(event.payload.id)) {
+                        this.handleRetryJob(event.payload);
+                    } else {
+                        this.handleDualJob(event.payload);
+                    }
+                });
+            }
+
             this.running = true;
             this.startRecordingLoop();
 
@@ -83,6 +254,16 @@ export class Whisper extends Recognizer {
         this.running = false;
         this.isRecording = false;
 
+        if (this.unlistenCapture) {
+            invoke('audio_stop_capture').catch(err => {
+                error(`[WHISPER] Error stopping native capture: ${err}`);
+            });
+            this.unlistenCapture.then(unlisten => unlisten()).catch(err => {
+                error(`[WHISPER] Error removing capture listener: ${err}`);
+            });
+            this.unlistenCapture = null;
+        }
+
         if (this.intervalId) {
             clearTimeout(this.intervalId); // intervalId now holds a setTimeout handle
             this.intervalId = null;
@@ -100,6 +281,20 @@ export class Whisper extends Recognizer {
 
         this.mediaRecorder = null;
         this.audioChunks = [];
+
+        if (this.unlistenJobs) {
+            this.unlistenJobs.then(unlisten => unlisten()).catch(err => {
+                error(`[WHISPER] Error removing job listener: ${err}`);
+            });
+            this.unlistenJobs = null;
+        }
+        this.dualMode = false;
+        this.pendingPartials.clear();
+        this.pendingFinals.clear();
+        this.previousTail = null;
+        this.lastFinalText = '';
+        this.pendingRetries.clear();
+        this.finalAudio.clear();
     }
 
     restart(): void {
@@ -152,6 +347,183 @@ export class Whisper extends Recognizer {
         }
     }
 
+    setFastModel(fastModel: string | null): void {
+        info(`[WHISPER] Setting fast model to: ${fastModel || 'none'}`);
+        this.fastModel = fastModel;
+
+        // Restart recognition if it's currently running
+        if (this.running) {
+            this.restart();
+        }
+    }
+
+    // Takes effect from the next chunk, no restart needed
+    setSegmentOverlap(overlapMs: number): void {
+        info(`[WHISPER] Setting segment overlap to: ${overlapMs}ms`);
+        this.segmentOverlapMs = Math.max(0, overlapMs);
+        if (this.segmentOverlapMs === 0) {
+            this.previousTail = null;
+        }
+    }
+
+    // Trim words repeated from the previous chunk's overlap; finals become the new reference
+    private dedupe(text: string, final: boolean): string {
+        const deduped = this.segmentOverlapMs > 0 ? dedupeBoundary(this.lastFinalText, text) : text;
+        if (final && text) {
+            this.lastFinalText = text;
+        }
+        return deduped;
+    }
+
+    setSegmentation(segmentation: CaptureSegmentation): void {
+        if (JSON.stringify(segmentation) === JSON.stringify(this.segmentation)) return;
+        info(`[WHISPER] Utterances padded ${segmentation.pre_padding_ms}/${segmentation.post_padding_ms}ms, max ${segmentation.max_utterance_ms}ms`);
+        this.segmentation = segmentation;
+
+        if (this.running && this.nativeCapture) {
+            this.restart();
+        }
+    }
+
+    setNativeCapture(enabled: boolean): void {
+        if (enabled === this.nativeCapture) return;
+        info(`[WHISPER] ${enabled ? 'Native' : 'Webview'} audio capture`);
+        this.nativeCapture = enabled;
+
+        // Restart recognition if it's currently running
+        if (this.running) {
+            this.restart();
+        }
+    }
+
+    // Let the backend record the microphone and transcribe; results come back as events
+    private async startNativeCapture(): Promise<void> {
+        this.unlistenCapture = listen<CaptureTranscription>('capture-transcription', (event) => {
+            // Loopback results are other players, never our own chatbox text
+            if (!this.running || event.payload.speaker !== 'me') return;
+            const text = event.payload.text?.trim() ?? '';
+            if (text) {
+                info(`[WHISPER] Transcription result: ${text}`);
+                this.emitFinal(text);
+            } else if (this.resultCallback) {
+                this.resultCallback("", true);
+            }
+        });
+
+        await invoke('audio_start_capture', {
+            config: {
+                device: null,
+                model: this.model,
+                language: this.language,
+                segmentation: this.segmentation ?? undefined
+            }
+        });
+        this.running = true;
+        if (this.resultCallback) {
+            this.resultCallback("Listening...", false);
+        }
+        info("[WHISPER] Native capture started");
+    }
+
+    // Re-transcribe results below `confidenceThreshold` with the next larger model
+    setRetranscribePolicy(enabled: boolean, confidenceThreshold: number): void {
+        info(`[WHISPER] Re-transcription ${enabled ? `below ${confidenceThreshold} confidence` : 'disabled'}`);
+        this.retranscribe = { enabled, confidenceThreshold };
+    }
+
+    private emitFinal(text: string): void {
+        if (text) this.finalCount++;
+        if (this.resultCallback) {
+            this.resultCallback(text, true);
+        }
+    }
+
+    // Queue low-confidence audio for the next model size up; called right after the
+    // final for `text` was emitted, with the final before it as `previous`
+    private async maybeRetranscribe(transcription: WhisperTranscription | null | undefined, model: string,
+                                    audio: Uint8Array, text: string, previous: string): Promise<void> {
+        if (!this.retranscribe.enabled || !this.localProvider || !text) return;
+        const confidence = transcriptConfidence(transcription);
+        if (confidence === null || confidence >= this.retranscribe.confidenceThreshold) return;
+
+        try {
+            const job = await invoke('whisper_submit_retry_job', {
+                audioData: Array.from(audio),
+                model: model,
+                language: this.language
+            }) as { id: string; model: string } | null;
+            if (job) {
+                info(`[WHISPER] Confidence ${confidence.toFixed(2)} below threshold, re-transcribing with ${job.model} (job ${job.id})`);
+                this.pendingRetries.set(job.id, { finalCount: this.finalCount, text, previous });
+            }
+        } catch (err: unknown) {
+            const errorMessage = err instanceof Error ? err.message : String(err);
+            error(`[WHISPER] Error submitting re-transcription: ${errorMessage}`);
+        }
+    }
+
+    // Correct the chatbox with the larger model's result, unless newer speech was sent since
+    private handleRetryJob(job: TranscriptionJob): void {
+        if (!FINISHED_JOB_STATUSES.includes(job.status)) return;
+        const retry = this.pendingRetries.get(job.id);
+        this.pendingRetries.delete(job.id);
+        if (!retry || job.status !== 'completed') return;
+
+        const raw = job.result?.text?.trim() ?? '';
+        const text = this.segmentOverlapMs > 0 ? dedupeBoundary(retry.previous, raw) : raw;
+        const normalize = (t: string) => t.toLowerCase().replace(/[^\p{L}\p{N}]+/gu, ' ').trim();
+        if (!text || normalize(text) === normalize(retry.text)) {
+            info(`[WHISPER] Re-transcription of job ${job.id} matches the original`);
+            return;
+        }
+        if (retry.finalCount !== this.finalCount) {
+            info(`[WHISPER] Re-transcription of job ${job.id} arrived after newer speech, not correcting`);
+            return;
+        }
+
+        info(`[WHISPER] Correcting "${retry.text}" -> "${text}"`);
+        this.lastFinalText = raw;
+        if (this.resultCallback) {
+            this.resultCallback(text, true);
+        }
+    }
+
+    // Show the fast model's partial right away, then replace it with the final text
+    private handleDualJob(job: TranscriptionJob): void {
+        if (!FINISHED_JOB_STATUSES.includes(job.status)) return;
+
+        if (this.pendingPartials.delete(job.id)) {
+            const text = this.dedupe(job.result?.text?.trim() ?? '', false);
+            // Ignore partials that lost the race against their final
+            if (job.status !== 'completed' || !job.linked_job || !this.pendingFinals.has(job.linked_job)) return;
+            this.pendingFinals.set(job.linked_job, text);
+            if (text && this.resultCallback) {
+                info(`[WHISPER] Partial transcription: ${text}`);
+                this.resultCallback(text, false);
+            }
+            return;
+        }
+
+        const partialText = this.pendingFinals.get(job.id);
+        if (partialText === undefined) return;
+        this.pendingFinals.delete(job.id);
+        const audio = this.finalAudio.get(job.id);
+        this.finalAudio.delete(job.id);
+
+        // Fall back to the partial if the accurate pass didn't make it
+        const previous = this.lastFinalText;
+        const text = job.status === 'completed' ? this.dedupe(job.result?.text?.trim() ?? '', true) : partialText;
+        if (job.status !== 'completed') {
+            error(`[WHISPER] Final transcription ${job.status}: ${job.error ?? 'no error'}, keeping partial`);
+        } else {
+            info(`[WHISPER] Final transcription: ${text}`);
+        }
+        this.emitFinal(text);
+        if (job.status === 'completed' && audio) {
+            this.maybeRetranscribe(job.result, job.model, audio, text, previous);
+        }
+    }
+
     private startRecordingLoop(): void {
         if (!this.running || !this.mediaRecorder) return;
 
@@ -221,21 +593,46 @@ export class Whisper extends Recognizer {
             }
 
             info(`[WHISPER] Converted to WAV: ${wavData.length} bytes`);
+
+            if (this.dualMode && this.fastModel) {
+                const job = await invoke('whisper_submit_dual_job', {
+                    audioData: Array.from(wavData),
+                    model: this.model,
+                    fastModel: this.fastModel,
+                    language: this.language
+                }) as { partial_id: string; final_id: string };
+                this.pendingPartials.add(job.partial_id);
+                this.pendingFinals.set(job.final_id, '');
+                if (this.retranscribe.enabled) {
+                    this.finalAudio.set(job.final_id, wavData);
+                }
+                info(`[WHISPER] Submitted dual-model jobs ${job.partial_id} / ${job.final_id}`);
+                return;
+            }
+
             info(`[WHISPER] Sending audio data to Rust backend`);
 
             // Send to Rust backend for Whisper processing
-            const result = await invoke('whisper_transcribe', {
+            const transcription = await invoke('stt_transcribe', {
                 audioData: Array.from(wavData),
                 model: this.model,
                 language: this.language
-            }) as string;
+            }) as WhisperTranscription;
+            const previous = this.lastFinalText;
+            const result = this.dedupe(transcription?.text?.trim() ?? '', true);
+
+            const segmentLanguages = new Set(transcription?.segments?.map(s => s.language) ?? []);
+            if (segmentLanguages.size > 1) {
+                info(`[WHISPER] Mixed-language utterance: ${[...segmentLanguages].join(', ')} (primary ${transcription.language})`);
+            }
 
             // Debug logging to see what we actually get back
             info(`[WHISPER] Raw transcription result: "${result}" (length: ${result?.length || 0})`);
 
             if (result && result.trim().length > 0 && this.resultCallback) {
                 info(`[WHISPER] Transcription result: ${result}`);
-                this.resultCallback(result.trim(), true); // Always final with Whisper
+                this.emitFinal(result.trim()); // Always final with Whisper
+                this.maybeRetranscribe(transcription, this.model, wavData, result.trim(), previous);
             } else {
                 info(`[WHISPER] Empty or null transcription result - no speech detected or language mismatch`);
                 // Even with no speech, we should still notify the UI that processing completed
@@ -295,6 +692,19 @@ export class Whisper extends Recognizer {
                 monoData = resampled;
             }
 
+            // Prepend the end of the previous chunk so words cut at the boundary are heard whole
+            const overlapSamples = Math.floor(16000 * this.segmentOverlapMs / 1000);
+            const chunkData = monoData;
+            if (overlapSamples > 0 && this.previousTail) {
+                const merged = new Float32Array(this.previousTail.length + chunkData.length);
+                merged.set(this.previousTail);
+                merged.set(chunkData, this.previousTail.length);
+                monoData = merged;
+            }
+            this.previousTail = overlapSamples > 0
+                ? chunkData.slice(Math.max(0, chunkData.length - overlapSamples))
+                : null;
+
             // Convert to 16-bit PCM
             const pcmData = new Int16Array(monoData.length);
             for (let i = 0; i < monoData.length; i++) {
@@ -417,6 +827,92 @@ export class Whisper extends Recognizer {
         }
     }
 
+    // Re-download only the files of a model that fail size or checksum verification
+    static async repairModel(model: string): Promise<string[] | null> {
+        try {
+            info(`[WHISPER] Verifying model files: ${model}`);
+            const repaired = await invoke('whisper_repair_model', {
+                model: model
+            }) as string[];
+            info(`[WHISPER] Repaired ${repaired.length} file(s) of model ${model}`);
+            return repaired;
+        } catch (err: unknown) {
+            const errorMessage = err instanceof Error ? err.message : String(err);
+            error(`[WHISPER] Error repairing model ${model}: ${errorMessage}`);
+            return null;
+        }
+    }
+
+    static async addCustomModel(model: CustomModel): Promise<boolean> {
+        try {
+            await invoke('whisper_add_custom_model', { model: model });
+            info(`[WHISPER] Registered custom model ${model.id}`);
+            return true;
+        } catch (err: unknown) {
+            const errorMessage = err instanceof Error ? err.message : String(err);
+            error(`[WHISPER] Error registering custom model ${model.id}: ${errorMessage}`);
+            return false;
+        }
+    }
+
+    static async removeCustomModel(modelId: string): Promise<boolean> {
+        try {
+            return await invoke('whisper_remove_custom_model', { modelId: modelId }) as boolean;
+        } catch (err: unknown) {
+            const errorMessage = err instanceof Error ? err.message : String(err);
+            error(`[WHISPER] Error removing custom model ${modelId}: ${errorMessage}`);
+            return false;
+        }
+    }
+
+    static async listAudioDevices(): Promise<AudioDevice[]> {
+        try {
+            return await invoke('list_audio_devices') as AudioDevice[];
+        } catch (err: unknown) {
+            const errorMessage = err instanceof Error ? err.message : String(err);
+            error(`[WHISPER] Error listing audio devices: ${errorMessage}`);
+            return [];
+        }
+    }
+
+    // Measure the mic's background noise; the user should stay quiet while it runs
+    static async calibrateNoiseGate(durationMs: number = 3000): Promise<NoiseCalibration | null> {
+        try {
+            return await invoke('audio_calibrate_noise_gate', { durationMs }) as NoiseCalibration;
+        } catch (err: unknown) {
+            const errorMessage = err instanceof Error ? err.message : String(err);
+            error(`[WHISPER] Error calibrating noise gate: ${errorMessage}`);
+            return null;
+        }
+    }
+
+    // Subscribe to the native capture's mic meter (about ten updates per second)
+    static onAudioLevel(callback: (level: AudioLevel) => void): Promise<UnlistenFn> {
+        return listen<AudioLevel>('audio-level', (event) => callback(event.payload));
+    }
+
+    static async getCorrections(): Promise<CorrectionDictionary | null> {
+        try {
+            return await invoke('whisper_get_corrections') as CorrectionDictionary;
+        } catch (err: unknown) {
+            const errorMessage = err instanceof Error ? err.message : String(err);
+            error(`[WHISPER] Error loading correction dictionary: ${errorMessage}`);
+            return null;
+        }
+    }
+
+    static async setCorrections(dictionary: CorrectionDictionary): Promise<boolean> {
+        try {
+            await invoke('whisper_set_corrections', { dictionary: dictionary });
+            info(`[WHISPER] Saved ${dictionary.entries.length} correction(s)`);
+            return true;
+        } catch (err: unknown) {
+            const errorMessage = err instanceof Error ? err.message : String(err);
+            error(`[WHISPER] Error saving correction dictionary: ${errorMessage}`);
+            return false;
+        }
+    }
+
     static async isModelDownloaded(model: string): Promise<boolean> {
         try {
             const downloaded = await invoke('whisper_is_model_downloaded', {
@@ -0,0 +1,62 @@
+import { invoke } from '@tauri-apps/api/core';
+import { error } from '@tauri-apps/plugin-log';
+
+export type TranslatorConfig =
+    | { type: 'google' }
+    | { type: 'deepl'; api_key: string; formality: 'default' | 'more' | 'less' }
+    | { type: 'libretranslate'; url: string; api_key: string | null }
+    | { type: 'local'; model: string }
+    | { type: 'azure'; api_key: string; region: string | null; category: string | null; free_tier: boolean }
+    | { type: 'llm'; base_url: string; api_key: string | null; model: string; prompt: string | null; max_length: number };
+
+export interface LocalTranslationModel {
+    id: string;
+    repo_id: string;
+    downloaded: boolean;
+}
+
+export interface Translation {
+    text: string;
+    provider: string; // Empty when source and target were the same language
+    cached: boolean;
+    source: string; // Identified language when translating from "auto"
+}
+
+// Translate through the provider the backend has for this language pair (or
+// `provider` when given)
+export default async function translateBackend(
+    text: string,
+    source: string,
+    target: string,
+    provider?: TranslatorConfig
+): Promise<string> {
+    try {
+        const translation = await invoke<Translation>('translate_text', { text, source, target, provider });
+        return translation.text;
+    } catch (err: unknown) {
+        const errorMessage = err instanceof Error ? err.message : String(err);
+        error(`[TRANSLATE] Backend error: ${errorMessage}`);
+
+        // Same fallback marker as the other translators
+        return `${text} (translation failed)`;
+    }
+}
+
+export async function listLocalTranslationModels(): Promise<LocalTranslationModel[]> {
+    try {
+        return await invoke<LocalTranslationModel[]>('translate_list_local_models');
+    } catch (err: unknown) {
+        error(`[TRANSLATE] Failed to list local translation models: ${err}`);
+        return [];
+    }
+}
+
+// Progress arrives as `download-progress` events, like Whisper model downloads
+export async function downloadLocalTranslationModel(model: string): Promise<boolean> {
+    try {
+        return await invoke<boolean>('translate_download_model', { model });
+    } catch (err: unknown) {
+        error(`[TRANSLATE] Failed to download translation model ${model}: ${err}`);
+        return false;
+    }
+}
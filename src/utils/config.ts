@@ -1,9 +1,6 @@
-import { readTextFile, writeTextFile, exists, create } from '@tauri-apps/plugin-fs';
-import { appConfigDir } from '@tauri-apps/api/path';
+import { invoke } from '@tauri-apps/api/core';
 import { info, error } from '@tauri-apps/plugin-log';
 
-// import { Store } from '@tauri-apps/plugin-fs';
-
 export const speed_presets = {
     slow: 1000,
     medium: 500,
@@ -25,13 +22,167 @@ export type Config = {
     target_language: string;
     secondary_target_language: string | null; // Optional second target language for dual translation
     mode: number;    // 0 = translation, 1 = transcription only
-    selected_microphone: string | null; // Device ID for selected microphone
     recognizer: string; // "webspeech" or "whisper"
     whisper_model: string; // Selected Whisper model ID
-    translator: string; // "google", "gemini", or "groq"
+    whisper_native_capture: boolean; // Record the microphone in the backend instead of the webview
+    audio_input_device: string | null; // Native capture device ID (null = system default)
+    vad: {
+        min_energy: number; // RMS below which audio is never speech
+        min_speech_ms: number; // Shorter sounds are ignored as clicks
+        hangover_ms: number; // Pauses shorter than this don't end an utterance
+        max_segment_ms: number; // Longest speech region forwarded to Whisper
+    };
+    capture_segmentation: {
+        pre_padding_ms: number; // Audio kept before detected speech
+        post_padding_ms: number; // Silence kept after speech ends
+        max_utterance_ms: number; // Longer speech is cut at a pause
+    };
+    capture_sources: {
+        microphone: { enabled: boolean; volume: number }; // Own speech, sent to the chatbox
+        loopback: { enabled: boolean; volume: number }; // Other players, shown in history
+    };
+    input_mix: {
+        primary_gain: number; // Gain of audio_input_device
+        inputs: { device: string; gain: number }[]; // Extra mics mixed in, e.g. a desk mic next to the headset
+    };
+    loopback_capture: boolean; // Also transcribe what other players say through the speakers (Windows)
+    captions: {
+        enabled: boolean; // Translate what other players say into local captions (needs loopback_capture)
+        target_language: string;
+        speak: boolean; // Read translated captions aloud on tts.headphones_device, as a live interpreter
+    };
+    echo_cancellation: {
+        enabled: boolean; // Remove speaker audio from the mic using loopback as reference (needs the aec build)
+        suppression: 'low' | 'moderate' | 'high';
+    };
+    debug_recording: {
+        enabled: boolean; // Save every transcribed segment as a WAV for bug reports
+        max_files: number;
+        max_total_mb: number;
+    };
+    noise_gate: {
+        enabled: boolean; // Silence background hum below the threshold before VAD
+        threshold_db: number; // dBFS, see Whisper.calibrateNoiseGate for a suggestion
+        hold_ms: number; // Time the gate stays open after the level drops
+    };
+    agc: {
+        enabled: boolean; // Boost quiet microphones before transcription
+        target_db: number; // Level to normalize speech to (dBFS)
+        max_gain_db: number; // Largest boost applied
+    };
+    push_to_talk: {
+        enabled: boolean; // Only transcribe the native mic while the hotkey is held
+        hotkey: string; // Global accelerator, e.g. "Ctrl+Shift+Space"
+        pre_roll_ms: number; // Audio kept from just before the press
+    };
+    whisper_fast_model: string | null; // Small model for quick partials, replaced by whisper_model's result (null = off)
+    whisper_quantization: 'auto' | 'full' | 'q8_0' | 'q5'; // Variant loaded on GPU backends; auto picks the best fitting VRAM
+    whisper_downloads: {
+        mirror_url: string; // Hugging Face mirror, e.g. "https://hf-mirror.com" (empty = huggingface.co)
+        proxy: string; // http://, https:// or socks5:// proxy for model downloads
+        hf_token: string; // Hugging Face token for gated models
+        max_connections: number;
+        chunked: boolean; // Split large files into ranged requests
+        max_bytes_per_second: number; // 0 = unlimited
+    };
+    whisper_retranscribe: {
+        enabled: boolean; // Re-transcribe low-confidence results with the next larger downloaded model
+        confidence_threshold: number; // Mean segment probability below which a result is retried
+    };
+    whisper_decoding: {
+        beam_size: number; // 1 = greedy decoding (fastest)
+        temperature: number;
+        temperature_increment: number; // 0 disables temperature fallback
+        no_speech_threshold: number;
+        compression_ratio_threshold: number; // Segments above this are retried at a higher temperature
+        logprob_threshold: number; // Segments below this are retried at a higher temperature
+        condition_on_previous_text: boolean;
+    };
+    whisper_denoise: boolean; // Run RNNoise on microphone audio before transcription
+    whisper_segment_overlap_ms: number; // Audio from the end of each chunk repeated at the start of the next (0 = off)
+    whisper_hallucination_filter: {
+        enabled: boolean;
+        drop_known_phrases: boolean; // "Subtitles by ...", "Thanks for watching", etc.
+        max_repeats: number; // Repeats of a phrase kept before a loop is cut off
+        no_speech_probability: number;
+        min_rms: number; // Segments quieter than this are treated as silence
+    };
+    hotword: {
+        enabled: boolean; // Only send speech to the chatbox after a wake phrase
+        phrases: string[];
+        armed_seconds: number;
+        detector_model: string | null; // Smaller Whisper model used while listening for the wake phrase
+    };
+    profanity_filter: {
+        enabled: boolean;
+        mode: string; // "mask" (replace with asterisks) or "drop"
+        use_default_list: boolean;
+        custom_words: string[]; // A trailing * matches any word with that prefix
+    };
+    text_normalization: {
+        enabled: boolean; // Write spoken numbers, times and units as digits ("twenty three" -> "23")
+        convert_small_numbers: boolean; // Also convert lone "one" through "nine"
+    };
+    watch_folder: {
+        enabled: boolean; // Transcribe audio files an external recorder drops into `path`
+        path: string;
+        include_existing: boolean; // Also transcribe files already there when watching starts
+    };
+    translator: string; // "google", "deepl", "libretranslate", "local", "azure", "llm", "gemini", or "groq"
     translation_style: string; // "casual", "formal", "polite", "friendly"
     gemini_api_key: string; // Gemini API key for translation
     groq_api_key: string; // Groq API key for translation
+    deepl: {
+        api_key: string; // DeepL Free (ending in ":fx") or Pro key
+        formality: 'default' | 'more' | 'less';
+    };
+    libretranslate: {
+        url: string; // Self-hosted instance, e.g. "http://192.168.1.20:5000"
+        api_key: string; // Only if the instance requires keys
+    };
+    azure: {
+        api_key: string;
+        region: string; // Resource region, e.g. "japaneast"; empty for global resources
+        category: string; // Custom Translator category ID
+        free_tier: boolean; // F0 pricing tier, for usage warnings
+    };
+    tts: {
+        enabled: boolean; // Speak every sent message (the translation in translation mode)
+        output_device: string | null; // e.g. "CABLE Input (VB-Audio Virtual Cable)" to feed VRChat's mic; null = default
+        headphones_device: string | null; // Where speech only meant for the user is played; null = default
+        volume: number; // 0.0 to 2.0
+        mic_ducking: 'off' | 'duck' | 'pause'; // Keep the spoken voice out of microphone transcription
+        duck_gain: number; // Microphone gain while ducked, 0.0 to 1.0
+        lip_sync: {
+            enabled: boolean; // Move the avatar's mouth over OSC while speaking on the virtual mic
+            mouth_parameter: string; // Float avatar parameter, 0 = closed
+            viseme_parameter: string; // Int avatar parameter for the estimated viseme; empty = not sent
+            sensitivity: number; // 0.1 to 20
+        };
+        provider: 'piper' | 'openai' | 'sapi';
+        piper_voice: string; // Downloaded with tts_download_piper_voice
+        sapi: {
+            voice: string; // Installed Windows voice from tts_list_sapi_voices; empty = system default
+            rate: number; // -10 to 10
+            volume: number; // 0 to 100
+        };
+        openai: {
+            base_url: string; // OpenAI-compatible speech server
+            api_key: string;
+            model: string;
+            voice: string;
+        };
+    };
+    llm: {
+        base_url: string; // OpenAI-compatible server, e.g. "https://api.openai.com" or "http://localhost:11434"
+        api_key: string;
+        model: string;
+        prompt: string; // System prompt with {source}, {target} and {max_length}; empty for the built-in one
+        max_length: number;
+    };
+    local_translation_model: string; // Offline NLLB model, downloaded with downloadLocalTranslationModel
+    translation_pairs: { source: string; target: string; translator: 'google' | 'deepl' | 'libretranslate' | 'local' | 'azure' | 'llm' }[]; // Per language pair provider ("*" matches any)
+    translation_fallbacks: ('google' | 'deepl' | 'libretranslate' | 'local' | 'azure' | 'llm')[]; // Tried in order when the provider is throttled or down
     layout: string; // "default" or "horizontal"
     theme_color: string; // "blue", "purple", "green", "orange", "pink", "red"
     language_settings: {
@@ -39,6 +190,16 @@ export type Config = {
         gender_change: boolean;
         gender_change_type: number;  // 0 = make masculine, 1 = make feminine
     };
+    chatbox_format: {
+        separator: string;
+        original_template: string; // "{text}" and "{lang}" are replaced with the text and its language tag
+        translation_template: string;
+        overflow: 'paginate' | 'shorten_original' | 'truncate'; // When the message is longer than one chatbox page
+        transliteration: {
+            mode: 'off' | 'append' | 'replace';
+            languages: ('ja' | 'zh' | 'ko')[]; // Romaji, pinyin and romaja respectively
+        };
+    };
     vrchat_settings: {
         translation_first: boolean;
         only_translation: boolean;
@@ -99,13 +260,167 @@ export const DEFAULT_CONFIG: Config = {
     target_language: "ja",
     secondary_target_language: null, // Disabled by default
     mode: 0,
-    selected_microphone: null, // Default to system default microphone
     recognizer: "webspeech", // Default to WebSpeech
     whisper_model: "base", // Default Whisper model
+    whisper_native_capture: true,
+    audio_input_device: null,
+    vad: {
+        min_energy: 0.01,
+        min_speech_ms: 250,
+        hangover_ms: 300,
+        max_segment_ms: 28000
+    },
+    capture_segmentation: {
+        pre_padding_ms: 300,
+        post_padding_ms: 300,
+        max_utterance_ms: 15000
+    },
+    capture_sources: {
+        microphone: { enabled: true, volume: 1 },
+        loopback: { enabled: true, volume: 1 }
+    },
+    input_mix: {
+        primary_gain: 1,
+        inputs: []
+    },
+    loopback_capture: false,
+    captions: {
+        enabled: false,
+        target_language: "en",
+        speak: false
+    },
+    echo_cancellation: {
+        enabled: false,
+        suppression: 'moderate'
+    },
+    debug_recording: {
+        enabled: false,
+        max_files: 100,
+        max_total_mb: 200
+    },
+    noise_gate: {
+        enabled: false,
+        threshold_db: -50,
+        hold_ms: 200
+    },
+    agc: {
+        enabled: true,
+        target_db: -20,
+        max_gain_db: 30
+    },
+    push_to_talk: {
+        enabled: false,
+        hotkey: "",
+        pre_roll_ms: 300
+    },
+    whisper_fast_model: null,
+    whisper_quantization: 'auto',
+    whisper_downloads: {
+        mirror_url: '',
+        proxy: '',
+        hf_token: '',
+        max_connections: 4,
+        chunked: true,
+        max_bytes_per_second: 0
+    },
+    whisper_retranscribe: {
+        enabled: false,
+        confidence_threshold: 0.6
+    },
+    whisper_decoding: {
+        beam_size: 5,
+        temperature: 0.0,
+        temperature_increment: 0.2,
+        no_speech_threshold: 0.6,
+        compression_ratio_threshold: 2.4,
+        logprob_threshold: -1.0,
+        condition_on_previous_text: false
+    },
+    whisper_denoise: false,
+    whisper_segment_overlap_ms: 200,
+    whisper_hallucination_filter: {
+        enabled: true,
+        drop_known_phrases: true,
+        max_repeats: 2,
+        no_speech_probability: 0.6,
+        min_rms: 0.005
+    },
+    hotword: {
+        enabled: false,
+        phrases: ["hey chat"],
+        armed_seconds: 10,
+        detector_model: null
+    },
+    profanity_filter: {
+        enabled: false,
+        mode: "mask",
+        use_default_list: true,
+        custom_words: []
+    },
+    text_normalization: {
+        enabled: true,
+        convert_small_numbers: false
+    },
+    watch_folder: {
+        enabled: false,
+        path: "",
+        include_existing: false
+    },
     translator: "groq", // Default to Groq for translation
     translation_style: "casual", // Default translation style
     gemini_api_key: "", // Empty by default
     groq_api_key: "", // Empty by default
+    deepl: {
+        api_key: "",
+        formality: 'default'
+    },
+    libretranslate: {
+        url: "",
+        api_key: ""
+    },
+    azure: {
+        api_key: "",
+        region: "",
+        category: "",
+        free_tier: false
+    },
+    tts: {
+        enabled: false,
+        output_device: null,
+        headphones_device: null,
+        volume: 1.0,
+        mic_ducking: 'off',
+        duck_gain: 0.2,
+        lip_sync: {
+            enabled: false,
+            mouth_parameter: "TTSMouthOpen",
+            viseme_parameter: "",
+            sensitivity: 4
+        },
+        provider: 'piper',
+        piper_voice: "en_US-lessac-medium",
+        sapi: {
+            voice: "",
+            rate: 0,
+            volume: 100
+        },
+        openai: {
+            base_url: "",
+            api_key: "",
+            model: "",
+            voice: ""
+        }
+    },
+    llm: {
+        base_url: "",
+        api_key: "",
+        model: "",
+        prompt: "",
+        max_length: 144
+    },
+    local_translation_model: "nllb-200-600m",
+    translation_pairs: [],
+    translation_fallbacks: [],
     layout: "default", // Default layout
     theme_color: "blue", // Default theme color
     language_settings: {
@@ -113,6 +428,13 @@ export const DEFAULT_CONFIG: Config = {
         gender_change: false,
         gender_change_type: 0
     },
+    chatbox_format: {
+        separator: " | ",
+        original_template: "{text} [{lang}]",
+        translation_template: "[{lang}] {text}",
+        overflow: 'paginate',
+        transliteration: { mode: 'off', languages: ['ja'] }
+    },
     vrchat_settings: {
         translation_first: true,
         only_translation: false,
@@ -124,62 +446,26 @@ export const DEFAULT_CONFIG: Config = {
     }
 };
 
-// Get the config file path
-async function getConfigPath(): Promise<string> {
-    const configDir = await appConfigDir();
-    return `${configDir}/config.json`;
-}
-
-// Load configuration from file
+// Load configuration from the backend settings store, which migrates older files
+// (including the config.json this used to write) on startup
 export async function loadConfig(): Promise<Config> {
     try {
-        const configPath = await getConfigPath();
-        const configDir = await appConfigDir();
-        
-        // Check if config directory exists, create if not
-        if (!(await exists(configDir))) {
-            // Create directory without options
-            await create(configDir);
-            info('[CONFIG] Created config directory');
-        }
-        
-        // Check if config file exists
-        if (await exists(configPath)) {
-            info('[CONFIG] Loading config from file');
-            const configJson = await readTextFile(configPath);
-            const loadedConfig = JSON.parse(configJson);
-            
-            // Validate and merge with defaults to ensure all fields exist
-            return validateConfig(loadedConfig);
-        } else {
-            info('[CONFIG] Config file not found, using defaults');
-            // Save default config for future use
-            await saveConfig(DEFAULT_CONFIG);
-            return DEFAULT_CONFIG;
-        }
+        const settings = await invoke<Config>('get_settings');
+        info('[CONFIG] Loaded settings from the backend');
+        // Validate and merge with defaults to ensure all fields exist
+        return validateConfig(settings);
     } catch (e) {
         error(`[CONFIG] Error loading config: ${e}`);
         return DEFAULT_CONFIG;
     }
 }
 
-// Save configuration to file
+// Save configuration to the backend settings store
 export async function saveConfig(config: Config): Promise<void> {
     try {
-        const configPath = await getConfigPath();
-        const configDir = await appConfigDir();
-        
-        // Ensure config directory exists
-        if (!(await exists(configDir))) {
-            // Create directory without options
-            await create(configDir);
-        }
-        
         // Validate config before saving
         const validatedConfig = validateConfig(config);
-        
-        // Save to file
-        await writeTextFile(configPath, JSON.stringify(validatedConfig, null, 2));
+        await invoke('update_settings', { patch: validatedConfig });
         info('[CONFIG] Config saved successfully');
     } catch (e) {
         error(`[CONFIG] Error saving config: ${e}`);
@@ -202,16 +488,217 @@ export function validateConfig(config: Config): Config {
     }
     if (typeof config.mode === 'number') validated.mode = config.mode;
     // Microphone selection is no longer user-configurable – always use system default
-    validated.selected_microphone = null;
     
     // Recognizer settings
     if (config.recognizer && ['webspeech', 'whisper'].includes(config.recognizer)) {
         validated.recognizer = config.recognizer;
     }
     if (config.whisper_model) validated.whisper_model = config.whisper_model;
+    if (typeof config.whisper_native_capture === 'boolean') validated.whisper_native_capture = config.whisper_native_capture;
+    if (typeof config.audio_input_device === 'string' || config.audio_input_device === null)
+        validated.audio_input_device = config.audio_input_device;
+    validated.vad = { ...DEFAULT_CONFIG.vad };
+    if (config.vad) {
+        const vad = config.vad;
+        if (typeof vad.min_energy === 'number' && vad.min_energy >= 0 && vad.min_energy <= 0.5)
+            validated.vad.min_energy = vad.min_energy;
+        if (typeof vad.min_speech_ms === 'number' && vad.min_speech_ms >= 0 && vad.min_speech_ms <= 5000)
+            validated.vad.min_speech_ms = vad.min_speech_ms;
+        if (typeof vad.hangover_ms === 'number' && vad.hangover_ms >= 0 && vad.hangover_ms <= 5000)
+            validated.vad.hangover_ms = vad.hangover_ms;
+        if (typeof vad.max_segment_ms === 'number' && vad.max_segment_ms >= 1000 && vad.max_segment_ms <= 30000)
+            validated.vad.max_segment_ms = vad.max_segment_ms;
+    }
+    validated.capture_segmentation = { ...DEFAULT_CONFIG.capture_segmentation };
+    if (config.capture_segmentation) {
+        const segmentation = config.capture_segmentation;
+        if (typeof segmentation.pre_padding_ms === 'number' && segmentation.pre_padding_ms >= 0 && segmentation.pre_padding_ms <= 2000)
+            validated.capture_segmentation.pre_padding_ms = segmentation.pre_padding_ms;
+        if (typeof segmentation.post_padding_ms === 'number' && segmentation.post_padding_ms >= 0 && segmentation.post_padding_ms <= 2000)
+            validated.capture_segmentation.post_padding_ms = segmentation.post_padding_ms;
+        if (typeof segmentation.max_utterance_ms === 'number' && segmentation.max_utterance_ms >= 1000 && segmentation.max_utterance_ms <= 30000)
+            validated.capture_segmentation.max_utterance_ms = segmentation.max_utterance_ms;
+    }
+    validated.capture_sources = {
+        microphone: { ...DEFAULT_CONFIG.capture_sources.microphone },
+        loopback: { ...DEFAULT_CONFIG.capture_sources.loopback }
+    };
+    if (config.capture_sources) {
+        for (const source of ['microphone', 'loopback'] as const) {
+            const settings = config.capture_sources[source];
+            if (!settings) continue;
+            if (typeof settings.enabled === 'boolean') validated.capture_sources[source].enabled = settings.enabled;
+            if (typeof settings.volume === 'number' && settings.volume >= 0 && settings.volume <= 4)
+                validated.capture_sources[source].volume = settings.volume;
+        }
+    }
+    if (typeof config.loopback_capture === 'boolean') validated.loopback_capture = config.loopback_capture;
+    validated.captions = { ...DEFAULT_CONFIG.captions };
+    if (config.captions) {
+        if (typeof config.captions.enabled === 'boolean') validated.captions.enabled = config.captions.enabled;
+        if (typeof config.captions.target_language === 'string' && config.captions.target_language.trim() !== '')
+            validated.captions.target_language = config.captions.target_language.trim();
+        if (typeof config.captions.speak === 'boolean') validated.captions.speak = config.captions.speak;
+    }
+    validated.debug_recording = { ...DEFAULT_CONFIG.debug_recording };
+    if (config.debug_recording) {
+        const debugRecording = config.debug_recording;
+        if (typeof debugRecording.enabled === 'boolean') validated.debug_recording.enabled = debugRecording.enabled;
+        if (typeof debugRecording.max_files === 'number' && debugRecording.max_files >= 1)
+            validated.debug_recording.max_files = Math.floor(debugRecording.max_files);
+        if (typeof debugRecording.max_total_mb === 'number' && debugRecording.max_total_mb >= 1)
+            validated.debug_recording.max_total_mb = Math.floor(debugRecording.max_total_mb);
+    }
+    validated.input_mix = { ...DEFAULT_CONFIG.input_mix, inputs: [] };
+    if (config.input_mix) {
+        const inputMix = config.input_mix;
+        if (typeof inputMix.primary_gain === 'number' && inputMix.primary_gain >= 0 && inputMix.primary_gain <= 4)
+            validated.input_mix.primary_gain = inputMix.primary_gain;
+        if (Array.isArray(inputMix.inputs)) {
+            validated.input_mix.inputs = inputMix.inputs
+                .filter(input => typeof input?.device === 'string' && input.device.trim() !== ''
+                    && typeof input.gain === 'number' && input.gain >= 0 && input.gain <= 4)
+                .filter((input, index, inputs) => inputs.findIndex(other => other.device === input.device) === index)
+                .slice(0, 4);
+        }
+    }
+    validated.echo_cancellation = { ...DEFAULT_CONFIG.echo_cancellation };
+    if (config.echo_cancellation) {
+        const echoCancellation = config.echo_cancellation;
+        if (typeof echoCancellation.enabled === 'boolean') validated.echo_cancellation.enabled = echoCancellation.enabled;
+        if (['low', 'moderate', 'high'].includes(echoCancellation.suppression))
+            validated.echo_cancellation.suppression = echoCancellation.suppression;
+    }
+    validated.noise_gate = { ...DEFAULT_CONFIG.noise_gate };
+    if (config.noise_gate) {
+        const noiseGate = config.noise_gate;
+        if (typeof noiseGate.enabled === 'boolean') validated.noise_gate.enabled = noiseGate.enabled;
+        if (typeof noiseGate.threshold_db === 'number' && noiseGate.threshold_db >= -90 && noiseGate.threshold_db <= -10)
+            validated.noise_gate.threshold_db = noiseGate.threshold_db;
+        if (typeof noiseGate.hold_ms === 'number' && noiseGate.hold_ms >= 0 && noiseGate.hold_ms <= 2000)
+            validated.noise_gate.hold_ms = noiseGate.hold_ms;
+    }
+    validated.agc = { ...DEFAULT_CONFIG.agc };
+    if (config.agc) {
+        const agc = config.agc;
+        if (typeof agc.enabled === 'boolean') validated.agc.enabled = agc.enabled;
+        if (typeof agc.target_db === 'number' && agc.target_db >= -40 && agc.target_db <= -3)
+            validated.agc.target_db = agc.target_db;
+        if (typeof agc.max_gain_db === 'number' && agc.max_gain_db >= 0 && agc.max_gain_db <= 60)
+            validated.agc.max_gain_db = agc.max_gain_db;
+    }
+    validated.push_to_talk = { ...DEFAULT_CONFIG.push_to_talk };
+    if (config.push_to_talk) {
+        const pushToTalk = config.push_to_talk;
+        if (typeof pushToTalk.enabled === 'boolean') validated.push_to_talk.enabled = pushToTalk.enabled;
+        if (typeof pushToTalk.hotkey === 'string') validated.push_to_talk.hotkey = pushToTalk.hotkey.trim();
+        if (typeof pushToTalk.pre_roll_ms === 'number' && pushToTalk.pre_roll_ms >= 0 && pushToTalk.pre_roll_ms <= 2000)
+            validated.push_to_talk.pre_roll_ms = pushToTalk.pre_roll_ms;
+    }
+    if (typeof config.whisper_fast_model === 'string' || config.whisper_fast_model === null)
+        validated.whisper_fast_model = config.whisper_fast_model;
+    if (config.whisper_quantization && ['auto', 'full', 'q8_0', 'q5'].includes(config.whisper_quantization))
+        validated.whisper_quantization = config.whisper_quantization;
+
+    validated.whisper_downloads = { ...DEFAULT_CONFIG.whisper_downloads };
+    if (config.whisper_downloads) {
+        const downloads = config.whisper_downloads;
+        if (typeof downloads.mirror_url === 'string')
+            validated.whisper_downloads.mirror_url = downloads.mirror_url.trim();
+        if (typeof downloads.proxy === 'string')
+            validated.whisper_downloads.proxy = downloads.proxy.trim();
+        if (typeof downloads.hf_token === 'string')
+            validated.whisper_downloads.hf_token = downloads.hf_token.trim();
+        if (typeof downloads.max_connections === 'number' && downloads.max_connections >= 1 && downloads.max_connections <= 16)
+            validated.whisper_downloads.max_connections = Math.round(downloads.max_connections);
+        if (typeof downloads.chunked === 'boolean')
+            validated.whisper_downloads.chunked = downloads.chunked;
+        if (typeof downloads.max_bytes_per_second === 'number' && downloads.max_bytes_per_second >= 0)
+            validated.whisper_downloads.max_bytes_per_second = Math.round(downloads.max_bytes_per_second);
+    }
+    validated.whisper_retranscribe = { ...DEFAULT_CONFIG.whisper_retranscribe };
+    if (config.whisper_retranscribe) {
+        const retranscribe = config.whisper_retranscribe;
+        if (typeof retranscribe.enabled === 'boolean') validated.whisper_retranscribe.enabled = retranscribe.enabled;
+        if (typeof retranscribe.confidence_threshold === 'number' && retranscribe.confidence_threshold >= 0 && retranscribe.confidence_threshold <= 1)
+            validated.whisper_retranscribe.confidence_threshold = retranscribe.confidence_threshold;
+    }
+    validated.whisper_decoding = { ...DEFAULT_CONFIG.whisper_decoding };
+    if (config.whisper_decoding) {
+        const decoding = config.whisper_decoding;
+        if (typeof decoding.beam_size === 'number' && decoding.beam_size >= 1 && decoding.beam_size <= 16)
+            validated.whisper_decoding.beam_size = Math.round(decoding.beam_size);
+        if (typeof decoding.temperature === 'number' && decoding.temperature >= 0 && decoding.temperature <= 1)
+            validated.whisper_decoding.temperature = decoding.temperature;
+        if (typeof decoding.temperature_increment === 'number' && decoding.temperature_increment >= 0 && decoding.temperature_increment <= 1)
+            validated.whisper_decoding.temperature_increment = decoding.temperature_increment;
+        if (typeof decoding.no_speech_threshold === 'number' && decoding.no_speech_threshold >= 0 && decoding.no_speech_threshold <= 1)
+            validated.whisper_decoding.no_speech_threshold = decoding.no_speech_threshold;
+        if (typeof decoding.compression_ratio_threshold === 'number' && decoding.compression_ratio_threshold >= 1 && decoding.compression_ratio_threshold <= 10)
+            validated.whisper_decoding.compression_ratio_threshold = decoding.compression_ratio_threshold;
+        if (typeof decoding.logprob_threshold === 'number' && decoding.logprob_threshold >= -10 && decoding.logprob_threshold <= 0)
+            validated.whisper_decoding.logprob_threshold = decoding.logprob_threshold;
+        if (typeof decoding.condition_on_previous_text === 'boolean')
+            validated.whisper_decoding.condition_on_previous_text = decoding.condition_on_previous_text;
+    }
+    if (typeof config.whisper_denoise === 'boolean') validated.whisper_denoise = config.whisper_denoise;
+    if (typeof config.whisper_segment_overlap_ms === 'number' && config.whisper_segment_overlap_ms >= 0 && config.whisper_segment_overlap_ms <= 1000)
+        validated.whisper_segment_overlap_ms = Math.round(config.whisper_segment_overlap_ms);
+    validated.whisper_hallucination_filter = { ...DEFAULT_CONFIG.whisper_hallucination_filter };
+    if (config.whisper_hallucination_filter) {
+        const filter = config.whisper_hallucination_filter;
+        if (typeof filter.enabled === 'boolean')
+            validated.whisper_hallucination_filter.enabled = filter.enabled;
+        if (typeof filter.drop_known_phrases === 'boolean')
+            validated.whisper_hallucination_filter.drop_known_phrases = filter.drop_known_phrases;
+        if (typeof filter.max_repeats === 'number' && filter.max_repeats >= 1)
+            validated.whisper_hallucination_filter.max_repeats = Math.round(filter.max_repeats);
+        if (typeof filter.no_speech_probability === 'number' && filter.no_speech_probability >= 0 && filter.no_speech_probability <= 1)
+            validated.whisper_hallucination_filter.no_speech_probability = filter.no_speech_probability;
+        if (typeof filter.min_rms === 'number' && filter.min_rms >= 0 && filter.min_rms <= 1)
+            validated.whisper_hallucination_filter.min_rms = filter.min_rms;
+    }
+    validated.hotword = { ...DEFAULT_CONFIG.hotword, phrases: [...DEFAULT_CONFIG.hotword.phrases] };
+    if (config.hotword) {
+        const hotword = config.hotword;
+        if (typeof hotword.enabled === 'boolean') validated.hotword.enabled = hotword.enabled;
+        if (Array.isArray(hotword.phrases)) {
+            const phrases = hotword.phrases.filter(p => typeof p === 'string' && p.trim() !== '');
+            if (phrases.length > 0) validated.hotword.phrases = phrases;
+        }
+        if (typeof hotword.armed_seconds === 'number' && hotword.armed_seconds >= 1 && hotword.armed_seconds <= 600)
+            validated.hotword.armed_seconds = Math.round(hotword.armed_seconds);
+        if (typeof hotword.detector_model === 'string' || hotword.detector_model === null)
+            validated.hotword.detector_model = hotword.detector_model;
+    }
+    validated.profanity_filter = { ...DEFAULT_CONFIG.profanity_filter, custom_words: [] };
+    if (config.profanity_filter) {
+        const filter = config.profanity_filter;
+        if (typeof filter.enabled === 'boolean') validated.profanity_filter.enabled = filter.enabled;
+        if (filter.mode && ['mask', 'drop'].includes(filter.mode)) validated.profanity_filter.mode = filter.mode;
+        if (typeof filter.use_default_list === 'boolean')
+            validated.profanity_filter.use_default_list = filter.use_default_list;
+        if (Array.isArray(filter.custom_words))
+            validated.profanity_filter.custom_words = filter.custom_words.filter(w => typeof w === 'string' && w.trim() !== '');
+    }
+    validated.text_normalization = { ...DEFAULT_CONFIG.text_normalization };
+    if (config.text_normalization) {
+        const normalization = config.text_normalization;
+        if (typeof normalization.enabled === 'boolean') validated.text_normalization.enabled = normalization.enabled;
+        if (typeof normalization.convert_small_numbers === 'boolean')
+            validated.text_normalization.convert_small_numbers = normalization.convert_small_numbers;
+    }
+    validated.watch_folder = { ...DEFAULT_CONFIG.watch_folder };
+    if (config.watch_folder) {
+        const watchFolder = config.watch_folder;
+        if (typeof watchFolder.enabled === 'boolean') validated.watch_folder.enabled = watchFolder.enabled;
+        if (typeof watchFolder.path === 'string') validated.watch_folder.path = watchFolder.path.trim();
+        if (typeof watchFolder.include_existing === 'boolean')
+            validated.watch_folder.include_existing = watchFolder.include_existing;
+    }
     
     // Translator settings
-    if (config.translator && ['google', 'gemini', 'groq'].includes(config.translator)) {
+    if (config.translator && ['google', 'deepl', 'libretranslate', 'local', 'azure', 'llm', 'gemini', 'groq'].includes(config.translator)) {
         validated.translator = config.translator;
     }
     if (config.translation_style && ['casual', 'formal', 'polite', 'friendly'].includes(config.translation_style)) {
@@ -223,6 +710,86 @@ export function validateConfig(config: Config): Config {
     if (typeof config.groq_api_key === 'string') {
         validated.groq_api_key = config.groq_api_key;
     }
+    validated.deepl = { ...DEFAULT_CONFIG.deepl };
+    if (config.deepl) {
+        if (typeof config.deepl.api_key === 'string') validated.deepl.api_key = config.deepl.api_key.trim();
+        if (['default', 'more', 'less'].includes(config.deepl.formality)) validated.deepl.formality = config.deepl.formality;
+    }
+    validated.libretranslate = { ...DEFAULT_CONFIG.libretranslate };
+    if (config.libretranslate) {
+        if (typeof config.libretranslate.url === 'string') validated.libretranslate.url = config.libretranslate.url.trim();
+        if (typeof config.libretranslate.api_key === 'string') validated.libretranslate.api_key = config.libretranslate.api_key.trim();
+    }
+    validated.azure = { ...DEFAULT_CONFIG.azure };
+    if (config.azure) {
+        if (typeof config.azure.api_key === 'string') validated.azure.api_key = config.azure.api_key.trim();
+        if (typeof config.azure.region === 'string') validated.azure.region = config.azure.region.trim();
+        if (typeof config.azure.category === 'string') validated.azure.category = config.azure.category.trim();
+        if (typeof config.azure.free_tier === 'boolean') validated.azure.free_tier = config.azure.free_tier;
+    }
+    validated.tts = {
+        ...DEFAULT_CONFIG.tts,
+        lip_sync: { ...DEFAULT_CONFIG.tts.lip_sync },
+        sapi: { ...DEFAULT_CONFIG.tts.sapi },
+        openai: { ...DEFAULT_CONFIG.tts.openai }
+    };
+    if (config.tts) {
+        if (typeof config.tts.enabled === 'boolean') validated.tts.enabled = config.tts.enabled;
+        if (typeof config.tts.output_device === 'string' && config.tts.output_device.trim() !== '')
+            validated.tts.output_device = config.tts.output_device;
+        if (typeof config.tts.headphones_device === 'string' && config.tts.headphones_device.trim() !== '')
+            validated.tts.headphones_device = config.tts.headphones_device;
+        if (typeof config.tts.volume === 'number' && config.tts.volume >= 0 && config.tts.volume <= 2)
+            validated.tts.volume = config.tts.volume;
+        if (['off', 'duck', 'pause'].includes(config.tts.mic_ducking)) validated.tts.mic_ducking = config.tts.mic_ducking;
+        if (typeof config.tts.duck_gain === 'number' && config.tts.duck_gain >= 0 && config.tts.duck_gain <= 1)
+            validated.tts.duck_gain = config.tts.duck_gain;
+        if (['piper', 'openai', 'sapi'].includes(config.tts.provider)) validated.tts.provider = config.tts.provider;
+        if (typeof config.tts.piper_voice === 'string' && config.tts.piper_voice.trim() !== '')
+            validated.tts.piper_voice = config.tts.piper_voice.trim();
+        if (config.tts.lip_sync) {
+            const lipSync = config.tts.lip_sync;
+            if (typeof lipSync.enabled === 'boolean') validated.tts.lip_sync.enabled = lipSync.enabled;
+            if (typeof lipSync.mouth_parameter === 'string' && lipSync.mouth_parameter.trim() !== '')
+                validated.tts.lip_sync.mouth_parameter = lipSync.mouth_parameter.trim();
+            if (typeof lipSync.viseme_parameter === 'string') validated.tts.lip_sync.viseme_parameter = lipSync.viseme_parameter.trim();
+            if (typeof lipSync.sensitivity === 'number' && lipSync.sensitivity >= 0.1 && lipSync.sensitivity <= 20)
+                validated.tts.lip_sync.sensitivity = lipSync.sensitivity;
+        }
+        if (config.tts.sapi) {
+            const sapi = config.tts.sapi;
+            if (typeof sapi.voice === 'string') validated.tts.sapi.voice = sapi.voice.trim();
+            if (typeof sapi.rate === 'number' && sapi.rate >= -10 && sapi.rate <= 10)
+                validated.tts.sapi.rate = Math.round(sapi.rate);
+            if (typeof sapi.volume === 'number' && sapi.volume >= 0 && sapi.volume <= 100)
+                validated.tts.sapi.volume = Math.round(sapi.volume);
+        }
+        if (config.tts.openai) {
+            const openai = config.tts.openai;
+            if (typeof openai.base_url === 'string') validated.tts.openai.base_url = openai.base_url.trim();
+            if (typeof openai.api_key === 'string') validated.tts.openai.api_key = openai.api_key.trim();
+            if (typeof openai.model === 'string') validated.tts.openai.model = openai.model.trim();
+            if (typeof openai.voice === 'string') validated.tts.openai.voice = openai.voice.trim();
+        }
+    }
+    validated.llm = { ...DEFAULT_CONFIG.llm };
+    if (config.llm) {
+        if (typeof config.llm.base_url === 'string') validated.llm.base_url = config.llm.base_url.trim();
+        if (typeof config.llm.api_key === 'string') validated.llm.api_key = config.llm.api_key.trim();
+        if (typeof config.llm.model === 'string') validated.llm.model = config.llm.model.trim();
+        if (typeof config.llm.prompt === 'string') validated.llm.prompt = config.llm.prompt;
+        if (typeof config.llm.max_length === 'number' && config.llm.max_length >= 1)
+            validated.llm.max_length = Math.round(config.llm.max_length);
+    }
+    if (typeof config.local_translation_model === 'string' && config.local_translation_model.trim() !== '')
+        validated.local_translation_model = config.local_translation_model.trim();
+    validated.translation_fallbacks = Array.isArray(config.translation_fallbacks)
+        ? config.translation_fallbacks.filter(translator => ['google', 'deepl', 'libretranslate', 'local', 'azure', 'llm'].includes(translator))
+        : [];
+    validated.translation_pairs = Array.isArray(config.translation_pairs)
+        ? config.translation_pairs.filter(pair => typeof pair?.source === 'string' && typeof pair.target === 'string'
+            && ['google', 'deepl', 'libretranslate', 'local', 'azure', 'llm'].includes(pair.translator))
+        : [];
     
     // Appearance settings
     if (config.layout && ['default', 'horizontal'].includes(config.layout)) {
@@ -242,6 +809,25 @@ export function validateConfig(config: Config): Config {
             validated.language_settings.gender_change_type = config.language_settings.gender_change_type;
     }
     
+    validated.chatbox_format = { ...DEFAULT_CONFIG.chatbox_format };
+    if (config.chatbox_format) {
+        const format = config.chatbox_format;
+        if (typeof format.separator === 'string') validated.chatbox_format.separator = format.separator;
+        if (typeof format.original_template === 'string' && format.original_template.includes('{text}'))
+            validated.chatbox_format.original_template = format.original_template;
+        if (typeof format.translation_template === 'string' && format.translation_template.includes('{text}'))
+            validated.chatbox_format.translation_template = format.translation_template;
+        if (['paginate', 'shorten_original', 'truncate'].includes(format.overflow))
+            validated.chatbox_format.overflow = format.overflow;
+        if (format.transliteration) {
+            const transliteration = validated.chatbox_format.transliteration = { ...DEFAULT_CONFIG.chatbox_format.transliteration };
+            if (['off', 'append', 'replace'].includes(format.transliteration.mode))
+                transliteration.mode = format.transliteration.mode;
+            if (Array.isArray(format.transliteration.languages))
+                transliteration.languages = format.transliteration.languages.filter(l => ['ja', 'zh', 'ko'].includes(l));
+        }
+    }
+
     // VRChat settings
     if (config.vrchat_settings) {
         if (typeof config.vrchat_settings.translation_first === 'boolean')
@@ -14,6 +14,7 @@ export const langSource = [
     { code: "id", name: "Indonesian" },
     { code: "ms", name: "Malaysian" },
     { code: "ar", name: "Arabic" },
+    { code: "auto", name: "Auto-detect" }, // Identified per message before translating
 ];
 
 // Language options for target language